@@ -0,0 +1,122 @@
+//! # Module `clock`
+//!
+//! This module implements the sources of the current time used by the cache, such that time can
+//! be controlled deterministically in tests.
+
+use std::{
+    sync::{
+        Mutex,
+        PoisonError
+    },
+    time::{
+        Duration,
+        SystemTime
+    }
+};
+
+/// # Trait `Clock`
+///
+/// A source of the current time.
+pub trait Clock: Send + Sync {
+    /// # Trait Method `Clock::now`
+    ///
+    /// Returns the current time.
+    fn now(&self) -> SystemTime;
+}
+
+/// # Struct `SystemClock`
+///
+/// A clock that returns the time of the system, this is the default clock of the cache.
+#[allow(clippy::module_name_repetitions)]
+#[derive(Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// # Struct `ManualClock`
+///
+/// A clock whose time only changes when it is explicitly set or advanced.
+///
+/// ## Example
+///
+/// ```
+/// use std::{
+///     sync::Arc,
+///     time::{
+///         Duration,
+///         SystemTime
+///     }
+/// };
+///
+/// use qlcache::{
+///     clock::{
+///         Clock,
+///         ManualClock
+///     },
+///     QlCache
+/// };
+///
+/// let clock = Arc::new(ManualClock::new(SystemTime::UNIX_EPOCH));
+/// let cache = QlCache::with_clock(clock.clone());
+///
+/// clock.advance(Duration::from_secs(60));
+/// assert_eq!(clock.now(), SystemTime::UNIX_EPOCH + Duration::from_secs(60));
+/// ```
+#[allow(clippy::module_name_repetitions)]
+pub struct ManualClock {
+    pub(crate) now: Mutex<SystemTime>
+}
+
+impl ManualClock {
+    /// # Constructor `ManualClock::new`
+    ///
+    /// Creates a new `ManualClock` starting at the specified time.
+    #[must_use]
+    pub fn new(now: SystemTime) -> Self {
+        Self {
+            now: Mutex::new(now)
+        }
+    }
+
+    /// # Instance Method `ManualClock::set`
+    ///
+    /// Sets the current time.
+    ///
+    /// ## Parameters
+    /// - `now`, type `SystemTime`; the time to set
+    pub fn set(&self, now: SystemTime) {
+        *self.now.lock().unwrap_or_else(PoisonError::into_inner) = now;
+    }
+
+    /// # Instance Method `ManualClock::advance`
+    ///
+    /// Advances the current time.
+    ///
+    /// ## Parameters
+    /// - `duration`, type `Duration`; the duration to advance the time by
+    pub fn advance(&self, duration: Duration) {
+        *self.now.lock().unwrap_or_else(PoisonError::into_inner) += duration;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> SystemTime {
+        *self.now.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Clock,
+        ManualClock,
+        SystemClock
+    };
+
+    static_assertions::assert_impl_all!(ManualClock: Clock, Send, Sync);
+    static_assertions::assert_impl_all!(SystemClock: Clock, Clone, Copy, Default, Send, Sync);
+}
@@ -0,0 +1,430 @@
+//! # Module `convert`
+//!
+//! This module implements the conversions between `ColumnValue`s and Rust types.
+//!
+//! Rust values convert into the `ColumnValue` variant of their type, and `None` converts into
+//! `null`. Converting back fails with `InvalidConversion` unless the value is of the target type
+//! or converts to it without losing precision: integers convert between all integer types that
+//! can hold their value, and numbers convert to floating point types that represent them exactly.
+
+use std::time::{
+    Duration,
+    SystemTime
+};
+
+use crate::{
+    error::{
+        QlError,
+        QlResult
+    },
+    numeric::Number,
+    types::{
+        EnumValue,
+        JsonValue,
+        Snowflake,
+        Uuid
+    },
+    ColumnValue
+};
+
+/// Implements the conversions of types that only convert to and from a single variant.
+macro_rules! variant_conversions {
+    ($($variant:ident($type:ty)),*) => {
+        $(
+            impl From<$type> for ColumnValue {
+                fn from(value: $type) -> Self {
+                    Self::$variant(value)
+                }
+            }
+
+            impl TryFrom<ColumnValue> for $type {
+                type Error = QlError;
+
+                fn try_from(value: ColumnValue) -> QlResult<Self> {
+                    match value {
+                        ColumnValue::$variant(value) => Ok(value),
+                        value => Err(invalid_conversion(&value, stringify!($type)))
+                    }
+                }
+            }
+        )*
+    };
+}
+
+/// Implements the conversions of integer types.
+macro_rules! integer_conversions {
+    ($($variant:ident($type:ty)),*) => {
+        $(
+            impl From<$type> for ColumnValue {
+                fn from(value: $type) -> Self {
+                    Self::$variant(value)
+                }
+            }
+
+            impl TryFrom<ColumnValue> for $type {
+                type Error = QlError;
+
+                #[allow(clippy::useless_conversion)] // for the 128-bit integer types
+                fn try_from(value: ColumnValue) -> QlResult<Self> {
+                    let converted = match Number::of(&value) {
+                        Some(Number::Integer {
+                            negative: true,
+                            magnitude
+                        }) => 0_i128
+                            .checked_sub_unsigned(magnitude)
+                            .and_then(|integer| <$type>::try_from(integer).ok()),
+                        Some(Number::Integer {
+                            negative: false,
+                            magnitude
+                        }) => <$type>::try_from(magnitude).ok(),
+                        _ => None
+                    };
+
+                    converted.ok_or_else(|| invalid_conversion(&value, stringify!($type)))
+                }
+            }
+        )*
+    };
+}
+
+variant_conversions!(
+    Bool(bool),
+    String(String),
+    Bytes(Vec<u8>),
+    Timestamp(SystemTime),
+    Duration(Duration),
+    Snowflake(Snowflake),
+    Uuid(Uuid),
+    Array(Vec<ColumnValue>),
+    Json(JsonValue),
+    Enum(EnumValue)
+);
+
+integer_conversions!(
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    I128(i128),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    U128(u128)
+);
+
+impl From<f32> for ColumnValue {
+    fn from(value: f32) -> Self {
+        Self::F32(value)
+    }
+}
+
+impl From<f64> for ColumnValue {
+    fn from(value: f64) -> Self {
+        Self::F64(value)
+    }
+}
+
+impl From<&str> for ColumnValue {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
+impl<T: Into<ColumnValue>> From<Option<T>> for ColumnValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Self::Null, Into::into)
+    }
+}
+
+impl TryFrom<ColumnValue> for f64 {
+    type Error = QlError;
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss, clippy::cast_sign_loss)]
+    fn try_from(value: ColumnValue) -> QlResult<Self> {
+        let converted = match Number::of(&value) {
+            Some(Number::Float(float)) => Some(float),
+            // the magnitude is exact if it converts back to itself
+            Some(Number::Integer {
+                negative,
+                magnitude
+            }) => {
+                let float = magnitude as f64;
+                let exact = float as u128 == magnitude && float < u128::MAX as f64;
+
+                match (exact, negative) {
+                    (false, _) => None,
+                    (true, false) => Some(float),
+                    (true, true) => Some(-float)
+                }
+            }
+            None => None
+        };
+
+        converted.ok_or_else(|| invalid_conversion(&value, "f64"))
+    }
+}
+
+impl TryFrom<ColumnValue> for f32 {
+    type Error = QlError;
+
+    #[allow(clippy::cast_possible_truncation, clippy::float_cmp)] // the conversion must be exact
+    fn try_from(value: ColumnValue) -> QlResult<Self> {
+        if let ColumnValue::F32(float) = value {
+            return Ok(float);
+        }
+
+        let converted = f64::try_from(value.clone())
+            .ok()
+            .filter(|float| f64::from(*float as f32) == *float || float.is_nan())
+            .map(|float| float as f32);
+
+        converted.ok_or_else(|| invalid_conversion(&value, "f32"))
+    }
+}
+
+impl<T: TryFrom<ColumnValue, Error = QlError>> TryFrom<ColumnValue> for Option<T> {
+    type Error = QlError;
+
+    fn try_from(value: ColumnValue) -> QlResult<Self> {
+        match value {
+            ColumnValue::Null => Ok(None),
+            value => T::try_from(value).map(Some)
+        }
+    }
+}
+
+fn invalid_conversion(value: &ColumnValue, target: &str) -> QlError {
+    QlError::InvalidConversion {
+        value: value.to_string(),
+        target: target.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fmt::Debug,
+        time::{
+            Duration,
+            SystemTime
+        }
+    };
+
+    use crate::{
+        error::QlError,
+        types::{
+            EnumType,
+            EnumValue,
+            JsonValue,
+            Snowflake,
+            Uuid
+        },
+        ColumnDataType,
+        ColumnValue
+    };
+
+    /// Converts a Rust value into a `ColumnValue` and back.
+    #[allow(clippy::needless_pass_by_value)]
+    fn round_trip<T>(value: T)
+    where
+        T: Clone + Debug + Into<ColumnValue> + PartialEq + TryFrom<ColumnValue, Error = QlError> {
+        assert_eq!(T::try_from(value.clone().into()).unwrap(), value);
+    }
+
+    fn is_invalid<T>(value: ColumnValue, target: &str) -> bool
+    where
+        T: TryFrom<ColumnValue, Error = QlError> {
+        let display = value.to_string();
+
+        matches!(
+            T::try_from(value),
+            Err(QlError::InvalidConversion { value, target: other })
+                if value == display && other == target
+        )
+    }
+
+    fn mute() -> EnumValue {
+        let kind = EnumType {
+            name: String::from("Kind"),
+            labels: vec![String::from("warn"), String::from("mute")]
+        };
+
+        match kind.value("mute").unwrap() {
+            ColumnValue::Enum(value) => value,
+            _ => unreachable!()
+        }
+    }
+
+    #[test]
+    fn rust_values_round_trip() {
+        round_trip(true);
+        round_trip(i8::MIN);
+        round_trip(i16::MIN);
+        round_trip(i32::MIN);
+        round_trip(i64::MIN);
+        round_trip(i128::MIN);
+        round_trip(u8::MAX);
+        round_trip(u16::MAX);
+        round_trip(u32::MAX);
+        round_trip(u64::MAX);
+        round_trip(u128::MAX);
+        round_trip(1.5_f32);
+        round_trip(-2.25_f64);
+        round_trip(String::from("text"));
+        round_trip(vec![0_u8, 255]);
+        round_trip(SystemTime::UNIX_EPOCH + Duration::from_nanos(1));
+        round_trip(Duration::from_secs(90));
+        round_trip(Snowflake(175_928_847_299_117_063));
+        round_trip(Uuid(u128::MAX));
+        round_trip(vec![ColumnValue::U8(1), ColumnValue::Null]);
+        round_trip("[1, {\"a\": null}]".parse::<JsonValue>().unwrap());
+        round_trip(mute());
+
+        round_trip(Some(5_u8));
+        round_trip(None::<u8>);
+        assert_eq!(ColumnValue::from(None::<String>), ColumnValue::Null);
+        assert_eq!(ColumnValue::from("text"), ColumnValue::String(String::from("text")));
+    }
+
+    #[test]
+    fn integers_convert_between_types_that_hold_them() {
+        assert_eq!(u8::try_from(ColumnValue::I64(255)).unwrap(), 255);
+        assert_eq!(i8::try_from(ColumnValue::U128(127)).unwrap(), 127);
+        assert_eq!(i128::try_from(ColumnValue::U64(u64::MAX)).unwrap(), i128::from(u64::MAX));
+        assert_eq!(i64::try_from(ColumnValue::I128(i128::from(i64::MIN))).unwrap(), i64::MIN);
+
+        // out of range of the target type
+        assert!(is_invalid::<u8>(ColumnValue::I64(256), "u8"));
+        assert!(is_invalid::<u8>(ColumnValue::I8(-1), "u8"));
+        assert!(is_invalid::<i8>(ColumnValue::U8(128), "i8"));
+        assert!(is_invalid::<i16>(ColumnValue::I32(i32::MIN), "i16"));
+        assert!(is_invalid::<u64>(ColumnValue::U128(u128::MAX), "u64"));
+        assert!(is_invalid::<i128>(ColumnValue::U128(u128::MAX), "i128"));
+        assert!(is_invalid::<u128>(ColumnValue::I128(i128::MIN), "u128"));
+
+        // floating point numbers never convert to integers, even if they are integral
+        assert!(is_invalid::<i32>(ColumnValue::F64(1.0), "i32"));
+        assert!(is_invalid::<u8>(ColumnValue::F32(0.0), "u8"));
+    }
+
+    #[allow(clippy::float_cmp)] // the conversions are exact
+    #[test]
+    fn numbers_convert_to_floats_that_represent_them_exactly() {
+        assert_eq!(f64::try_from(ColumnValue::I64(-(1 << 53))).unwrap(), -9_007_199_254_740_992.0);
+        assert_eq!(f64::try_from(ColumnValue::U128(1 << 100)).unwrap(), 2.0_f64.powi(100));
+        assert_eq!(f64::try_from(ColumnValue::F32(0.1)).unwrap(), f64::from(0.1_f32));
+        assert_eq!(f32::try_from(ColumnValue::U32(1 << 24)).unwrap(), 16_777_216.0);
+        assert_eq!(f32::try_from(ColumnValue::F64(0.5)).unwrap(), 0.5);
+        assert!(f32::try_from(ColumnValue::F64(f64::NAN)).unwrap().is_nan());
+        assert!(f32::try_from(ColumnValue::F64(f64::INFINITY)).unwrap().is_infinite());
+
+        // precision would be lost
+        assert!(is_invalid::<f64>(ColumnValue::U64(u64::MAX), "f64"));
+        assert!(is_invalid::<f64>(ColumnValue::I64((1 << 53) + 1), "f64"));
+        assert!(is_invalid::<f64>(ColumnValue::U128(u128::MAX), "f64"));
+        assert!(is_invalid::<f32>(ColumnValue::U32((1 << 24) + 1), "f32"));
+        assert!(is_invalid::<f32>(ColumnValue::F64(0.1), "f32"));
+        assert!(is_invalid::<f32>(ColumnValue::F64(f64::MAX), "f32"));
+    }
+
+    #[test]
+    fn values_of_other_variants_do_not_convert() {
+        assert!(is_invalid::<bool>(ColumnValue::U8(1), "bool"));
+        assert!(is_invalid::<String>(ColumnValue::Bytes(vec![b'a']), "String"));
+        assert!(is_invalid::<Vec<u8>>(ColumnValue::from("a"), "Vec<u8>"));
+        assert!(is_invalid::<SystemTime>(ColumnValue::Date(0), "SystemTime"));
+        assert!(is_invalid::<Duration>(ColumnValue::U64(1), "Duration"));
+        assert!(is_invalid::<Snowflake>(ColumnValue::U64(1), "Snowflake"));
+        assert!(is_invalid::<Uuid>(ColumnValue::from("00000000000000000000000000000000"), "Uuid"));
+        assert!(is_invalid::<JsonValue>(ColumnValue::from("{}"), "JsonValue"));
+        assert!(is_invalid::<EnumValue>(ColumnValue::from("mute"), "EnumValue"));
+        assert!(is_invalid::<u8>(ColumnValue::from("1"), "u8"));
+        assert!(is_invalid::<f64>(ColumnValue::Bool(true), "f64"));
+
+        // `null` only converts to `Option`
+        assert!(is_invalid::<u8>(ColumnValue::Null, "u8"));
+        assert!(is_invalid::<Option<u8>>(ColumnValue::I8(-1), "u8"));
+    }
+
+    #[test]
+    fn values_are_displayed() {
+        let cases = [
+            (ColumnValue::Bool(true), "true"),
+            (ColumnValue::I8(-8), "-8"),
+            (ColumnValue::I16(-16), "-16"),
+            (ColumnValue::I32(-32), "-32"),
+            (ColumnValue::I64(-64), "-64"),
+            (ColumnValue::I128(i128::MIN), "-170141183460469231731687303715884105728"),
+            (ColumnValue::U8(8), "8"),
+            (ColumnValue::U16(16), "16"),
+            (ColumnValue::U32(32), "32"),
+            (ColumnValue::U64(64), "64"),
+            (ColumnValue::U128(u128::MAX), "340282366920938463463374607431768211455"),
+            (ColumnValue::F32(1.5), "1.5"),
+            (ColumnValue::F64(-0.25), "-0.25"),
+            (ColumnValue::F64(f64::NAN), "NaN"),
+            (ColumnValue::from("text"), "text"),
+            (ColumnValue::Bytes(vec![0, 171, 255]), "\\x00abff"),
+            (ColumnValue::Bytes(Vec::new()), "\\x"),
+            (ColumnValue::Timestamp(SystemTime::UNIX_EPOCH), "1970-01-01T00:00:00.000000000Z"),
+            (ColumnValue::Date(0), "1970-01-01"),
+            (ColumnValue::Duration(Duration::from_millis(1500)), "1.5s"),
+            (ColumnValue::Snowflake(Snowflake(42)), "42"),
+            (ColumnValue::Uuid(Uuid(0xFF)), "00000000-0000-0000-0000-0000000000ff"),
+            (
+                ColumnValue::Array(vec![ColumnValue::U8(1), ColumnValue::Null]),
+                "[1, NULL]"
+            ),
+            (
+                ColumnValue::Json("{\"a\": [1, \"b\"]}".parse().unwrap()),
+                "{\"a\":[1,\"b\"]}"
+            ),
+            (ColumnValue::Enum(mute()), "mute"),
+            (ColumnValue::Null, "NULL")
+        ];
+
+        for (value, display) in cases {
+            assert_eq!(value.to_string(), display);
+        }
+    }
+
+    #[test]
+    fn data_types_are_displayed() {
+        let cases = [
+            (ColumnDataType::Bool, "BOOL"),
+            (ColumnDataType::I8, "I8"),
+            (ColumnDataType::I16, "I16"),
+            (ColumnDataType::I32, "I32"),
+            (ColumnDataType::I64, "I64"),
+            (ColumnDataType::I128, "I128"),
+            (ColumnDataType::U8, "U8"),
+            (ColumnDataType::U16, "U16"),
+            (ColumnDataType::U32, "U32"),
+            (ColumnDataType::U64, "U64"),
+            (ColumnDataType::U128, "U128"),
+            (ColumnDataType::F32, "F32"),
+            (ColumnDataType::F64, "F64"),
+            (ColumnDataType::String, "STRING"),
+            (ColumnDataType::Bytes, "BYTES"),
+            (ColumnDataType::Timestamp, "TIMESTAMP"),
+            (ColumnDataType::Date, "DATE"),
+            (ColumnDataType::Duration, "DURATION"),
+            (ColumnDataType::Snowflake, "SNOWFLAKE"),
+            (ColumnDataType::Uuid, "UUID"),
+            (ColumnDataType::Array(Box::new(ColumnDataType::U8)), "U8[]"),
+            (
+                ColumnDataType::Array(Box::new(ColumnDataType::Array(Box::new(
+                    ColumnDataType::Json
+                )))),
+                "JSON[][]"
+            ),
+            (ColumnDataType::Json, "JSON"),
+            (ColumnDataType::Enum(String::from("Kind")), "Kind")
+        ];
+
+        for (data_type, display) in cases {
+            assert_eq!(data_type.to_string(), display);
+        }
+    }
+}
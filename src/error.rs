@@ -9,12 +9,31 @@
 #[allow(missing_docs)] // variants are pretty self explanatory?
 #[derive(Debug)]
 pub enum QlError {
+    ColumnCannotBeNull { name: String },
     ColumnDoesNotExist { name: String },
+    ColumnNotGrouped { name: String },
+    ColumnTypeMismatch { name: String },
+    DuplicateEnumLabel { label: String },
+    InvalidArgumentCount { function: String, count: usize },
+    InvalidConversion { value: String, target: String },
+    InvalidEnumValue { type_name: String, label: String },
+    InvalidJson { value: String },
+    InvalidUuid { value: String },
     NoFirstConstraintFoundBeforeAndOr,
+    NumericOverflow { target: String },
     PrimaryKeyAlreadySet,
+    PrimaryKeyDoesNotExist { table_name: String },
+    PrimaryKeyViolation { table_name: String, key: u64 },
     QueryError(QueryError),
+    RecursionLimitExceeded { name: String, limit: usize },
     RequiredFieldIsNone { field_name: String },
-    VecCannotBeEmpty { vec_name: String }
+    RowVersionConflict { key: u64, expected: u64, found: Option<u64> },
+    SavepointDoesNotExist { name: String },
+    SetOperationColumnCount { expected: usize, found: usize },
+    SubqueryColumnCount { count: usize },
+    SubqueryRowCount { count: usize },
+    VecCannotBeEmpty { vec_name: String },
+    WriteConflict { table_name: String, key: u64 }
 }
 
 /// # Enumeration `QueryError`
@@ -25,7 +44,8 @@ pub enum QlError {
 #[derive(Debug)]
 pub enum QueryError {
     RelationAlreadyExists { name: String },
-    RelationDoesNotExist { name: String }
+    RelationDoesNotExist { name: String },
+    RelationHasDependents { name: String, dependent: String }
 }
 
 /// # Typealias `QlResult`
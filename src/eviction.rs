@@ -0,0 +1,763 @@
+//! # Module `eviction`
+//!
+//! This module implements capacity limits for tables and for the whole cache, as well as the
+//! policies deciding which rows are evicted when a limit is exceeded.
+//!
+//! Limits are enforced whenever a row is written. Every table has an eviction policy, which is
+//! least recently used unless another one is set, and which chooses the rows evicted from the
+//! table. When the limits of a table are exceeded, rows are evicted from that table; when the
+//! limits of the whole cache are exceeded, each row is evicted from the table that uses the most
+//! of the exceeded limit, whichever table is being written to.
+//!
+//! Eviction respects [foreign keys](crate::ql::key::ForeignKey): when a row is evicted because a
+//! limit is exceeded, the rows that refer to it are evicted with it, so that no row is left
+//! referring to a row that was evicted.
+
+use std::{
+    collections::{
+        BTreeMap,
+        HashMap
+    },
+    sync::{
+        atomic::{
+            AtomicU64,
+            AtomicUsize,
+            Ordering
+        },
+        Mutex,
+        PoisonError,
+        RwLock
+    }
+};
+
+/// # Trait `EvictionPolicy`
+///
+/// A policy that decides which row of a table is evicted when a capacity limit is exceeded.
+pub trait EvictionPolicy: Send + Sync {
+    /// # Trait Method `EvictionPolicy::record_insert`
+    ///
+    /// Records that the row with the specified key has been inserted or replaced.
+    fn record_insert(&self, key: u64);
+
+    /// # Trait Method `EvictionPolicy::record_access`
+    ///
+    /// Records that the row with the specified key has been read, through a `SELECT` or a key
+    /// lookup.
+    fn record_access(&self, key: u64);
+
+    /// # Trait Method `EvictionPolicy::record_remove`
+    ///
+    /// Records that the row with the specified key has been removed.
+    fn record_remove(&self, key: u64);
+
+    /// # Trait Method `EvictionPolicy::victim`
+    ///
+    /// Chooses the row to evict and stops tracking it, returns `None` if no row is tracked.
+    fn victim(&self) -> Option<u64>;
+}
+
+/// # Struct `LruPolicy`
+///
+/// Evicts the least recently used row.
+#[derive(Default)]
+pub struct LruPolicy {
+    pub(crate) keys: Mutex<RankedKeys>
+}
+
+impl EvictionPolicy for LruPolicy {
+    fn record_insert(&self, key: u64) {
+        self.record_access(key);
+    }
+
+    fn record_access(&self, key: u64) {
+        let mut keys = self.keys.lock().unwrap_or_else(PoisonError::into_inner);
+        keys.insert(key, 0);
+    }
+
+    fn record_remove(&self, key: u64) {
+        let mut keys = self.keys.lock().unwrap_or_else(PoisonError::into_inner);
+        keys.remove(key);
+    }
+
+    fn victim(&self) -> Option<u64> {
+        let mut keys = self.keys.lock().unwrap_or_else(PoisonError::into_inner);
+        keys.pop_first()
+    }
+}
+
+/// # Struct `LfuPolicy`
+///
+/// Evicts the least frequently used row, or the least recently used one among the rows that are
+/// used equally frequently.
+#[derive(Default)]
+pub struct LfuPolicy {
+    pub(crate) keys: Mutex<RankedKeys>
+}
+
+impl EvictionPolicy for LfuPolicy {
+    fn record_insert(&self, key: u64) {
+        self.record_access(key);
+    }
+
+    fn record_access(&self, key: u64) {
+        let mut keys = self.keys.lock().unwrap_or_else(PoisonError::into_inner);
+        let uses = keys.rank(key).map_or(1, |uses| uses.saturating_add(1));
+        keys.insert(key, uses);
+    }
+
+    fn record_remove(&self, key: u64) {
+        let mut keys = self.keys.lock().unwrap_or_else(PoisonError::into_inner);
+        keys.remove(key);
+    }
+
+    fn victim(&self) -> Option<u64> {
+        let mut keys = self.keys.lock().unwrap_or_else(PoisonError::into_inner);
+        keys.pop_first()
+    }
+}
+
+/// # Struct `FifoPolicy`
+///
+/// Evicts the row that was inserted first, regardless of how it is used afterwards.
+#[derive(Default)]
+pub struct FifoPolicy {
+    pub(crate) keys: Mutex<RankedKeys>
+}
+
+impl EvictionPolicy for FifoPolicy {
+    fn record_insert(&self, key: u64) {
+        let mut keys = self.keys.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if keys.rank(key).is_none() {
+            keys.insert(key, 0);
+        }
+    }
+
+    fn record_access(&self, _: u64) {}
+
+    fn record_remove(&self, key: u64) {
+        let mut keys = self.keys.lock().unwrap_or_else(PoisonError::into_inner);
+        keys.remove(key);
+    }
+
+    fn victim(&self) -> Option<u64> {
+        let mut keys = self.keys.lock().unwrap_or_else(PoisonError::into_inner);
+        keys.pop_first()
+    }
+}
+
+/// Keys ordered by a rank, and then by the order in which they were last ranked.
+#[derive(Default)]
+pub(crate) struct RankedKeys {
+    pub(crate) order: BTreeMap<(u64, u64), u64>,
+    pub(crate) ranks: HashMap<u64, (u64, u64)>,
+    pub(crate) sequence: u64
+}
+
+impl RankedKeys {
+    /// Returns the rank of the specified key, if it is tracked.
+    pub(crate) fn rank(&self, key: u64) -> Option<u64> {
+        self.ranks.get(&key).map(|(rank, _)| *rank)
+    }
+
+    /// Tracks the specified key with the specified rank, after every other key of the same rank.
+    pub(crate) fn insert(&mut self, key: u64, rank: u64) {
+        self.remove(key);
+        self.sequence += 1;
+
+        self.order.insert((rank, self.sequence), key);
+        self.ranks.insert(key, (rank, self.sequence));
+    }
+
+    /// Stops tracking the specified key.
+    pub(crate) fn remove(&mut self, key: u64) {
+        if let Some(position) = self.ranks.remove(&key) {
+            self.order.remove(&position);
+        }
+    }
+
+    /// Stops tracking the key with the lowest rank and returns it.
+    pub(crate) fn pop_first(&mut self) -> Option<u64> {
+        let position = *self.order.keys().next()?;
+        let key = self.order.remove(&position)?;
+        self.ranks.remove(&key);
+
+        Some(key)
+    }
+}
+
+/// # Struct `CapacityLimits`
+///
+/// The capacity limits of a table or of the whole cache, a limit of `None` means unlimited.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CapacityLimits {
+    /// # Struct Field `max_rows`
+    ///
+    /// The maximum number of rows.
+    pub max_rows: Option<usize>,
+
+    /// # Struct Field `max_bytes`
+    ///
+    /// The maximum approximate size of the rows, in bytes.
+    pub max_bytes: Option<usize>
+}
+
+impl CapacityLimits {
+    /// # Instance Method `CapacityLimits::exceeded_by`
+    ///
+    /// Returns the reason of the eviction if the specified usage exceeds these limits.
+    ///
+    /// ## Parameters
+    /// - `rows`, type `usize`; the number of rows
+    /// - `bytes`, type `usize`; the approximate size of the rows
+    #[must_use]
+    pub fn exceeded_by(&self, rows: usize, bytes: usize) -> Option<CapacityLimit> {
+        if matches!(self.max_rows, Some(max_rows) if rows > max_rows) {
+            Some(CapacityLimit::Rows)
+        }
+        else if matches!(self.max_bytes, Some(max_bytes) if bytes > max_bytes) {
+            Some(CapacityLimit::Bytes)
+        }
+        else {
+            None
+        }
+    }
+}
+
+/// # Enumeration `CapacityLimit`
+///
+/// A kind of capacity limit.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum CapacityLimit {
+    /// # Enumeration Variant `CapacityLimit::Rows`
+    ///
+    /// The limit on the number of rows.
+    Rows,
+
+    /// # Enumeration Variant `CapacityLimit::Bytes`
+    ///
+    /// The limit on the approximate size of the rows.
+    Bytes
+}
+
+/// # Enumeration `EvictionReason`
+///
+/// The reason why a row was evicted.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum EvictionReason {
+    /// # Enumeration Variant `EvictionReason::TableLimit`
+    ///
+    /// A capacity limit of the table was exceeded.
+    TableLimit(CapacityLimit),
+
+    /// # Enumeration Variant `EvictionReason::CacheLimit`
+    ///
+    /// A capacity limit of the whole cache was exceeded.
+    CacheLimit(CapacityLimit),
+
+    /// # Enumeration Variant `EvictionReason::ForeignKey`
+    ///
+    /// The row referred to a row that was evicted, by a foreign key.
+    ForeignKey,
+
+    /// # Enumeration Variant `EvictionReason::Expired`
+    ///
+    /// The time-to-live of the row has passed.
+    Expired
+}
+
+/// # Struct `EvictionStatistics`
+///
+/// The number of rows evicted, by reason.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct EvictionStatistics {
+    /// # Struct Field `table_row_limit`
+    ///
+    /// The number of rows evicted because the row limit of the table was exceeded.
+    pub table_row_limit: u64,
+
+    /// # Struct Field `table_byte_limit`
+    ///
+    /// The number of rows evicted because the byte limit of the table was exceeded.
+    pub table_byte_limit: u64,
+
+    /// # Struct Field `cache_row_limit`
+    ///
+    /// The number of rows evicted because the row limit of the cache was exceeded.
+    pub cache_row_limit: u64,
+
+    /// # Struct Field `cache_byte_limit`
+    ///
+    /// The number of rows evicted because the byte limit of the cache was exceeded.
+    pub cache_byte_limit: u64,
+
+    /// # Struct Field `foreign_key`
+    ///
+    /// The number of rows evicted because they referred to a row that was evicted.
+    pub foreign_key: u64,
+
+    /// # Struct Field `expired`
+    ///
+    /// The number of rows removed because they expired.
+    pub expired: u64
+}
+
+impl EvictionStatistics {
+    /// # Instance Method `EvictionStatistics::evicted`
+    ///
+    /// Returns the number of rows evicted for the specified reason.
+    ///
+    /// ## Parameters
+    /// - `reason`, type `EvictionReason`; the reason of the evictions
+    #[must_use]
+    pub fn evicted(&self, reason: EvictionReason) -> u64 {
+        match reason {
+            EvictionReason::TableLimit(CapacityLimit::Rows) => self.table_row_limit,
+            EvictionReason::TableLimit(CapacityLimit::Bytes) => self.table_byte_limit,
+            EvictionReason::CacheLimit(CapacityLimit::Rows) => self.cache_row_limit,
+            EvictionReason::CacheLimit(CapacityLimit::Bytes) => self.cache_byte_limit,
+            EvictionReason::ForeignKey => self.foreign_key,
+            EvictionReason::Expired => self.expired
+        }
+    }
+
+    /// # Instance Method `EvictionStatistics::total`
+    ///
+    /// Returns the number of rows evicted for any reason.
+    #[must_use]
+    pub fn total(&self) -> u64 {
+        self.table_row_limit
+            + self.table_byte_limit
+            + self.cache_row_limit
+            + self.cache_byte_limit
+            + self.foreign_key
+            + self.expired
+    }
+}
+
+/// # Struct `Usage`
+///
+/// The number and approximate size of the live rows of a table or of the whole cache, as well as
+/// the number of rows evicted from it.
+#[derive(Default)]
+pub struct Usage {
+    pub(crate) rows: AtomicUsize,
+    pub(crate) bytes: AtomicUsize,
+    pub(crate) evicted: [AtomicU64; 6]
+}
+
+impl Usage {
+    /// # Instance Method `Usage::rows`
+    ///
+    /// Returns the number of live rows.
+    #[must_use]
+    pub fn rows(&self) -> usize {
+        self.rows.load(Ordering::Acquire)
+    }
+
+    /// # Instance Method `Usage::bytes`
+    ///
+    /// Returns the approximate size of the live rows, in bytes.
+    #[must_use]
+    pub fn bytes(&self) -> usize {
+        self.bytes.load(Ordering::Acquire)
+    }
+
+    /// # Instance Method `Usage::statistics`
+    ///
+    /// Returns the number of rows evicted, by reason.
+    #[must_use]
+    pub fn statistics(&self) -> EvictionStatistics {
+        let [
+            table_row_limit,
+            table_byte_limit,
+            cache_row_limit,
+            cache_byte_limit,
+            foreign_key,
+            expired
+        ] = &self.evicted;
+
+        EvictionStatistics {
+            table_row_limit: table_row_limit.load(Ordering::Relaxed),
+            table_byte_limit: table_byte_limit.load(Ordering::Relaxed),
+            cache_row_limit: cache_row_limit.load(Ordering::Relaxed),
+            cache_byte_limit: cache_byte_limit.load(Ordering::Relaxed),
+            foreign_key: foreign_key.load(Ordering::Relaxed),
+            expired: expired.load(Ordering::Relaxed)
+        }
+    }
+
+    pub(crate) fn add(&self, bytes: usize) {
+        self.rows.fetch_add(1, Ordering::AcqRel);
+        self.bytes.fetch_add(bytes, Ordering::AcqRel);
+    }
+
+    pub(crate) fn subtract(&self, bytes: usize) {
+        self.rows.fetch_sub(1, Ordering::AcqRel);
+        self.bytes.fetch_sub(bytes, Ordering::AcqRel);
+    }
+
+    pub(crate) fn record_eviction(&self, reason: EvictionReason) {
+        let index = match reason {
+            EvictionReason::TableLimit(CapacityLimit::Rows) => 0,
+            EvictionReason::TableLimit(CapacityLimit::Bytes) => 1,
+            EvictionReason::CacheLimit(CapacityLimit::Rows) => 2,
+            EvictionReason::CacheLimit(CapacityLimit::Bytes) => 3,
+            EvictionReason::ForeignKey => 4,
+            EvictionReason::Expired => 5
+        };
+
+        self.evicted[index].fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl Clone for Usage {
+    fn clone(&self) -> Self {
+        let usage = Self::default();
+        usage.rows.store(self.rows(), Ordering::Release);
+        usage.bytes.store(self.bytes(), Ordering::Release);
+        usage
+            .evicted
+            .iter()
+            .zip(&self.evicted)
+            .for_each(|(count, other)| {
+                count.store(other.load(Ordering::Relaxed), Ordering::Relaxed);
+            });
+
+        usage
+    }
+}
+
+/// # Struct `CacheCapacity`
+///
+/// The capacity limits and usage of the whole cache, shared by its tables.
+#[derive(Default)]
+pub struct CacheCapacity {
+    pub(crate) limits: RwLock<CapacityLimits>,
+    pub(crate) usage: Usage
+}
+
+impl CacheCapacity {
+    /// # Instance Method `CacheCapacity::limits`
+    ///
+    /// Returns the capacity limits of the whole cache.
+    #[must_use]
+    pub fn limits(&self) -> CapacityLimits {
+        *self.limits.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// # Instance Method `CacheCapacity::usage`
+    ///
+    /// Returns the usage of the whole cache.
+    #[must_use]
+    pub fn usage(&self) -> &Usage {
+        &self.usage
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        CacheCapacity,
+        CapacityLimit,
+        CapacityLimits,
+        EvictionPolicy,
+        EvictionReason,
+        EvictionStatistics,
+        FifoPolicy,
+        LfuPolicy,
+        LruPolicy,
+        Usage
+    };
+    use crate::{
+        error::QlError,
+        ql::{
+            create::table::CreateTableBuilder,
+            key::{
+                ForeignKey,
+                PrimaryKey
+            },
+            QueryBuilder
+        },
+        testing,
+        ColumnDataType,
+        ColumnValue,
+        QlCache
+    };
+
+    static_assertions::assert_impl_all!(CacheCapacity: Default, Send, Sync);
+    static_assertions::assert_impl_all!(CapacityLimit: Clone, Copy, Eq, PartialEq, Send, Sync);
+    static_assertions::assert_impl_all!(CapacityLimits: Clone, Copy, Default, Send, Sync);
+    static_assertions::assert_impl_all!(EvictionReason: Clone, Copy, Eq, PartialEq, Send, Sync);
+    static_assertions::assert_impl_all!(EvictionStatistics: Clone, Copy, Default, Send, Sync);
+    static_assertions::assert_impl_all!(FifoPolicy: EvictionPolicy, Default, Send, Sync);
+    static_assertions::assert_impl_all!(LfuPolicy: EvictionPolicy, Default, Send, Sync);
+    static_assertions::assert_impl_all!(LruPolicy: EvictionPolicy, Default, Send, Sync);
+    static_assertions::assert_impl_all!(Usage: Clone, Default, Send, Sync);
+
+    #[test]
+    fn lru_evicts_least_recently_used() {
+        let policy = LruPolicy::default();
+        policy.record_insert(1);
+        policy.record_insert(2);
+        policy.record_insert(3);
+        policy.record_access(1);
+
+        assert_eq!(policy.victim(), Some(2));
+        assert_eq!(policy.victim(), Some(3));
+        assert_eq!(policy.victim(), Some(1));
+        assert_eq!(policy.victim(), None);
+    }
+
+    #[test]
+    fn lfu_evicts_least_frequently_used() {
+        let policy = LfuPolicy::default();
+        policy.record_insert(1);
+        policy.record_insert(2);
+        policy.record_insert(3);
+        policy.record_access(1);
+        policy.record_access(3);
+        policy.record_access(3);
+
+        // 2 is used once, 1 twice and 3 three times
+        assert_eq!(policy.victim(), Some(2));
+        assert_eq!(policy.victim(), Some(1));
+        assert_eq!(policy.victim(), Some(3));
+    }
+
+    #[test]
+    fn fifo_ignores_accesses_and_replacements() {
+        let policy = FifoPolicy::default();
+        policy.record_insert(1);
+        policy.record_insert(2);
+        policy.record_access(1);
+        policy.record_insert(1);
+
+        assert_eq!(policy.victim(), Some(1));
+        assert_eq!(policy.victim(), Some(2));
+    }
+
+    #[test]
+    fn removed_keys_are_never_victims() {
+        let policy = LruPolicy::default();
+        policy.record_insert(1);
+        policy.record_insert(2);
+        policy.record_remove(1);
+
+        assert_eq!(policy.victim(), Some(2));
+        assert_eq!(policy.victim(), None);
+    }
+
+    #[test]
+    fn limits_are_exceeded_by_rows_before_bytes() {
+        let limits = CapacityLimits {
+            max_rows: Some(2),
+            max_bytes: Some(100)
+        };
+
+        assert_eq!(limits.exceeded_by(2, 100), None);
+        assert_eq!(limits.exceeded_by(3, 200), Some(CapacityLimit::Rows));
+        assert_eq!(limits.exceeded_by(2, 101), Some(CapacityLimit::Bytes));
+        assert_eq!(CapacityLimits::default().exceeded_by(usize::MAX, usize::MAX), None);
+    }
+
+    #[test]
+    fn statistics_count_evictions_by_reason() {
+        let usage = Usage::default();
+        usage.record_eviction(EvictionReason::TableLimit(CapacityLimit::Rows));
+        usage.record_eviction(EvictionReason::Expired);
+        usage.record_eviction(EvictionReason::Expired);
+
+        let statistics = usage.statistics();
+        assert_eq!(statistics.evicted(EvictionReason::Expired), 2);
+        assert_eq!(statistics.table_row_limit, 1);
+        assert_eq!(statistics.total(), 3);
+    }
+
+    #[test]
+    fn tables_evict_on_write_past_their_limits() {
+        let cache = QlCache::new();
+        QueryBuilder::create()
+            .table()
+            .name(String::from("Members"))
+            .columns(vec![(String::from("Xp"), (ColumnDataType::U64, false))])
+            .limits(CapacityLimits {
+                max_rows: Some(2),
+                max_bytes: None
+            })
+            .build()
+            .unwrap()
+            .execute(&cache)
+            .unwrap();
+
+        cache
+            .with_table("Members", |table| {
+                for key in 1..=2 {
+                    table.insert_row(key, testing::row([("Xp", ColumnValue::U64(key))])).unwrap();
+                }
+
+                // reading the first row makes the second the least recently used
+                assert!(table.row(1).is_some());
+                table.insert_row(3, testing::row([("Xp", ColumnValue::U64(3))])).unwrap();
+
+                assert!(table.row(1).is_some());
+                assert!(table.row(2).is_none());
+                assert!(table.row(3).is_some());
+                assert_eq!(table.usage().rows(), 2);
+                assert_eq!(table.usage().statistics().table_row_limit, 1);
+            })
+            .unwrap();
+
+        assert_eq!(cache.eviction_statistics().table_row_limit, 1);
+    }
+
+    fn guilds_and_members(
+        cache: &QlCache,
+        guilds: impl FnOnce(CreateTableBuilder) -> CreateTableBuilder
+    ) {
+        guilds(QueryBuilder::create().table())
+            .name(String::from("Guilds"))
+            .columns(vec![(String::from("Id"), (ColumnDataType::U64, false))])
+            .primary_key(PrimaryKey::new(String::from("Id")))
+            .unwrap()
+            .build()
+            .unwrap()
+            .execute(cache)
+            .unwrap();
+
+        QueryBuilder::create()
+            .table()
+            .name(String::from("Members"))
+            .columns(vec![
+                (String::from("Id"), (ColumnDataType::U64, false)),
+                (String::from("GuildId"), (ColumnDataType::U64, true)),
+            ])
+            .foreign_key(ForeignKey::new(String::from("GuildId"), String::from("Guilds")))
+            .unwrap()
+            .build()
+            .unwrap()
+            .execute(cache)
+            .unwrap();
+    }
+
+    fn insert(cache: &QlCache, table_name: &str, key: u64, column_values: &[(&str, u64)]) {
+        cache
+            .with_table(table_name, |table| {
+                let values = column_values
+                    .iter()
+                    .map(|(name, value)| (*name, ColumnValue::U64(*value)));
+                table.insert_row(key, testing::row(values))
+            })
+            .unwrap()
+            .unwrap();
+    }
+
+    fn keys(cache: &QlCache, table_name: &str) -> Vec<u64> {
+        cache
+            .with_table(table_name, |table| {
+                (1..=10)
+                    .filter(|key| table.row(*key).is_some())
+                    .collect()
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn cache_limits_evict_from_the_largest_table() {
+        let cache = QlCache::new();
+        guilds_and_members(&cache, |guilds| guilds);
+        cache.set_limits(CapacityLimits {
+            max_rows: Some(4),
+            max_bytes: None
+        });
+
+        for key in 1..=3 {
+            insert(&cache, "Guilds", key, &[("Id", key)]);
+        }
+
+        // the guilds are the largest table when the limit is first exceeded, even though the
+        // members are written to
+        insert(&cache, "Members", 1, &[("Id", 1)]);
+        insert(&cache, "Members", 2, &[("Id", 2)]);
+        assert_eq!(keys(&cache, "Guilds"), vec![2, 3]);
+
+        // then the members are, and the member that was just written is kept
+        insert(&cache, "Members", 3, &[("Id", 3)]);
+        assert_eq!(keys(&cache, "Members"), vec![2, 3]);
+        assert_eq!(cache.capacity().usage.rows(), 4);
+        assert_eq!(cache.eviction_statistics().cache_row_limit, 2);
+    }
+
+    #[test]
+    fn tables_without_a_policy_are_evicted_from_by_recency() {
+        let cache = QlCache::new();
+        guilds_and_members(&cache, |guilds| guilds.eviction_policy(FifoPolicy::default()));
+        cache.set_limits(CapacityLimits {
+            max_rows: Some(4),
+            max_bytes: None
+        });
+
+        insert(&cache, "Guilds", 1, &[("Id", 1)]);
+        insert(&cache, "Guilds", 2, &[("Id", 2)]);
+
+        // the members have no policy of their own, they neither grow past the limit nor empty the
+        // guilds
+        for key in 1..=5 {
+            insert(&cache, "Members", key, &[("Id", key)]);
+        }
+
+        assert_eq!(keys(&cache, "Guilds"), vec![1, 2]);
+        assert_eq!(keys(&cache, "Members"), vec![4, 5]);
+        assert_eq!(cache.eviction_statistics().cache_row_limit, 3);
+    }
+
+    #[test]
+    fn rows_referring_to_evicted_rows_are_evicted() {
+        let cache = QlCache::new();
+        guilds_and_members(&cache, |guilds| {
+            guilds.limits(CapacityLimits {
+                max_rows: Some(1),
+                max_bytes: None
+            })
+        });
+
+        insert(&cache, "Guilds", 1, &[("Id", 1)]);
+        insert(&cache, "Members", 1, &[("Id", 1), ("GuildId", 1)]);
+        insert(&cache, "Members", 2, &[("Id", 2), ("GuildId", 1)]);
+        insert(&cache, "Members", 3, &[("Id", 3)]);
+        insert(&cache, "Guilds", 2, &[("Id", 2)]);
+
+        assert_eq!(keys(&cache, "Guilds"), vec![2]);
+        assert_eq!(keys(&cache, "Members"), vec![3]);
+        assert_eq!(cache.eviction_statistics().table_row_limit, 1);
+        assert_eq!(cache.eviction_statistics().foreign_key, 2);
+    }
+
+    #[test]
+    fn foreign_keys_must_refer_to_primary_keys_of_the_same_type() {
+        let cache = QlCache::new();
+        guilds_and_members(&cache, |guilds| guilds);
+
+        let create = |data_type, table_name: &str| {
+            QueryBuilder::create()
+                .table()
+                .name(String::from("Roles"))
+                .columns(vec![(String::from("GuildId"), (data_type, false))])
+                .foreign_key(ForeignKey::new(String::from("GuildId"), table_name.to_string()))
+                .unwrap()
+                .build()
+                .unwrap()
+                .execute(&cache)
+        };
+
+        assert!(matches!(
+            create(ColumnDataType::String, "Guilds"),
+            Err(QlError::ColumnTypeMismatch { .. })
+        ));
+        assert!(matches!(
+            create(ColumnDataType::U64, "Members"),
+            Err(QlError::PrimaryKeyDoesNotExist { .. })
+        ));
+        assert!(create(ColumnDataType::U64, "Guilds").is_ok());
+    }
+}
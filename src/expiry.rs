@@ -0,0 +1,304 @@
+//! # Module `expiry`
+//!
+//! This module implements the expiry of rows with a time-to-live.
+//!
+//! Expired rows are invisible to reads as soon as they expire. They are reclaimed by an amortised
+//! sweeper, which removes a bounded number of expired rows whenever a table is written to, or by
+//! [`QlCache::sweep_expired`](crate::QlCache::sweep_expired), which can be called periodically
+//! from a background task.
+
+use std::{
+    cmp::Reverse,
+    collections::{
+        BinaryHeap,
+        HashMap
+    },
+    sync::{
+        Mutex,
+        PoisonError
+    },
+    time::SystemTime
+};
+
+/// The maximum number of expired rows that are reclaimed per write.
+pub(crate) const SWEEP_BATCH_SIZE: usize = 16;
+
+/// # Struct `ExpiryQueue`
+///
+/// The keys of the rows of a table that have a time-to-live, ordered by their expiry time.
+#[derive(Default)]
+pub struct ExpiryQueue {
+    pub(crate) queue: Mutex<Schedule>
+}
+
+/// The heap of scheduled expiry times, and the current expiry time of every key. Entries of the
+/// heap whose expiry time is no longer the current one of their key are stale, they are skipped
+/// when popped and dropped when they outnumber the current ones.
+#[derive(Clone, Default)]
+pub(crate) struct Schedule {
+    heap: BinaryHeap<Reverse<(SystemTime, u64)>>,
+    deadlines: HashMap<u64, SystemTime>
+}
+
+impl Schedule {
+    fn is_current(&self, expires_at: SystemTime, key: u64) -> bool {
+        self.deadlines.get(&key) == Some(&expires_at)
+    }
+
+    fn compact(&mut self) {
+        if self.heap.len() > 2 * self.deadlines.len().max(SWEEP_BATCH_SIZE) {
+            self.heap = self
+                .deadlines
+                .iter()
+                .map(|(key, expires_at)| Reverse((*expires_at, *key)))
+                .collect();
+        }
+    }
+}
+
+impl ExpiryQueue {
+    /// # Instance Method `ExpiryQueue::len`
+    ///
+    /// Returns the number of rows that are scheduled to expire.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.queue
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .deadlines
+            .len()
+    }
+
+    /// # Instance Method `ExpiryQueue::is_empty`
+    ///
+    /// Returns whether the queue is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Schedules the row with the specified key to be checked at `expires_at`, replacing its
+    /// previous expiry time, or unschedules it if `expires_at` is `None`.
+    pub(crate) fn schedule(&self, key: u64, expires_at: Option<SystemTime>) {
+        let mut queue = self.queue.lock().unwrap_or_else(PoisonError::into_inner);
+
+        match expires_at {
+            Some(expires_at) => {
+                if queue.deadlines.insert(key, expires_at) != Some(expires_at) {
+                    queue.heap.push(Reverse((expires_at, key)));
+                }
+            }
+            None => {
+                queue.deadlines.remove(&key);
+            }
+        }
+
+        queue.compact();
+    }
+
+    /// Removes and returns at most `limit` keys that were scheduled to expire at or before `now`.
+    pub(crate) fn pop_expired(&self, now: SystemTime, limit: usize) -> Vec<u64> {
+        let mut queue = self.queue.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut keys = Vec::new();
+
+        while keys.len() < limit {
+            match queue.heap.peek() {
+                Some(Reverse((expires_at, _))) if *expires_at <= now => {
+                    let Reverse((expires_at, key)) = queue.heap.pop().unwrap();
+                    if queue.is_current(expires_at, key) {
+                        queue.deadlines.remove(&key);
+                        keys.push(key);
+                    }
+                }
+                _ => break
+            }
+        }
+
+        keys
+    }
+}
+
+impl Clone for ExpiryQueue {
+    fn clone(&self) -> Self {
+        Self {
+            queue: Mutex::new(
+                self.queue
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .clone()
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            Arc,
+            PoisonError
+        },
+        time::{
+            Duration,
+            SystemTime
+        }
+    };
+
+    use super::{
+        ExpiryQueue,
+        SWEEP_BATCH_SIZE
+    };
+    use crate::{
+        clock::ManualClock,
+        ql::{
+            select::SelectScope,
+            QueryBuilder
+        },
+        testing,
+        CacheTableRow,
+        ColumnDataType,
+        ColumnValue,
+        QlCache
+    };
+
+    static_assertions::assert_impl_all!(ExpiryQueue: Clone, Default, Send, Sync);
+
+    fn cache(ttl: Duration) -> (QlCache, Arc<ManualClock>) {
+        let clock = Arc::new(ManualClock::new(SystemTime::UNIX_EPOCH));
+        let cache = QlCache::with_clock(clock.clone());
+        QueryBuilder::create()
+            .table()
+            .name(String::from("Sessions"))
+            .columns(vec![(String::from("UserId"), (ColumnDataType::U64, false))])
+            .ttl(ttl)
+            .build()
+            .unwrap()
+            .execute(&cache)
+            .unwrap();
+
+        (cache, clock)
+    }
+
+    fn row(user_id: u64) -> CacheTableRow {
+        testing::row([("UserId", ColumnValue::U64(user_id))])
+    }
+
+    fn selected(cache: &QlCache) -> usize {
+        QueryBuilder::select()
+            .table_name(String::from("Sessions"))
+            .scope(SelectScope::Everything)
+            .build()
+            .unwrap()
+            .execute(cache)
+            .unwrap()
+            .len()
+    }
+
+    #[test]
+    fn keys_are_popped_in_expiry_order_up_to_the_limit() {
+        let queue = ExpiryQueue::default();
+        let at = |seconds| SystemTime::UNIX_EPOCH + Duration::from_secs(seconds);
+        for (seconds, key) in [(30, 3), (10, 1), (20, 2), (40, 4)] {
+            queue.schedule(key, Some(at(seconds)));
+        }
+
+        assert_eq!(queue.pop_expired(at(5), usize::MAX), Vec::<u64>::new());
+        assert_eq!(queue.pop_expired(at(30), 2), vec![1, 2]);
+        assert_eq!(queue.pop_expired(at(30), usize::MAX), vec![3]);
+        assert_eq!(queue.len(), 1);
+    }
+
+    #[test]
+    fn rescheduled_keys_replace_their_previous_expiry_time() {
+        let queue = ExpiryQueue::default();
+        let at = |seconds| SystemTime::UNIX_EPOCH + Duration::from_secs(seconds);
+        for seconds in 1..=1000 {
+            queue.schedule(1, Some(at(seconds)));
+        }
+        queue.schedule(2, Some(at(10)));
+        queue.schedule(2, None);
+
+        assert_eq!(queue.len(), 1);
+        assert!(
+            queue
+                .queue
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .heap
+                .len()
+                <= 2 * SWEEP_BATCH_SIZE
+        );
+        assert_eq!(queue.pop_expired(at(999), usize::MAX), Vec::<u64>::new());
+        assert_eq!(queue.pop_expired(at(1000), usize::MAX), vec![1]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn rows_are_invisible_from_the_time_they_expire() {
+        let (cache, clock) = cache(Duration::from_secs(10));
+        cache
+            .with_table("Sessions", |table| {
+                table.insert_row(1, row(1)).unwrap();
+                table.insert_row_with_ttl(2, row(2), Duration::from_secs(20)).unwrap();
+            })
+            .unwrap();
+
+        clock.advance(Duration::from_secs(9));
+        assert_eq!(selected(&cache), 2);
+
+        // the row expires exactly at its expiry time, but is only reclaimed when swept
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(selected(&cache), 1);
+        cache
+            .with_table("Sessions", |table| {
+                assert!(table.row(1).is_none());
+                assert!(table.row_version(1).is_none());
+                assert!(table.rows.contains_key(&1));
+            })
+            .unwrap();
+        assert_eq!(cache.sweep_expired(), 1);
+
+        // updates keep the expiry time of the row unless they set a time-to-live
+        let update = |ttl: Option<Duration>| {
+            let mut builder = QueryBuilder::update()
+                .table_name(String::from("Sessions"))
+                .key(2)
+                .set(String::from("UserId"), ColumnValue::U64(3));
+            if let Some(ttl) = ttl {
+                builder = builder.ttl(ttl);
+            }
+            builder.build().unwrap().execute(&cache).unwrap();
+        };
+        update(None);
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(selected(&cache), 0);
+
+        cache
+            .with_table("Sessions", |table| table.insert_row(2, row(2)).unwrap())
+            .unwrap();
+        update(Some(Duration::from_secs(40)));
+        clock.advance(Duration::from_secs(30));
+        assert_eq!(selected(&cache), 1);
+    }
+
+    #[test]
+    fn writes_reclaim_a_bounded_number_of_expired_rows() {
+        let (cache, clock) = cache(Duration::from_secs(1));
+        let rows = u64::try_from(SWEEP_BATCH_SIZE).unwrap() + 4;
+
+        cache
+            .with_table("Sessions", |table| {
+                for key in 0..rows {
+                    table.insert_row(key, row(key)).unwrap();
+                }
+                clock.advance(Duration::from_secs(1));
+
+                let live = || table.rows.iter().filter(|chain| chain.latest().is_some()).count();
+                table.insert_row(rows, row(rows)).unwrap();
+                assert_eq!(live(), 5);
+                table.insert_row(rows, row(rows)).unwrap();
+                assert_eq!(live(), 1);
+            })
+            .unwrap();
+    }
+}
@@ -2,22 +2,85 @@
 //!
 //! This module implements the querying from the cache.
 
+use std::{
+    cmp::Ordering,
+    collections::HashSet,
+    fmt::{
+        Display,
+        Formatter,
+        Result as FmtResult
+    },
+    hash::{
+        Hash,
+        Hasher
+    },
+    mem,
+    ptr,
+    sync::{
+        Arc,
+        Mutex,
+        MutexGuard,
+        PoisonError
+    },
+    time::{
+        Duration,
+        SystemTime
+    }
+};
+
 use dashmap::DashMap;
 
 use crate::{
-    error::QlResult,
+    clock::{
+        Clock,
+        SystemClock
+    },
+    error::{
+        QlError,
+        QlResult,
+        QueryError
+    },
+    eviction::{
+        CacheCapacity,
+        CapacityLimit,
+        CapacityLimits,
+        EvictionReason,
+        EvictionStatistics,
+        Usage
+    },
+    expiry::SWEEP_BATCH_SIZE,
+    mvcc::{
+        RowVersion,
+        Snapshot,
+        VersionChain,
+        VersionClock
+    },
+    numeric::{
+        self,
+        Number
+    },
     ql::{
+        function::UserFunction,
+        materialized::MaterializedView,
+        view::View,
         Query,
         QueryAs,
         QueryAsType,
         QueryRow
     },
+    transaction::Transaction,
+    types::EnumType,
     CacheSchema,
+    CacheTable,
     CacheTableRow,
+    ColumnDataType,
+    ColumnValue,
     FromRow,
     QlCache
 };
 
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
 impl QlCache {
     /// # Constructor `QlCache::new`
     ///
@@ -32,16 +95,162 @@ impl QlCache {
     /// ```
     #[must_use]
     pub fn new() -> Self {
+        Self::with_clock(Arc::new(SystemClock))
+    }
+
+    /// # Constructor `QlCache::with_clock`
+    ///
+    /// Creates a new `QlCache` that takes the current time from the specified clock.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use std::{
+    ///     sync::Arc,
+    ///     time::SystemTime
+    /// };
+    ///
+    /// use qlcache::{
+    ///     clock::ManualClock,
+    ///     QlCache
+    /// };
+    ///
+    /// let cache = QlCache::with_clock(Arc::new(ManualClock::new(SystemTime::UNIX_EPOCH)));
+    /// ```
+    #[must_use]
+    pub fn with_clock(time: Arc<dyn Clock>) -> Self {
         let dashmap = DashMap::new();
         let default_schema = String::from("PUBLIC");
 
         dashmap.insert(default_schema.clone(), CacheSchema::new(default_schema));
 
         Self {
-            cache: dashmap
+            cache: Arc::new(dashmap),
+            clock: Arc::new(VersionClock::default()),
+            time,
+            capacity: Arc::new(CacheCapacity::default()),
+            functions: DashMap::new(),
+            definitions: Arc::new(Mutex::new(()))
+        }
+    }
+
+    /// # Instance Method `QlCache::set_limits`
+    ///
+    /// Sets the capacity limits of the whole cache. When a limit is exceeded, rows are evicted from
+    /// the table that uses the most of it, according to the eviction policy of that table.
+    ///
+    /// ## Parameters
+    /// - `limits`, type `CapacityLimits`; the capacity limits to set
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use qlcache::{
+    ///     eviction::CapacityLimits,
+    ///     QlCache
+    /// };
+    ///
+    /// let cache = QlCache::new();
+    /// cache.set_limits(CapacityLimits {
+    ///     max_rows: Some(10_000),
+    ///     max_bytes: Some(64 * 1024 * 1024)
+    /// });
+    /// ```
+    pub fn set_limits(&self, limits: CapacityLimits) {
+        *self
+            .capacity
+            .limits
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = limits;
+    }
+
+    /// # Instance Method `QlCache::capacity`
+    ///
+    /// Returns the capacity limits and the usage of the whole cache.
+    #[must_use]
+    pub fn capacity(&self) -> &CacheCapacity {
+        &self.capacity
+    }
+
+    /// # Instance Method `QlCache::eviction_statistics`
+    ///
+    /// Returns the number of rows evicted from the whole cache, by reason.
+    #[must_use]
+    pub fn eviction_statistics(&self) -> EvictionStatistics {
+        self.capacity.usage.statistics()
+    }
+
+    /// # Instance Method `QlCache::snapshot`
+    ///
+    /// Takes a consistent snapshot of the cache, that queries can be run against while writers
+    /// proceed.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use qlcache::QlCache;
+    ///
+    /// let cache = QlCache::new();
+    /// let snapshot = cache.snapshot();
+    /// ```
+    #[must_use]
+    pub fn snapshot(&self) -> Snapshot {
+        self.clock.snapshot()
+    }
+
+    /// # Instance Method `QlCache::begin`
+    ///
+    /// Begins a transaction.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use qlcache::QlCache;
+    ///
+    /// let cache = QlCache::new();
+    /// let transaction = cache.begin();
+    /// ```
+    #[must_use]
+    pub fn begin(&self) -> Transaction<'_> {
+        Transaction {
+            cache: self,
+            snapshot: self.snapshot(),
+            writes: Vec::new(),
+            savepoints: Vec::new()
         }
     }
 
+    /// # Instance Method `QlCache::collect_garbage`
+    ///
+    /// Removes the row versions of every table that are no longer visible to any snapshot.
+    pub fn collect_garbage(&self) {
+        self.cache.iter().for_each(|schema| {
+            schema
+                .tables
+                .iter()
+                .for_each(|table| table.collect_garbage());
+        });
+    }
+
+    /// # Instance Method `QlCache::sweep_expired`
+    ///
+    /// Removes every row that has expired from every table, returns the number of rows removed.
+    /// This can be called periodically from a background task, in addition to the amortised
+    /// sweeping on writes.
+    #[allow(clippy::must_use_candidate)] // the rows are removed regardless of the result being used
+    pub fn sweep_expired(&self) -> usize {
+        self.cache
+            .iter()
+            .map(|schema| {
+                schema
+                    .tables
+                    .iter()
+                    .map(|table| table.sweep_expired())
+                    .sum::<usize>()
+            })
+            .sum()
+    }
+
     /// # Instance Method `QlCache::execute`
     ///
     /// Executes a query.
@@ -56,7 +265,6 @@ impl QlCache {
         query.execute(self)
     }
 
-    // TODO: implement this method
     /// # Instance Method `QlCache::execute_as`
     ///
     /// Executes a query, returns the value as specified by `U`.
@@ -73,6 +281,303 @@ impl QlCache {
     ) -> QlResult<Vec<U>> {
         query.execute_as(self)
     }
+
+    /// # Instance Method `QlCache::compare_and_swap`
+    ///
+    /// Replaces the row with the specified key in the specified table, only if the version number
+    /// of the row is still `expected`. Pass `0` as `expected` to only insert the row if it does not
+    /// exist yet. Returns the new version number of the row.
+    ///
+    /// ## Parameters
+    /// - `table_name`, type `&str`; the name of the table
+    /// - `key`, type `u64`; the key of the row
+    /// - `expected`, type `u64`; the expected version number of the row
+    /// - `new`, type `CacheTableRow`; the new row
+    ///
+    /// ## Errors
+    ///
+    /// Returns `RelationDoesNotExist` if the table does not exist; returns `RowVersionConflict`
+    /// if the row has been changed since the expected version; returns `ColumnDoesNotExist`,
+    /// `ColumnCannotBeNull`, `ColumnTypeMismatch` or `InvalidEnumValue` if the new row is not
+    /// valid for the table; returns `PrimaryKeyViolation` if another live row has the same value
+    /// of the primary key.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use qlcache::{
+    ///     ql::QueryBuilder,
+    ///     CacheTableRow,
+    ///     ColumnDataType,
+    ///     ColumnValue,
+    ///     QlCache
+    /// };
+    ///
+    /// let cache = QlCache::new();
+    /// QueryBuilder::create()
+    ///     .table()
+    ///     .name(String::from("Members"))
+    ///     .columns(vec![(String::from("Xp"), (ColumnDataType::U64, false))])
+    ///     .build()
+    ///     .unwrap()
+    ///     .execute(&cache)
+    ///     .unwrap();
+    ///
+    /// let row = CacheTableRow {
+    ///     column_values: vec![(String::from("Xp"), ColumnValue::U64(10))]
+    ///         .into_iter()
+    ///         .collect()
+    /// };
+    /// let version = cache.compare_and_swap("Members", 1, 0, row.clone()).unwrap();
+    ///
+    /// assert!(cache.compare_and_swap("Members", 1, 0, row.clone()).is_err());
+    /// assert!(cache.compare_and_swap("Members", 1, version, row).is_ok());
+    /// ```
+    pub fn compare_and_swap(
+        &self,
+        table_name: &str,
+        key: u64,
+        expected: u64,
+        new: CacheTableRow
+    ) -> QlResult<u64> {
+        self.with_table(table_name, |table| {
+            table.validate_row(&new)?;
+            table.commit_row(key, Some(expected), None, |_| Ok(Some(new)))
+        })?
+    }
+
+    /// # Instance Method `QlCache::enum_type`
+    ///
+    /// Returns the user-defined enum type specified by `name`, which is either a type in the
+    /// `PUBLIC` schema, or a schema name and a type name separated by a space.
+    ///
+    /// ## Parameters
+    /// - `name`, type `&str`; the name of the type
+    ///
+    /// ## Errors
+    ///
+    /// Returns `RelationDoesNotExist` if the schema or the type does not exist.
+    pub fn enum_type(&self, name: &str) -> QlResult<EnumType> {
+        let (schema_name, type_name) = name.split_once(' ').unwrap_or(("PUBLIC", name));
+
+        self.cache
+            .get(schema_name)
+            .and_then(|schema| schema.types.get(type_name).map(|r#type| r#type.clone()))
+            .ok_or_else(|| {
+                QlError::QueryError(QueryError::RelationDoesNotExist {
+                    name: name.to_string()
+                })
+            })
+    }
+
+    /// # Instance Method `QlCache::view`
+    ///
+    /// Returns the view specified by `name`, which is either a view in the `PUBLIC` schema, or a
+    /// schema name and a view name separated by a space.
+    ///
+    /// ## Parameters
+    /// - `name`, type `&str`; the name of the view
+    ///
+    /// ## Errors
+    ///
+    /// Returns `RelationDoesNotExist` if the schema or the view does not exist.
+    pub fn view(&self, name: &str) -> QlResult<View> {
+        self.find_view(name).ok_or_else(|| {
+            QlError::QueryError(QueryError::RelationDoesNotExist {
+                name: name.to_string()
+            })
+        })
+    }
+
+    /// # Instance Method `QlCache::materialized_view`
+    ///
+    /// Returns the materialized view specified by `name`, which is either a materialized view in
+    /// the `PUBLIC` schema, or a schema name and a materialized view name separated by a space.
+    ///
+    /// ## Parameters
+    /// - `name`, type `&str`; the name of the materialized view
+    ///
+    /// ## Errors
+    ///
+    /// Returns `RelationDoesNotExist` if the schema or the materialized view does not exist.
+    pub fn materialized_view(&self, name: &str) -> QlResult<MaterializedView> {
+        self.find_materialized_view(name).ok_or_else(|| {
+            QlError::QueryError(QueryError::RelationDoesNotExist {
+                name: name.to_string()
+            })
+        })
+    }
+
+    /// # Instance Method `QlCache::register_function`
+    ///
+    /// Registers a user-defined scalar function, which can then be looked up by its name with
+    /// [`function`](Self::function) to call it in expressions.
+    ///
+    /// ## Parameters
+    /// - `function`, type `UserFunction`; the function to register
+    ///
+    /// ## Errors
+    ///
+    /// Returns `RelationAlreadyExists` if a function with the same name is registered already.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use qlcache::{
+    ///     ql::function::UserFunction,
+    ///     ColumnDataType,
+    ///     ColumnValue,
+    ///     QlCache
+    /// };
+    ///
+    /// let cache = QlCache::new();
+    /// cache
+    ///     .register_function(UserFunction::new(
+    ///         String::from("level"),
+    ///         vec![ColumnDataType::U64],
+    ///         ColumnDataType::U64,
+    ///         |arguments| match arguments {
+    ///             [ColumnValue::U64(xp)] => ColumnValue::U64(xp / 100),
+    ///             _ => ColumnValue::Null
+    ///         }
+    ///     ))
+    ///     .unwrap();
+    ///
+    /// let level = cache.function("level").unwrap();
+    /// ```
+    pub fn register_function(&self, function: UserFunction) -> QlResult<()> {
+        if self.functions.contains_key(&function.name) {
+            return Err(QlError::QueryError(QueryError::RelationAlreadyExists {
+                name: function.name
+            }));
+        }
+
+        self.functions.insert(function.name.clone(), function);
+        Ok(())
+    }
+
+    /// # Instance Method `QlCache::unregister_function`
+    ///
+    /// Removes a user-defined scalar function, returning whether it was registered. Queries that
+    /// call the function fail with `RelationDoesNotExist` until a function with the same name is
+    /// registered again.
+    ///
+    /// ## Parameters
+    /// - `name`, type `&str`; the name of the function
+    #[allow(clippy::must_use_candidate)] // the function is removed regardless of the result
+    pub fn unregister_function(&self, name: &str) -> bool {
+        self.functions.remove(name).is_some()
+    }
+
+    /// # Instance Method `QlCache::function`
+    ///
+    /// Returns the user-defined scalar function specified by `name`.
+    ///
+    /// ## Parameters
+    /// - `name`, type `&str`; the name of the function
+    ///
+    /// ## Errors
+    ///
+    /// Returns `RelationDoesNotExist` if no function with the name is registered.
+    pub fn function(&self, name: &str) -> QlResult<UserFunction> {
+        self.functions
+            .get(name)
+            .map(|function| function.clone())
+            .ok_or_else(|| {
+                QlError::QueryError(QueryError::RelationDoesNotExist {
+                    name: name.to_string()
+                })
+            })
+    }
+
+    /// Looks up the view specified by `name`, which is named like a table.
+    pub(crate) fn find_view(&self, name: &str) -> Option<View> {
+        let (schema_name, view_name) = name.split_once(' ').unwrap_or(("PUBLIC", name));
+
+        self.cache
+            .get(schema_name)
+            .and_then(|schema| schema.views.get(view_name).map(|view| view.clone()))
+    }
+
+    /// Looks up the materialized view specified by `name`, which is named like a table.
+    pub(crate) fn find_materialized_view(&self, name: &str) -> Option<MaterializedView> {
+        let (schema_name, view_name) = name.split_once(' ').unwrap_or(("PUBLIC", name));
+
+        self.cache.get(schema_name).and_then(|schema| {
+            schema
+                .materialized_views
+                .get(view_name)
+                .map(|view| view.clone())
+        })
+    }
+
+    /// Returns the name of a view or a materialized view, other than the relation itself, that
+    /// reads the relation `name` of the schema `schema`, if there is any. Names are qualified with
+    /// their schema unless it is `PUBLIC`.
+    pub(crate) fn dependent_of(&self, schema: &str, name: &str) -> Option<String> {
+        let qualify = |schema_name: &str, name: &str| {
+            if schema_name == "PUBLIC" {
+                name.to_string()
+            }
+            else {
+                format!("{schema_name} {name}")
+            }
+        };
+        let target = qualify(schema, name);
+        let reads_target = |relations: Vec<&str>| {
+            relations.into_iter().any(|relation| {
+                relation.split_once(' ').unwrap_or(("PUBLIC", relation)) == (schema, name)
+            })
+        };
+
+        self.cache.iter().find_map(|other| {
+            let views = other
+                .views
+                .iter()
+                .filter(|view| reads_target(view.select.relations()))
+                .map(|view| view.key().clone());
+            let materialized_views = other
+                .materialized_views
+                .iter()
+                .filter(|view| reads_target(view.select.relations()))
+                .map(|view| view.key().clone());
+
+            views
+                .chain(materialized_views)
+                .map(|view| qualify(&other.name, &view))
+                .find(|dependent| *dependent != target)
+        })
+    }
+
+    /// Locks the definitions of the cache until the guard is dropped. Statements that create or
+    /// drop schemas, types and relations hold the lock while they check which relations exist and
+    /// create or drop theirs, so that no other statement changes them in between.
+    pub(crate) fn lock_definitions(&self) -> MutexGuard<'_, ()> {
+        self.definitions.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Looks up the table specified by `name`, calling `f` with it. The name is either a table in
+    /// the `PUBLIC` schema, or a schema name and a table name separated by a space.
+    pub(crate) fn with_table<T>(
+        &self,
+        name: &str,
+        f: impl FnOnce(&CacheTable) -> T
+    ) -> QlResult<T> {
+        let (schema_name, table_name) = name.split_once(' ').unwrap_or(("PUBLIC", name));
+
+        let schema = self.cache.get(schema_name).ok_or_else(|| {
+            QlError::QueryError(QueryError::RelationDoesNotExist {
+                name: name.to_string()
+            })
+        })?;
+        let table = schema.tables.get(table_name).ok_or_else(|| {
+            QlError::QueryError(QueryError::RelationDoesNotExist {
+                name: name.to_string()
+            })
+        })?;
+
+        Ok(f(table.value()))
+    }
 }
 
 impl CacheSchema {
@@ -83,13 +588,1637 @@ impl CacheSchema {
     pub fn new(name: String) -> Self {
         Self {
             name,
-            tables: DashMap::new()
+            tables: DashMap::new(),
+            types: DashMap::new(),
+            views: DashMap::new(),
+            materialized_views: DashMap::new()
         }
     }
+
+    /// Returns whether the schema has a table, a view or a materialized view named `name`.
+    pub(crate) fn contains_relation(&self, name: &str) -> bool {
+        self.tables.contains_key(name)
+            || self.views.contains_key(name)
+            || self.materialized_views.contains_key(name)
+    }
 }
 
-impl Default for QlCache {
-    fn default() -> Self {
-        Self::new()
+impl CacheTable {
+    /// # Instance Method `CacheTable::row`
+    ///
+    /// Returns the latest committed version of the row with the specified key, unless it has
+    /// expired.
+    ///
+    /// ## Parameters
+    /// - `key`, type `u64`; the key of the row
+    #[must_use]
+    pub fn row(&self, key: u64) -> Option<CacheTableRow> {
+        let now = self.time.now();
+
+        let row = self.rows.get(&key).and_then(|chain| {
+            chain
+                .latest_version()
+                .filter(|version| !version.is_expired_at(now))
+                .map(|version| version.row().clone())
+        });
+
+        if row.is_some() {
+            self.record_access([key]);
+        }
+
+        row
+    }
+
+    /// # Instance Method `CacheTable::row_version`
+    ///
+    /// Returns the version number of the latest committed version of the row with the specified
+    /// key, unless it has expired.
+    ///
+    /// ## Parameters
+    /// - `key`, type `u64`; the key of the row
+    #[must_use]
+    pub fn row_version(&self, key: u64) -> Option<u64> {
+        let now = self.time.now();
+
+        self.rows.get(&key).and_then(|chain| {
+            chain
+                .latest_version()
+                .filter(|version| !version.is_expired_at(now))
+                .map(RowVersion::version)
+        })
+    }
+
+    /// # Instance Method `CacheTable::row_by_primary_key`
+    ///
+    /// Returns the key and the latest committed version of the row whose primary key has the
+    /// specified value, unless it has expired. The row is looked up in the index of the primary
+    /// key, so this returns `None` for a table without a primary key.
+    ///
+    /// ## Parameters
+    /// - `value`, type `&ColumnValue`; the value of the primary key
+    #[must_use]
+    pub fn row_by_primary_key(&self, value: &ColumnValue) -> Option<(u64, CacheTableRow)> {
+        let primary_key = self.primary_key.as_ref()?;
+        let key = self.primary_index.get(value).0?;
+
+        // the row may have been changed since its key was looked up
+        self.row(key)
+            .filter(|row| primary_key.value_of(row).as_ref() == Some(value))
+            .map(|row| (key, row))
+    }
+
+    /// Returns the key and the version visible to a snapshot taken at `timestamp` of the row whose
+    /// primary key has the specified value, if there is one that has not expired. Returns `None`
+    /// if the table has no primary key, or if the row cannot be found in the index because it
+    /// changed after `timestamp`, in which case the table has to be scanned.
+    pub(crate) fn row_by_primary_key_at(
+        &self,
+        value: &ColumnValue,
+        timestamp: u64
+    ) -> Option<Vec<(u64, Arc<CacheTableRow>)>> {
+        let primary_key = self.primary_key.as_ref()?;
+        let (key, changed_at) = self.primary_index.get(value);
+        let now = self.time.now();
+
+        let found = key.and_then(|key| {
+            self.rows.get(&key).and_then(|chain| {
+                chain
+                    .visible_version_at(timestamp)
+                    .filter(|version| !version.is_expired_at(now))
+                    .filter(|version| primary_key.value_of(version.row()).as_ref() == Some(value))
+                    .map(|version| (key, version.row.clone()))
+            })
+        });
+
+        // the values of the primary key are unique at every timestamp, so a row that is found has
+        // to be the only one, and no row is found if the index is the same as at `timestamp`
+        match found {
+            Some(found) => Some(vec![found]),
+            None if changed_at <= timestamp => Some(Vec::new()),
+            None => None
+        }
+    }
+
+    /// Returns whether the row with the specified key was changed by a commit after `timestamp`.
+    /// Must be called while committing.
+    pub(crate) fn is_changed_after(&self, key: u64, timestamp: u64) -> bool {
+        self.rows
+            .get(&key)
+            .is_some_and(|chain| chain.is_changed_after(timestamp))
+    }
+
+    /// # Instance Method `CacheTable::insert_row`
+    ///
+    /// Inserts a row with the specified key, or replaces the row if it already exists. The row
+    /// expires after the default time-to-live of the table, if any. The previous version remains
+    /// visible to snapshots taken before this write.
+    ///
+    /// ## Parameters
+    /// - `key`, type `u64`; the key of the row
+    /// - `row`, type `CacheTableRow`; the row to insert
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ColumnDoesNotExist`, `ColumnCannotBeNull`, `ColumnTypeMismatch` or
+    /// `InvalidEnumValue` if the row is not valid for the table; returns `PrimaryKeyViolation` if
+    /// another live row has the same value of the primary key. Nothing is inserted in either
+    /// case.
+    pub fn insert_row(&self, key: u64, row: CacheTableRow) -> QlResult<()> {
+        self.validate_row(&row)?;
+
+        let expires_at = self.ttl.map(|ttl| self.time.now() + ttl);
+        let watermark = self.clock.watermark();
+
+        self.clock.commit(|timestamp| {
+            self.check_primary_key(&self.name, key, &row, |_| false)?;
+            self.install(timestamp, watermark, key, Some(row), expires_at);
+
+            Ok(())
+        })
+    }
+
+    /// # Instance Method `CacheTable::insert_row_with_ttl`
+    ///
+    /// Inserts a row with the specified key that expires after the specified time-to-live, or
+    /// replaces the row if it already exists.
+    ///
+    /// ## Parameters
+    /// - `key`, type `u64`; the key of the row
+    /// - `row`, type `CacheTableRow`; the row to insert
+    /// - `ttl`, type `Duration`; the time-to-live of the row
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ColumnDoesNotExist`, `ColumnCannotBeNull`, `ColumnTypeMismatch` or
+    /// `InvalidEnumValue` if the row is not valid for the table; returns `PrimaryKeyViolation` if
+    /// another live row has the same value of the primary key. Nothing is inserted in either
+    /// case.
+    pub fn insert_row_with_ttl(
+        &self,
+        key: u64,
+        row: CacheTableRow,
+        ttl: Duration
+    ) -> QlResult<()> {
+        self.validate_row(&row)?;
+
+        let expires_at = self.time.now() + ttl;
+        let watermark = self.clock.watermark();
+
+        self.clock.commit(|timestamp| {
+            self.check_primary_key(&self.name, key, &row, |_| false)?;
+            self.install(timestamp, watermark, key, Some(row), Some(expires_at));
+
+            Ok(())
+        })
+    }
+
+    /// # Instance Method `CacheTable::remove_row`
+    ///
+    /// Removes the row with the specified key, returns whether the row existed. The removed row
+    /// remains visible to snapshots taken before this write.
+    ///
+    /// ## Parameters
+    /// - `key`, type `u64`; the key of the row
+    #[allow(clippy::must_use_candidate)] // the row is removed regardless of the result being used
+    pub fn remove_row(&self, key: u64) -> bool {
+        let watermark = self.clock.watermark();
+
+        self.clock
+            .commit(|timestamp| self.install(timestamp, watermark, key, None, None))
+    }
+
+    /// # Instance Method `CacheTable::usage`
+    ///
+    /// Returns the number and approximate size of the live rows of the table, as well as the
+    /// number of rows evicted from it.
+    #[must_use]
+    pub fn usage(&self) -> &Usage {
+        &self.usage
+    }
+
+    /// # Instance Method `CacheTable::collect_garbage`
+    ///
+    /// Removes the row versions that are no longer visible to any snapshot.
+    pub fn collect_garbage(&self) {
+        let watermark = self.clock.watermark();
+
+        // every version that can be collected is found by the scan below
+        self.garbage.pop_collectable(watermark, usize::MAX);
+
+        self.rows.iter_mut().for_each(|mut chain| chain.prune(watermark));
+        self.rows.retain(|_, chain| !chain.versions.is_empty());
+    }
+
+    /// # Instance Method `CacheTable::sweep_expired`
+    ///
+    /// Removes every row that has expired, returns the number of rows removed.
+    #[allow(clippy::must_use_candidate)] // the rows are removed regardless of the result being used
+    pub fn sweep_expired(&self) -> usize {
+        let now = self.time.now();
+        let watermark = self.clock.watermark();
+
+        self.clock.commit(|timestamp| {
+            // every row that is due is found by the scan below, so the due entries of the queue
+            // can be dropped
+            self.expirations.pop_expired(now, usize::MAX);
+
+            let expired = self
+                .rows
+                .iter()
+                .filter(|chain| {
+                    matches!(chain.latest_version(), Some(version) if version.is_expired_at(now))
+                })
+                .map(|chain| *chain.key())
+                .collect();
+
+            self.expire(timestamp, watermark, now, expired)
+        })
+    }
+
+    /// Checks that no live row has the value of the primary key of `row` besides the row with the
+    /// specified key, and the rows for which `is_rewritten` returns `true` because they are
+    /// written in the same commit. Must be called while committing.
+    pub(crate) fn check_primary_key(
+        &self,
+        table_name: &str,
+        key: u64,
+        row: &CacheTableRow,
+        is_rewritten: impl Fn(u64) -> bool
+    ) -> QlResult<()> {
+        let (primary_key, value) = match &self.primary_key {
+            Some(primary_key) => match primary_key.value_of(row) {
+                Some(value) => (primary_key, value),
+                None => return Ok(())
+            },
+            None => return Ok(())
+        };
+
+        let other = match self.primary_index.get(&value).0 {
+            Some(other) if other != key && !is_rewritten(other) => other,
+            _ => return Ok(())
+        };
+
+        // the index may still map the value to a row that has expired but was not reclaimed yet
+        let now = self.time.now();
+        let taken = self.rows.get(&other).is_some_and(|chain| {
+            chain.latest_version().is_some_and(|version| {
+                !version.is_expired_at(now)
+                    && primary_key.value_of(version.row()).as_ref() == Some(&value)
+            })
+        });
+
+        if taken {
+            return Err(QlError::PrimaryKeyViolation {
+                table_name: table_name.to_string(),
+                key: other
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Checks that every column of `row` exists in this table, that no column that cannot be
+    /// `null` is missing or `null`, that every value has the type of its column, and that the
+    /// values of columns of enum types are values of those types.
+    pub(crate) fn validate_row(&self, row: &CacheTableRow) -> QlResult<()> {
+        if let Some(column) = row
+            .column_values
+            .iter()
+            .find(|column| !self.columns.contains_key(column.key()))
+        {
+            return Err(QlError::ColumnDoesNotExist {
+                name: column.key().clone()
+            });
+        }
+
+        if let Some(column) = self.columns.iter().find(|column| {
+            let value = row.column_values.get(column.key());
+
+            !column.value().1
+                && !matches!(value.as_deref(), Some(value) if *value != ColumnValue::Null)
+        }) {
+            return Err(QlError::ColumnCannotBeNull {
+                name: column.key().clone()
+            });
+        }
+
+        for column in &row.column_values {
+            let data_type = match self.columns.get(column.key()) {
+                Some(column) => column.value().0.clone(),
+                None => continue
+            };
+
+            match (data_type, column.value()) {
+                (ColumnDataType::Enum(type_name), ColumnValue::Enum(value))
+                    if !matches!(
+                        self.enum_types.get(&type_name),
+                        Some(r#type) if r#type.is_valid(value)
+                    ) =>
+                {
+                    return Err(QlError::InvalidEnumValue {
+                        type_name,
+                        label: value.label.clone()
+                    });
+                }
+                (data_type, value) if !data_type.accepts(value) => {
+                    return Err(QlError::ColumnTypeMismatch {
+                        name: column.key().clone()
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Installs a new version of the row with the specified key at `timestamp` that expires at
+    /// `expires_at`, or ends the latest version if `row` is `None`. A bounded number of expired
+    /// rows and of versions that are no longer visible are reclaimed, and rows are evicted if a
+    /// capacity limit is exceeded along the way.
+    /// Returns whether a live version was ended. Must be called while committing.
+    pub(crate) fn install(
+        &self,
+        timestamp: u64,
+        watermark: u64,
+        key: u64,
+        row: Option<CacheTableRow>,
+        expires_at: Option<SystemTime>
+    ) -> bool {
+        let size = row.as_ref().map(CacheTableRow::approximate_size);
+
+        let (ended, installed) = if row.is_some() {
+            let mut chain = self.rows.entry(key).or_default();
+            let ended = chain.push(timestamp, row, expires_at);
+            let installed = chain.latest_version().cloned();
+            chain.prune(watermark);
+
+            (ended, installed)
+        }
+        else if let Some(mut chain) = self.rows.get_mut(&key) {
+            let ended = chain.push(timestamp, None, None);
+            chain.prune(watermark);
+
+            (ended, None)
+        }
+        else {
+            (None, None)
+        };
+
+        self.rows.remove_if(&key, |_, chain| chain.versions.is_empty());
+        if ended.is_some() || installed.is_some() {
+            self.record_change(timestamp, key, installed.as_ref());
+            self.index(timestamp, key, ended.as_deref(), installed.as_ref().map(RowVersion::row));
+        }
+
+        if let Some(ended) = &ended {
+            self.garbage.push(timestamp, key);
+            self.account_removed(ended.approximate_size());
+        }
+
+        if let Some(size) = size {
+            self.account_added(size);
+            self.eviction_policy.record_insert(key);
+        }
+        else if ended.is_some() {
+            self.eviction_policy.record_remove(key);
+        }
+
+        self.expirations.schedule(key, expires_at);
+
+        let now = self.time.now();
+        let expired = self.expirations.pop_expired(now, SWEEP_BATCH_SIZE);
+        self.expire(timestamp, watermark, now, expired);
+        self.evict(timestamp, watermark, key);
+        self.prune(watermark, SWEEP_BATCH_SIZE);
+
+        ended.is_some()
+    }
+
+    /// Commits a write to the row with the specified key, the new row is computed by `write` from
+    /// the latest version of the row, and the row is removed if it returns `None`. If `expected`
+    /// is `Some`, the write fails unless the version number of the latest version is the expected
+    /// one, where `0` means that the row must not exist. The new row expires after `ttl` if it is
+    /// `Some`, and otherwise keeps the expiry time of the row, or the default time-to-live of the
+    /// table for new rows. Returns the new version number.
+    pub(crate) fn commit_row(
+        &self,
+        key: u64,
+        expected: Option<u64>,
+        ttl: Option<Duration>,
+        write: impl FnOnce(Option<&CacheTableRow>) -> QlResult<Option<CacheTableRow>>
+    ) -> QlResult<u64> {
+        let watermark = self.clock.watermark();
+
+        self.clock.commit(|timestamp| {
+            let now = self.time.now();
+            let latest = self.rows.get(&key).and_then(|chain| {
+                chain
+                    .latest_version()
+                    .filter(|version| !version.is_expired_at(now))
+                    .cloned()
+            });
+            let found = latest.as_ref().map(RowVersion::version);
+
+            if let Some(expected) = expected {
+                if found.unwrap_or(0) != expected {
+                    return Err(QlError::RowVersionConflict {
+                        key,
+                        expected,
+                        found
+                    });
+                }
+            }
+
+            let expires_at = match (ttl, &latest) {
+                (Some(ttl), _) => Some(now + ttl),
+                (None, Some(latest)) => latest.expires_at(),
+                (None, None) => self.ttl.map(|ttl| now + ttl)
+            };
+            let row = write(latest.as_ref().map(RowVersion::row))?;
+            if let Some(row) = &row {
+                self.check_primary_key(&self.name, key, row, |_| false)?;
+            }
+
+            self.install(timestamp, watermark, key, row, expires_at);
+
+            Ok(timestamp)
+        })
+    }
+
+    /// Returns the version of the row with the specified key that is visible to a snapshot taken
+    /// at `timestamp`, unless it has expired.
+    pub(crate) fn row_at(&self, key: u64, timestamp: u64) -> Option<Arc<CacheTableRow>> {
+        let now = self.time.now();
+
+        let row = self.rows.get(&key).and_then(|chain| {
+            chain
+                .visible_version_at(timestamp)
+                .filter(|version| !version.is_expired_at(now))
+                .map(|version| version.row.clone())
+        });
+
+        if row.is_some() {
+            self.record_access([key]);
+        }
+
+        row
+    }
+
+    /// Returns the keys and rows visible to a snapshot taken at `timestamp` that have not expired.
+    /// The shard locks of the table are only held while the row pointers are collected.
+    pub(crate) fn visible_rows(&self, timestamp: u64) -> Vec<(u64, Arc<CacheTableRow>)> {
+        let now = self.time.now();
+
+        self.rows
+            .iter()
+            .filter_map(|chain| {
+                chain
+                    .visible_version_at(timestamp)
+                    .filter(|version| !version.is_expired_at(now))
+                    .map(|version| (*chain.key(), version.row.clone()))
+            })
+            .collect()
+    }
+
+    /// Records that the rows with the specified keys have been read.
+    pub(crate) fn record_access(&self, keys: impl IntoIterator<Item = u64>) {
+        keys.into_iter()
+            .for_each(|key| self.eviction_policy.record_access(key));
+    }
+
+    /// Ends the latest version of each of the rows with the specified keys that has expired at
+    /// `now`, returns the number of rows removed. Must be called while committing.
+    fn expire(&self, timestamp: u64, watermark: u64, now: SystemTime, keys: Vec<u64>) -> usize {
+        keys.into_iter()
+            .filter(|key| {
+                let expired = if let Some(mut chain) = self.rows.get_mut(key) {
+                    let expired = matches!(
+                        chain.latest_version(),
+                        Some(version) if version.is_expired_at(now)
+                    );
+
+                    if expired {
+                        let reason = EvictionReason::Expired;
+                        self.end_evicted(&mut chain, timestamp, watermark, *key, reason);
+                    }
+
+                    expired
+                }
+                else {
+                    false
+                };
+
+                self.rows.remove_if(key, |_, chain| chain.versions.is_empty());
+                expired
+            })
+            .count()
+    }
+
+    /// Evicts rows while the capacity limits of the table or of the whole cache are exceeded. The
+    /// row that was just written is never evicted. Must be called while committing.
+    fn evict(&self, timestamp: u64, watermark: u64, written: u64) {
+        while let Some(limit) = self.limits.exceeded_by(self.usage.rows(), self.usage.bytes()) {
+            let reason = EvictionReason::TableLimit(limit);
+
+            if !self.evict_victim(timestamp, watermark, Some(written), reason) {
+                break;
+            }
+        }
+
+        if let Some(schemas) = self.schemas.upgrade() {
+            self.evict_from_cache(&schemas, timestamp, watermark, written);
+        }
+    }
+
+    /// Evicts rows while the capacity limits of the whole cache are exceeded, each from the table
+    /// that uses the most of the exceeded limit, except for the row of this table that was just
+    /// written. Must be called while committing.
+    fn evict_from_cache(
+        &self,
+        schemas: &DashMap<String, CacheSchema>,
+        timestamp: u64,
+        watermark: u64,
+        written: u64
+    ) {
+        let capacity = &self.capacity;
+        let mut exhausted = HashSet::new();
+
+        while let Some(limit) = capacity
+            .limits()
+            .exceeded_by(capacity.usage.rows(), capacity.usage.bytes())
+        {
+            let used = |usage: &Usage| {
+                match limit {
+                    CapacityLimit::Rows => usage.rows(),
+                    CapacityLimit::Bytes => usage.bytes()
+                }
+            };
+            let largest = schemas
+                .iter()
+                .flat_map(|schema| {
+                    schema
+                        .tables
+                        .iter()
+                        .map(|table| (table.schema.clone(), table.name.clone(), used(&table.usage)))
+                        .collect::<Vec<_>>()
+                })
+                .filter(|(schema_name, table_name, _)| {
+                    !exhausted.contains(&(schema_name.clone(), table_name.clone()))
+                })
+                .max_by_key(|(_, _, used)| *used);
+
+            if let Some((schema_name, table_name, _)) = largest {
+                let evicted = schemas.get(&schema_name).is_some_and(|schema| {
+                    schema.tables.get(&table_name).is_some_and(|table| {
+                        let written = ptr::eq(table.value(), self).then_some(written);
+                        let reason = EvictionReason::CacheLimit(limit);
+
+                        table.evict_victim(timestamp, watermark, written, reason)
+                    })
+                });
+
+                if !evicted {
+                    exhausted.insert((schema_name, table_name));
+                }
+            }
+            else {
+                break;
+            }
+        }
+    }
+
+    /// Evicts the row chosen by the eviction policy for the specified reason, or the next one if
+    /// that is the row with the key `written`. Returns whether a row was evicted. Must be called
+    /// while committing.
+    fn evict_victim(
+        &self,
+        timestamp: u64,
+        watermark: u64,
+        written: Option<u64>,
+        reason: EvictionReason
+    ) -> bool {
+        let mut deferred = None;
+
+        let evicted = loop {
+            match self.eviction_policy.victim() {
+                Some(victim) if Some(victim) == written => deferred = Some(victim),
+                Some(victim) => {
+                    if self.evict_row(timestamp, watermark, victim, reason) {
+                        break true;
+                    }
+                }
+                None => break false
+            }
+        };
+
+        if let Some(written) = deferred {
+            self.eviction_policy.record_insert(written);
+        }
+
+        evicted
+    }
+
+    /// Evicts the row with the specified key for the specified reason, along with the rows that
+    /// refer to it by a foreign key. Returns whether the row was live. Must be called while
+    /// committing.
+    fn evict_row(&self, timestamp: u64, watermark: u64, key: u64, reason: EvictionReason) -> bool {
+        let ended = self.rows.get_mut(&key).and_then(|mut chain| {
+            self.end_evicted(&mut chain, timestamp, watermark, key, reason)
+        });
+        self.rows.remove_if(&key, |_, chain| chain.versions.is_empty());
+
+        // the chain is no longer borrowed here, as the rows referring to the row may be in the
+        // same table
+        match ended {
+            Some(row) => {
+                self.evict_referring(timestamp, watermark, &row);
+                true
+            }
+            None => false
+        }
+    }
+
+    /// Evicts the rows of every table that refer to `row` of this table by a foreign key. Must be
+    /// called while committing.
+    fn evict_referring(&self, timestamp: u64, watermark: u64, row: &CacheTableRow) {
+        let value = self.primary_key.as_ref().and_then(|key| key.value_of(row));
+
+        if let (Some(value), Some(schemas)) = (value, self.schemas.upgrade()) {
+            for schema in &*schemas {
+                for table in &schema.tables {
+                    let referring = table
+                        .foreign_keys
+                        .iter()
+                        .zip(&table.foreign_indexes)
+                        .filter(|(foreign_key, _)| foreign_key.refers_to(&self.schema, &self.name))
+                        .flat_map(|(_, index)| index.get(&value))
+                        .collect::<Vec<_>>();
+
+                    for key in referring {
+                        table.evict_row(timestamp, watermark, key, EvictionReason::ForeignKey);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Ends the latest version of a row that is evicted for the specified reason, returns the row
+    /// of the version that was ended.
+    fn end_evicted(
+        &self,
+        chain: &mut VersionChain,
+        timestamp: u64,
+        watermark: u64,
+        key: u64,
+        reason: EvictionReason
+    ) -> Option<Arc<CacheTableRow>> {
+        let ended = chain.push(timestamp, None, None);
+
+        if let Some(ended) = &ended {
+            self.garbage.push(timestamp, key);
+            self.record_change(timestamp, key, None);
+            self.index(timestamp, key, Some(ended), None);
+            self.account_removed(ended.approximate_size());
+            self.usage.record_eviction(reason);
+            self.capacity.usage.record_eviction(reason);
+            self.eviction_policy.record_remove(key);
+        }
+
+        chain.prune(watermark);
+        ended
+    }
+
+    /// Removes the versions that ended at or before `watermark` from at most `limit` rows, in the
+    /// order in which they were ended.
+    fn prune(&self, watermark: u64, limit: usize) {
+        for key in self.garbage.pop_collectable(watermark, limit) {
+            if let Some(mut chain) = self.rows.get_mut(&key) {
+                chain.prune(watermark);
+            }
+
+            self.rows.remove_if(&key, |_, chain| chain.versions.is_empty());
+        }
+    }
+
+    /// Applies a change of the row with the specified key that is committed at `timestamp` to the
+    /// materialized views maintained from the table, where `version` is the new version of the
+    /// row, or `None` if the row was removed. Must be called while committing.
+    fn record_change(&self, timestamp: u64, key: u64, version: Option<&RowVersion>) {
+        for materialization in &self.materializations {
+            materialization.record(timestamp, key, version);
+        }
+    }
+
+    /// Updates the indexes of the primary key and of the foreign keys for a change of the row with
+    /// the specified key that is committed at `timestamp`, where `ended` is the row of the version
+    /// that was ended and `installed` is the new row. Must be called while committing.
+    fn index(
+        &self,
+        timestamp: u64,
+        key: u64,
+        ended: Option<&CacheTableRow>,
+        installed: Option<&CacheTableRow>
+    ) {
+        if let Some(primary_key) = &self.primary_key {
+            if let Some(value) = ended.and_then(|row| primary_key.value_of(row)) {
+                self.primary_index.remove(timestamp, &value, key);
+            }
+
+            if let Some(value) = installed.and_then(|row| primary_key.value_of(row)) {
+                self.primary_index.insert(timestamp, value, key);
+            }
+        }
+
+        for (foreign_key, index) in self.foreign_keys.iter().zip(&self.foreign_indexes) {
+            if let Some(value) = ended.and_then(|row| foreign_key.value_of(row)) {
+                index.remove(&value, key);
+            }
+
+            if let Some(value) = installed.and_then(|row| foreign_key.value_of(row)) {
+                index.insert(value, key);
+            }
+        }
+    }
+
+    fn account_added(&self, bytes: usize) {
+        self.usage.add(bytes);
+        self.capacity.usage.add(bytes);
+    }
+
+    fn account_removed(&self, bytes: usize) {
+        self.usage.subtract(bytes);
+        self.capacity.usage.subtract(bytes);
+    }
+}
+
+impl CacheTableRow {
+    /// # Instance Method `CacheTableRow::approximate_size`
+    ///
+    /// Returns the approximate size of the row in memory, in bytes.
+    #[must_use]
+    pub fn approximate_size(&self) -> usize {
+        self.column_values
+            .iter()
+            .map(|column| column.key().len() + column.value().approximate_size())
+            .sum()
+    }
+
+    /// # Instance Method `CacheTableRow::get`
+    ///
+    /// Returns the value of a column converted into a Rust type, a column missing from the row is
+    /// treated as `null`.
+    ///
+    /// ## Parameters
+    /// - `column`, type `&str`; the name of the column
+    ///
+    /// ## Errors
+    ///
+    /// Returns `InvalidConversion` if the value cannot be converted into the type, as described in
+    /// [`convert`](crate::convert).
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    /// use qlcache::{
+    ///     CacheTableRow,
+    ///     ColumnValue
+    /// };
+    ///
+    /// let row = CacheTableRow {
+    ///     column_values: DashMap::from_iter([
+    ///         (String::from("id"), ColumnValue::from(42_u8)),
+    ///         (String::from("name"), ColumnValue::from("Alice"))
+    ///     ])
+    /// };
+    ///
+    /// assert_eq!(row.get::<u64>("id").unwrap(), 42);
+    /// assert_eq!(row.get::<String>("name").unwrap(), "Alice");
+    /// assert_eq!(row.get::<Option<String>>("nickname").unwrap(), None);
+    /// assert!(row.get::<i8>("name").is_err());
+    /// ```
+    pub fn get<T: TryFrom<ColumnValue, Error = QlError>>(&self, column: &str) -> QlResult<T> {
+        let value = self
+            .column_values
+            .get(column)
+            .map_or(ColumnValue::Null, |value| value.clone());
+
+        T::try_from(value)
+    }
+
+    /// Returns the values of columns, a column missing from the row is treated as `null`.
+    pub(crate) fn values_of(&self, columns: &[String]) -> Vec<ColumnValue> {
+        columns
+            .iter()
+            .map(|column| {
+                self.column_values
+                    .get(column)
+                    .map_or(ColumnValue::Null, |value| value.clone())
+            })
+            .collect()
+    }
+}
+
+impl ColumnDataType {
+    /// # Instance Method `ColumnDataType::is_comparable_with`
+    ///
+    /// Returns whether values of this type can be compared with a value: `null` can be compared
+    /// with every type, numbers of any types can be compared with each other, and values of other
+    /// types can only be compared with values of the same type.
+    ///
+    /// ## Parameters
+    /// - `value`, type `&ColumnValue`; the value
+    #[must_use]
+    pub fn is_comparable_with(&self, value: &ColumnValue) -> bool {
+        match (self, value) {
+            (_, ColumnValue::Null)
+            | (Self::Bool, ColumnValue::Bool(_))
+            | (Self::String, ColumnValue::String(_))
+            | (Self::Bytes, ColumnValue::Bytes(_))
+            | (Self::Timestamp, ColumnValue::Timestamp(_))
+            | (Self::Date, ColumnValue::Date(_))
+            | (Self::Duration, ColumnValue::Duration(_))
+            | (Self::Snowflake, ColumnValue::Snowflake(_))
+            | (Self::Uuid, ColumnValue::Uuid(_))
+            | (Self::Json, ColumnValue::Json(_)) => true,
+            (Self::Array(element), ColumnValue::Array(values)) => {
+                values.iter().all(|value| element.is_comparable_with(value))
+            }
+            (Self::Enum(type_name), ColumnValue::Enum(value)) => value.type_name == *type_name,
+            (data_type, value) => data_type.is_numeric() && Number::of(value).is_some()
+        }
+    }
+
+    /// # Instance Method `ColumnDataType::is_comparable_to`
+    ///
+    /// Returns whether values of this type can be compared with values of another type: numeric
+    /// types can be compared with each other, `Array`s can be compared if their elements can, and
+    /// other types can only be compared with the same type.
+    ///
+    /// ## Parameters
+    /// - `other`, type `&ColumnDataType`; the other type
+    #[must_use]
+    pub fn is_comparable_to(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Array(left), Self::Array(right)) => left.is_comparable_to(right),
+            (left, right) => left == right || (left.is_numeric() && right.is_numeric())
+        }
+    }
+
+    /// Returns whether a value can be stored in a column of this type: `null` can be stored in a
+    /// column of every type, other values only in a column of their own type.
+    pub(crate) fn accepts(&self, value: &ColumnValue) -> bool {
+        match (self, value) {
+            (_, ColumnValue::Null) => true,
+            (Self::Array(element), ColumnValue::Array(values)) => {
+                values.iter().all(|value| element.accepts(value))
+            }
+            (data_type, value) => value.data_type().as_ref() == Some(data_type)
+        }
+    }
+
+    pub(crate) fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            Self::I8
+                | Self::I16
+                | Self::I32
+                | Self::I64
+                | Self::I128
+                | Self::U8
+                | Self::U16
+                | Self::U32
+                | Self::U64
+                | Self::U128
+                | Self::F32
+                | Self::F64
+        )
+    }
+}
+
+impl ColumnValue {
+    /// # Instance Method `ColumnValue::approximate_size`
+    ///
+    /// Returns the approximate size of the value in memory, in bytes.
+    #[must_use]
+    pub fn approximate_size(&self) -> usize {
+        let heap = match self {
+            Self::String(string) => string.len(),
+            Self::Bytes(bytes) => bytes.len(),
+            Self::Array(values) => values.iter().map(Self::approximate_size).sum(),
+            Self::Json(json) => json.approximate_size(),
+            Self::Enum(value) => value.type_name.len() + value.label.len(),
+            _ => 0
+        };
+
+        mem::size_of::<Self>() + heap
+    }
+
+    /// # Instance Method `ColumnValue::compare`
+    ///
+    /// Compares this value with another value of the same type, or with another number if this
+    /// value is a number: numbers of any types are compared by their exact values, as described
+    /// in [`numeric`](crate::numeric). Returns `None` if either of the values is `null`, or if the
+    /// types of the values cannot be compared. `Array`s are compared element by element, where
+    /// elements are ordered as they are for sorting.
+    ///
+    /// ## Parameters
+    /// - `other`, type `&ColumnValue`; the value to compare with
+    #[allow(clippy::match_same_arms)] // the values of each arm are of different variants
+    #[must_use]
+    pub fn compare(&self, other: &Self) -> Option<Ordering> {
+        if let (Some(left), Some(right)) = (Number::of(self), Number::of(other)) {
+            return Some(left.compare(right));
+        }
+
+        match (self, other) {
+            (Self::Bool(left), Self::Bool(right)) => Some(left.cmp(right)),
+            (Self::String(left), Self::String(right)) => Some(left.cmp(right)),
+            (Self::Bytes(left), Self::Bytes(right)) => Some(left.cmp(right)),
+            (Self::Timestamp(left), Self::Timestamp(right)) => Some(left.cmp(right)),
+            (Self::Date(left), Self::Date(right)) => Some(left.cmp(right)),
+            (Self::Duration(left), Self::Duration(right)) => Some(left.cmp(right)),
+            (Self::Snowflake(left), Self::Snowflake(right)) => Some(left.cmp(right)),
+            (Self::Uuid(left), Self::Uuid(right)) => Some(left.cmp(right)),
+            (Self::Array(left), Self::Array(right)) => {
+                let ordering = left
+                    .iter()
+                    .zip(right)
+                    .map(|(left, right)| left.cmp(right))
+                    .find(|ordering| *ordering != Ordering::Equal);
+
+                Some(ordering.unwrap_or_else(|| left.len().cmp(&right.len())))
+            }
+            (Self::Json(left), Self::Json(right)) => Some(left.cmp(right)),
+            (Self::Enum(left), Self::Enum(right)) if left.type_name == right.type_name => {
+                Some(left.ordinal.cmp(&right.ordinal))
+            }
+            _ => None
+        }
+    }
+
+    /// # Instance Method `ColumnValue::contains`
+    ///
+    /// Returns whether this value contains another value: an `Array` contains an element, or all of
+    /// the elements of another `Array`; a `Json` document contains another `Json` document as
+    /// described in [`JsonValue::contains`](crate::types::JsonValue::contains); and a `String`
+    /// contains a substring.
+    ///
+    /// ## Parameters
+    /// - `other`, type `&ColumnValue`; the value to check for
+    #[must_use]
+    pub fn contains(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Array(values), Self::Array(others)) => {
+                others.iter().all(|other| values.contains(other))
+            }
+            (Self::Array(values), other) => values.contains(other),
+            (Self::Json(json), Self::Json(other)) => json.contains(other),
+            (Self::String(string), Self::String(other)) => string.contains(other.as_str()),
+            _ => false
+        }
+    }
+
+    /// # Static Method `ColumnValue::date_of`
+    ///
+    /// Returns the `Date` of a timestamp, in UTC.
+    ///
+    /// ## Parameters
+    /// - `timestamp`, type `SystemTime`; the timestamp
+    #[must_use]
+    pub fn date_of(timestamp: SystemTime) -> Self {
+        let (seconds, _) = seconds_since_epoch(timestamp);
+
+        i32::try_from(seconds.div_euclid(i128::from(SECONDS_PER_DAY)))
+            .map_or(Self::Null, Self::Date)
+    }
+
+    /// # Instance Method `ColumnValue::checked_add`
+    ///
+    /// Adds a value to this value. Numbers of any types can be added, with the result of the type
+    /// described in [`checked_mul`](Self::checked_mul). A `Duration` can be added to a
+    /// `Timestamp`, a `Date` (the result is a `Timestamp`) or another `Duration`. Returns `None` if
+    /// the values cannot be added or the result overflows.
+    ///
+    /// ## Parameters
+    /// - `other`, type `&ColumnValue`; the value to add
+    #[must_use]
+    pub fn checked_add(&self, other: &Self) -> Option<Self> {
+        match (self, other) {
+            (Self::Timestamp(timestamp), Self::Duration(duration))
+            | (Self::Duration(duration), Self::Timestamp(timestamp)) => {
+                timestamp.checked_add(*duration).map(Self::Timestamp)
+            }
+            (Self::Date(days), Self::Duration(duration))
+            | (Self::Duration(duration), Self::Date(days)) => {
+                start_of_date(*days)?
+                    .checked_add(*duration)
+                    .map(Self::Timestamp)
+            }
+            (Self::Duration(left), Self::Duration(right)) => {
+                left.checked_add(*right).map(Self::Duration)
+            }
+            _ => self.arithmetic(other, Number::checked_add)
+        }
+    }
+
+    /// # Instance Method `ColumnValue::checked_sub`
+    ///
+    /// Subtracts a value from this value. Numbers of any types can be subtracted, with the result
+    /// of the type described in [`checked_mul`](Self::checked_mul). A `Duration` can be subtracted
+    /// from a `Timestamp`, a `Date` (the result is a `Timestamp`) or another `Duration`, and
+    /// subtracting two `Timestamp`s or two `Date`s results in the `Duration` between them. Returns
+    /// `None` if the values cannot be subtracted or the result overflows; as durations cannot be
+    /// negative, this includes subtracting a later time from an earlier one.
+    ///
+    /// ## Parameters
+    /// - `other`, type `&ColumnValue`; the value to subtract
+    #[must_use]
+    pub fn checked_sub(&self, other: &Self) -> Option<Self> {
+        match (self, other) {
+            (Self::Timestamp(timestamp), Self::Duration(duration)) => {
+                timestamp.checked_sub(*duration).map(Self::Timestamp)
+            }
+            (Self::Date(days), Self::Duration(duration)) => {
+                start_of_date(*days)?
+                    .checked_sub(*duration)
+                    .map(Self::Timestamp)
+            }
+            (Self::Timestamp(left), Self::Timestamp(right)) => {
+                left.duration_since(*right).ok().map(Self::Duration)
+            }
+            (Self::Date(left), Self::Date(right)) => {
+                u64::try_from(i64::from(*left) - i64::from(*right))
+                    .ok()
+                    .map(|days| Self::Duration(Duration::from_secs(days * SECONDS_PER_DAY)))
+            }
+            (Self::Duration(left), Self::Duration(right)) => {
+                left.checked_sub(*right).map(Self::Duration)
+            }
+            _ => self.arithmetic(other, Number::checked_sub)
+        }
+    }
+
+    /// # Instance Method `ColumnValue::checked_mul`
+    ///
+    /// Multiplies this value with another value, both of which must be numbers. The result is of
+    /// the same type if both are of the same type; of the wider type if both are signed or both
+    /// are unsigned integers; of the narrowest signed type that can hold both if one is signed and
+    /// the other unsigned, or `I128` if there is none; and `F64` if either is a floating point
+    /// number, unless both are `F32`. Returns `None` if the values are not numbers or the result
+    /// does not fit its type.
+    ///
+    /// ## Parameters
+    /// - `other`, type `&ColumnValue`; the value to multiply with
+    #[must_use]
+    pub fn checked_mul(&self, other: &Self) -> Option<Self> {
+        self.arithmetic(other, Number::checked_mul)
+    }
+
+    /// # Instance Method `ColumnValue::checked_div`
+    ///
+    /// Divides this value by another value, both of which must be numbers, with the result of the
+    /// type described in [`checked_mul`](Self::checked_mul). The quotient of integers is truncated
+    /// towards zero. Returns `None` if the values are not numbers, the divisor is zero or the
+    /// result does not fit its type.
+    ///
+    /// ## Parameters
+    /// - `other`, type `&ColumnValue`; the divisor
+    #[must_use]
+    pub fn checked_div(&self, other: &Self) -> Option<Self> {
+        self.arithmetic(other, Number::checked_div)
+    }
+
+    /// # Instance Method `ColumnValue::checked_rem`
+    ///
+    /// Returns the remainder of dividing this value by another value, both of which must be
+    /// numbers, with the result of the type described in [`checked_mul`](Self::checked_mul). The
+    /// remainder has the sign of this value. Returns `None` if the values are not numbers or the
+    /// divisor is zero.
+    ///
+    /// ## Parameters
+    /// - `other`, type `&ColumnValue`; the divisor
+    #[must_use]
+    pub fn checked_rem(&self, other: &Self) -> Option<Self> {
+        self.arithmetic(other, Number::checked_rem)
+    }
+
+    /// # Instance Method `ColumnValue::checked_neg`
+    ///
+    /// Negates this value, which must be a number, keeping its type. Returns `None` if the value
+    /// is not a number or its type cannot represent the result, as for any unsigned integer other
+    /// than zero.
+    #[must_use]
+    pub fn checked_neg(&self) -> Option<Self> {
+        (-Number::of(self)?).to_value(&self.data_type()?)
+    }
+
+    /// # Instance Method `ColumnValue::concat`
+    ///
+    /// Concatenates this value with another value: a `String` with any other value, which is
+    /// concatenated as it is displayed, and an `Array` with another `Array`. Returns `None` if the
+    /// values cannot be concatenated or either is `null`.
+    ///
+    /// ## Parameters
+    /// - `other`, type `&ColumnValue`; the value to append
+    #[must_use]
+    pub fn concat(&self, other: &Self) -> Option<Self> {
+        match (self, other) {
+            (Self::Null, _) | (_, Self::Null) => None,
+            (Self::Array(left), Self::Array(right)) => {
+                Some(Self::Array(left.iter().chain(right).cloned().collect()))
+            }
+            (Self::String(_), _) | (_, Self::String(_)) => {
+                Some(Self::String(format!("{self}{other}")))
+            }
+            _ => None
+        }
+    }
+
+    /// # Instance Method `ColumnValue::data_type`
+    ///
+    /// Returns the type of this value, or `None` if the value is `null`. The element type of an
+    /// `Array` is the type of its first element that is not `null`, and is unknown if there is no
+    /// such element.
+    #[must_use]
+    pub fn data_type(&self) -> Option<ColumnDataType> {
+        let data_type = match self {
+            Self::Bool(_) => ColumnDataType::Bool,
+            Self::I8(_) => ColumnDataType::I8,
+            Self::I16(_) => ColumnDataType::I16,
+            Self::I32(_) => ColumnDataType::I32,
+            Self::I64(_) => ColumnDataType::I64,
+            Self::I128(_) => ColumnDataType::I128,
+            Self::U8(_) => ColumnDataType::U8,
+            Self::U16(_) => ColumnDataType::U16,
+            Self::U32(_) => ColumnDataType::U32,
+            Self::U64(_) => ColumnDataType::U64,
+            Self::U128(_) => ColumnDataType::U128,
+            Self::F32(_) => ColumnDataType::F32,
+            Self::F64(_) => ColumnDataType::F64,
+            Self::String(_) => ColumnDataType::String,
+            Self::Bytes(_) => ColumnDataType::Bytes,
+            Self::Timestamp(_) => ColumnDataType::Timestamp,
+            Self::Date(_) => ColumnDataType::Date,
+            Self::Duration(_) => ColumnDataType::Duration,
+            Self::Snowflake(_) => ColumnDataType::Snowflake,
+            Self::Uuid(_) => ColumnDataType::Uuid,
+            Self::Array(values) => {
+                ColumnDataType::Array(Box::new(values.iter().find_map(Self::data_type)?))
+            }
+            Self::Json(_) => ColumnDataType::Json,
+            Self::Enum(value) => ColumnDataType::Enum(value.type_name.clone()),
+            Self::Null => return None
+        };
+
+        Some(data_type)
+    }
+
+    /// Applies an arithmetic operation to two numbers, converting the result to the type described
+    /// in `checked_mul`.
+    fn arithmetic(&self, other: &Self, op: fn(Number, Number) -> Option<Number>) -> Option<Self> {
+        let data_type = numeric::result_type(&self.data_type()?, &other.data_type()?)?;
+
+        op(Number::of(self)?, Number::of(other)?)?.to_value(&data_type)
+    }
+
+    fn type_rank(&self) -> u8 {
+        match self {
+            Self::Bool(_) => 0,
+            Self::I8(_)
+            | Self::I16(_)
+            | Self::I32(_)
+            | Self::I64(_)
+            | Self::I128(_)
+            | Self::U8(_)
+            | Self::U16(_)
+            | Self::U32(_)
+            | Self::U64(_)
+            | Self::U128(_)
+            | Self::F32(_)
+            | Self::F64(_) => 1,
+            Self::String(_) => 2,
+            Self::Bytes(_) => 3,
+            Self::Timestamp(_) => 4,
+            Self::Date(_) => 5,
+            Self::Duration(_) => 6,
+            Self::Snowflake(_) => 7,
+            Self::Uuid(_) => 8,
+            Self::Array(_) => 9,
+            Self::Json(_) => 10,
+            Self::Enum(_) => 11,
+            Self::Null => u8::MAX
+        }
+    }
+}
+
+impl Display for ColumnDataType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let name = match self {
+            Self::Bool => "BOOL",
+            Self::I8 => "I8",
+            Self::I16 => "I16",
+            Self::I32 => "I32",
+            Self::I64 => "I64",
+            Self::I128 => "I128",
+            Self::U8 => "U8",
+            Self::U16 => "U16",
+            Self::U32 => "U32",
+            Self::U64 => "U64",
+            Self::U128 => "U128",
+            Self::F32 => "F32",
+            Self::F64 => "F64",
+            Self::String => "STRING",
+            Self::Bytes => "BYTES",
+            Self::Timestamp => "TIMESTAMP",
+            Self::Date => "DATE",
+            Self::Duration => "DURATION",
+            Self::Snowflake => "SNOWFLAKE",
+            Self::Uuid => "UUID",
+            Self::Array(element) => return write!(f, "{element}[]"),
+            Self::Json => "JSON",
+            Self::Enum(type_name) => type_name
+        };
+
+        f.write_str(name)
+    }
+}
+
+impl Display for ColumnValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Bool(value) => Display::fmt(value, f),
+            Self::I8(value) => Display::fmt(value, f),
+            Self::I16(value) => Display::fmt(value, f),
+            Self::I32(value) => Display::fmt(value, f),
+            Self::I64(value) => Display::fmt(value, f),
+            Self::I128(value) => Display::fmt(value, f),
+            Self::U8(value) => Display::fmt(value, f),
+            Self::U16(value) => Display::fmt(value, f),
+            Self::U32(value) => Display::fmt(value, f),
+            Self::U64(value) => Display::fmt(value, f),
+            Self::U128(value) => Display::fmt(value, f),
+            Self::F32(value) => Display::fmt(value, f),
+            Self::F64(value) => Display::fmt(value, f),
+            Self::String(value) => f.write_str(value),
+            Self::Bytes(bytes) => {
+                f.write_str("\\x")?;
+                bytes.iter().try_for_each(|byte| write!(f, "{byte:02x}"))
+            }
+            Self::Timestamp(timestamp) => {
+                let (seconds, nanoseconds) = seconds_since_epoch(*timestamp);
+                let day_length = i128::from(SECONDS_PER_DAY);
+                let (year, month, day) = civil_date(seconds.div_euclid(day_length));
+                let time = seconds.rem_euclid(day_length);
+
+                write!(
+                    f,
+                    "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{nanoseconds:09}Z",
+                    time / 3600,
+                    time / 60 % 60,
+                    time % 60
+                )
+            }
+            Self::Date(days) => {
+                let (year, month, day) = civil_date(i128::from(*days));
+                write!(f, "{year:04}-{month:02}-{day:02}")
+            }
+            Self::Duration(duration) => write!(f, "{duration:?}"),
+            Self::Snowflake(snowflake) => Display::fmt(snowflake, f),
+            Self::Uuid(uuid) => Display::fmt(uuid, f),
+            Self::Array(values) => {
+                f.write_str("[")?;
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(", ")?;
+                    }
+
+                    Display::fmt(value, f)?;
+                }
+                f.write_str("]")
+            }
+            Self::Json(json) => Display::fmt(json, f),
+            Self::Enum(value) => f.write_str(&value.label),
+            Self::Null => f.write_str("NULL")
+        }
+    }
+}
+
+impl Hash for ColumnValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.type_rank().hash(state);
+
+        // numbers that are equal hash the same regardless of their types
+        if let Some(number) = Number::of(self) {
+            number.hash(state);
+            return;
+        }
+
+        match self {
+            Self::Bool(value) => value.hash(state),
+            Self::String(value) => value.hash(state),
+            Self::Bytes(value) => value.hash(state),
+            Self::Timestamp(value) => value.hash(state),
+            Self::Date(value) => value.hash(state),
+            Self::Duration(value) => value.hash(state),
+            Self::Snowflake(value) => value.hash(state),
+            Self::Uuid(value) => value.hash(state),
+            Self::Array(values) => values.hash(state),
+            Self::Json(json) => json.hash(state),
+            Self::Enum(value) => value.hash(state),
+            _ => {}
+        }
+    }
+}
+
+/// Values are ordered for sorting: values that can be compared are ordered as they are by
+/// [`compare`](ColumnValue::compare), values of different types by the order of their types, values
+/// of different enum types by the names of the types, and `null` is greater than every other value.
+impl Ord for ColumnValue {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.compare(other), self, other) {
+            (Some(ordering), _, _) => ordering,
+            (None, Self::Enum(left), Self::Enum(right)) => left
+                .type_name
+                .cmp(&right.type_name)
+                .then(left.ordinal.cmp(&right.ordinal)),
+            (None, _, _) => self.type_rank().cmp(&other.type_rank())
+        }
+    }
+}
+
+impl PartialEq for ColumnValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Null, Self::Null) => true,
+            _ => self.compare(other) == Some(Ordering::Equal)
+        }
+    }
+}
+
+impl Eq for ColumnValue {}
+
+impl PartialOrd for ColumnValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Returns the timestamp of the start of a date, in UTC.
+fn start_of_date(days: i32) -> Option<SystemTime> {
+    let seconds = Duration::from_secs(u64::from(days.unsigned_abs()) * SECONDS_PER_DAY);
+
+    if days < 0 {
+        SystemTime::UNIX_EPOCH.checked_sub(seconds)
+    }
+    else {
+        SystemTime::UNIX_EPOCH.checked_add(seconds)
+    }
+}
+
+/// Returns the whole seconds since the Unix epoch of a timestamp, rounded down, and the
+/// nanoseconds since the start of that second.
+fn seconds_since_epoch(timestamp: SystemTime) -> (i128, u32) {
+    match timestamp.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(since) => (i128::from(since.as_secs()), since.subsec_nanos()),
+        Err(error) => {
+            let before = error.duration();
+
+            match before.subsec_nanos() {
+                0 => (-i128::from(before.as_secs()), 0),
+                nanoseconds => (-i128::from(before.as_secs()) - 1, 1_000_000_000 - nanoseconds)
+            }
+        }
+    }
+}
+
+/// Returns the year, month and day of the date a number of days since the Unix epoch, in the
+/// proleptic Gregorian calendar.
+fn civil_date(days: i128) -> (i128, i128, i128) {
+    // shift the epoch to 0000-03-01, so that leap days are at the end of each year, and count in
+    // eras of 400 years, which are all of the same length
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    }
+    else {
+        shifted_month - 9
+    };
+    let year = era * 400 + year_of_era + i128::from(month <= 2);
+
+    (year, month, day)
+}
+
+/// Hashes a floating point number consistently with `compare_floats`.
+pub(crate) fn hash_float<H: Hasher>(float: f64, state: &mut H) {
+    let canonical = if float.is_nan() {
+        f64::NAN
+    }
+    else if float == 0.0 {
+        0.0
+    }
+    else {
+        float
+    };
+
+    canonical.to_bits().hash(state);
+}
+
+/// Compares two floating point numbers, where `NaN` is equal to `NaN` and greater than every other
+/// number.
+pub(crate) fn compare_floats(left: f64, right: f64) -> Ordering {
+    match (left.is_nan(), right.is_nan()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (false, false) => left.partial_cmp(&right).unwrap_or(Ordering::Equal)
+    }
+}
+
+impl Default for QlCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cmp::Ordering,
+        collections::hash_map::DefaultHasher,
+        hash::{
+            Hash,
+            Hasher
+        },
+        time::{
+            Duration,
+            SystemTime
+        }
+    };
+
+    use crate::ColumnValue;
+
+    const DAY: Duration = Duration::from_hours(24);
+
+    fn hash(value: &ColumnValue) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+
+        hasher.finish()
+    }
+
+    #[test]
+    fn booleans_and_bytes_are_ordered_by_their_values() {
+        let f = ColumnValue::Bool(false);
+        let t = ColumnValue::Bool(true);
+        assert_eq!(f.compare(&t), Some(Ordering::Less));
+        assert_eq!(t.compare(&t), Some(Ordering::Equal));
+
+        // bytes are ordered lexicographically, a prefix before the longer array
+        let bytes = |bytes: &[u8]| ColumnValue::Bytes(bytes.to_vec());
+        assert_eq!(bytes(&[1]).compare(&bytes(&[1, 0])), Some(Ordering::Less));
+        assert_eq!(bytes(&[1, 0]).compare(&bytes(&[2])), Some(Ordering::Less));
+        assert_eq!(bytes(&[]).compare(&bytes(&[])), Some(Ordering::Equal));
+
+        // values of different types are not comparable
+        assert_eq!(t.compare(&ColumnValue::U8(1)), None);
+        assert_eq!(bytes(&[1]).compare(&ColumnValue::from("\u{1}")), None);
+    }
+
+    #[test]
+    fn floating_point_numbers_sort_with_nan_last() {
+        let mut values = [
+            ColumnValue::Null,
+            ColumnValue::F64(f64::NAN),
+            ColumnValue::F32(1.5),
+            ColumnValue::F64(f64::INFINITY),
+            ColumnValue::F32(f32::NAN),
+            ColumnValue::F64(-2.0),
+            ColumnValue::F64(f64::NEG_INFINITY)
+        ];
+        values.sort();
+
+        assert!(matches!(values[0], ColumnValue::F64(value) if value == f64::NEG_INFINITY));
+        assert_eq!(values[1], ColumnValue::F64(-2.0));
+        assert_eq!(values[2], ColumnValue::F32(1.5));
+        assert!(matches!(values[3], ColumnValue::F64(value) if value == f64::INFINITY));
+        assert!(values[4..6].iter().all(|value| {
+            matches!(value, ColumnValue::F32(value) if value.is_nan())
+                || matches!(value, ColumnValue::F64(value) if value.is_nan())
+        }));
+        assert_eq!(values[6], ColumnValue::Null);
+
+        // NaN is equal to NaN, unlike in IEEE 754, so that it can be grouped and deduplicated
+        assert_eq!(ColumnValue::F64(f64::NAN), ColumnValue::F32(f32::NAN));
+        assert_eq!(
+            ColumnValue::F64(f64::NAN).compare(&ColumnValue::F64(f64::MAX)),
+            Some(Ordering::Greater)
+        );
+    }
+
+    #[test]
+    fn values_that_are_equal_hash_the_same() {
+        let pairs = [
+            (ColumnValue::F64(-0.0), ColumnValue::F64(0.0)),
+            (ColumnValue::F32(-0.0), ColumnValue::F64(0.0)),
+            (ColumnValue::F64(f64::NAN), ColumnValue::F64(-f64::NAN)),
+            (ColumnValue::F32(f32::NAN), ColumnValue::F64(f64::NAN)),
+            (ColumnValue::F32(2.5), ColumnValue::F64(2.5)),
+            (ColumnValue::F64(3.0), ColumnValue::U8(3)),
+            (ColumnValue::Bool(true), ColumnValue::Bool(true)),
+            (ColumnValue::Bytes(vec![1, 2]), ColumnValue::Bytes(vec![1, 2]))
+        ];
+
+        for (left, right) in pairs {
+            assert_eq!(left, right);
+            assert_eq!(hash(&left), hash(&right));
+        }
+        assert_ne!(ColumnValue::Bool(true), ColumnValue::U8(1));
+        assert_ne!(hash(&ColumnValue::F64(0.0)), hash(&ColumnValue::F64(f64::NAN)));
+    }
+
+    #[test]
+    fn durations_are_added_to_and_subtracted_from_points_in_time() {
+        let at = |seconds| {
+            ColumnValue::Timestamp(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds))
+        };
+        let hour = ColumnValue::Duration(Duration::from_hours(1));
+
+        assert_eq!(at(0).checked_add(&hour), Some(at(3600)));
+        assert_eq!(hour.checked_add(&at(0)), Some(at(3600)));
+        assert_eq!(at(3600).checked_sub(&hour), Some(at(0)));
+        assert_eq!(at(3600).checked_sub(&at(0)), Some(hour.clone()));
+
+        // a date is added to as the timestamp of its start
+        assert_eq!(ColumnValue::Date(1).checked_add(&hour), Some(at(DAY.as_secs() + 3600)));
+        assert_eq!(ColumnValue::Date(1).checked_sub(&hour), Some(at(DAY.as_secs() - 3600)));
+        assert_eq!(
+            ColumnValue::Date(3).checked_sub(&ColumnValue::Date(1)),
+            Some(ColumnValue::Duration(2 * DAY))
+        );
+
+        assert_eq!(
+            hour.checked_add(&hour),
+            Some(ColumnValue::Duration(Duration::from_hours(2)))
+        );
+        assert_eq!(hour.checked_sub(&hour), Some(ColumnValue::Duration(Duration::ZERO)));
+
+        // durations cannot be negative
+        assert_eq!(at(0).checked_sub(&at(1)), None);
+        assert_eq!(ColumnValue::Date(1).checked_sub(&ColumnValue::Date(3)), None);
+        assert_eq!(ColumnValue::Duration(Duration::ZERO).checked_sub(&hour), None);
+
+        // overflow and operands that cannot be combined
+        let longest = ColumnValue::Duration(Duration::MAX);
+        assert_eq!(longest.checked_add(&hour), None);
+        assert_eq!(at(0).checked_add(&longest), None);
+        assert_eq!(at(0).checked_add(&at(0)), None);
+        assert_eq!(hour.checked_sub(&at(0)), None);
+        assert_eq!(ColumnValue::Date(1).checked_add(&ColumnValue::Date(1)), None);
+        assert_eq!(at(0).checked_add(&ColumnValue::U64(1)), None);
+    }
+
+    #[test]
+    fn dates_and_timestamps_are_displayed_in_the_gregorian_calendar() {
+        let cases = [
+            (0, "1970-01-01"),
+            (-1, "1969-12-31"),
+            (11016, "2000-02-29"),
+            (-25509, "1900-02-28"),
+            (20088, "2024-12-31"),
+            (-719_162, "0001-01-01")
+        ];
+
+        for (days, display) in cases {
+            assert_eq!(ColumnValue::Date(days).to_string(), display);
+        }
+
+        assert_eq!(
+            ColumnValue::Timestamp(SystemTime::UNIX_EPOCH).to_string(),
+            "1970-01-01T00:00:00.000000000Z"
+        );
+        assert_eq!(
+            ColumnValue::Timestamp(
+                SystemTime::UNIX_EPOCH + Duration::new(1_677_906_367, 123_456_789)
+            )
+            .to_string(),
+            "2023-03-04T05:06:07.123456789Z"
+        );
+        assert_eq!(
+            ColumnValue::Timestamp(SystemTime::UNIX_EPOCH - Duration::from_secs(1)).to_string(),
+            "1969-12-31T23:59:59.000000000Z"
+        );
+        assert_eq!(
+            ColumnValue::date_of(SystemTime::UNIX_EPOCH + 11016 * DAY + Duration::from_secs(1)),
+            ColumnValue::Date(11016)
+        );
     }
 }
@@ -15,16 +15,73 @@
 #![feature(type_alias_impl_trait)]
 #![feature(let_chains)]
 
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        Mutex,
+        Weak
+    },
+    time::{
+        Duration,
+        SystemTime
+    }
+};
+
 use dashmap::DashMap;
 
 use crate::{
+    clock::Clock,
     error::QlResult,
-    ql::key::PrimaryKey
+    eviction::{
+        CacheCapacity,
+        CapacityLimits,
+        EvictionPolicy,
+        Usage
+    },
+    expiry::ExpiryQueue,
+    mvcc::{
+        GarbageQueue,
+        VersionChain,
+        VersionClock
+    },
+    ql::{
+        function::UserFunction,
+        key::{
+            ForeignIndex,
+            ForeignKey,
+            PrimaryIndex,
+            PrimaryKey
+        },
+        materialized::{
+            Materialization,
+            MaterializedView
+        },
+        view::View
+    },
+    types::{
+        EnumType,
+        EnumValue,
+        JsonValue,
+        Snowflake,
+        Uuid
+    }
 };
 
+pub mod clock;
+pub mod convert;
 pub mod error;
+pub mod eviction;
+pub mod expiry;
 pub mod r#impl;
+pub mod mvcc;
+pub mod numeric;
 pub mod ql;
+pub mod transaction;
+pub mod types;
+
+#[cfg(test)]
+mod testing;
 
 /// # Struct `QlCache`
 ///
@@ -32,7 +89,12 @@ pub mod ql;
 #[allow(dead_code)]
 #[derive(Clone)]
 pub struct QlCache {
-    pub(crate) cache: DashMap<String, CacheSchema>
+    pub(crate) cache: Arc<DashMap<String, CacheSchema>>,
+    pub(crate) clock: Arc<VersionClock>,
+    pub(crate) time: Arc<dyn Clock>,
+    pub(crate) capacity: Arc<CacheCapacity>,
+    pub(crate) functions: DashMap<String, UserFunction>,
+    pub(crate) definitions: Arc<Mutex<()>>
 }
 
 /// # Struct `CacheSchema`
@@ -48,7 +110,22 @@ pub struct CacheSchema {
     /// # Struct Field `tables`
     ///
     /// The tables of the schema.
-    pub tables: DashMap<String, CacheTable>
+    pub tables: DashMap<String, CacheTable>,
+
+    /// # Struct Field `types`
+    ///
+    /// The user-defined enum types of the schema.
+    pub types: DashMap<String, EnumType>,
+
+    /// # Struct Field `views`
+    ///
+    /// The views of the schema.
+    pub views: DashMap<String, View>,
+
+    /// # Struct Field `materialized_views`
+    ///
+    /// The materialized views of the schema.
+    pub materialized_views: DashMap<String, MaterializedView>
 }
 
 /// # Struct `CacheTable`
@@ -71,10 +148,39 @@ pub struct CacheTable {
     /// The primary key of the table.
     pub primary_key: Option<PrimaryKey>,
 
+    /// # Struct Field `foreign_keys`
+    ///
+    /// The foreign keys of the table.
+    pub foreign_keys: Vec<ForeignKey>,
+
     /// # Struct Field `rows`
     ///
-    /// The rows of the table.
-    pub rows: DashMap<u64, CacheTableRow>
+    /// The rows of the table, each stored as a chain of versions.
+    pub rows: DashMap<u64, VersionChain>,
+
+    /// # Struct Field `ttl`
+    ///
+    /// The default time-to-live of the rows of the table, rows never expire if it is `None`.
+    pub ttl: Option<Duration>,
+
+    /// # Struct Field `limits`
+    ///
+    /// The capacity limits of the table.
+    pub limits: CapacityLimits,
+
+    pub(crate) schema: String,
+    pub(crate) schemas: Weak<DashMap<String, CacheSchema>>,
+    pub(crate) clock: Arc<VersionClock>,
+    pub(crate) time: Arc<dyn Clock>,
+    pub(crate) expirations: ExpiryQueue,
+    pub(crate) garbage: GarbageQueue,
+    pub(crate) primary_index: PrimaryIndex,
+    pub(crate) foreign_indexes: Vec<ForeignIndex>,
+    pub(crate) eviction_policy: Arc<dyn EvictionPolicy>,
+    pub(crate) usage: Usage,
+    pub(crate) capacity: Arc<CacheCapacity>,
+    pub(crate) enum_types: HashMap<String, EnumType>,
+    pub(crate) materializations: DashMap<String, Arc<Materialization>>
 }
 
 /// # Struct `CacheTableRow`
@@ -111,8 +217,11 @@ where
 ///
 /// The datatype of a column. Almost all of the variants correspond to their Rust types.
 #[allow(missing_docs)] // variants are pretty self explanatory?
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum ColumnDataType {
+    // boolean
+    Bool,
+
     // integer types
     I8,
     I16,
@@ -125,16 +234,50 @@ pub enum ColumnDataType {
     U64,
     U128,
 
+    // floating point types
+    F32,
+    F64,
+
     // text
-    String
+    String,
+
+    // byte array
+    Bytes,
+
+    // date and time
+    Timestamp,
+    Date,
+    Duration,
+
+    // identifiers
+    Snowflake,
+    Uuid,
+
+    // collections
+    Array(Box<ColumnDataType>),
+    Json,
+
+    // user-defined enum type, by name
+    Enum(String)
 }
 
 /// # Enumeration `ColumnValue`
 ///
 /// The value of a column.
+///
+/// Unlike their Rust types, floating point values are totally ordered: `NaN` is equal to `NaN`
+/// and greater than every other number, and `-0.0` is equal to `0.0`.
+///
+/// A `Timestamp` is a point in time in UTC with nanosecond precision, and a `Date` is the number of
+/// days since the Unix epoch (1970-01-01).
+///
+/// Values convert from and into Rust types as described in [`convert`](crate::convert).
 #[allow(missing_docs)] // variants are pretty self explanatory?
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Clone, Debug)]
 pub enum ColumnValue {
+    // boolean
+    Bool(bool),
+
     // integer types
     I8(i8),
     I16(i16),
@@ -147,16 +290,50 @@ pub enum ColumnValue {
     U64(u64),
     U128(u128),
 
+    // floating point types
+    F32(f32),
+    F64(f64),
+
     // text
     String(String),
 
+    // byte array
+    Bytes(Vec<u8>),
+
+    // date and time
+    Timestamp(SystemTime),
+    Date(i32),
+    Duration(Duration),
+
+    // identifiers
+    Snowflake(Snowflake),
+    Uuid(Uuid),
+
+    // collections
+    Array(Vec<ColumnValue>),
+    Json(JsonValue),
+
+    // user-defined enum type
+    Enum(EnumValue),
+
     // null
     Null
 }
 
 #[cfg(test)]
 mod tests {
+    use std::{
+        fmt::{
+            Debug,
+            Display
+        },
+        hash::Hash
+    };
+
     use super::{
+        error::QlError,
+        ql::QueryBuilder,
+        testing,
         CacheTable,
         CacheTableRow,
         ColumnDataType,
@@ -167,6 +344,136 @@ mod tests {
     static_assertions::assert_impl_all!(QlCache: Clone, Send, Sync);
     static_assertions::assert_impl_all!(CacheTable: Clone, Send, Sync);
     static_assertions::assert_impl_all!(CacheTableRow: Clone, Send, Sync);
-    static_assertions::assert_impl_all!(ColumnDataType: Clone, Eq, PartialEq, Send, Sync);
-    static_assertions::assert_impl_all!(ColumnValue: Clone, Eq, PartialEq, Send, Sync);
+    static_assertions::assert_impl_all!(
+        ColumnDataType: Clone,
+        Debug,
+        Display,
+        Eq,
+        Hash,
+        Ord,
+        PartialEq,
+        PartialOrd,
+        Send,
+        Sync
+    );
+    static_assertions::assert_impl_all!(
+        ColumnValue: Clone,
+        Debug,
+        Display,
+        Eq,
+        Hash,
+        Ord,
+        PartialEq,
+        PartialOrd,
+        Send,
+        Sync
+    );
+
+    fn cache() -> QlCache {
+        let cache = QlCache::new();
+        testing::table(
+            &cache,
+            "Members",
+            [
+                ("Xp", ColumnDataType::U64, false),
+                ("Tags", ColumnDataType::Array(Box::new(ColumnDataType::String)), true)
+            ],
+            []
+        );
+
+        cache
+    }
+
+
+    #[test]
+    fn rows_are_validated_against_column_types() {
+        let cache = cache();
+
+        cache
+            .with_table("Members", |table| {
+                assert!(table.insert_row(1, testing::row([("Xp", ColumnValue::U64(1))])).is_ok());
+                assert!(table
+                    .insert_row(2, testing::row([
+                        ("Xp", ColumnValue::U64(2)),
+                        ("Tags", ColumnValue::Null)
+                    ]))
+                    .is_ok());
+                assert!(table
+                    .insert_row(3, testing::row([
+                        ("Xp", ColumnValue::U64(3)),
+                        ("Tags", ColumnValue::Array(vec![
+                            ColumnValue::String(String::from("Officer")),
+                            ColumnValue::Null
+                        ]))
+                    ]))
+                    .is_ok());
+
+                // numbers of other types are comparable with the column, but are not stored
+                assert!(matches!(
+                    table.insert_row(4, testing::row([("Xp", ColumnValue::I64(4))])),
+                    Err(QlError::ColumnTypeMismatch { .. })
+                ));
+                assert!(matches!(
+                    table.insert_row(5, testing::row([
+                        ("Xp", ColumnValue::U64(5)),
+                        ("Tags", ColumnValue::Array(vec![
+                            ColumnValue::String(String::from("Officer")),
+                            ColumnValue::U64(1)
+                        ]))
+                    ])),
+                    Err(QlError::ColumnTypeMismatch { .. })
+                ));
+                assert!(matches!(
+                    table.insert_row(6, testing::row([("Xp", ColumnValue::Null)])),
+                    Err(QlError::ColumnCannotBeNull { .. })
+                ));
+                assert!(matches!(
+                    table.insert_row(7, testing::row([
+                        ("Xp", ColumnValue::U64(7)),
+                        ("Rank", ColumnValue::U64(1))
+                    ])),
+                    Err(QlError::ColumnDoesNotExist { .. })
+                ));
+
+                assert!(table.row(1).is_some());
+                assert!((4..=7).all(|key| table.row(key).is_none()));
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn every_write_path_validates_rows() {
+        let cache = cache();
+        let invalid = || testing::row([("Xp", ColumnValue::String(String::from("many")))]);
+
+        cache
+            .with_table("Members", |table| {
+                assert!(matches!(
+                    table.insert_row_with_ttl(1, invalid(), std::time::Duration::from_secs(1)),
+                    Err(QlError::ColumnTypeMismatch { .. })
+                ));
+                table.insert_row(1, testing::row([("Xp", ColumnValue::U64(1))])).unwrap();
+            })
+            .unwrap();
+        assert!(matches!(
+            cache.compare_and_swap("Members", 1, 0, invalid()),
+            Err(QlError::ColumnTypeMismatch { .. })
+        ));
+        assert!(matches!(
+            cache
+                .begin()
+                .insert_row(String::from("Members"), 2, invalid()),
+            Err(QlError::ColumnTypeMismatch { .. })
+        ));
+        assert!(matches!(
+            QueryBuilder::update()
+                .table_name(String::from("Members"))
+                .key(1)
+                .set(String::from("Xp"), ColumnValue::String(String::from("many")))
+                .build()
+                .unwrap()
+                .execute(&cache),
+            Err(QlError::ColumnTypeMismatch { .. })
+        ));
+    }
 }
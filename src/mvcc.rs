@@ -0,0 +1,581 @@
+//! # Module `mvcc`
+//!
+//! This module implements multi-version concurrency control for the rows of the cache, allowing
+//! reads to run against a consistent snapshot while writers proceed.
+//!
+//! Every write is committed at a timestamp handed out by the [`VersionClock`] of the cache, and
+//! creates a new [`RowVersion`] instead of overwriting the old one in place. A [`Snapshot`] only
+//! sees the versions that were committed at or before its timestamp. Versions that are no longer
+//! visible to any active snapshot are garbage-collected: the rows whose latest version was ended
+//! are queued in a [`GarbageQueue`], from which a bounded number are pruned whenever their table
+//! is written to, or by [`QlCache::collect_garbage`](crate::QlCache::collect_garbage).
+//!
+//! Commits are serialized by a single lock per cache, shared by all of its tables. A commit only
+//! holds it while its versions are installed and rows are evicted, which never waits for anything
+//! but shard locks of the cache, and those are never held while waiting for the commit lock.
+
+use std::{
+    collections::{
+        BTreeMap,
+        VecDeque
+    },
+    sync::{
+        atomic::{
+            AtomicU64,
+            Ordering
+        },
+        Arc,
+        Mutex,
+        PoisonError
+    },
+    time::SystemTime
+};
+
+use crate::CacheTableRow;
+
+/// # Struct `VersionClock`
+///
+/// The logical clock of a cache, hands out commit timestamps and keeps track of the active
+/// snapshots.
+#[derive(Default)]
+pub struct VersionClock {
+    pub(crate) current: AtomicU64,
+    pub(crate) commit_lock: Mutex<()>,
+    pub(crate) active: Mutex<BTreeMap<u64, usize>>
+}
+
+impl VersionClock {
+    /// # Instance Method `VersionClock::timestamp`
+    ///
+    /// Returns the timestamp of the latest commit.
+    #[must_use]
+    pub fn timestamp(&self) -> u64 {
+        self.current.load(Ordering::Acquire)
+    }
+
+    /// # Instance Method `VersionClock::active_snapshots`
+    ///
+    /// Returns the number of snapshots that are currently alive.
+    #[must_use]
+    pub fn active_snapshots(&self) -> usize {
+        self.active
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .values()
+            .sum()
+    }
+
+    /// Takes a snapshot at the timestamp of the latest commit.
+    pub(crate) fn snapshot(self: &Arc<Self>) -> Snapshot {
+        let mut active = self.active.lock().unwrap_or_else(PoisonError::into_inner);
+        let timestamp = self.timestamp();
+        *active.entry(timestamp).or_insert(0) += 1;
+
+        Snapshot {
+            timestamp,
+            clock: self.clone()
+        }
+    }
+
+    /// Commits a write. Writes to every table of the cache are serialized by the same lock, and
+    /// the new timestamp only becomes visible to new snapshots after `install` returns, so a
+    /// snapshot never observes a half-installed commit.
+    pub(crate) fn commit<T>(&self, install: impl FnOnce(u64) -> T) -> T {
+        let _guard = self.commit_lock.lock().unwrap_or_else(PoisonError::into_inner);
+        let timestamp = self.timestamp() + 1;
+        let result = install(timestamp);
+        self.current.store(timestamp, Ordering::Release);
+
+        result
+    }
+
+    /// Runs `f` while no write can be committed, without committing a write itself.
+    pub(crate) fn exclusive<T>(&self, f: impl FnOnce() -> T) -> T {
+        let _guard = self.commit_lock.lock().unwrap_or_else(PoisonError::into_inner);
+
+        f()
+    }
+
+    /// Returns the oldest timestamp that may still be read; versions that ended at or before it
+    /// are not visible to any snapshot.
+    pub(crate) fn watermark(&self) -> u64 {
+        let active = self.active.lock().unwrap_or_else(PoisonError::into_inner);
+
+        active
+            .keys()
+            .next()
+            .copied()
+            .unwrap_or_else(|| self.timestamp())
+    }
+
+    fn release(&self, timestamp: u64) {
+        let mut active = self.active.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if let Some(count) = active.get_mut(&timestamp) {
+            *count -= 1;
+
+            if *count == 0 {
+                active.remove(&timestamp);
+            }
+        }
+    }
+}
+
+/// # Struct `GarbageQueue`
+///
+/// The keys of the rows of a table whose latest version was ended, in the order of the commits
+/// that ended them.
+#[derive(Default)]
+pub struct GarbageQueue {
+    pub(crate) queue: Mutex<VecDeque<(u64, u64)>>
+}
+
+impl GarbageQueue {
+    /// # Instance Method `GarbageQueue::len`
+    ///
+    /// Returns the number of ended versions that have not been collected yet.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.queue.lock().unwrap_or_else(PoisonError::into_inner).len()
+    }
+
+    /// # Instance Method `GarbageQueue::is_empty`
+    ///
+    /// Returns whether the queue is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Records that the latest version of the row with the specified key was ended at
+    /// `timestamp`. Must be called while committing, so that the queue stays ordered by timestamp.
+    pub(crate) fn push(&self, timestamp: u64, key: u64) {
+        self.queue
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push_back((timestamp, key));
+    }
+
+    /// Removes and returns at most `limit` keys of rows with a version that ended at or before
+    /// `watermark`.
+    pub(crate) fn pop_collectable(&self, watermark: u64, limit: usize) -> Vec<u64> {
+        let mut queue = self.queue.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut keys = Vec::new();
+
+        while keys.len() < limit {
+            match queue.front() {
+                Some((end, _)) if *end <= watermark => {
+                    keys.extend(queue.pop_front().map(|(_, key)| key));
+                }
+                _ => break
+            }
+        }
+
+        keys
+    }
+}
+
+impl Clone for GarbageQueue {
+    fn clone(&self) -> Self {
+        Self {
+            queue: Mutex::new(
+                self.queue
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .clone()
+            )
+        }
+    }
+}
+
+/// # Struct `Snapshot`
+///
+/// A consistent, read-only view of the cache at a point in time. Versions referenced by a
+/// snapshot are kept alive until the snapshot is dropped.
+///
+/// ## Example
+///
+/// ```
+/// use qlcache::{
+///     ql::{
+///         select::SelectScope,
+///         QueryBuilder
+///     },
+///     QlCache
+/// };
+///
+/// let cache = QlCache::new();
+/// let snapshot = cache.snapshot();
+///
+/// let select = QueryBuilder::select()
+///     .table_name(String::from("TableName"))
+///     .scope(SelectScope::Everything)
+///     .snapshot(snapshot.clone())
+///     .build()
+///     .unwrap();
+/// ```
+pub struct Snapshot {
+    pub(crate) timestamp: u64,
+    pub(crate) clock: Arc<VersionClock>
+}
+
+impl Snapshot {
+    /// # Instance Method `Snapshot::timestamp`
+    ///
+    /// Returns the timestamp this snapshot reads at.
+    #[must_use]
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+}
+
+impl Clone for Snapshot {
+    fn clone(&self) -> Self {
+        let mut active = self.clock.active.lock().unwrap_or_else(PoisonError::into_inner);
+        *active.entry(self.timestamp).or_insert(0) += 1;
+
+        Self {
+            timestamp: self.timestamp,
+            clock: self.clock.clone()
+        }
+    }
+}
+
+impl Drop for Snapshot {
+    fn drop(&mut self) {
+        self.clock.release(self.timestamp);
+    }
+}
+
+/// # Struct `RowVersion`
+///
+/// A version of a row, visible to snapshots taken at or after `begin`, and before `end`, as long
+/// as it has not expired.
+#[derive(Clone)]
+pub struct RowVersion {
+    pub(crate) begin: u64,
+    pub(crate) end: Option<u64>,
+    pub(crate) expires_at: Option<SystemTime>,
+    pub(crate) row: Arc<CacheTableRow>
+}
+
+impl RowVersion {
+    /// # Instance Method `RowVersion::is_visible_at`
+    ///
+    /// Returns whether this version is visible to a snapshot taken at `timestamp`.
+    ///
+    /// ## Parameters
+    /// - `timestamp`, type `u64`; the timestamp of the snapshot
+    #[must_use]
+    pub fn is_visible_at(&self, timestamp: u64) -> bool {
+        self.begin <= timestamp && !matches!(self.end, Some(end) if end <= timestamp)
+    }
+
+    /// # Instance Method `RowVersion::expires_at`
+    ///
+    /// Returns the time at which this version expires, if it has a time-to-live.
+    #[must_use]
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        self.expires_at
+    }
+
+    /// # Instance Method `RowVersion::is_expired_at`
+    ///
+    /// Returns whether this version has expired at the specified time.
+    ///
+    /// ## Parameters
+    /// - `now`, type `SystemTime`; the current time
+    #[must_use]
+    pub fn is_expired_at(&self, now: SystemTime) -> bool {
+        matches!(self.expires_at, Some(expires_at) if expires_at <= now)
+    }
+
+    /// # Instance Method `RowVersion::version`
+    ///
+    /// Returns the version number of the row, which is the timestamp of the commit that wrote
+    /// it. Version numbers are never reused, even if a row is removed and inserted again.
+    #[must_use]
+    pub fn version(&self) -> u64 {
+        self.begin
+    }
+
+    /// # Instance Method `RowVersion::row`
+    ///
+    /// Returns the row of this version.
+    #[must_use]
+    pub fn row(&self) -> &CacheTableRow {
+        &self.row
+    }
+}
+
+/// # Struct `VersionChain`
+///
+/// The versions of a row, ordered from the oldest to the newest.
+#[derive(Clone, Default)]
+pub struct VersionChain {
+    pub(crate) versions: Vec<RowVersion>
+}
+
+impl VersionChain {
+    /// # Instance Method `VersionChain::visible_at`
+    ///
+    /// Returns the version of the row that is visible to a snapshot taken at `timestamp`, if any.
+    ///
+    /// ## Parameters
+    /// - `timestamp`, type `u64`; the timestamp of the snapshot
+    #[must_use]
+    pub fn visible_at(&self, timestamp: u64) -> Option<&Arc<CacheTableRow>> {
+        self.visible_version_at(timestamp).map(|version| &version.row)
+    }
+
+    /// # Instance Method `VersionChain::visible_version_at`
+    ///
+    /// Returns the version that is visible to a snapshot taken at `timestamp`, if any.
+    ///
+    /// ## Parameters
+    /// - `timestamp`, type `u64`; the timestamp of the snapshot
+    #[must_use]
+    pub fn visible_version_at(&self, timestamp: u64) -> Option<&RowVersion> {
+        self.versions
+            .iter()
+            .rev()
+            .find(|version| version.is_visible_at(timestamp))
+    }
+
+    /// # Instance Method `VersionChain::latest`
+    ///
+    /// Returns the latest committed version of the row, or `None` if the row has been deleted.
+    #[must_use]
+    pub fn latest(&self) -> Option<&Arc<CacheTableRow>> {
+        self.latest_version().map(|version| &version.row)
+    }
+
+    /// # Instance Method `VersionChain::latest_version`
+    ///
+    /// Returns the latest committed version, or `None` if the row has been deleted.
+    #[must_use]
+    pub fn latest_version(&self) -> Option<&RowVersion> {
+        self.versions.last().filter(|version| version.end.is_none())
+    }
+
+    /// Returns whether a version of the row was committed or ended after `timestamp`.
+    pub(crate) fn is_changed_after(&self, timestamp: u64) -> bool {
+        self.versions.last().is_some_and(|version| {
+            version.begin > timestamp || version.end.is_some_and(|end| end > timestamp)
+        })
+    }
+
+    /// # Instance Method `VersionChain::versions`
+    ///
+    /// Returns the versions of the row that have not been garbage-collected yet.
+    #[must_use]
+    pub fn versions(&self) -> &[RowVersion] {
+        &self.versions
+    }
+
+    /// Ends the latest version at `timestamp`, and appends `row` as the new latest version if it
+    /// is `Some`, expiring at `expires_at`. Returns the row of the live version that was ended.
+    pub(crate) fn push(
+        &mut self,
+        timestamp: u64,
+        row: Option<CacheTableRow>,
+        expires_at: Option<SystemTime>
+    ) -> Option<Arc<CacheTableRow>> {
+        let ended = match self.versions.last_mut() {
+            Some(latest) if latest.end.is_none() => {
+                latest.end.replace(timestamp);
+                Some(latest.row.clone())
+            }
+            _ => None
+        };
+
+        if let Some(row) = row {
+            self.versions.push(RowVersion {
+                begin: timestamp,
+                end: None,
+                expires_at,
+                row: Arc::new(row)
+            });
+        }
+
+        ended
+    }
+
+    /// Removes the versions that are not visible to any snapshot at or after `watermark`.
+    pub(crate) fn prune(&mut self, watermark: u64) {
+        self.versions
+            .retain(|version| !matches!(version.end, Some(end) if end <= watermark));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{
+        GarbageQueue,
+        RowVersion,
+        Snapshot,
+        VersionChain,
+        VersionClock
+    };
+    use crate::{
+        ql::{
+            select::SelectScope,
+            QueryBuilder
+        },
+        testing,
+        CacheTableRow,
+        ColumnDataType,
+        ColumnValue,
+        QlCache
+    };
+
+    static_assertions::assert_impl_all!(GarbageQueue: Clone, Default, Send, Sync);
+    static_assertions::assert_impl_all!(RowVersion: Clone, Send, Sync);
+    static_assertions::assert_impl_all!(Snapshot: Clone, Send, Sync);
+    static_assertions::assert_impl_all!(VersionChain: Clone, Default, Send, Sync);
+    static_assertions::assert_impl_all!(VersionClock: Default, Send, Sync);
+
+    fn row(xp: u64) -> CacheTableRow {
+        testing::row([("Xp", ColumnValue::U64(xp))])
+    }
+
+    fn xp(row: &CacheTableRow) -> u64 {
+        match row.column_values.get("Xp").as_deref() {
+            Some(ColumnValue::U64(xp)) => *xp,
+            _ => unreachable!()
+        }
+    }
+
+    #[test]
+    fn chains_show_each_timestamp_the_version_committed_before_it() {
+        let mut chain = VersionChain::default();
+        assert!(chain.push(1, Some(row(10)), None).is_none());
+        let ended = chain.push(3, Some(row(20)), None);
+        assert_eq!(ended.map(|row| xp(&row)), Some(10));
+        assert!(chain.push(5, None, None).is_some());
+
+        assert!(chain.visible_at(0).is_none());
+        assert_eq!(chain.visible_at(1).map(|row| xp(row)), Some(10));
+        assert_eq!(chain.visible_at(2).map(|row| xp(row)), Some(10));
+        assert_eq!(chain.visible_at(3).map(|row| xp(row)), Some(20));
+        assert!(chain.visible_at(5).is_none());
+        assert!(chain.latest().is_none());
+
+        // the versions that ended at or before the watermark are not visible to any snapshot
+        chain.prune(3);
+        assert_eq!(chain.versions().len(), 1);
+        assert!(chain.versions()[0].is_visible_at(4));
+        assert_eq!(xp(chain.versions()[0].row()), 20);
+    }
+
+    #[test]
+    fn snapshots_hold_the_watermark_until_they_are_dropped() {
+        let clock = Arc::new(VersionClock::default());
+        clock.commit(|_| ());
+        let snapshot = clock.snapshot();
+        let copy = snapshot.clone();
+        clock.commit(|_| ());
+
+        assert_eq!(clock.timestamp(), 2);
+        assert_eq!(clock.active_snapshots(), 2);
+        assert_eq!(clock.watermark(), 1);
+
+        drop(snapshot);
+        assert_eq!(clock.watermark(), 1);
+        drop(copy);
+        assert_eq!(clock.active_snapshots(), 0);
+        assert_eq!(clock.watermark(), 2);
+    }
+
+    #[test]
+    fn queries_read_the_rows_of_their_snapshots() {
+        let cache = QlCache::new();
+        testing::table(
+            &cache,
+            "Members",
+            [("Xp", ColumnDataType::U64, false)],
+            [10, 20].map(|xp| [ColumnValue::U64(xp)])
+        );
+
+        let snapshot = cache.snapshot();
+        cache
+            .with_table("Members", |table| {
+                table.insert_row(1, row(11)).unwrap();
+                assert!(table.remove_row(2));
+                table.insert_row(3, row(30)).unwrap();
+            })
+            .unwrap();
+
+        let read = |snapshot: Option<Snapshot>| {
+            let mut builder = QueryBuilder::select()
+                .table_name(String::from("Members"))
+                .scope(SelectScope::Everything);
+            if let Some(snapshot) = snapshot {
+                builder = builder.snapshot(snapshot);
+            }
+            let mut values = builder
+                .build()
+                .unwrap()
+                .execute(&cache)
+                .unwrap()
+                .iter()
+                .map(xp)
+                .collect::<Vec<_>>();
+            values.sort_unstable();
+
+            values
+        };
+        assert_eq!(read(Some(snapshot.clone())), vec![10, 20]);
+        assert_eq!(read(None), vec![11, 30]);
+
+        // the versions the snapshot reads survive garbage collection until it is dropped
+        let versions = || {
+            cache
+                .with_table("Members", |table| {
+                    table.rows.iter().map(|chain| chain.versions().len()).sum::<usize>()
+                })
+                .unwrap()
+        };
+        cache.collect_garbage();
+        assert_eq!(versions(), 4);
+        assert_eq!(read(Some(snapshot.clone())), vec![10, 20]);
+
+        drop(snapshot);
+        cache.collect_garbage();
+        assert_eq!(versions(), 2);
+    }
+
+    #[test]
+    fn versions_are_reclaimed_as_the_table_is_written_to() {
+        let cache = QlCache::new();
+        testing::table(&cache, "Members", [("Xp", ColumnDataType::U64, false)], []);
+
+        cache
+            .with_table("Members", |table| {
+                (1..=32).for_each(|key| table.insert_row(key, row(key)).unwrap());
+                let snapshot = cache.snapshot();
+                (1..=32).for_each(|key| assert!(table.remove_row(key)));
+
+                // the snapshot still reads the removed rows
+                assert_eq!(table.visible_rows(snapshot.timestamp()).len(), 32);
+                assert_eq!(table.rows.len(), 32);
+                drop(snapshot);
+
+                // each write prunes a bounded number of rows whose versions ended, without the
+                // garbage being collected explicitly
+                table.insert_row(100, row(100)).unwrap();
+                assert_eq!(table.rows.len(), 17);
+                table.insert_row(100, row(101)).unwrap();
+                assert_eq!(table.rows.len(), 1);
+                assert_eq!(table.garbage.len(), 1);
+                assert_eq!(table.rows.get(&100).unwrap().versions().len(), 2);
+
+                table.insert_row(101, row(101)).unwrap();
+                assert!(table.garbage.is_empty());
+                assert_eq!(table.rows.get(&100).unwrap().versions().len(), 1);
+            })
+            .unwrap();
+    }
+}
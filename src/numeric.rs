@@ -0,0 +1,770 @@
+//! # Module `numeric`
+//!
+//! This module implements the comparison of numbers of different types.
+//!
+//! Every integer type is promoted to a sign and a 128-bit magnitude, which represents the values
+//! of all of them exactly, and floating point types are promoted to `f64`. Integers and floating
+//! point numbers are compared by their exact values, so that, for example, `U64(u64::MAX)` is
+//! less than `F64(18446744073709551616.0)` even though the two convert to the same `f64`.
+
+use std::{
+    cmp::Ordering,
+    hash::{
+        Hash,
+        Hasher
+    },
+    ops::Neg
+};
+
+use crate::{
+    r#impl::{
+        compare_floats,
+        hash_float
+    },
+    ColumnDataType,
+    ColumnValue
+};
+
+/// `2^128`, the smallest magnitude of a floating point number that no integer type reaches.
+const INTEGER_LIMIT: f64 = 340_282_366_920_938_463_463_374_607_431_768_211_456.0;
+
+/// # Enumeration `Number`
+///
+/// The value of a number of any numeric type.
+#[derive(Clone, Copy, Debug)]
+pub enum Number {
+    /// # Enumeration Variant `Number::Integer`
+    ///
+    /// An integer, the magnitude of zero is never negative.
+    Integer {
+        /// Whether the integer is less than zero.
+        negative: bool,
+
+        /// The absolute value of the integer.
+        magnitude: u128
+    },
+
+    /// # Enumeration Variant `Number::Float`
+    ///
+    /// A floating point number.
+    Float(f64)
+}
+
+impl Number {
+    /// # Static Method `Number::of`
+    ///
+    /// Returns the number of a value, or `None` if the value is not of a numeric type.
+    ///
+    /// ## Parameters
+    /// - `value`, type `&ColumnValue`; the value
+    #[must_use]
+    pub fn of(value: &ColumnValue) -> Option<Self> {
+        let number = match value {
+            ColumnValue::I8(value) => Self::signed(i128::from(*value)),
+            ColumnValue::I16(value) => Self::signed(i128::from(*value)),
+            ColumnValue::I32(value) => Self::signed(i128::from(*value)),
+            ColumnValue::I64(value) => Self::signed(i128::from(*value)),
+            ColumnValue::I128(value) => Self::signed(*value),
+            ColumnValue::U8(value) => Self::unsigned(u128::from(*value)),
+            ColumnValue::U16(value) => Self::unsigned(u128::from(*value)),
+            ColumnValue::U32(value) => Self::unsigned(u128::from(*value)),
+            ColumnValue::U64(value) => Self::unsigned(u128::from(*value)),
+            ColumnValue::U128(value) => Self::unsigned(*value),
+            ColumnValue::F32(value) => Self::Float(f64::from(*value)),
+            ColumnValue::F64(value) => Self::Float(*value),
+            _ => return None
+        };
+
+        Some(number)
+    }
+
+    /// # Instance Method `Number::compare`
+    ///
+    /// Compares this number with another number by their exact values, where `NaN` is equal to
+    /// `NaN` and greater than every other number.
+    ///
+    /// ## Parameters
+    /// - `other`, type `Number`; the number to compare with
+    #[must_use]
+    pub fn compare(self, other: Self) -> Ordering {
+        match (self, other) {
+            (Self::Float(left), Self::Float(right)) => compare_floats(left, right),
+            (Self::Integer { .. }, Self::Float(float)) => Self::compare_with_float(self, float),
+            (Self::Float(float), Self::Integer { .. }) => {
+                Self::compare_with_float(other, float).reverse()
+            }
+            (
+                Self::Integer {
+                    negative: left_negative,
+                    magnitude: left
+                },
+                Self::Integer {
+                    negative: right_negative,
+                    magnitude: right
+                }
+            ) => {
+                match (left_negative, right_negative) {
+                    (false, false) => left.cmp(&right),
+                    (true, true) => right.cmp(&left),
+                    (true, false) => Ordering::Less,
+                    (false, true) => Ordering::Greater
+                }
+            }
+        }
+    }
+
+    /// # Instance Method `Number::checked_add`
+    ///
+    /// Adds another number to this number. The sum of two integers is exact, and returns `None` if
+    /// its magnitude exceeds the range of `u128`; the sum of a floating point number and any other
+    /// number is a floating point number.
+    ///
+    /// ## Parameters
+    /// - `other`, type `Number`; the number to add
+    #[must_use]
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        match (self, other) {
+            (
+                Self::Integer {
+                    negative: left_negative,
+                    magnitude: left
+                },
+                Self::Integer {
+                    negative: right_negative,
+                    magnitude: right
+                }
+            ) => {
+                if left_negative == right_negative {
+                    left.checked_add(right).map(|magnitude| {
+                        Self::Integer {
+                            negative: left_negative,
+                            magnitude
+                        }
+                    })
+                }
+                else if left >= right {
+                    Some(Self::integer(left_negative, left - right))
+                }
+                else {
+                    Some(Self::integer(right_negative, right - left))
+                }
+            }
+            (left, right) => Some(Self::Float(left.to_f64() + right.to_f64()))
+        }
+    }
+
+    /// # Instance Method `Number::checked_sub`
+    ///
+    /// Subtracts another number from this number, as described in `checked_add`.
+    ///
+    /// ## Parameters
+    /// - `other`, type `Number`; the number to subtract
+    #[must_use]
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        self.checked_add(-other)
+    }
+
+    /// # Instance Method `Number::checked_mul`
+    ///
+    /// Multiplies this number with another number, as described in `checked_add`.
+    ///
+    /// ## Parameters
+    /// - `other`, type `Number`; the number to multiply with
+    #[must_use]
+    pub fn checked_mul(self, other: Self) -> Option<Self> {
+        match (self, other) {
+            (
+                Self::Integer {
+                    negative: left_negative,
+                    magnitude: left
+                },
+                Self::Integer {
+                    negative: right_negative,
+                    magnitude: right
+                }
+            ) => {
+                left.checked_mul(right)
+                    .map(|magnitude| Self::integer(left_negative != right_negative, magnitude))
+            }
+            (left, right) => Some(Self::Float(left.to_f64() * right.to_f64()))
+        }
+    }
+
+    /// # Instance Method `Number::checked_div`
+    ///
+    /// Divides this number by another number. The quotient of two integers is truncated towards
+    /// zero; the quotient of a floating point number and any other number is a floating point
+    /// number. Returns `None` if the divisor is zero.
+    ///
+    /// ## Parameters
+    /// - `other`, type `Number`; the divisor
+    #[must_use]
+    pub fn checked_div(self, other: Self) -> Option<Self> {
+        match (self, other) {
+            (
+                Self::Integer {
+                    negative: left_negative,
+                    magnitude: left
+                },
+                Self::Integer {
+                    negative: right_negative,
+                    magnitude: right
+                }
+            ) => {
+                left.checked_div(right)
+                    .map(|magnitude| Self::integer(left_negative != right_negative, magnitude))
+            }
+            (_, right) if right.is_zero() => None,
+            (left, right) => Some(Self::Float(left.to_f64() / right.to_f64()))
+        }
+    }
+
+    /// # Instance Method `Number::checked_rem`
+    ///
+    /// Returns the remainder of dividing this number by another number, which has the sign of
+    /// this number. Returns `None` if the divisor is zero.
+    ///
+    /// ## Parameters
+    /// - `other`, type `Number`; the divisor
+    #[must_use]
+    pub fn checked_rem(self, other: Self) -> Option<Self> {
+        match (self, other) {
+            (
+                Self::Integer {
+                    negative,
+                    magnitude: left
+                },
+                Self::Integer {
+                    magnitude: right, ..
+                }
+            ) => left.checked_rem(right).map(|magnitude| Self::integer(negative, magnitude)),
+            (_, right) if right.is_zero() => None,
+            (left, right) => Some(Self::Float(left.to_f64() % right.to_f64()))
+        }
+    }
+
+    /// # Instance Method `Number::to_value`
+    ///
+    /// Converts this number into a value of a numeric type, or returns `None` if the type cannot
+    /// represent the number. Integers convert to integer types that can hold them, and to
+    /// floating point types with rounding; floating point numbers convert to `F32` if the type is
+    /// `F32`, and to `F64` otherwise.
+    ///
+    /// ## Parameters
+    /// - `data_type`, type `&ColumnDataType`; the type of the value
+    #[allow(clippy::cast_possible_truncation)] // the conversion to `f32` is allowed to round
+    #[must_use]
+    pub fn to_value(self, data_type: &ColumnDataType) -> Option<ColumnValue> {
+        let integer = match (self, data_type) {
+            (Self::Float(float), ColumnDataType::F32) => {
+                return Some(ColumnValue::F32(float as f32));
+            }
+            (Self::Float(float), _) => return Some(ColumnValue::F64(float)),
+            (
+                Self::Integer {
+                    negative: true,
+                    magnitude
+                },
+                _
+            ) => ColumnValue::I128(0_i128.checked_sub_unsigned(magnitude)?),
+            (
+                Self::Integer {
+                    negative: false,
+                    magnitude
+                },
+                _
+            ) => ColumnValue::U128(magnitude)
+        };
+
+        match data_type {
+            ColumnDataType::I8 => i8::try_from(integer).ok().map(ColumnValue::I8),
+            ColumnDataType::I16 => i16::try_from(integer).ok().map(ColumnValue::I16),
+            ColumnDataType::I32 => i32::try_from(integer).ok().map(ColumnValue::I32),
+            ColumnDataType::I64 => i64::try_from(integer).ok().map(ColumnValue::I64),
+            ColumnDataType::I128 => i128::try_from(integer).ok().map(ColumnValue::I128),
+            ColumnDataType::U8 => u8::try_from(integer).ok().map(ColumnValue::U8),
+            ColumnDataType::U16 => u16::try_from(integer).ok().map(ColumnValue::U16),
+            ColumnDataType::U32 => u32::try_from(integer).ok().map(ColumnValue::U32),
+            ColumnDataType::U64 => u64::try_from(integer).ok().map(ColumnValue::U64),
+            ColumnDataType::U128 => u128::try_from(integer).ok().map(ColumnValue::U128),
+            ColumnDataType::F32 => Some(ColumnValue::F32(self.to_f64() as f32)),
+            ColumnDataType::F64 => Some(ColumnValue::F64(self.to_f64())),
+            _ => None
+        }
+    }
+
+    /// # Instance Method `Number::to_f64`
+    ///
+    /// Returns the nearest `f64` to this number.
+    #[allow(clippy::cast_precision_loss)] // the conversion is allowed to round
+    #[must_use]
+    pub fn to_f64(self) -> f64 {
+        match self {
+            Self::Integer {
+                negative: true,
+                magnitude
+            } => -(magnitude as f64),
+            Self::Integer {
+                negative: false,
+                magnitude
+            } => magnitude as f64,
+            Self::Float(float) => float
+        }
+    }
+
+    fn is_zero(self) -> bool {
+        match self {
+            Self::Integer {
+                magnitude, ..
+            } => magnitude == 0,
+            Self::Float(float) => float == 0.0
+        }
+    }
+
+    /// Returns an integer of a sign and a magnitude, where zero is never negative.
+    fn integer(negative: bool, magnitude: u128) -> Self {
+        Self::Integer {
+            negative: negative && magnitude != 0,
+            magnitude
+        }
+    }
+
+    fn signed(value: i128) -> Self {
+        Self::Integer {
+            negative: value < 0,
+            magnitude: value.unsigned_abs()
+        }
+    }
+
+    fn unsigned(value: u128) -> Self {
+        Self::Integer {
+            negative: false,
+            magnitude: value
+        }
+    }
+
+    /// Compares an integer with a floating point number.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)] // the cast is exact
+    fn compare_with_float(integer: Self, float: f64) -> Ordering {
+        if float.is_nan() || float >= INTEGER_LIMIT {
+            return Ordering::Less;
+        }
+
+        if float <= -INTEGER_LIMIT {
+            return Ordering::Greater;
+        }
+
+        // the integral part of the float is within the range of the magnitude, compare with it
+        // first and then with the fractional part
+        let integral = float.trunc();
+        let ordering = integer.compare(Self::Integer {
+            negative: integral < 0.0,
+            magnitude: integral.abs() as u128
+        });
+
+        match ordering {
+            Ordering::Equal => 0.0_f64.partial_cmp(&(float - integral)).unwrap_or(ordering),
+            ordering => ordering
+        }
+    }
+}
+
+/// Returns the type of the result of an arithmetic operation on values of numeric types, or `None`
+/// if either type is not numeric.
+///
+/// Integers of the same signedness result in the wider of their types, and an unsigned integer and
+/// a signed integer result in the narrowest signed type that is at least as wide as the signed
+/// type and can hold every value of the unsigned type, up to `I128`. Floating point numbers
+/// result in `F64`, unless both are `F32`.
+pub(crate) fn result_type(left: &ColumnDataType, right: &ColumnDataType) -> Option<ColumnDataType> {
+    let result_type = match (integer_width(left), integer_width(right)) {
+        (Some((left_signed, left_bits)), Some((right_signed, right_bits))) => {
+            let bits = match (left_signed, right_signed) {
+                (true, false) => left_bits.max(right_bits.saturating_mul(2)).min(128),
+                (false, true) => right_bits.max(left_bits.saturating_mul(2)).min(128),
+                _ => left_bits.max(right_bits)
+            };
+
+            integer_type(left_signed || right_signed, bits)
+        }
+        _ if !left.is_numeric() || !right.is_numeric() => return None,
+        _ if *left == ColumnDataType::F32 && *right == ColumnDataType::F32 => ColumnDataType::F32,
+        _ => ColumnDataType::F64
+    };
+
+    Some(result_type)
+}
+
+/// Returns whether an integer type is signed and its width in bits, or `None` if the type is not
+/// an integer type.
+fn integer_width(data_type: &ColumnDataType) -> Option<(bool, u8)> {
+    let width = match data_type {
+        ColumnDataType::I8 => (true, 8),
+        ColumnDataType::I16 => (true, 16),
+        ColumnDataType::I32 => (true, 32),
+        ColumnDataType::I64 => (true, 64),
+        ColumnDataType::I128 => (true, 128),
+        ColumnDataType::U8 => (false, 8),
+        ColumnDataType::U16 => (false, 16),
+        ColumnDataType::U32 => (false, 32),
+        ColumnDataType::U64 => (false, 64),
+        ColumnDataType::U128 => (false, 128),
+        _ => return None
+    };
+
+    Some(width)
+}
+
+fn integer_type(signed: bool, bits: u8) -> ColumnDataType {
+    match (signed, bits) {
+        (true, 8) => ColumnDataType::I8,
+        (true, 16) => ColumnDataType::I16,
+        (true, 32) => ColumnDataType::I32,
+        (true, 64) => ColumnDataType::I64,
+        (true, _) => ColumnDataType::I128,
+        (false, 8) => ColumnDataType::U8,
+        (false, 16) => ColumnDataType::U16,
+        (false, 32) => ColumnDataType::U32,
+        (false, 64) => ColumnDataType::U64,
+        (false, _) => ColumnDataType::U128
+    }
+}
+
+/// Numbers that are equal by `compare` hash the same: an integral floating point number hashes as
+/// the integer it is equal to.
+impl Hash for Number {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::float_cmp)]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match *self {
+            Self::Integer {
+                negative,
+                magnitude
+            } => {
+                negative.hash(state);
+                magnitude.hash(state);
+            }
+            Self::Float(float) if float.trunc() == float && float.abs() < INTEGER_LIMIT => {
+                (float < 0.0).hash(state);
+                (float.abs() as u128).hash(state);
+            }
+            Self::Float(float) => hash_float(float, state)
+        }
+    }
+}
+
+impl Neg for Number {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        match self {
+            Self::Integer {
+                negative,
+                magnitude
+            } => Self::integer(!negative, magnitude),
+            Self::Float(float) => Self::Float(-float)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cmp::Ordering,
+        collections::hash_map::DefaultHasher,
+        hash::{
+            Hash,
+            Hasher
+        },
+        ops::Neg,
+        sync::Arc
+    };
+
+    use dashmap::DashMap;
+
+    use super::{
+        result_type,
+        Number
+    };
+    use crate::{
+        error::QlError,
+        ql::{
+            constraints::{
+                ComputableConstraint,
+                Constraint,
+                ConstraintOp
+            },
+            sortby::SortBy,
+            QueryContext
+        },
+        CacheTableRow,
+        ColumnDataType,
+        ColumnValue,
+        QlCache
+    };
+
+    static_assertions::assert_impl_all!(Number: Clone, Copy, Hash, Neg, Send, Sync);
+
+    /// `2^64` and `2^127`, the nearest `f64`s to `u64::MAX` and `i128::MAX`.
+    const TWO_TO_64: f64 = 18_446_744_073_709_551_616.0;
+    const TWO_TO_127: f64 = 170_141_183_460_469_231_731_687_303_715_884_105_728.0;
+
+    fn compare(left: &ColumnValue, right: &ColumnValue) -> Ordering {
+        Number::of(left)
+            .unwrap()
+            .compare(Number::of(right).unwrap())
+    }
+
+    fn hash(number: Number) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        number.hash(&mut hasher);
+
+        hasher.finish()
+    }
+
+    #[test]
+    fn integers_compare_exactly_across_widths_and_signedness() {
+        use ColumnValue::{
+            I128,
+            I32,
+            I64,
+            I8,
+            U128,
+            U64,
+            U8
+        };
+
+        assert_eq!(compare(&I64(i64::MIN), &U64(0)), Ordering::Less);
+        assert_eq!(compare(&I64(i64::MIN), &U64(u64::MAX)), Ordering::Less);
+        assert_eq!(compare(&U64(u64::MAX), &I64(i64::MAX)), Ordering::Greater);
+        assert_eq!(compare(&I128(i128::MIN), &I8(i8::MIN)), Ordering::Less);
+        assert_eq!(compare(&U128(u128::MAX), &I128(i128::MAX)), Ordering::Greater);
+        assert_eq!(compare(&I32(5), &U64(5)), Ordering::Equal);
+        assert_eq!(compare(&I8(-1), &U8(255)), Ordering::Less);
+        assert_eq!(compare(&I8(0), &U8(0)), Ordering::Equal);
+        assert_eq!(compare(&I8(-2), &I128(-1)), Ordering::Less);
+    }
+
+    #[test]
+    fn integers_and_floats_compare_by_their_exact_values() {
+        use ColumnValue::{
+            F32,
+            F64,
+            I128,
+            I64,
+            I8,
+            U128,
+            U64,
+            U8
+        };
+
+        // `u64::MAX` converts to `2^64`, but is less than it
+        #[allow(clippy::cast_precision_loss)]
+        let rounded = u64::MAX as f64;
+        assert_eq!(compare(&U64(u64::MAX), &F64(rounded)), Ordering::Less);
+        assert_eq!(compare(&F64(TWO_TO_64), &U128(1 << 64)), Ordering::Equal);
+        assert_eq!(compare(&F64(-TWO_TO_127), &I128(i128::MIN)), Ordering::Equal);
+        assert_eq!(compare(&F64(TWO_TO_127), &I128(i128::MAX)), Ordering::Greater);
+
+        // fractions are compared with the integers around them
+        assert_eq!(compare(&F64(-1.5), &I8(-1)), Ordering::Less);
+        assert_eq!(compare(&F64(-1.5), &I8(-2)), Ordering::Greater);
+        assert_eq!(compare(&F64(0.5), &U8(0)), Ordering::Greater);
+        assert_eq!(compare(&F64(-0.5), &U8(0)), Ordering::Less);
+        assert_eq!(compare(&F32(2.0), &I64(2)), Ordering::Equal);
+
+        // beyond the range of every integer type
+        assert_eq!(compare(&F64(f64::INFINITY), &U128(u128::MAX)), Ordering::Greater);
+        assert_eq!(compare(&F64(f64::NEG_INFINITY), &I128(i128::MIN)), Ordering::Less);
+        assert_eq!(compare(&F64(f64::MAX), &U128(u128::MAX)), Ordering::Greater);
+
+        // `f32` converts to `f64` exactly, so `0.1_f32` is not equal to `0.1_f64`
+        assert_eq!(compare(&F32(0.1), &F64(0.1)), Ordering::Greater);
+        assert_eq!(compare(&F32(0.5), &F64(0.5)), Ordering::Equal);
+    }
+
+    #[test]
+    fn nan_is_greatest_and_zeros_are_equal() {
+        use ColumnValue::{
+            F32,
+            F64,
+            I8,
+            U128,
+            U8
+        };
+
+        assert_eq!(compare(&F64(f64::NAN), &F64(f64::NAN)), Ordering::Equal);
+        assert_eq!(compare(&F32(f32::NAN), &F64(-f64::NAN)), Ordering::Equal);
+        assert_eq!(compare(&F64(f64::NAN), &F64(f64::INFINITY)), Ordering::Greater);
+        assert_eq!(compare(&F64(f64::NAN), &U128(u128::MAX)), Ordering::Greater);
+        assert_eq!(compare(&I8(i8::MIN), &F64(f64::NAN)), Ordering::Less);
+
+        assert_eq!(compare(&F64(-0.0), &F64(0.0)), Ordering::Equal);
+        assert_eq!(compare(&F32(-0.0), &U8(0)), Ordering::Equal);
+        assert_eq!(compare(&I8(0), &F64(-0.0)), Ordering::Equal);
+    }
+
+    #[test]
+    fn numbers_that_compare_equal_hash_the_same() {
+        use ColumnValue::{
+            F32,
+            F64,
+            I128,
+            I32,
+            I8,
+            U128,
+            U64,
+            U8
+        };
+
+        let pairs = [
+            (I8(-1), I128(-1)),
+            (I32(5), U64(5)),
+            (U64(5), F64(5.0)),
+            (F32(-0.0), U8(0)),
+            (F64(-0.0), F64(0.0)),
+            (F64(f64::NAN), F32(f32::NAN)),
+            (F64(TWO_TO_64), U128(1 << 64)),
+            (F64(-TWO_TO_127), I128(i128::MIN)),
+            (F64(0.5), F32(0.5)),
+            (F64(f64::INFINITY), F32(f32::INFINITY))
+        ];
+
+        for (left, right) in pairs {
+            let (left, right) = (Number::of(&left).unwrap(), Number::of(&right).unwrap());
+
+            assert_eq!(left.compare(right), Ordering::Equal);
+            assert_eq!(hash(left), hash(right));
+        }
+    }
+
+    #[test]
+    fn arithmetic_results_in_the_narrowest_type_that_holds_both() {
+        use ColumnDataType::{
+            Bool,
+            String,
+            F32,
+            F64,
+            I128,
+            I16,
+            I32,
+            I64,
+            I8,
+            U128,
+            U16,
+            U32,
+            U64,
+            U8
+        };
+
+        let cases = [
+            (I8, I8, I8),
+            (I8, I32, I32),
+            (I64, I16, I64),
+            (U8, U64, U64),
+            (U128, U16, U128),
+            // a signed type at least twice as wide as the unsigned type, up to `I128`
+            (U8, I8, I16),
+            (I8, U8, I16),
+            (U16, I8, I32),
+            (I64, U32, I64),
+            (U32, I8, I64),
+            (U64, I8, I128),
+            (I128, U128, I128),
+            (U128, I8, I128),
+            // floating point numbers are `F64` unless both are `F32`
+            (F32, F32, F32),
+            (F32, F64, F64),
+            (F32, I8, F64),
+            (U128, F32, F64),
+            (F64, U64, F64)
+        ];
+
+        for (left, right, result) in cases {
+            assert_eq!(result_type(&left, &right), Some(result.clone()));
+            assert_eq!(result_type(&right, &left), Some(result));
+        }
+
+        assert_eq!(result_type(&String, &I8), None);
+        assert_eq!(result_type(&F64, &Bool), None);
+    }
+
+    #[test]
+    fn constraints_in_lists_and_sorts_compare_across_widths() {
+        let cache = QlCache::new();
+        let context = QueryContext::new(&cache, 0);
+        let columns = DashMap::from_iter([(String::from("Xp"), (ColumnDataType::U64, false))]);
+        let row = |xp| {
+            Arc::new(CacheTableRow {
+                column_values: DashMap::from_iter([(String::from("Xp"), xp)])
+            })
+        };
+        let xp = |op, value| {
+            Constraint::builder()
+                .field_name(String::from("Xp"))
+                .op(op)
+                .value(value)
+                .build()
+                .unwrap()
+        };
+
+        // `Xp = 5`, `Xp > 4.5` and `Xp > -1` against a `U64` column
+        for constraint in [
+            xp(ConstraintOp::Eq, ColumnValue::I32(5)),
+            xp(ConstraintOp::Eq, ColumnValue::F64(5.0)),
+            xp(ConstraintOp::Gt, ColumnValue::F32(4.5)),
+            xp(ConstraintOp::Gt, ColumnValue::I64(-1))
+        ] {
+            constraint.validate(&columns, &context).unwrap();
+            assert_eq!(
+                constraint.compute_row(&row(ColumnValue::U64(5)), &context).unwrap(),
+                Some(true)
+            );
+        }
+
+        // `Xp IN (-5, 5.0)`
+        let in_list = xp(
+            ConstraintOp::In,
+            ColumnValue::Array(vec![ColumnValue::I8(-5), ColumnValue::F32(5.0)])
+        );
+        in_list.validate(&columns, &context).unwrap();
+        assert_eq!(in_list.compute_row(&row(ColumnValue::U64(5)), &context).unwrap(), Some(true));
+        assert_eq!(in_list.compute_row(&row(ColumnValue::U64(6)), &context).unwrap(), Some(false));
+
+        // values of other types are rejected before any row is read
+        for constraint in [
+            xp(ConstraintOp::Eq, ColumnValue::from("5")),
+            xp(ConstraintOp::In, ColumnValue::Array(vec![ColumnValue::Bool(true)]))
+        ] {
+            assert!(matches!(
+                constraint.validate(&columns, &context),
+                Err(QlError::ColumnTypeMismatch { name }) if name == "Xp"
+            ));
+        }
+
+        let mut rows = vec![
+            row(ColumnValue::U64(u64::MAX)),
+            row(ColumnValue::F64(f64::NAN)),
+            row(ColumnValue::I64(i64::MIN)),
+            row(ColumnValue::F64(0.5)),
+            row(ColumnValue::U128(1 << 64)),
+            row(ColumnValue::I8(0))
+        ];
+        SortBy::new(vec![String::from("Xp")], None)
+            .unwrap()
+            .sort(&mut rows, &context)
+            .unwrap();
+
+        let sorted = rows
+            .iter()
+            .map(|row| row.column_values.get("Xp").unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(sorted, [
+            "-9223372036854775808",
+            "0",
+            "0.5",
+            "18446744073709551615",
+            "18446744073709551616",
+            "NaN"
+        ]);
+    }
+}
@@ -0,0 +1,528 @@
+//! # Module `aggregate`
+//!
+//! This module implements aggregate functions, which compute a single value from the values of a
+//! column in many rows, for use in `SELECT` queries.
+
+use std::collections::HashSet;
+
+use dashmap::DashMap;
+
+use crate::{
+    error::{
+        QlError,
+        QlResult,
+        QueryError
+    },
+    numeric::Number,
+    CacheTableRow,
+    ColumnDataType,
+    ColumnValue
+};
+
+/// # Enumeration `AggregateFunction`
+///
+/// An aggregate function. Except for `COUNT(*)`, `null` values are ignored.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AggregateFunction {
+    /// # Enumeration Variant `AggregateFunction::Count`
+    ///
+    /// The number of values, as a `U64`.
+    Count,
+
+    /// # Enumeration Variant `AggregateFunction::Sum`
+    ///
+    /// The sum of the values of a numeric column, or `null` if there are no values. The sum is
+    /// computed exactly, and is of a wider type than the column to make overflows unlikely:
+    /// `I64` for `I8`, `I16` and `I32` columns, `I128` for `I64` and `I128` columns, `U64` for
+    /// `U8`, `U16` and `U32` columns, `U128` for `U64` and `U128` columns, and `F64` for floating
+    /// point columns.
+    Sum,
+
+    /// # Enumeration Variant `AggregateFunction::Min`
+    ///
+    /// The least of the values, or `null` if there are no values.
+    Min,
+
+    /// # Enumeration Variant `AggregateFunction::Max`
+    ///
+    /// The greatest of the values, or `null` if there are no values.
+    Max,
+
+    /// # Enumeration Variant `AggregateFunction::Avg`
+    ///
+    /// The average of the values of a numeric column as an `F64`, or `null` if there are no
+    /// values.
+    Avg
+}
+
+/// # Struct `Aggregate`
+///
+/// An aggregate function of a column, which is a column of a `SELECT` result.
+///
+/// ## Examples
+///
+/// - `COUNT(*) AS count`:
+/// ```
+/// use qlcache::ql::aggregate::Aggregate;
+///
+/// let aggregate = Aggregate::count_all(String::from("count"));
+/// ```
+///
+/// - `COUNT(DISTINCT GuildId) AS guilds`:
+/// ```
+/// use qlcache::ql::aggregate::{
+///     Aggregate,
+///     AggregateFunction
+/// };
+///
+/// let aggregate = Aggregate::new(
+///     String::from("guilds"),
+///     AggregateFunction::Count,
+///     String::from("GuildId")
+/// )
+/// .distinct();
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Aggregate {
+    pub(crate) name: String,
+    pub(crate) function: AggregateFunction,
+    pub(crate) column: Option<String>,
+    pub(crate) distinct: bool
+}
+
+impl Aggregate {
+    /// # Constructor `Aggregate::new`
+    ///
+    /// Constructs a new `Aggregate` of a column, which is named `name` in the result.
+    #[must_use]
+    pub fn new(name: String, function: AggregateFunction, column: String) -> Self {
+        Self {
+            name,
+            function,
+            column: Some(column),
+            distinct: false
+        }
+    }
+
+    /// # Constructor `Aggregate::count_all`
+    ///
+    /// Constructs a new `COUNT(*)` aggregate, the number of rows, which is named `name` in the
+    /// result.
+    #[must_use]
+    pub fn count_all(name: String) -> Self {
+        Self {
+            name,
+            function: AggregateFunction::Count,
+            column: None,
+            distinct: false
+        }
+    }
+
+    /// # Instance Method `Aggregate::distinct`
+    ///
+    /// Sets the aggregate to only count each distinct value once (`DISTINCT`).
+    #[must_use]
+    pub fn distinct(mut self) -> Self {
+        self.distinct = true;
+        self
+    }
+
+    /// Checks the aggregate against the columns of the table it is computed on.
+    pub(crate) fn validate(
+        &self,
+        table_name: &str,
+        columns: &DashMap<String, (ColumnDataType, bool)>
+    ) -> QlResult<()> {
+        if let Some(column) = &self.column {
+            let data_type = columns.get(column).map(|entry| entry.0.clone()).ok_or_else(|| {
+                QlError::QueryError(QueryError::RelationDoesNotExist {
+                    name: format!("{table_name}.{column}")
+                })
+            })?;
+
+            if matches!(self.function, AggregateFunction::Sum | AggregateFunction::Avg)
+                && sum_type(&data_type).is_none()
+            {
+                return Err(QlError::ColumnTypeMismatch {
+                    name: column.clone()
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the type of the result of the aggregate.
+    pub(crate) fn data_type(
+        &self,
+        columns: &DashMap<String, (ColumnDataType, bool)>
+    ) -> ColumnDataType {
+        let data_type = self.column.as_ref().and_then(|column| columns.get(column));
+
+        match (self.function, data_type) {
+            (AggregateFunction::Sum, Some(data_type)) => {
+                sum_type(&data_type.0).unwrap_or(ColumnDataType::F64)
+            }
+            (AggregateFunction::Min | AggregateFunction::Max, Some(data_type)) => {
+                data_type.0.clone()
+            }
+            (AggregateFunction::Count, _) => ColumnDataType::U64,
+            _ => ColumnDataType::F64
+        }
+    }
+
+    /// Computes the aggregate over rows of a table with columns `columns`, a column missing from a
+    /// row is treated as `null`.
+    pub(crate) fn compute(
+        &self,
+        rows: &[&CacheTableRow],
+        columns: &DashMap<String, (ColumnDataType, bool)>
+    ) -> QlResult<ColumnValue> {
+        let values = match &self.column {
+            Some(column) => self.values(rows, column),
+            None => return Ok(ColumnValue::U64(rows.len() as u64))
+        };
+
+        match self.function {
+            AggregateFunction::Count => Ok(ColumnValue::U64(values.len() as u64)),
+            AggregateFunction::Min => Ok(values.into_iter().min().unwrap_or(ColumnValue::Null)),
+            AggregateFunction::Max => Ok(values.into_iter().max().unwrap_or(ColumnValue::Null)),
+            AggregateFunction::Sum => {
+                let target = self.data_type(columns);
+
+                match sum(&values, &target)? {
+                    Some(sum) => {
+                        sum.to_value(&target).ok_or_else(|| {
+                            QlError::NumericOverflow {
+                                target: target.to_string()
+                            }
+                        })
+                    }
+                    None => Ok(ColumnValue::Null)
+                }
+            }
+            AggregateFunction::Avg => {
+                #[allow(clippy::cast_precision_loss)] // the average is allowed to round
+                let average = sum(&values, &ColumnDataType::F64)?
+                    .map_or(ColumnValue::Null, |sum| {
+                        ColumnValue::F64(sum.to_f64() / values.len() as f64)
+                    });
+
+                Ok(average)
+            }
+        }
+    }
+
+    /// Returns the values of a column in rows that are not `null`, and only the first of equal
+    /// values if the aggregate is `DISTINCT`.
+    fn values(&self, rows: &[&CacheTableRow], column: &str) -> Vec<ColumnValue> {
+        let mut values = rows
+            .iter()
+            .filter_map(|row| row.column_values.get(column))
+            .filter(|value| *value.value() != ColumnValue::Null)
+            .map(|value| value.value().clone())
+            .collect::<Vec<_>>();
+
+        if self.distinct {
+            let mut seen = HashSet::new();
+            values.retain(|value| seen.insert(value.clone()));
+        }
+
+        values
+    }
+}
+
+/// Returns the type of the sum of a column of a type, or `None` if the type cannot be summed.
+fn sum_type(data_type: &ColumnDataType) -> Option<ColumnDataType> {
+    let sum_type = match data_type {
+        ColumnDataType::I8 | ColumnDataType::I16 | ColumnDataType::I32 => ColumnDataType::I64,
+        ColumnDataType::I64 | ColumnDataType::I128 => ColumnDataType::I128,
+        ColumnDataType::U8 | ColumnDataType::U16 | ColumnDataType::U32 => ColumnDataType::U64,
+        ColumnDataType::U64 | ColumnDataType::U128 => ColumnDataType::U128,
+        ColumnDataType::F32 | ColumnDataType::F64 => ColumnDataType::F64,
+        _ => return None
+    };
+
+    Some(sum_type)
+}
+
+/// Sums numeric values exactly, values that are not numbers are ignored. Returns `None` if there
+/// are no numbers.
+fn sum(values: &[ColumnValue], target: &ColumnDataType) -> QlResult<Option<Number>> {
+    values
+        .iter()
+        .filter_map(Number::of)
+        .try_fold(None, |sum: Option<Number>, number| {
+            match sum {
+                Some(sum) => sum.checked_add(number).map(Some),
+                None => Some(Some(number))
+            }
+        })
+        .ok_or_else(|| {
+            QlError::NumericOverflow {
+                target: target.to_string()
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use dashmap::DashMap;
+
+    use super::{
+        Aggregate,
+        AggregateFunction
+    };
+    use crate::{
+        error::{
+            QlError,
+            QlResult,
+            QueryError
+        },
+        CacheTableRow,
+        ColumnDataType,
+        ColumnValue
+    };
+
+    static_assertions::assert_impl_all!(AggregateFunction: Clone, Copy, Eq, PartialEq, Send, Sync);
+    static_assertions::assert_impl_all!(Aggregate: Clone, Eq, PartialEq, Send, Sync);
+
+    /// Rows with the values in column `Value`, where `None` leaves the column out of the row.
+    fn rows(values: Vec<Option<ColumnValue>>) -> Vec<CacheTableRow> {
+        values
+            .into_iter()
+            .map(|value| {
+                CacheTableRow {
+                    column_values: value
+                        .map(|value| (String::from("Value"), value))
+                        .into_iter()
+                        .collect()
+                }
+            })
+            .collect()
+    }
+
+    /// Computes an aggregate of column `Value`, of type `data_type`.
+    fn compute(
+        aggregate: &Aggregate,
+        data_type: ColumnDataType,
+        values: Vec<Option<ColumnValue>>
+    ) -> QlResult<ColumnValue> {
+        let columns = DashMap::from_iter([(String::from("Value"), (data_type, true))]);
+        aggregate.validate("Table", &columns)?;
+
+        let rows = rows(values);
+        aggregate.compute(&rows.iter().collect::<Vec<_>>(), &columns)
+    }
+
+    fn of(function: AggregateFunction) -> Aggregate {
+        Aggregate::new(String::from("Result"), function, String::from("Value"))
+    }
+
+    #[test]
+    fn sums_are_of_a_wider_type() {
+        let cases = [
+            (ColumnDataType::I8, ColumnValue::I8(i8::MAX), ColumnValue::I64(254)),
+            (ColumnDataType::I16, ColumnValue::I16(i16::MIN), ColumnValue::I64(-65536)),
+            (
+                ColumnDataType::I32,
+                ColumnValue::I32(i32::MAX),
+                ColumnValue::I64(2 * i64::from(i32::MAX))
+            ),
+            (
+                ColumnDataType::I64,
+                ColumnValue::I64(i64::MIN),
+                ColumnValue::I128(2 * i128::from(i64::MIN))
+            ),
+            (ColumnDataType::U8, ColumnValue::U8(u8::MAX), ColumnValue::U64(510)),
+            (ColumnDataType::U16, ColumnValue::U16(u16::MAX), ColumnValue::U64(131_070)),
+            (
+                ColumnDataType::U32,
+                ColumnValue::U32(u32::MAX),
+                ColumnValue::U64(2 * u64::from(u32::MAX))
+            ),
+            (
+                ColumnDataType::U64,
+                ColumnValue::U64(u64::MAX),
+                ColumnValue::U128(2 * u128::from(u64::MAX))
+            ),
+            (ColumnDataType::F32, ColumnValue::F32(0.5), ColumnValue::F64(1.0)),
+            (ColumnDataType::F64, ColumnValue::F64(-0.25), ColumnValue::F64(-0.5))
+        ];
+
+        for (data_type, value, sum) in cases {
+            let values = vec![Some(value.clone()), Some(value)];
+            assert_eq!(compute(&of(AggregateFunction::Sum), data_type, values).unwrap(), sum);
+        }
+    }
+
+    #[test]
+    fn sums_that_overflow_their_type_are_errors() {
+        // the sums of narrower columns are wide enough for more rows than a table can hold, so
+        // only the sums of 128-bit columns can overflow
+        let cases = [
+            (ColumnDataType::I128, ColumnValue::I128(i128::MAX), ColumnValue::I128(1), "I128"),
+            (ColumnDataType::I128, ColumnValue::I128(i128::MIN), ColumnValue::I128(-1), "I128"),
+            (ColumnDataType::U128, ColumnValue::U128(u128::MAX), ColumnValue::U128(1), "U128")
+        ];
+
+        for (data_type, value, other, type_name) in cases {
+            let values = vec![Some(value), Some(other)];
+            assert!(matches!(
+                compute(&of(AggregateFunction::Sum), data_type, values),
+                Err(QlError::NumericOverflow { target }) if target == type_name
+            ));
+        }
+
+        // the sum is computed exactly, so it only overflows if the result does
+        assert_eq!(
+            compute(&of(AggregateFunction::Sum), ColumnDataType::I128, vec![
+                Some(ColumnValue::I128(i128::MAX)),
+                Some(ColumnValue::I128(i128::MAX)),
+                Some(ColumnValue::I128(-i128::MAX))
+            ])
+            .unwrap(),
+            ColumnValue::I128(i128::MAX)
+        );
+    }
+
+    #[test]
+    fn null_values_are_skipped() {
+        let values = || {
+            vec![
+                Some(ColumnValue::I32(4)),
+                Some(ColumnValue::Null),
+                None,
+                Some(ColumnValue::I32(2))
+            ]
+        };
+
+        let cases = [
+            (AggregateFunction::Count, ColumnValue::U64(2)),
+            (AggregateFunction::Sum, ColumnValue::I64(6)),
+            (AggregateFunction::Min, ColumnValue::I32(2)),
+            (AggregateFunction::Max, ColumnValue::I32(4)),
+            (AggregateFunction::Avg, ColumnValue::F64(3.0))
+        ];
+
+        for (function, result) in cases {
+            assert_eq!(compute(&of(function), ColumnDataType::I32, values()).unwrap(), result);
+        }
+
+        // `COUNT(*)` counts rows, whatever their values
+        assert_eq!(
+            compute(&Aggregate::count_all(String::from("Rows")), ColumnDataType::I32, values())
+                .unwrap(),
+            ColumnValue::U64(4)
+        );
+    }
+
+    #[test]
+    fn aggregates_of_no_values_are_null_except_counts() {
+        for values in [Vec::new(), vec![Some(ColumnValue::Null), None]] {
+            let rows = values.len() as u64;
+
+            for function in [
+                AggregateFunction::Sum,
+                AggregateFunction::Min,
+                AggregateFunction::Max,
+                AggregateFunction::Avg
+            ] {
+                assert_eq!(
+                    compute(&of(function), ColumnDataType::F64, values.clone()).unwrap(),
+                    ColumnValue::Null
+                );
+            }
+
+            assert_eq!(
+                compute(&of(AggregateFunction::Count), ColumnDataType::F64, values.clone())
+                    .unwrap(),
+                ColumnValue::U64(0)
+            );
+            assert_eq!(
+                compute(&Aggregate::count_all(String::from("Rows")), ColumnDataType::F64, values)
+                    .unwrap(),
+                ColumnValue::U64(rows)
+            );
+        }
+    }
+
+    #[test]
+    fn distinct_aggregates_count_equal_values_once() {
+        let values = || {
+            [
+                ColumnValue::F64(1.0),
+                ColumnValue::F64(0.0),
+                ColumnValue::F64(-0.0),
+                ColumnValue::F64(f64::NAN),
+                ColumnValue::F64(1.0),
+                ColumnValue::F64(f64::NAN),
+                ColumnValue::Null
+            ]
+            .into_iter()
+            .map(Some)
+            .collect::<Vec<_>>()
+        };
+
+        // `-0.0` is equal to `0.0` and `NaN` to `NaN`
+        let count = of(AggregateFunction::Count).distinct();
+        assert_eq!(
+            compute(&count, ColumnDataType::F64, values()).unwrap(),
+            ColumnValue::U64(3)
+        );
+        assert_eq!(
+            compute(&of(AggregateFunction::Count), ColumnDataType::F64, values()).unwrap(),
+            ColumnValue::U64(6)
+        );
+
+        let integers = || {
+            [3, 1, 3, 3]
+                .into_iter()
+                .map(|value| Some(ColumnValue::U8(value)))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            compute(&of(AggregateFunction::Sum).distinct(), ColumnDataType::U8, integers())
+                .unwrap(),
+            ColumnValue::U64(4)
+        );
+        assert_eq!(
+            compute(&of(AggregateFunction::Avg).distinct(), ColumnDataType::U8, integers())
+                .unwrap(),
+            ColumnValue::F64(2.0)
+        );
+    }
+
+    #[test]
+    fn aggregates_are_checked_against_the_columns() {
+        let strings = vec![Some(ColumnValue::from("b")), Some(ColumnValue::from("a"))];
+
+        for function in [AggregateFunction::Sum, AggregateFunction::Avg] {
+            assert!(matches!(
+                compute(&of(function), ColumnDataType::String, strings.clone()),
+                Err(QlError::ColumnTypeMismatch { name }) if name == "Value"
+            ));
+        }
+
+        // other aggregates apply to values of every type
+        assert_eq!(
+            compute(&of(AggregateFunction::Min), ColumnDataType::String, strings.clone()).unwrap(),
+            ColumnValue::from("a")
+        );
+        assert_eq!(
+            compute(&of(AggregateFunction::Count), ColumnDataType::String, strings).unwrap(),
+            ColumnValue::U64(2)
+        );
+
+        let missing = Aggregate::new(
+            String::from("Result"),
+            AggregateFunction::Count,
+            String::from("Missing")
+        );
+        assert!(matches!(
+            compute(&missing, ColumnDataType::U8, Vec::new()),
+            Err(QlError::QueryError(QueryError::RelationDoesNotExist { name }))
+                if name == "Table.Missing"
+        ));
+    }
+}
@@ -0,0 +1,562 @@
+//! # Module `compound`
+//!
+//! This module implements the set operations of the query language, `UNION`, `INTERSECT` and
+//! `EXCEPT` between `SELECT` queries.
+
+use std::{
+    collections::HashSet,
+    sync::Arc
+};
+
+use dashmap::DashMap;
+
+use crate::{
+    error::{
+        QlError,
+        QlResult
+    },
+    mvcc::Snapshot,
+    ql::{
+        select::{
+            self,
+            ResultColumns,
+            Select
+        },
+        sortby::SortBy,
+        Query,
+        QueryContext,
+        QueryRow
+    },
+    CacheTableRow,
+    ColumnValue,
+    QlCache
+};
+
+/// # Enumeration `SetOperator`
+///
+/// The operator combining the rows of a `SELECT` query with the rows combined so far.
+#[allow(clippy::module_name_repetitions)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SetOperator {
+    /// # Enumeration Variant `SetOperator::Union`
+    ///
+    /// `UNION`, the distinct rows of either side.
+    Union,
+
+    /// # Enumeration Variant `SetOperator::UnionAll`
+    ///
+    /// `UNION ALL`, the rows of both sides, including duplicates.
+    UnionAll,
+
+    /// # Enumeration Variant `SetOperator::Intersect`
+    ///
+    /// `INTERSECT`, the distinct rows of the left side that are also on the right side.
+    Intersect,
+
+    /// # Enumeration Variant `SetOperator::Except`
+    ///
+    /// `EXCEPT`, the distinct rows of the left side that are not on the right side.
+    Except
+}
+
+impl SetOperator {
+    /// Combines the rows of the left side with the rows of the right side, which are compared by
+    /// their values of the columns `columns`.
+    fn combine(
+        self,
+        mut left: Vec<Arc<CacheTableRow>>,
+        right: Vec<Arc<CacheTableRow>>,
+        columns: &[String]
+    ) -> Vec<Arc<CacheTableRow>> {
+        let key = |row: &Arc<CacheTableRow>| row.values_of(columns);
+        match self {
+            Self::UnionAll => {
+                left.extend(right);
+                return left;
+            }
+            Self::Union => left.extend(right),
+            Self::Intersect | Self::Except => {
+                let right = right.iter().map(key).collect::<HashSet<_>>();
+                let intersect = self == Self::Intersect;
+                left.retain(|row| right.contains(&key(row)) == intersect);
+            }
+        }
+
+        select::retain_first(&mut left, key);
+        left
+    }
+}
+
+/// # Struct `Compound`
+///
+/// A compound query, which combines the results of `SELECT` queries with set operations.
+#[allow(dead_code)]
+pub struct Compound {
+    pub(crate) select: Select,
+    pub(crate) operations: Vec<(SetOperator, Select)>,
+    pub(crate) sort_by: Option<SortBy>,
+    pub(crate) limit: Option<usize>,
+    pub(crate) snapshot: Option<Snapshot>
+}
+
+impl Compound {
+    #[must_use]
+    pub(crate) fn builder() -> CompoundBuilder {
+        CompoundBuilder {
+            select: None,
+            operations: Vec::new(),
+            sort_by: None,
+            limit: None,
+            snapshot: None
+        }
+    }
+
+    /// Checks that the results of the `SELECT` queries have the same number of columns, and that
+    /// the types of the columns at the same positions are comparable. Returns the columns of the
+    /// result of each query.
+    fn validate(&self, context: &QueryContext<'_>) -> QlResult<Vec<ResultColumns>> {
+        let columns = self.select.result_columns(context)?;
+        let mut result_columns = vec![columns];
+
+        for (_, select) in &self.operations {
+            let columns = select.result_columns(context)?;
+            check_columns(&result_columns[0], &columns)?;
+            result_columns.push(columns);
+        }
+
+        Ok(result_columns)
+    }
+}
+
+impl QueryRow for Compound {
+    fn execute(mut self, cache: &QlCache) -> QlResult<Vec<CacheTableRow>> {
+        let snapshot = self.snapshot.take().unwrap_or_else(|| cache.snapshot());
+        let context = QueryContext::new(cache, snapshot.timestamp);
+
+        // the queries are all checked before any of them is run
+        let result_columns = self.validate(&context)?;
+        let names = result_columns[0]
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+
+        // the columns of the result have the types of the first query that knows them
+        let columns = DashMap::new();
+        for (index, name) in names.iter().enumerate() {
+            let data_type = result_columns
+                .iter()
+                .find_map(|columns| columns[index].1.clone());
+            if let Some(data_type) = data_type {
+                columns.insert(name.clone(), (data_type, true));
+            }
+        }
+
+        if let Some(sort_by) = &self.sort_by {
+            if let Some(column) = sort_by.columns().find(|column| !names.contains(column)) {
+                return Err(QlError::ColumnDoesNotExist {
+                    name: column.clone()
+                });
+            }
+
+            sort_by.validate(&columns, &context)?;
+        }
+
+        let mut rows = self
+            .select
+            .run(&context)?
+            .into_iter()
+            .map(Arc::new)
+            .collect::<Vec<_>>();
+        let operations = self.operations.iter().zip(&result_columns[1..]);
+        for ((operator, select), select_columns) in operations {
+            // the columns are matched by position, and renamed after the columns of the first query
+            let right = select
+                .run(&context)?
+                .iter()
+                .map(|row| Arc::new(rename(row, select_columns, &names)))
+                .collect();
+
+            rows = operator.combine(rows, right, &names);
+        }
+
+        if let Some(sort_by) = &self.sort_by {
+            sort_by.sort(&mut rows, &context)?;
+        }
+
+        if let Some(limit) = self.limit {
+            rows.truncate(limit);
+        }
+
+        Ok(rows
+            .into_iter()
+            .map(|row| Arc::try_unwrap(row).unwrap_or_else(|row| (*row).clone()))
+            .collect())
+    }
+}
+
+/// Checks that the columns of the result of a query match the columns `expected` of the result of
+/// another query it is combined with, by number and by the types at the same positions.
+pub(crate) fn check_columns(expected: &ResultColumns, columns: &ResultColumns) -> QlResult<()> {
+    if columns.len() != expected.len() {
+        return Err(QlError::SetOperationColumnCount {
+            expected: expected.len(),
+            found: columns.len()
+        });
+    }
+
+    for ((name, left), (_, right)) in expected.iter().zip(columns) {
+        if let (Some(left), Some(right)) = (left, right) {
+            if !left.is_comparable_to(right) {
+                return Err(QlError::ColumnTypeMismatch {
+                    name: name.clone()
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Renames the columns `columns` of a row by position after the columns `names` of the query it
+/// is combined with.
+pub(crate) fn rename(
+    row: &CacheTableRow,
+    columns: &ResultColumns,
+    names: &[String]
+) -> CacheTableRow {
+    let column_values = columns
+        .iter()
+        .zip(names)
+        .map(|((column, _), name)| {
+            let value = row
+                .column_values
+                .get(column)
+                .map_or(ColumnValue::Null, |value| value.clone());
+
+            (name.clone(), value)
+        })
+        .collect();
+
+    CacheTableRow {
+        column_values
+    }
+}
+
+/// # Struct `CompoundBuilder`
+///
+/// A builder for a `Compound`, constructs a compound query of `SELECT` queries.
+///
+/// The set operations are applied in order, each to the rows combined so far and the rows of its
+/// query. The columns of the queries are matched by position, and the columns of the result are
+/// named after the columns of the first query. The snapshots of the queries are ignored, as they
+/// all read the snapshot of the compound query.
+///
+/// ## Example
+///
+/// The names of the members of two guilds, except banned members, sorted by name (`SELECT Name
+/// FROM Guild1 UNION SELECT Name FROM Guild2 EXCEPT SELECT Name FROM Bans SORT BY Name LIMIT
+/// 10`):
+/// ```
+/// use qlcache::ql::{
+///     select::SelectScope,
+///     sortby::SortBy,
+///     QueryBuilder
+/// };
+///
+/// let names = |table_name: &str| {
+///     QueryBuilder::select()
+///         .table_name(String::from(table_name))
+///         .scope(SelectScope::Fields(vec![String::from("Name")]))
+///         .build()
+///         .unwrap()
+/// };
+///
+/// let compound = QueryBuilder::compound()
+///     .select(names("Guild1"))
+///     .union(names("Guild2"))
+///     .except(names("Bans"))
+///     .sort_by(SortBy::new(vec![String::from("Name")], None).unwrap())
+///     .limit(10)
+///     .build()
+///     .unwrap();
+/// ```
+#[allow(clippy::module_name_repetitions)]
+pub struct CompoundBuilder {
+    pub(crate) select: Option<Select>,
+    pub(crate) operations: Vec<(SetOperator, Select)>,
+    pub(crate) sort_by: Option<SortBy>,
+    pub(crate) limit: Option<usize>,
+    pub(crate) snapshot: Option<Snapshot>
+}
+
+impl CompoundBuilder {
+    /// # Instance Method `CompoundBuilder::select`
+    ///
+    /// Sets the first `SELECT` query, which the other queries are combined with.
+    ///
+    /// ## Parameters
+    /// - `select`, type `Query<Select>`; the query to set
+    #[must_use]
+    pub fn select(mut self, select: Query<Select>) -> Self {
+        self.select.replace(select.query);
+        self
+    }
+
+    /// # Instance Method `CompoundBuilder::operation`
+    ///
+    /// Combines the rows combined so far with the rows of a `SELECT` query.
+    ///
+    /// ## Parameters
+    /// - `operator`, type `SetOperator`; the set operation to combine the rows with
+    /// - `select`, type `Query<Select>`; the query to combine
+    #[must_use]
+    pub fn operation(mut self, operator: SetOperator, select: Query<Select>) -> Self {
+        self.operations.push((operator, select.query));
+        self
+    }
+
+    /// # Instance Method `CompoundBuilder::union`
+    ///
+    /// Adds the distinct rows of a `SELECT` query (`UNION`).
+    #[must_use]
+    pub fn union(self, select: Query<Select>) -> Self {
+        self.operation(SetOperator::Union, select)
+    }
+
+    /// # Instance Method `CompoundBuilder::union_all`
+    ///
+    /// Adds the rows of a `SELECT` query, including duplicates (`UNION ALL`).
+    #[must_use]
+    pub fn union_all(self, select: Query<Select>) -> Self {
+        self.operation(SetOperator::UnionAll, select)
+    }
+
+    /// # Instance Method `CompoundBuilder::intersect`
+    ///
+    /// Keeps the rows that a `SELECT` query also returns (`INTERSECT`).
+    #[must_use]
+    pub fn intersect(self, select: Query<Select>) -> Self {
+        self.operation(SetOperator::Intersect, select)
+    }
+
+    /// # Instance Method `CompoundBuilder::except`
+    ///
+    /// Removes the rows that a `SELECT` query returns (`EXCEPT`).
+    #[must_use]
+    pub fn except(self, select: Query<Select>) -> Self {
+        self.operation(SetOperator::Except, select)
+    }
+
+    /// # Instance Method `CompoundBuilder::sort_by`
+    ///
+    /// Sets the order of the combined rows, by the columns of the result.
+    ///
+    /// ## Parameters
+    /// - `sort_by`, type `SortBy`; the order to set
+    #[must_use]
+    pub fn sort_by(mut self, sort_by: SortBy) -> Self {
+        self.sort_by.replace(sort_by);
+        self
+    }
+
+    /// # Instance Method `CompoundBuilder::limit`
+    ///
+    /// Sets the maximum number of rows to return, which are the first rows after sorting.
+    ///
+    /// ## Parameters
+    /// - `limit`, type `usize`; the maximum number of rows
+    #[must_use]
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit.replace(limit);
+        self
+    }
+
+    /// # Instance Method `CompoundBuilder::snapshot`
+    ///
+    /// Sets the snapshot to run the queries against. If no snapshot is set, a snapshot is taken
+    /// when the query is executed.
+    ///
+    /// ## Parameters
+    /// - `snapshot`, type `Snapshot`; the snapshot to read from
+    #[must_use]
+    pub fn snapshot(mut self, snapshot: Snapshot) -> Self {
+        self.snapshot.replace(snapshot);
+        self
+    }
+
+    /// # Instance Method `CompoundBuilder::build`
+    ///
+    /// Consumes the builder and returns a `Query<Compound>`.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `RequiredFieldIsNone` if the first query is not set; returns `VecCannotBeEmpty` if
+    /// no query is combined with it.
+    #[allow(clippy::missing_panics_doc)] // this function never panics
+    pub fn build(self) -> QlResult<Query<Compound>> {
+        if self.select.is_none() {
+            return Err(QlError::RequiredFieldIsNone {
+                field_name: String::from("CompoundBuilder.select")
+            });
+        }
+
+        if self.operations.is_empty() {
+            return Err(QlError::VecCannotBeEmpty {
+                vec_name: String::from("CompoundBuilder.operations")
+            });
+        }
+
+        Ok(Query {
+            query: Compound {
+                select: self.select.unwrap(),
+                operations: self.operations,
+                sort_by: self.sort_by,
+                limit: self.limit,
+                snapshot: self.snapshot
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Compound,
+        CompoundBuilder,
+        QueryRow,
+        SetOperator
+    };
+    use crate::{
+        error::{
+            QlError,
+            QlResult
+        },
+        ql::{
+            select::{
+                Select,
+                SelectScope
+            },
+            sortby::SortBy,
+            Query,
+            QueryBuilder
+        },
+        testing,
+        ColumnDataType,
+        ColumnValue,
+        QlCache
+    };
+
+    static_assertions::assert_impl_all!(Compound: QueryRow, Send, Sync);
+    static_assertions::assert_impl_all!(CompoundBuilder: Send, Sync);
+    static_assertions::assert_impl_all!(SetOperator: Clone, Copy, Eq, PartialEq, Send, Sync);
+
+    /// Returns a cache with the tables `Guild1 (Name String)` of the names `a`, `b`, `b` and `c`,
+    /// `Guild2 (Nickname String, Level U64)` of the nicknames `b` and `d`, and `Bans (Name String)`
+    /// of the name `c`.
+    fn cache() -> QlCache {
+        let cache = QlCache::new();
+        testing::table(
+            &cache,
+            "Guild1",
+            [("Name", ColumnDataType::String, false)],
+            ["a", "b", "b", "c"].map(|name| [ColumnValue::from(name)])
+        );
+        testing::table(
+            &cache,
+            "Guild2",
+            [("Nickname", ColumnDataType::String, false), ("Level", ColumnDataType::U64, false)],
+            [("b", 1), ("d", 2)].map(|(nickname, level)| {
+                [ColumnValue::from(nickname), ColumnValue::U64(level)]
+            })
+        );
+        testing::table(
+            &cache,
+            "Bans",
+            [("Name", ColumnDataType::String, false)],
+            [[ColumnValue::from("c")]]
+        );
+
+        cache
+    }
+
+    /// `SELECT columns FROM table_name`
+    fn select(table_name: &str, columns: &[&str]) -> Query<Select> {
+        QueryBuilder::select()
+            .table_name(String::from(table_name))
+            .scope(SelectScope::Fields(
+                columns.iter().map(|column| String::from(*column)).collect()
+            ))
+            .build()
+            .unwrap()
+    }
+
+    /// Returns the names of the rows of a compound query sorted by name.
+    fn names(
+        cache: &QlCache,
+        operations: impl FnOnce(CompoundBuilder) -> CompoundBuilder
+    ) -> QlResult<Vec<String>> {
+        let builder = QueryBuilder::compound()
+            .select(select("Guild1", &["Name"]))
+            .sort_by(SortBy::new(vec![String::from("Name")], None).unwrap());
+
+        operations(builder)
+            .build()
+            .unwrap()
+            .execute(cache)?
+            .iter()
+            .map(|row| row.get::<String>("Name"))
+            .collect()
+    }
+
+    #[test]
+    fn unions_combine_rows_by_position() {
+        let cache = cache();
+        let guild2 = || select("Guild2", &["Nickname"]);
+
+        // the nicknames are named after the names of the first query
+        assert_eq!(names(&cache, |builder| builder.union(guild2())).unwrap(), [
+            "a", "b", "c", "d"
+        ]);
+        assert_eq!(names(&cache, |builder| builder.union_all(guild2())).unwrap(), [
+            "a", "b", "b", "b", "c", "d"
+        ]);
+        assert_eq!(
+            names(&cache, |builder| builder.union_all(guild2()).limit(2)).unwrap(),
+            ["a", "b"]
+        );
+    }
+
+    #[test]
+    fn intersections_and_differences_keep_distinct_rows_of_the_left_side() {
+        let cache = cache();
+        let guild2 = || select("Guild2", &["Nickname"]);
+        let bans = || select("Bans", &["Name"]);
+
+        assert_eq!(names(&cache, |builder| builder.intersect(guild2())).unwrap(), ["b"]);
+        assert_eq!(names(&cache, |builder| builder.except(bans())).unwrap(), ["a", "b"]);
+
+        // the operations are applied in order to the rows combined so far
+        assert_eq!(
+            names(&cache, |builder| builder.except(bans()).union(bans())).unwrap(),
+            ["a", "b", "c"]
+        );
+        assert_eq!(
+            names(&cache, |builder| builder.union(guild2()).except(bans())).unwrap(),
+            ["a", "b", "d"]
+        );
+    }
+
+    #[test]
+    fn combined_queries_must_have_matching_columns() {
+        let cache = cache();
+
+        assert!(matches!(
+            names(&cache, |builder| builder.union(select("Guild2", &["Nickname", "Level"]))),
+            Err(QlError::SetOperationColumnCount { expected: 1, found: 2 })
+        ));
+        assert!(matches!(
+            names(&cache, |builder| builder.union(select("Guild2", &["Level"]))),
+            Err(QlError::ColumnTypeMismatch { name }) if name == "Name"
+        ));
+    }
+}
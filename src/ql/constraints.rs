@@ -2,11 +2,26 @@
 //!
 //! This module implements constraints for the query language.
 
+use std::{
+    cmp::Ordering,
+    time::SystemTime
+};
+
+use dashmap::DashMap;
+
 use crate::{
     error::{
         QlError,
         QlResult
     },
+    ql::{
+        expression::Expression,
+        extract::Extraction,
+        subquery::Subquery,
+        QueryContext
+    },
+    CacheTableRow,
+    ColumnDataType,
     ColumnValue
 };
 
@@ -28,6 +43,68 @@ pub trait ComputableConstraint {
     fn compute_two_operands(&self, _: ColumnValue, _: ColumnValue) -> bool {
         false
     }
+
+    /// # Trait Method `ComputableConstraint::compute_row`
+    ///
+    /// Computes this constraint against the values of a row in the context of a query, and
+    /// returns whether the constraint is satisfied, or `None` if that is unknown because `null`
+    /// was compared. A column missing from the row is treated as `null`. As in SQL, `AND` is
+    /// unsatisfied if either operand is, `OR` is satisfied if either operand is, `NOT` of an
+    /// unknown result is unknown, and a row is only selected if the constraint is satisfied.
+    /// Returns `None` unless overridden, so a constraint that does not override it is unknown for
+    /// every row and selects no rows, even when negated.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `RelationDoesNotExist` if the constraint calls a user-defined function that is not
+    /// registered, or the error of a call or a subquery of the constraint that fails, such as
+    /// `SubqueryRowCount` if a scalar subquery returns more than one row.
+    fn compute_row(&self, _: &CacheTableRow, _: &QueryContext<'_>) -> QlResult<Option<bool>> {
+        Ok(None)
+    }
+
+    /// # Trait Method `ComputableConstraint::equality`
+    ///
+    /// Returns the name of a column and the value it has to be equal to for a row to satisfy this
+    /// constraint, if there is such a column, so that the rows can be looked up in an index instead
+    /// of being scanned. Returns `None` unless overridden.
+    fn equality(&self) -> Option<(&str, &ColumnValue)> {
+        None
+    }
+
+    /// # Trait Method `ComputableConstraint::is_row_local`
+    ///
+    /// Returns whether the result of this constraint only depends on the values of the row it is
+    /// computed against, and not on the time, other rows or other tables, which is required for
+    /// a materialized view to be maintained incrementally. Returns `false` unless overridden.
+    fn is_row_local(&self) -> bool {
+        false
+    }
+
+    /// # Trait Method `ComputableConstraint::relations`
+    ///
+    /// Returns the names of the tables and views the subqueries of this constraint read, which a
+    /// view with the constraint depends on. Returns no names unless overridden.
+    fn relations(&self) -> Vec<&str> {
+        Vec::new()
+    }
+
+    /// # Trait Method `ComputableConstraint::validate`
+    ///
+    /// Checks this constraint against the columns of the table it is computed on, before any
+    /// row is computed.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ColumnDoesNotExist` if a column of the constraint does not exist; returns
+    /// `ColumnTypeMismatch` if the value of the constraint cannot be compared with a column.
+    fn validate(
+        &self,
+        _: &DashMap<String, (ColumnDataType, bool)>,
+        _: &QueryContext<'_>
+    ) -> QlResult<()> {
+        Ok(())
+    }
 }
 
 /// # Struct `Constraint`
@@ -55,12 +132,88 @@ pub trait ComputableConstraint {
 ///     .build()
 ///     .unwrap();
 /// ```
+///
+/// To construct a constraint, that the value of field `JoinedAt`, of type `Timestamp`, is within
+/// the last 7 days (`JoinedAt > now() - interval '7 days'`):
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use qlcache::{
+///     ql::constraints::{
+///         Constraint,
+///         ConstraintOp,
+///         ConstraintValue
+///     },
+///     ColumnValue
+/// };
+///
+/// let constraint = Constraint::builder()
+///     .field_name(String::from("JoinedAt"))
+///     .op(ConstraintOp::Gt)
+///     .value(ConstraintValue::Sub(
+///         Box::new(ConstraintValue::Now),
+///         Box::new(ConstraintValue::Value(ColumnValue::Duration(
+///             Duration::from_secs(7 * 24 * 60 * 60)
+///         )))
+///     ))
+///     .build()
+///     .unwrap();
+/// ```
+///
+/// To construct a constraint, that the snowflake in field `UserId`, of type `Snowflake`, was
+/// created by worker `1`:
+///
+/// ```
+/// use qlcache::{
+///     ql::constraints::{
+///         Constraint,
+///         ConstraintOp
+///     },
+///     types::SnowflakePart,
+///     ColumnValue
+/// };
+///
+/// let constraint = Constraint::builder()
+///     .field_name(String::from("UserId"))
+///     .extract(SnowflakePart::WorkerId)
+///     .op(ConstraintOp::Eq)
+///     .value(ColumnValue::U8(1))
+///     .build()
+///     .unwrap();
+/// ```
+///
+/// To construct a constraint, that the JSON document in field `Data` has a string `b` in the
+/// object `a` that is `"c"` (`Data->'a'->>'b' = 'c'`):
+///
+/// ```
+/// use qlcache::{
+///     ql::{
+///         constraints::{
+///             Constraint,
+///             ConstraintOp
+///         },
+///         extract::Extraction
+///     },
+///     ColumnValue
+/// };
+///
+/// let constraint = Constraint::builder()
+///     .field_name(String::from("Data"))
+///     .extract(Extraction::Field(String::from("a")))
+///     .extract(Extraction::FieldText(String::from("b")))
+///     .op(ConstraintOp::Eq)
+///     .value(ColumnValue::String(String::from("c")))
+///     .build()
+///     .unwrap();
+/// ```
 #[allow(dead_code)]
 #[derive(Clone)]
 pub struct Constraint {
     pub(crate) field_name: String,
+    pub(crate) extract: Vec<Extraction>,
     pub(crate) op: ConstraintOp,
-    pub(crate) value: ColumnValue
+    pub(crate) value: ConstraintValue
 }
 
 impl Constraint {
@@ -71,15 +224,75 @@ impl Constraint {
     pub fn builder() -> ConstraintBuilder {
         ConstraintBuilder {
             field_name: None,
+            extract: Vec::new(),
             op: None,
             value: None
         }
     }
+
+    fn compute_at(&self, col_val: ColumnValue, now: SystemTime) -> Option<bool> {
+        let col_val = Extraction::apply_all(&self.extract, col_val);
+
+        self.op.evaluate(&col_val, &self.value.evaluate(now))
+    }
 }
 
 impl ComputableConstraint for Constraint {
-    fn compute(&self, _: ColumnValue) -> bool {
-        todo!()
+    fn compute(&self, col_val: ColumnValue) -> bool {
+        // `now()` is read from the clock of the cache, so it is unknown outside of a query
+        !self.value.uses_now() && self.compute_at(col_val, SystemTime::UNIX_EPOCH) == Some(true)
+    }
+
+    fn compute_row(
+        &self,
+        row: &CacheTableRow,
+        context: &QueryContext<'_>
+    ) -> QlResult<Option<bool>> {
+        let col_val = row
+            .column_values
+            .get(&self.field_name)
+            .map_or(ColumnValue::Null, |value| value.clone());
+
+        Ok(self.compute_at(col_val, context.now))
+    }
+
+    fn equality(&self) -> Option<(&str, &ColumnValue)> {
+        match (&self.op, &self.value) {
+            (ConstraintOp::Eq, ConstraintValue::Value(value)) if self.extract.is_empty() => {
+                Some((&self.field_name, value))
+            }
+            _ => None
+        }
+    }
+
+    fn is_row_local(&self) -> bool {
+        !self.value.uses_now()
+    }
+
+    fn validate(
+        &self,
+        columns: &DashMap<String, (ColumnDataType, bool)>,
+        _: &QueryContext<'_>
+    ) -> QlResult<()> {
+        let column = columns
+            .get(&self.field_name)
+            .ok_or_else(|| QlError::ColumnDoesNotExist {
+                name: self.field_name.clone()
+            })?;
+
+        // the types of extracted parts and computed values are only known per row
+        let value = match &self.value {
+            ConstraintValue::Value(value) if self.extract.is_empty() => value,
+            _ => return Ok(())
+        };
+
+        if !self.op.is_applicable(&column.0, value) {
+            return Err(QlError::ColumnTypeMismatch {
+                name: self.field_name.clone()
+            });
+        }
+
+        Ok(())
     }
 }
 
@@ -89,8 +302,9 @@ impl ComputableConstraint for Constraint {
 #[derive(Clone)]
 pub struct ConstraintBuilder {
     pub(crate) field_name: Option<String>,
+    pub(crate) extract: Vec<Extraction>,
     pub(crate) op: Option<ConstraintOp>,
-    pub(crate) value: Option<ColumnValue>
+    pub(crate) value: Option<ConstraintValue>
 }
 
 impl ConstraintBuilder {
@@ -106,6 +320,19 @@ impl ConstraintBuilder {
         self
     }
 
+    /// # Instance Method `ConstraintBuilder::extract`
+    ///
+    /// Checks a part of the field against the value instead of the field itself. Parts can be
+    /// extracted in a chain, each from the result of the previous extraction.
+    ///
+    /// ## Parameters
+    /// - `extraction`, type `impl Into<Extraction>`; the part to extract
+    #[must_use]
+    pub fn extract(mut self, extraction: impl Into<Extraction>) -> Self {
+        self.extract.push(extraction.into());
+        self
+    }
+
     /// # Instance Method `ConstraintBuilder::op`
     ///
     /// Sets the "constraint relational operator" for the constraint.
@@ -123,10 +350,11 @@ impl ConstraintBuilder {
     /// Sets the value to check against for the constraint.
     ///
     /// ## Parameters
-    /// - `op`, type `ConstraintOp`; the "constraint relational operator" name to set
+    /// - `value`, type `impl Into<ConstraintValue>`; the value to set, either a `ColumnValue` or a
+    ///   `ConstraintValue` to evaluate when the query is executed
     #[must_use]
-    pub fn value(mut self, value: ColumnValue) -> Self {
-        self.value.replace(value);
+    pub fn value(mut self, value: impl Into<ConstraintValue>) -> Self {
+        self.value.replace(value.into());
         self
     }
 
@@ -134,9 +362,19 @@ impl ConstraintBuilder {
     ///
     /// Consumes the builder and returns a `Constraint`.
     ///
+    /// The type of the field is only known once a query with the constraint is executed on a
+    /// table, so whether the value can be compared with the field is checked then, before any row
+    /// is read (see [`ComputableConstraint::validate`]). The type of the value is checked against
+    /// the operator here: the value of `In` has to be an `Array`, the value of an `Any` of
+    /// `Contains` has to be an `Array`, a `String` or a `Json`, and the elements of an `Array`
+    /// have to be comparable with each other.
+    ///
     /// ## Errors
     ///
-    /// Returns `RequiredFieldIsNone` if any of the fields required is `None`.
+    /// Returns `RequiredFieldIsNone` if any of the fields required is `None`; returns
+    /// `ColumnTypeMismatch` if the operator cannot be applied to the value and a field of any
+    /// type, such as `In` with a value that is not an `Array`, or an `Array` of numbers and
+    /// strings.
     #[allow(clippy::missing_panics_doc)] // this function never panics
     pub fn build(self) -> QlResult<Constraint> {
         if self.field_name.is_none() {
@@ -157,8 +395,17 @@ impl ConstraintBuilder {
             });
         }
 
+        // a computed value has the type of its value at any time
+        let value = self.value.as_ref().unwrap().evaluate(SystemTime::UNIX_EPOCH);
+        if !self.op.as_ref().unwrap().accepts(&value) {
+            return Err(QlError::ColumnTypeMismatch {
+                name: self.field_name.unwrap()
+            });
+        }
+
         Ok(Constraint {
             field_name: self.field_name.unwrap(),
+            extract: self.extract,
             op: self.op.unwrap(),
             value: self.value.unwrap()
         })
@@ -193,7 +440,507 @@ pub enum ConstraintOp {
     /// # Enumeration Variant `ConstraintOp::Ge`
     ///
     /// `>=`
-    Ge
+    Ge,
+
+    /// # Enumeration Variant `ConstraintOp::Contains`
+    ///
+    /// `@>`, whether the column value contains the value, see
+    /// [`ColumnValue::contains`](crate::ColumnValue::contains).
+    Contains,
+
+    /// # Enumeration Variant `ConstraintOp::Any`
+    ///
+    /// `value op ANY(column)`, whether the value satisfies the operator with any element of the
+    /// column value, which is an `Array`. The value is the left operand, so
+    /// `Any(Box::new(ConstraintOp::Gt))` is satisfied if the value is greater than an element.
+    Any(Box<ConstraintOp>),
+
+    /// # Enumeration Variant `ConstraintOp::In`
+    ///
+    /// `column IN (values...)`, whether the column value is equal to any element of the value,
+    /// which is an `Array`.
+    In
+}
+
+impl ConstraintOp {
+    /// # Instance Method `ConstraintOp::is_satisfied_by`
+    ///
+    /// Returns whether the result of comparing a column value with the value of a constraint
+    /// satisfies this operator. `Contains` and `In` are never satisfied by an ordering.
+    ///
+    /// ## Parameters
+    /// - `ordering`, type `Ordering`; the ordering of the column value relative to the value of
+    ///   the constraint
+    #[must_use]
+    pub fn is_satisfied_by(&self, ordering: Ordering) -> bool {
+        match self {
+            Self::Eq => ordering == Ordering::Equal,
+            Self::Lt => ordering == Ordering::Less,
+            Self::Gt => ordering == Ordering::Greater,
+            Self::Le => ordering != Ordering::Greater,
+            Self::Ge => ordering != Ordering::Less,
+            Self::Contains | Self::In => false,
+            Self::Any(op) => op.is_satisfied_by(ordering)
+        }
+    }
+
+    /// Returns whether this operator can be applied to a column of `data_type` and a value.
+    pub(crate) fn is_applicable(&self, data_type: &ColumnDataType, value: &ColumnValue) -> bool {
+        match (self, data_type) {
+            (Self::Contains, ColumnDataType::Array(element)) => {
+                data_type.is_comparable_with(value) || element.is_comparable_with(value)
+            }
+            (Self::Contains, ColumnDataType::String | ColumnDataType::Json) => {
+                data_type.is_comparable_with(value)
+            }
+            (Self::Any(op), ColumnDataType::Array(element)) => {
+                // the type of `null` or of an array of them is unknown, so they can be compared
+                match value.data_type() {
+                    Some(data_type) => op.is_applicable_to(&data_type, element),
+                    None => true
+                }
+            }
+            (Self::Contains | Self::Any(_), _) => false,
+            (Self::In, _) => {
+                match value {
+                    ColumnValue::Array(values) => {
+                        values.iter().all(|value| data_type.is_comparable_with(value))
+                    }
+                    _ => false
+                }
+            }
+            _ => data_type.is_comparable_with(value)
+        }
+    }
+
+    /// Returns whether this operator can be applied to a value and a column of some type, which
+    /// only depends on the value.
+    pub(crate) fn accepts(&self, value: &ColumnValue) -> bool {
+        let is_applicable = match self {
+            Self::In => matches!(value, ColumnValue::Array(_)),
+            Self::Any(op) => {
+                // the value is the left operand of the operator applied to the elements
+                match **op {
+                    Self::Contains => matches!(
+                        value,
+                        ColumnValue::Null
+                            | ColumnValue::Array(_)
+                            | ColumnValue::String(_)
+                            | ColumnValue::Json(_)
+                    ),
+                    Self::Any(_) => matches!(value, ColumnValue::Null | ColumnValue::Array(_)),
+                    _ => true
+                }
+            }
+            _ => true
+        };
+
+        is_applicable && is_comparable(value)
+    }
+
+    /// Returns whether this operator can be applied to values of `left` and `right`.
+    pub(crate) fn is_applicable_to(&self, left: &ColumnDataType, right: &ColumnDataType) -> bool {
+        match (self, left) {
+            (Self::Contains, ColumnDataType::Array(element)) => {
+                left.is_comparable_to(right) || element.is_comparable_to(right)
+            }
+            (Self::Contains, ColumnDataType::String | ColumnDataType::Json) => left == right,
+            (Self::Any(op), ColumnDataType::Array(element)) => op.is_applicable_to(right, element),
+            (Self::Contains | Self::Any(_), _) => false,
+            (Self::In, _) => {
+                match right {
+                    ColumnDataType::Array(element) => left.is_comparable_to(element),
+                    _ => false
+                }
+            }
+            _ => left.is_comparable_to(right)
+        }
+    }
+
+    /// Returns whether a column value satisfies this operator with the value of a constraint.
+    pub(crate) fn is_satisfied(&self, col_val: &ColumnValue, value: &ColumnValue) -> bool {
+        self.evaluate(col_val, value) == Some(true)
+    }
+
+    /// Returns whether a column value satisfies this operator with the value of a constraint, or
+    /// `None` if that is unknown because `null` is compared. An `Any` or `In` is satisfied if an
+    /// element is, unsatisfied if no element is and none is unknown, and unknown otherwise, so
+    /// `null` is not in an empty list.
+    pub(crate) fn evaluate(&self, col_val: &ColumnValue, value: &ColumnValue) -> Option<bool> {
+        match (self, col_val, value) {
+            (Self::In, _, ColumnValue::Array(values)) => {
+                any(values.iter().map(|value| Self::Eq.evaluate(col_val, value)))
+            }
+            (_, ColumnValue::Null, _) | (Self::Contains | Self::Any(_), _, ColumnValue::Null) => {
+                None
+            }
+            (Self::Contains, _, _) => Some(col_val.contains(value)),
+            (Self::Any(op), ColumnValue::Array(elements), _) => {
+                any(elements.iter().map(|element| op.evaluate(value, element)))
+            }
+            (Self::Any(_) | Self::In, _, _) => Some(false),
+            (op, _, _) => col_val
+                .compare(value)
+                .map(|ordering| op.is_satisfied_by(ordering))
+        }
+    }
+}
+
+/// Returns whether a value can be compared with a value of some type, which is not the case for an
+/// `Array` whose elements cannot be compared with each other, such as numbers and strings.
+fn is_comparable(value: &ColumnValue) -> bool {
+    match value {
+        ColumnValue::Array(values) => {
+            let data_type = values.iter().find_map(ColumnValue::data_type);
+
+            values.iter().all(is_comparable)
+                && data_type.is_none_or(|data_type| {
+                    values.iter().all(|value| data_type.is_comparable_with(value))
+                })
+        }
+        _ => true
+    }
+}
+
+/// Returns whether any of the results is satisfied, as an `OR` of all of them.
+fn any(results: impl Iterator<Item = Option<bool>>) -> Option<bool> {
+    let mut unknown = false;
+
+    for result in results {
+        match result {
+            Some(true) => return Some(true),
+            Some(false) => {}
+            None => unknown = true
+        }
+    }
+
+    if unknown {
+        None
+    }
+    else {
+        Some(false)
+    }
+}
+
+/// # Enumeration `ConstraintValue`
+///
+/// The value to check against for a constraint. It is evaluated once per row, with `now()` fixed
+/// to the time of the clock of the cache when the query started executing.
+#[derive(Clone)]
+pub enum ConstraintValue {
+    /// # Enumeration Variant `ConstraintValue::Value`
+    ///
+    /// A constant value.
+    Value(ColumnValue),
+
+    /// # Enumeration Variant `ConstraintValue::Now`
+    ///
+    /// `now()`, the current time as a `Timestamp`. It is unknown outside of a query, so
+    /// `ComputableConstraint::compute` is unsatisfied for a constraint comparing it.
+    Now,
+
+    /// # Enumeration Variant `ConstraintValue::Add`
+    ///
+    /// `left + right`
+    Add(Box<ConstraintValue>, Box<ConstraintValue>),
+
+    /// # Enumeration Variant `ConstraintValue::Sub`
+    ///
+    /// `left - right`
+    Sub(Box<ConstraintValue>, Box<ConstraintValue>)
+}
+
+impl ConstraintValue {
+    /// # Instance Method `ConstraintValue::evaluate`
+    ///
+    /// Evaluates this value. The result is `null` if an addition or subtraction is invalid or
+    /// overflows.
+    ///
+    /// ## Parameters
+    /// - `now`, type `SystemTime`; the value of `now()`
+    #[must_use]
+    pub fn evaluate(&self, now: SystemTime) -> ColumnValue {
+        match self {
+            Self::Value(value) => value.clone(),
+            Self::Now => ColumnValue::Timestamp(now),
+            Self::Add(left, right) => left
+                .evaluate(now)
+                .checked_add(&right.evaluate(now))
+                .unwrap_or(ColumnValue::Null),
+            Self::Sub(left, right) => left
+                .evaluate(now)
+                .checked_sub(&right.evaluate(now))
+                .unwrap_or(ColumnValue::Null)
+        }
+    }
+
+    /// Returns whether the value depends on `now()`.
+    fn uses_now(&self) -> bool {
+        match self {
+            Self::Value(_) => false,
+            Self::Now => true,
+            Self::Add(left, right) | Self::Sub(left, right) => left.uses_now() || right.uses_now()
+        }
+    }
+}
+
+impl From<ColumnValue> for ConstraintValue {
+    fn from(value: ColumnValue) -> Self {
+        Self::Value(value)
+    }
+}
+
+/// # Struct `ExpressionConstraint`
+///
+/// A constraint comparing two expressions, which can refer to any columns of the row.
+///
+/// ## Example
+///
+/// To construct a constraint, that the sum of the fields `Xp` and `Bonus` is at least the value of
+/// the field `Threshold` (`Xp + Bonus >= Threshold`):
+///
+/// ```
+/// use qlcache::ql::{
+///     constraints::{
+///         ConstraintOp,
+///         ExpressionConstraint
+///     },
+///     expression::Expression
+/// };
+///
+/// let constraint = ExpressionConstraint::new(
+///     Expression::column(String::from("Xp")) + Expression::column(String::from("Bonus")),
+///     ConstraintOp::Ge,
+///     Expression::column(String::from("Threshold"))
+/// );
+/// ```
+#[derive(Clone)]
+pub struct ExpressionConstraint {
+    pub(crate) left: Expression,
+    pub(crate) op: ConstraintOp,
+    pub(crate) right: Expression
+}
+
+impl ExpressionConstraint {
+    /// # Constructor `ExpressionConstraint::new`
+    ///
+    /// Constructs a new `ExpressionConstraint`, that `left op right` is satisfied. As in other
+    /// constraints, comparisons with `null` are never satisfied.
+    #[must_use]
+    pub fn new(left: Expression, op: ConstraintOp, right: Expression) -> Self {
+        Self {
+            left,
+            op,
+            right
+        }
+    }
+}
+
+impl ComputableConstraint for ExpressionConstraint {
+    fn compute_row(
+        &self,
+        row: &CacheTableRow,
+        context: &QueryContext<'_>
+    ) -> QlResult<Option<bool>> {
+        Ok(self.op.evaluate(
+            &self.left.evaluate(row, context)?,
+            &self.right.evaluate(row, context)?
+        ))
+    }
+
+    fn is_row_local(&self) -> bool {
+        self.left.is_row_local() && self.right.is_row_local()
+    }
+
+    fn relations(&self) -> Vec<&str> {
+        let mut relations = self.left.relations();
+        relations.extend(self.right.relations());
+        relations
+    }
+
+    fn validate(
+        &self,
+        columns: &DashMap<String, (ColumnDataType, bool)>,
+        context: &QueryContext<'_>
+    ) -> QlResult<()> {
+        let left = self.left.data_type(columns, context)?;
+        let right = self.right.data_type(columns, context)?;
+
+        // an expression that is always null can be compared with anything
+        if let (Some(left), Some(right)) = (left, right) {
+            if !self.op.is_applicable_to(&left, &right) {
+                return Err(QlError::ColumnTypeMismatch {
+                    name: self.right.to_string()
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// # Struct `ExistsConstraint`
+///
+/// An `EXISTS (SELECT ...)` constraint, that a subquery returns any rows. The subquery can refer
+/// to the columns of the row the constraint is computed against with
+/// [`Expression::outer`](crate::ql::expression::Expression::outer).
+///
+/// ## Example
+///
+/// To construct a constraint, that a member has an infraction (`EXISTS (SELECT * FROM Infractions
+/// WHERE Infractions.UserId = Members.UserId)`):
+///
+/// ```
+/// use qlcache::ql::{
+///     constraints::{
+///         ConstraintOp,
+///         ExistsConstraint,
+///         ExpressionConstraint
+///     },
+///     expression::Expression,
+///     select::SelectScope,
+///     subquery::Subquery,
+///     QueryBuilder
+/// };
+///
+/// let infractions = QueryBuilder::select()
+///     .table_name(String::from("Infractions"))
+///     .scope(SelectScope::Everything)
+///     .constraint(Box::new(ExpressionConstraint::new(
+///         Expression::column(String::from("UserId")),
+///         ConstraintOp::Eq,
+///         Expression::outer(String::from("UserId"))
+///     )))
+///     .build()
+///     .unwrap();
+///
+/// let constraint = ExistsConstraint::new(Subquery::new(infractions));
+/// ```
+#[derive(Clone)]
+pub struct ExistsConstraint {
+    pub(crate) subquery: Subquery
+}
+
+impl ExistsConstraint {
+    /// # Constructor `ExistsConstraint::new`
+    ///
+    /// Constructs a new `ExistsConstraint`.
+    #[must_use]
+    pub fn new(subquery: Subquery) -> Self {
+        Self {
+            subquery
+        }
+    }
+}
+
+impl ComputableConstraint for ExistsConstraint {
+    fn compute_row(
+        &self,
+        row: &CacheTableRow,
+        context: &QueryContext<'_>
+    ) -> QlResult<Option<bool>> {
+        Ok(Some(!self.subquery.execute(row, context)?.is_empty()))
+    }
+
+    fn relations(&self) -> Vec<&str> {
+        self.subquery.select.relations()
+    }
+
+    fn validate(
+        &self,
+        columns: &DashMap<String, (ColumnDataType, bool)>,
+        context: &QueryContext<'_>
+    ) -> QlResult<()> {
+        self.subquery.validate(columns, context).map(|_| ())
+    }
+}
+
+/// # Struct `InConstraint`
+///
+/// An `expression IN (SELECT ...)` constraint, that the value of an expression is equal to a
+/// value of the only column of the rows a subquery returns. As with `ConstraintOp::In`, `null` is
+/// never equal to anything.
+///
+/// ## Example
+///
+/// To construct a constraint, that a user is a member of a guild (`UserId IN (SELECT UserId FROM
+/// Members)`):
+///
+/// ```
+/// use qlcache::ql::{
+///     constraints::InConstraint,
+///     expression::Expression,
+///     select::SelectScope,
+///     subquery::Subquery,
+///     QueryBuilder
+/// };
+///
+/// let members = QueryBuilder::select()
+///     .table_name(String::from("Members"))
+///     .scope(SelectScope::Fields(vec![String::from("UserId")]))
+///     .build()
+///     .unwrap();
+///
+/// let constraint = InConstraint::new(
+///     Expression::column(String::from("UserId")),
+///     Subquery::new(members)
+/// );
+/// ```
+#[derive(Clone)]
+pub struct InConstraint {
+    pub(crate) expression: Expression,
+    pub(crate) subquery: Subquery
+}
+
+impl InConstraint {
+    /// # Constructor `InConstraint::new`
+    ///
+    /// Constructs a new `InConstraint`.
+    #[must_use]
+    pub fn new(expression: Expression, subquery: Subquery) -> Self {
+        Self {
+            expression,
+            subquery
+        }
+    }
+}
+
+impl ComputableConstraint for InConstraint {
+    fn compute_row(
+        &self,
+        row: &CacheTableRow,
+        context: &QueryContext<'_>
+    ) -> QlResult<Option<bool>> {
+        let value = self.expression.evaluate(row, context)?;
+        let values = ColumnValue::Array(self.subquery.values(row, context)?);
+
+        Ok(ConstraintOp::In.evaluate(&value, &values))
+    }
+
+    fn relations(&self) -> Vec<&str> {
+        let mut relations = self.expression.relations();
+        relations.extend(self.subquery.select.relations());
+        relations
+    }
+
+    fn validate(
+        &self,
+        columns: &DashMap<String, (ColumnDataType, bool)>,
+        context: &QueryContext<'_>
+    ) -> QlResult<()> {
+        let data_type = self.expression.data_type(columns, context)?;
+        let column_type = self.subquery.column_type(columns, context)?;
+
+        if let (Some(data_type), Some(column_type)) = (data_type, column_type) {
+            if !data_type.is_comparable_to(&column_type) {
+                return Err(QlError::ColumnTypeMismatch {
+                    name: self.expression.to_string()
+                });
+            }
+        }
+
+        Ok(())
+    }
 }
 
 /// # Struct `AndConstraint`
@@ -221,6 +968,49 @@ impl ComputableConstraint for AndConstraint {
     fn compute_two_operands(&self, left_val: ColumnValue, right_val: ColumnValue) -> bool {
         self.left.compute(left_val) && self.right.compute(right_val)
     }
+
+    fn compute_row(
+        &self,
+        row: &CacheTableRow,
+        context: &QueryContext<'_>
+    ) -> QlResult<Option<bool>> {
+        let satisfied = match self.left.compute_row(row, context)? {
+            Some(false) => Some(false),
+            left => {
+                match (left, self.right.compute_row(row, context)?) {
+                    (_, Some(false)) => Some(false),
+                    (Some(true), Some(true)) => Some(true),
+                    _ => None
+                }
+            }
+        };
+
+        Ok(satisfied)
+    }
+
+    // a row only satisfies `AND` if it satisfies both operands
+    fn equality(&self) -> Option<(&str, &ColumnValue)> {
+        self.left.equality().or_else(|| self.right.equality())
+    }
+
+    fn is_row_local(&self) -> bool {
+        self.left.is_row_local() && self.right.is_row_local()
+    }
+
+    fn relations(&self) -> Vec<&str> {
+        let mut relations = self.left.relations();
+        relations.extend(self.right.relations());
+        relations
+    }
+
+    fn validate(
+        &self,
+        columns: &DashMap<String, (ColumnDataType, bool)>,
+        context: &QueryContext<'_>
+    ) -> QlResult<()> {
+        self.left.validate(columns, context)?;
+        self.right.validate(columns, context)
+    }
 }
 
 /// # Struct `AndConstraint`
@@ -244,7 +1034,35 @@ impl NotConstraint {
 
 impl ComputableConstraint for NotConstraint {
     fn compute(&self, col_val: ColumnValue) -> bool {
-        !self.constraint.compute(col_val)
+        // comparing `null` is unknown, and so is its negation
+        col_val != ColumnValue::Null && !self.constraint.compute(col_val)
+    }
+
+    fn compute_row(
+        &self,
+        row: &CacheTableRow,
+        context: &QueryContext<'_>
+    ) -> QlResult<Option<bool>> {
+        Ok(self
+            .constraint
+            .compute_row(row, context)?
+            .map(|satisfied| !satisfied))
+    }
+
+    fn is_row_local(&self) -> bool {
+        self.constraint.is_row_local()
+    }
+
+    fn relations(&self) -> Vec<&str> {
+        self.constraint.relations()
+    }
+
+    fn validate(
+        &self,
+        columns: &DashMap<String, (ColumnDataType, bool)>,
+        context: &QueryContext<'_>
+    ) -> QlResult<()> {
+        self.constraint.validate(columns, context)
     }
 }
 
@@ -271,7 +1089,45 @@ impl OrConstraint {
 
 impl ComputableConstraint for OrConstraint {
     fn compute_two_operands(&self, left_val: ColumnValue, right_val: ColumnValue) -> bool {
-        self.left.compute(left_val) && self.right.compute(right_val)
+        self.left.compute(left_val) || self.right.compute(right_val)
+    }
+
+    fn compute_row(
+        &self,
+        row: &CacheTableRow,
+        context: &QueryContext<'_>
+    ) -> QlResult<Option<bool>> {
+        let satisfied = match self.left.compute_row(row, context)? {
+            Some(true) => Some(true),
+            left => {
+                match (left, self.right.compute_row(row, context)?) {
+                    (_, Some(true)) => Some(true),
+                    (Some(false), Some(false)) => Some(false),
+                    _ => None
+                }
+            }
+        };
+
+        Ok(satisfied)
+    }
+
+    fn is_row_local(&self) -> bool {
+        self.left.is_row_local() && self.right.is_row_local()
+    }
+
+    fn relations(&self) -> Vec<&str> {
+        let mut relations = self.left.relations();
+        relations.extend(self.right.relations());
+        relations
+    }
+
+    fn validate(
+        &self,
+        columns: &DashMap<String, (ColumnDataType, bool)>,
+        context: &QueryContext<'_>
+    ) -> QlResult<()> {
+        self.left.validate(columns, context)?;
+        self.right.validate(columns, context)
     }
 }
 
@@ -279,18 +1135,300 @@ pub(crate) type BoxedConstraint = Box<dyn ComputableConstraint + Send + Sync>;
 
 #[cfg(test)]
 mod tests {
+    use std::{
+        sync::Arc,
+        time::{
+            Duration,
+            SystemTime
+        }
+    };
+
+    use dashmap::DashMap;
+
     use super::{
         AndConstraint,
         ComputableConstraint,
         Constraint,
         ConstraintBuilder,
         ConstraintOp,
+        ConstraintValue,
+        ExistsConstraint,
+        ExpressionConstraint,
+        InConstraint,
+        NotConstraint,
         OrConstraint
     };
+    use crate::{
+        clock::ManualClock,
+        error::QlError,
+        ql::{
+            expression::Expression,
+            QueryContext
+        },
+        CacheTableRow,
+        ColumnValue,
+        QlCache
+    };
 
     static_assertions::assert_impl_all!(AndConstraint: ComputableConstraint, Send, Sync);
     static_assertions::assert_impl_all!(Constraint: Clone, ComputableConstraint, Send, Sync);
     static_assertions::assert_impl_all!(ConstraintBuilder: Clone, Send, Sync);
     static_assertions::assert_impl_all!(ConstraintOp: Clone, Eq, PartialEq, Send, Sync);
+    static_assertions::assert_impl_all!(ConstraintValue: Clone, Send, Sync);
+    static_assertions::assert_impl_all!(ExistsConstraint: Clone, ComputableConstraint, Send, Sync);
+    static_assertions::assert_impl_all!(
+        ExpressionConstraint: Clone, ComputableConstraint, Send, Sync
+    );
+    static_assertions::assert_impl_all!(InConstraint: Clone, ComputableConstraint, Send, Sync);
     static_assertions::assert_impl_all!(OrConstraint: ComputableConstraint, Send, Sync);
+
+    fn xp_is(value: u64) -> Constraint {
+        Constraint::builder()
+            .field_name(String::from("Xp"))
+            .op(ConstraintOp::Eq)
+            .value(ColumnValue::U64(value))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn every_constraint_computes_against_rows() {
+        let cache = QlCache::new();
+        let context = QueryContext::new(&cache, 0);
+        let row = CacheTableRow {
+            column_values: DashMap::from_iter([
+                (String::from("Xp"), ColumnValue::U64(10)),
+                (String::from("Bonus"), ColumnValue::U64(5))
+            ])
+        };
+
+        assert_eq!(xp_is(10).compute_row(&row, &context).unwrap(), Some(true));
+        assert_eq!(xp_is(11).compute_row(&row, &context).unwrap(), Some(false));
+        assert_eq!(
+            ExpressionConstraint::new(
+                Expression::column(String::from("Xp")),
+                ConstraintOp::Gt,
+                Expression::column(String::from("Bonus"))
+            )
+            .compute_row(&row, &context)
+            .unwrap(),
+            Some(true)
+        );
+        assert_eq!(
+            AndConstraint::new(Box::new(xp_is(10)), Box::new(xp_is(11)))
+                .compute_row(&row, &context)
+                .unwrap(),
+            Some(false)
+        );
+        assert_eq!(
+            OrConstraint::new(Box::new(xp_is(11)), Box::new(xp_is(10)))
+                .compute_row(&row, &context)
+                .unwrap(),
+            Some(true)
+        );
+        assert_eq!(
+            NotConstraint::new(Box::new(xp_is(11)))
+                .compute_row(&row, &context)
+                .unwrap(),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn now_is_read_from_the_clock_of_the_cache() {
+        const DAY: Duration = Duration::from_hours(24);
+
+        let clock = Arc::new(ManualClock::new(SystemTime::UNIX_EPOCH + DAY));
+        let cache = QlCache::with_clock(clock.clone());
+        let joined_at = ColumnValue::Timestamp(SystemTime::UNIX_EPOCH);
+        let row = CacheTableRow {
+            column_values: DashMap::from_iter([(String::from("JoinedAt"), joined_at.clone())])
+        };
+
+        // `JoinedAt > now() - interval '7 days'`
+        let constraint = Constraint::builder()
+            .field_name(String::from("JoinedAt"))
+            .op(ConstraintOp::Gt)
+            .value(ConstraintValue::Sub(
+                Box::new(ConstraintValue::Now),
+                Box::new(ConstraintValue::Value(ColumnValue::Duration(7 * DAY)))
+            ))
+            .build()
+            .unwrap();
+
+        let context = QueryContext::new(&cache, 0);
+        assert_eq!(constraint.compute_row(&row, &context).unwrap(), Some(true));
+
+        clock.advance(7 * DAY);
+        let context = QueryContext::new(&cache, 0);
+        assert_eq!(constraint.compute_row(&row, &context).unwrap(), Some(false));
+
+        // without a cache there is no clock to read `now()` from
+        assert!(!constraint.compute(joined_at));
+    }
+
+    #[test]
+    fn constraints_of_other_crates_need_not_compute_rows() {
+        struct Never;
+
+        impl ComputableConstraint for Never {}
+
+        let cache = QlCache::new();
+        let context = QueryContext::new(&cache, 0);
+        let row = CacheTableRow {
+            column_values: DashMap::from_iter([(String::from("Xp"), ColumnValue::U64(10))])
+        };
+
+        // the result is unknown, so neither the constraint nor its negation selects the row
+        assert_eq!(Never.compute_row(&row, &context).unwrap(), None);
+        assert_eq!(
+            NotConstraint::new(Box::new(Never))
+                .compute_row(&row, &context)
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn comparisons_with_null_are_unknown() {
+        let cache = QlCache::new();
+        let context = QueryContext::new(&cache, 0);
+        let row = CacheTableRow {
+            column_values: DashMap::from_iter([(String::from("Xp"), ColumnValue::Null)])
+        };
+        let unknown = || Box::new(xp_is(5));
+        let satisfied = || {
+            Box::new(
+                Constraint::builder()
+                    .field_name(String::from("Xp"))
+                    .op(ConstraintOp::In)
+                    .value(ColumnValue::Array(Vec::new()))
+                    .build()
+                    .unwrap()
+            )
+        };
+
+        // NOT (Xp = 5) does not match a null Xp
+        assert_eq!(unknown().compute_row(&row, &context).unwrap(), None);
+        assert_eq!(NotConstraint::new(unknown()).compute_row(&row, &context).unwrap(), None);
+        assert!(!NotConstraint::new(unknown()).compute(ColumnValue::Null));
+
+        // an empty list never contains a value, not even null
+        assert_eq!(satisfied().compute_row(&row, &context).unwrap(), Some(false));
+        assert_eq!(
+            NotConstraint::new(satisfied()).compute_row(&row, &context).unwrap(),
+            Some(true)
+        );
+
+        assert_eq!(
+            AndConstraint::new(unknown(), Box::new(NotConstraint::new(satisfied())))
+                .compute_row(&row, &context).unwrap(),
+            None
+        );
+        assert_eq!(
+            AndConstraint::new(unknown(), satisfied()).compute_row(&row, &context).unwrap(),
+            Some(false)
+        );
+        assert_eq!(
+            OrConstraint::new(unknown(), satisfied()).compute_row(&row, &context).unwrap(),
+            None
+        );
+        assert_eq!(
+            OrConstraint::new(unknown(), Box::new(NotConstraint::new(satisfied())))
+                .compute_row(&row, &context).unwrap(),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn in_lists_must_be_arrays() {
+        let build = |value| {
+            Constraint::builder()
+                .field_name(String::from("Xp"))
+                .op(ConstraintOp::In)
+                .value(value)
+                .build()
+        };
+
+        assert!(build(ConstraintValue::from(ColumnValue::Array(Vec::new()))).is_ok());
+        for value in [
+            ConstraintValue::from(ColumnValue::U64(1)),
+            ConstraintValue::from(ColumnValue::Null),
+            ConstraintValue::Now
+        ] {
+            assert!(matches!(
+                build(value),
+                Err(QlError::ColumnTypeMismatch { name }) if name == "Xp"
+            ));
+        }
+    }
+
+    #[test]
+    fn values_are_checked_against_the_operator_when_built() {
+        let build = |op, value: ColumnValue| {
+            Constraint::builder()
+                .field_name(String::from("Tags"))
+                .op(op)
+                .value(value)
+                .build()
+        };
+        let string = |string: &str| ColumnValue::String(string.to_string());
+        let strings = |strings: &[&str]| {
+            ColumnValue::Array(strings.iter().map(|value| string(value)).collect())
+        };
+        let mixed = ColumnValue::Array(vec![ColumnValue::U64(1), string("a")]);
+        let contains_any = || ConstraintOp::Any(Box::new(ConstraintOp::Contains));
+        let any_any = ConstraintOp::Any(Box::new(ConstraintOp::Any(Box::new(ConstraintOp::Eq))));
+
+        // numbers of any types and nulls can be in the same list
+        let numbers = vec![ColumnValue::U8(1), ColumnValue::Null, ColumnValue::I64(-1)];
+        assert!(build(ConstraintOp::In, ColumnValue::Array(numbers)).is_ok());
+        assert!(build(ConstraintOp::Eq, strings(&["a", "b"])).is_ok());
+        assert!(build(contains_any(), strings(&["a"])).is_ok());
+        assert!(build(contains_any(), string("a")).is_ok());
+
+        for (op, value) in [
+            (ConstraintOp::In, mixed.clone()),
+            (ConstraintOp::Eq, mixed.clone()),
+            (ConstraintOp::Contains, ColumnValue::Array(vec![mixed])),
+            (contains_any(), ColumnValue::U64(1)),
+            (any_any, ColumnValue::Bool(true))
+        ] {
+            assert!(matches!(
+                build(op, value),
+                Err(QlError::ColumnTypeMismatch { name }) if name == "Tags"
+            ));
+        }
+    }
+
+    #[test]
+    fn lists_with_null_elements_are_unknown_unless_matched() {
+        let in_list = ConstraintOp::In;
+        let list = ColumnValue::Array(vec![ColumnValue::U64(1), ColumnValue::Null]);
+
+        assert_eq!(in_list.evaluate(&ColumnValue::U64(1), &list), Some(true));
+        assert_eq!(in_list.evaluate(&ColumnValue::U64(2), &list), None);
+
+        // `value > ANY(list)`
+        let greater_than_any = ConstraintOp::Any(Box::new(ConstraintOp::Gt));
+        assert_eq!(greater_than_any.evaluate(&list, &ColumnValue::U64(2)), Some(true));
+        assert_eq!(greater_than_any.evaluate(&list, &ColumnValue::U64(1)), None);
+        assert_eq!(greater_than_any.evaluate(&list, &ColumnValue::U64(0)), None);
+
+        let numbers = ColumnValue::Array(vec![ColumnValue::U64(1), ColumnValue::U64(3)]);
+        assert_eq!(greater_than_any.evaluate(&numbers, &ColumnValue::U64(2)), Some(true));
+        assert_eq!(greater_than_any.evaluate(&numbers, &ColumnValue::U64(1)), Some(false));
+        assert_eq!(
+            ConstraintOp::Any(Box::new(ConstraintOp::Lt)).evaluate(&numbers, &ColumnValue::U64(2)),
+            Some(true)
+        );
+        assert_eq!(
+            ConstraintOp::Any(Box::new(ConstraintOp::Lt)).evaluate(&numbers, &ColumnValue::U64(3)),
+            Some(false)
+        );
+        assert_eq!(
+            ConstraintOp::Contains.evaluate(&list, &ColumnValue::Null),
+            None
+        );
+    }
 }
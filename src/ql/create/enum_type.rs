@@ -0,0 +1,203 @@
+//! # Module `enum_type`
+//!
+//! This module implements the `CREATE TYPE ... AS ENUM` query.
+
+use crate::{
+    error::{
+        QlError,
+        QlResult,
+        QueryError
+    },
+    ql::{
+        Query,
+        QueryRow
+    },
+    types::EnumType,
+    CacheTableRow,
+    QlCache
+};
+
+/// # Struct `CreateEnumType`
+///
+/// A `CREATE TYPE ... AS ENUM` query.
+#[allow(clippy::module_name_repetitions)]
+pub struct CreateEnumType {
+    pub(crate) name: String,
+    pub(crate) labels: Vec<String>,
+    pub(crate) schema: String,
+    pub(crate) if_not_exist: bool
+}
+
+impl QueryRow for CreateEnumType {
+    fn execute(self, cache: &QlCache) -> QlResult<Vec<CacheTableRow>> {
+        let _definitions = cache.lock_definitions();
+        let schema = cache.cache.get(&self.schema).ok_or_else(|| {
+            QlError::QueryError(QueryError::RelationDoesNotExist {
+                name: self.schema.clone()
+            })
+        })?;
+
+        if schema.types.contains_key(&self.name) {
+            if !self.if_not_exist {
+                return Err(QlError::QueryError(QueryError::RelationAlreadyExists {
+                    name: self.name
+                }));
+            }
+
+            return Ok(vec![]);
+        }
+
+        schema.types.insert(self.name.clone(), EnumType {
+            name: self.name,
+            labels: self.labels
+        });
+
+        Ok(vec![])
+    }
+}
+
+/// # Struct `CreateEnumTypeBuilder`
+///
+/// A builder for a `CreateEnumType`, constructs a `CREATE TYPE ... AS ENUM` query.
+///
+/// ## Examples
+///
+/// - `CREATE TYPE InfractionKind AS ENUM ('warn', 'mute', 'ban')`:
+/// ```
+/// use qlcache::ql::QueryBuilder;
+///
+/// let create_type = QueryBuilder::create()
+///     .enum_type()
+///     .name(String::from("InfractionKind"))
+///     .labels(vec![String::from("warn"), String::from("mute"), String::from("ban")])
+///     .build()
+///     .unwrap();
+/// ```
+///
+/// - `CREATE TABLE Infractions COLUMNS (Kind InfractionKind NOT NULL)`, using the type:
+/// ```
+/// use qlcache::{
+///     ql::QueryBuilder,
+///     ColumnDataType
+/// };
+///
+/// let create_table = QueryBuilder::create()
+///     .table()
+///     .name(String::from("Infractions"))
+///     .columns(vec![(
+///         String::from("Kind"),
+///         (ColumnDataType::Enum(String::from("InfractionKind")), false)
+///     )])
+///     .build()
+///     .unwrap();
+/// ```
+#[allow(clippy::module_name_repetitions)]
+pub struct CreateEnumTypeBuilder {
+    pub(crate) name: Option<String>,
+    pub(crate) labels: Vec<String>,
+    pub(crate) schema: Option<String>,
+    pub(crate) if_not_exist: bool
+}
+
+impl CreateEnumTypeBuilder {
+    /// # Instance Method `CreateEnumTypeBuilder::name`
+    ///
+    /// Sets the name of the type to create.
+    ///
+    /// ## Parameters
+    /// - `name`, type `String`; the name of the type
+    #[must_use]
+    pub fn name(mut self, name: String) -> Self {
+        self.name.replace(name);
+        self
+    }
+
+    /// # Instance Method `CreateEnumTypeBuilder::labels`
+    ///
+    /// Sets the labels of the type, in the order the values of the type are sorted in.
+    ///
+    /// ## Parameters
+    /// - `labels`, type `Vec<String>`; the labels of the type
+    #[must_use]
+    pub fn labels(mut self, labels: Vec<String>) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    /// # Instance Method `CreateEnumTypeBuilder::schema`
+    ///
+    /// Sets the parent schema for this type to be created.
+    ///
+    /// ## Parameters
+    /// - `schema`, type `String`; the parent schema name
+    #[must_use]
+    pub fn schema(mut self, schema: String) -> Self {
+        self.schema.replace(schema);
+        self
+    }
+
+    /// # Instance Method `CreateEnumTypeBuilder::if_not_exist`
+    ///
+    /// Sets the type to be created if it does not exist yet, otherwise does nothing.
+    #[must_use]
+    pub fn if_not_exist(mut self) -> Self {
+        self.if_not_exist = true;
+        self
+    }
+
+    /// # Instance Method `CreateEnumTypeBuilder::build`
+    ///
+    /// Consumes the builder and returns a `Query<CreateEnumType>`.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `RequiredFieldIsNone` if any of the fields required is `None`; returns
+    /// `VecCannotBeEmpty` if the type has no labels; returns `DuplicateEnumLabel` if a label is
+    /// declared more than once.
+    #[allow(clippy::missing_panics_doc)] // this function never panics
+    pub fn build(self) -> QlResult<Query<CreateEnumType>> {
+        if self.name.is_none() {
+            return Err(QlError::RequiredFieldIsNone {
+                field_name: String::from("CreateEnumTypeBuilder.name")
+            });
+        }
+
+        if self.labels.is_empty() {
+            return Err(QlError::VecCannotBeEmpty {
+                vec_name: String::from("CreateEnumTypeBuilder.labels")
+            });
+        }
+
+        if let Some((_, label)) = self
+            .labels
+            .iter()
+            .enumerate()
+            .find(|(index, label)| self.labels[..*index].contains(label))
+        {
+            return Err(QlError::DuplicateEnumLabel {
+                label: label.clone()
+            });
+        }
+
+        Ok(Query {
+            query: CreateEnumType {
+                name: self.name.unwrap(),
+                labels: self.labels,
+                schema: self.schema.unwrap_or_else(|| String::from("PUBLIC")),
+                if_not_exist: self.if_not_exist
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        CreateEnumType,
+        CreateEnumTypeBuilder,
+        QueryRow
+    };
+
+    static_assertions::assert_impl_all!(CreateEnumType: QueryRow, Send, Sync);
+    static_assertions::assert_impl_all!(CreateEnumTypeBuilder: Send, Sync);
+}
@@ -0,0 +1,248 @@
+//! # Module `materialized_view`
+//!
+//! This module implements the `CREATE MATERIALIZED VIEW` query.
+
+use crate::{
+    error::{
+        QlError,
+        QlResult,
+        QueryError
+    },
+    ql::{
+        materialized::MaterializedView,
+        select::Select,
+        Query,
+        QueryRow
+    },
+    CacheTableRow,
+    QlCache
+};
+
+/// # Struct `CreateMaterializedView`
+///
+/// A `CREATE MATERIALIZED VIEW ... AS SELECT ...` query.
+#[allow(clippy::module_name_repetitions)]
+pub struct CreateMaterializedView {
+    pub(crate) name: String,
+    pub(crate) select: Select,
+    pub(crate) schema: String,
+    pub(crate) if_not_exist: bool
+}
+
+impl CreateMaterializedView {
+    /// Returns whether a relation named like the view exists in the schema.
+    fn exists(&self, cache: &QlCache) -> QlResult<bool> {
+        let schema = cache.cache.get(&self.schema).ok_or_else(|| {
+            QlError::QueryError(QueryError::RelationDoesNotExist {
+                name: self.schema.clone()
+            })
+        })?;
+
+        Ok(schema.contains_relation(&self.name))
+    }
+}
+
+impl QueryRow for CreateMaterializedView {
+    fn execute(self, cache: &QlCache) -> QlResult<Vec<CacheTableRow>> {
+        // the rows of the view are computed without holding the schema, and no relation can be
+        // created or dropped until the view is stored
+        let _definitions = cache.lock_definitions();
+        if !self.exists(cache)? {
+            let view = MaterializedView::new(cache, &self.schema, self.name.clone(), self.select)?;
+
+            // schemas are never dropped, so the schema checked above still exists
+            if let Some(schema) = cache.cache.get(&self.schema) {
+                schema.materialized_views.insert(self.name, view);
+            }
+
+            return Ok(vec![]);
+        }
+
+        if !self.if_not_exist {
+            return Err(QlError::QueryError(QueryError::RelationAlreadyExists {
+                name: self.name
+            }));
+        }
+
+        Ok(vec![])
+    }
+}
+
+/// # Struct `CreateMaterializedViewBuilder`
+///
+/// A builder for a `CreateMaterializedView`, constructs a `CREATE MATERIALIZED VIEW ... AS
+/// SELECT ...` query.
+///
+/// The snapshot of the query of the view is discarded, as the rows of the view are computed from
+/// the latest rows of the relation it selects from, and a stored snapshot would keep the versions
+/// it reads from being reclaimed.
+///
+/// ## Example
+///
+/// The number of members of each guild (`CREATE MATERIALIZED VIEW MemberCounts AS SELECT
+/// GuildId, count(*) AS Members FROM Members GROUP BY GuildId`), which is maintained
+/// incrementally:
+/// ```
+/// use qlcache::{
+///     ql::{
+///         aggregate::Aggregate,
+///         select::SelectScope,
+///         QueryBuilder
+///     },
+///     CacheTableRow,
+///     ColumnDataType,
+///     ColumnValue,
+///     QlCache
+/// };
+///
+/// let cache = QlCache::new();
+/// cache
+///     .execute(
+///         QueryBuilder::create()
+///             .table()
+///             .name(String::from("Members"))
+///             .columns(vec![
+///                 (String::from("UserId"), (ColumnDataType::U64, false)),
+///                 (String::from("GuildId"), (ColumnDataType::U64, false))
+///             ])
+///             .build()
+///             .unwrap()
+///     )
+///     .unwrap();
+///
+/// let create_view = QueryBuilder::create()
+///     .materialized_view()
+///     .name(String::from("MemberCounts"))
+///     .select(
+///         QueryBuilder::select()
+///             .table_name(String::from("Members"))
+///             .scope(SelectScope::Aggregates(vec![Aggregate::count_all(String::from(
+///                 "Members"
+///             ))]))
+///             .group_by(vec![String::from("GuildId")])
+///             .build()
+///             .unwrap()
+///     )
+///     .build()
+///     .unwrap();
+/// cache.execute(create_view).unwrap();
+/// assert!(cache.materialized_view("MemberCounts").unwrap().is_incremental());
+///
+/// let row = CacheTableRow {
+///     column_values: Default::default()
+/// };
+/// row.column_values.insert(String::from("UserId"), ColumnValue::U64(1));
+/// row.column_values.insert(String::from("GuildId"), ColumnValue::U64(7));
+/// cache.compare_and_swap("Members", 1, 0, row).unwrap();
+///
+/// let counts = QueryBuilder::select()
+///     .table_name(String::from("MemberCounts"))
+///     .scope(SelectScope::Everything)
+///     .build()
+///     .unwrap();
+/// let counts = cache.execute(counts).unwrap();
+/// assert_eq!(counts[0].get::<u64>("Members").unwrap(), 1);
+/// ```
+#[allow(clippy::module_name_repetitions)]
+pub struct CreateMaterializedViewBuilder {
+    pub(crate) name: Option<String>,
+    pub(crate) select: Option<Select>,
+    pub(crate) schema: Option<String>,
+    pub(crate) if_not_exist: bool
+}
+
+impl CreateMaterializedViewBuilder {
+    /// # Instance Method `CreateMaterializedViewBuilder::name`
+    ///
+    /// Sets the name of the materialized view to create.
+    ///
+    /// ## Parameters
+    /// - `name`, type `String`; the name of the materialized view
+    #[must_use]
+    pub fn name(mut self, name: String) -> Self {
+        self.name.replace(name);
+        self
+    }
+
+    /// # Instance Method `CreateMaterializedViewBuilder::select`
+    ///
+    /// Sets the query of the materialized view, discarding its snapshot.
+    ///
+    /// ## Parameters
+    /// - `select`, type `Query<Select>`; the query of the materialized view
+    #[must_use]
+    pub fn select(mut self, select: Query<Select>) -> Self {
+        let mut select = select.query;
+        select.snapshot = None;
+
+        self.select.replace(select);
+        self
+    }
+
+    /// # Instance Method `CreateMaterializedViewBuilder::schema`
+    ///
+    /// Sets the parent schema for this materialized view to be created.
+    ///
+    /// ## Parameters
+    /// - `schema`, type `String`; the parent schema name
+    #[must_use]
+    pub fn schema(mut self, schema: String) -> Self {
+        self.schema.replace(schema);
+        self
+    }
+
+    /// # Instance Method `CreateMaterializedViewBuilder::if_not_exist`
+    ///
+    /// Sets the materialized view to be created if it does not exist yet, otherwise does
+    /// nothing.
+    #[must_use]
+    pub fn if_not_exist(mut self) -> Self {
+        self.if_not_exist = true;
+        self
+    }
+
+    /// # Instance Method `CreateMaterializedViewBuilder::build`
+    ///
+    /// Consumes the builder and returns a `Query<CreateMaterializedView>`. The query of the view
+    /// is checked against the relations it reads when the `CREATE MATERIALIZED VIEW` query is
+    /// executed, which fails with `RelationDoesNotExist` if one of them does not exist.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `RequiredFieldIsNone` if any of the fields required is `None`.
+    #[allow(clippy::missing_panics_doc)] // this function never panics
+    pub fn build(self) -> QlResult<Query<CreateMaterializedView>> {
+        if self.name.is_none() {
+            return Err(QlError::RequiredFieldIsNone {
+                field_name: String::from("CreateMaterializedViewBuilder.name")
+            });
+        }
+
+        if self.select.is_none() {
+            return Err(QlError::RequiredFieldIsNone {
+                field_name: String::from("CreateMaterializedViewBuilder.select")
+            });
+        }
+
+        Ok(Query {
+            query: CreateMaterializedView {
+                name: self.name.unwrap(),
+                select: self.select.unwrap(),
+                schema: self.schema.unwrap_or_else(|| String::from("PUBLIC")),
+                if_not_exist: self.if_not_exist
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        CreateMaterializedView,
+        CreateMaterializedViewBuilder,
+        QueryRow
+    };
+
+    static_assertions::assert_impl_all!(CreateMaterializedView: QueryRow, Send, Sync);
+    static_assertions::assert_impl_all!(CreateMaterializedViewBuilder: Send, Sync);
+}
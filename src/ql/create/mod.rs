@@ -2,13 +2,22 @@
 //!
 //! This module implements the `CREATE` query.
 
-use crate::ql::create::{
-    schema::CreateSchemaBuilder,
-    table::CreateTableBuilder
+use crate::{
+    eviction::CapacityLimits,
+    ql::create::{
+        enum_type::CreateEnumTypeBuilder,
+        materialized_view::CreateMaterializedViewBuilder,
+        schema::CreateSchemaBuilder,
+        table::CreateTableBuilder,
+        view::CreateViewBuilder
+    }
 };
 
+pub mod enum_type;
+pub mod materialized_view;
 pub mod schema;
 pub mod table;
+pub mod view;
 
 /// # Struct `Create`
 ///
@@ -29,6 +38,34 @@ impl Create {
 pub struct CreateBuilder;
 
 impl CreateBuilder {
+    /// # Instance Method `CreateBuilder::enum_type`
+    ///
+    /// Returns a builder for a `CREATE TYPE ... AS ENUM` query.
+    #[allow(clippy::unused_self)]
+    #[must_use]
+    pub fn enum_type(self) -> CreateEnumTypeBuilder {
+        CreateEnumTypeBuilder {
+            name: None,
+            labels: Vec::new(),
+            schema: None,
+            if_not_exist: false
+        }
+    }
+
+    /// # Instance Method `CreateBuilder::materialized_view`
+    ///
+    /// Returns a builder for a `CREATE MATERIALIZED VIEW` query.
+    #[allow(clippy::unused_self)]
+    #[must_use]
+    pub fn materialized_view(self) -> CreateMaterializedViewBuilder {
+        CreateMaterializedViewBuilder {
+            name: None,
+            select: None,
+            schema: None,
+            if_not_exist: false
+        }
+    }
+
     /// # Instance Method `CreateBuilder::schema`
     ///
     /// Returns a builder for a `CREATE SCHEMA` query.
@@ -51,6 +88,24 @@ impl CreateBuilder {
             name: None,
             columns: Vec::new(),
             primary_key: None,
+            foreign_keys: Vec::new(),
+            schema: None,
+            if_not_exist: false,
+            ttl: None,
+            limits: CapacityLimits::default(),
+            eviction_policy: None
+        }
+    }
+
+    /// # Instance Method `CreateBuilder::view`
+    ///
+    /// Returns a builder for a `CREATE VIEW` query.
+    #[allow(clippy::unused_self)]
+    #[must_use]
+    pub fn view(self) -> CreateViewBuilder {
+        CreateViewBuilder {
+            name: None,
+            select: None,
             schema: None,
             if_not_exist: false
         }
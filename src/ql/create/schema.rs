@@ -30,6 +30,7 @@ pub struct CreateSchema {
 
 impl QueryRow for CreateSchema {
     fn execute(self, cache: &QlCache) -> QlResult<Vec<CacheTableRow>> {
+        let _definitions = cache.lock_definitions();
         if cache.cache.contains_key(&self.name) {
             if !self.if_not_exist {
                 return Err(QlError::QueryError(QueryError::RelationAlreadyExists {
@@ -44,7 +45,10 @@ impl QueryRow for CreateSchema {
             self.name.clone(),
             CacheSchema {
                 name: self.name,
-                tables: DashMap::new()
+                tables: DashMap::new(),
+                types: DashMap::new(),
+                views: DashMap::new(),
+                materialized_views: DashMap::new()
             }
         );
 
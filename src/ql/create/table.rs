@@ -2,6 +2,12 @@
 //!
 //! This module implements the `CREATE TABLE` query.
 
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::Duration
+};
+
 use dashmap::DashMap;
 
 use crate::{
@@ -11,10 +17,23 @@ use crate::{
         QueryError
     },
     ql::{
-        key::PrimaryKey,
+        key::{
+            ForeignIndex,
+            ForeignKey,
+            PrimaryIndex,
+            PrimaryKey
+        },
         Query,
         QueryRow
     },
+    eviction::{
+        CapacityLimits,
+        EvictionPolicy,
+        LruPolicy,
+        Usage
+    },
+    expiry::ExpiryQueue,
+    mvcc::GarbageQueue,
     CacheTable,
     CacheTableRow,
     ColumnDataType,
@@ -29,22 +48,24 @@ pub struct CreateTable {
     pub(crate) name: String,
     pub(crate) columns: Vec<(String, (ColumnDataType, bool))>,
     pub(crate) primary_key: Option<PrimaryKey>,
+    pub(crate) foreign_keys: Vec<ForeignKey>,
     pub(crate) schema: String,
-    pub(crate) if_not_exist: bool
+    pub(crate) if_not_exist: bool,
+    pub(crate) ttl: Option<Duration>,
+    pub(crate) limits: CapacityLimits,
+    pub(crate) eviction_policy: Option<Arc<dyn EvictionPolicy>>
 }
 
 impl QueryRow for CreateTable {
     fn execute(self, cache: &QlCache) -> QlResult<Vec<CacheTableRow>> {
-        let schema = if let Some(entry) = cache.cache.get(&self.schema) {
-            entry.value().clone()
-        }
-        else {
-            return Err(QlError::QueryError(QueryError::RelationDoesNotExist {
-                name: self.schema
-            }));
-        };
+        let _definitions = cache.lock_definitions();
+        let schema = cache.cache.get(&self.schema).ok_or_else(|| {
+            QlError::QueryError(QueryError::RelationDoesNotExist {
+                name: self.schema.clone()
+            })
+        })?;
 
-        if schema.tables.contains_key(&self.name) {
+        if schema.contains_relation(&self.name) {
             if !self.if_not_exist {
                 return Err(QlError::QueryError(QueryError::RelationAlreadyExists {
                     name: self.name
@@ -54,20 +75,102 @@ impl QueryRow for CreateTable {
             return Ok(vec![]);
         }
 
+        let enum_types = self
+            .columns
+            .iter()
+            .filter_map(|(_, (data_type, _))| {
+                match data_type {
+                    ColumnDataType::Enum(type_name) => Some(type_name),
+                    _ => None
+                }
+            })
+            .map(|type_name| {
+                let r#type = schema.types.get(type_name).ok_or_else(|| {
+                    QlError::QueryError(QueryError::RelationDoesNotExist {
+                        name: type_name.clone()
+                    })
+                })?;
+
+                Ok((type_name.clone(), r#type.clone()))
+            })
+            .collect::<QlResult<HashMap<_, _>>>()?;
+
+        for foreign_key in &self.foreign_keys {
+            self.check_foreign_key(cache, foreign_key)?;
+        }
+
+        // every table can be evicted from once the whole cache has limits, in least recently used
+        // order by default
+        let eviction_policy = self
+            .eviction_policy
+            .unwrap_or_else(|| Arc::new(LruPolicy::default()));
+
         let table = CacheTable {
             name: self.name.clone(),
             columns: self.columns.into_iter().collect(),
             primary_key: self.primary_key,
-            rows: DashMap::new()
+            foreign_indexes: vec![ForeignIndex::default(); self.foreign_keys.len()],
+            foreign_keys: self.foreign_keys,
+            rows: DashMap::new(),
+            ttl: self.ttl,
+            limits: self.limits,
+            schema: self.schema.clone(),
+            schemas: Arc::downgrade(&cache.cache),
+            clock: cache.clock.clone(),
+            time: cache.time.clone(),
+            expirations: ExpiryQueue::default(),
+            garbage: GarbageQueue::default(),
+            primary_index: PrimaryIndex::default(),
+            eviction_policy,
+            usage: Usage::default(),
+            capacity: cache.capacity.clone(),
+            enum_types,
+            materializations: DashMap::new()
         };
 
         schema.tables.insert(self.name, table);
-        cache.cache.insert(self.schema, schema);
 
         Ok(vec![])
     }
 }
 
+impl CreateTable {
+    /// Checks that a foreign key refers to the primary key of a table, which is either an existing
+    /// table or the table being created, and that the types of the columns can be compared.
+    fn check_foreign_key(&self, cache: &QlCache, foreign_key: &ForeignKey) -> QlResult<()> {
+        let data_type = self
+            .columns
+            .iter()
+            .find(|(name, _)| *name == foreign_key.column)
+            .map(|(_, (data_type, _))| data_type.clone());
+        let referred = if foreign_key.refers_to(&self.schema, &self.name) {
+            self.primary_key.as_ref().and_then(|primary_key| {
+                self.columns
+                    .iter()
+                    .find(|(name, _)| *name == primary_key.0)
+                    .map(|(_, (data_type, _))| data_type.clone())
+            })
+        }
+        else {
+            cache.with_table(&foreign_key.table_name, |table| {
+                table.primary_key.as_ref().and_then(|primary_key| {
+                    table.columns.get(&primary_key.0).map(|column| column.0.clone())
+                })
+            })?
+        };
+
+        match (data_type, referred) {
+            (Some(data_type), Some(referred)) if data_type.is_comparable_to(&referred) => Ok(()),
+            (_, Some(_)) => Err(QlError::ColumnTypeMismatch {
+                name: foreign_key.column.clone()
+            }),
+            (_, None) => Err(QlError::PrimaryKeyDoesNotExist {
+                table_name: foreign_key.table_name.clone()
+            })
+        }
+    }
+}
+
 /// # Struct `CreateTableBuilder`
 ///
 /// A builder for a `CreateTable`, constructs a `CREATE TABLE` query.
@@ -138,6 +241,48 @@ impl QueryRow for CreateTable {
 ///     .unwrap();
 /// ```
 ///
+/// - `CREATE TABLE TableName COLUMNS (Field1 STRING NOT NULL) TTL 60 SECONDS`
+/// ```
+/// use std::time::Duration;
+///
+/// use qlcache::{
+///     ql::QueryBuilder,
+///     ColumnDataType
+/// };
+///
+/// let create_table = QueryBuilder::create()
+///     .table()
+///     .name(String::from("TableName"))
+///     .columns(vec![(String::from("Field1"), (ColumnDataType::String, false))])
+///     .ttl(Duration::from_secs(60))
+///     .build()
+///     .unwrap();
+/// ```
+///
+/// - `CREATE TABLE TableName COLUMNS (Field1 STRING NOT NULL) MAX ROWS 1000 EVICT LFU`
+/// ```
+/// use qlcache::{
+///     eviction::{
+///         CapacityLimits,
+///         LfuPolicy
+///     },
+///     ql::QueryBuilder,
+///     ColumnDataType
+/// };
+///
+/// let create_table = QueryBuilder::create()
+///     .table()
+///     .name(String::from("TableName"))
+///     .columns(vec![(String::from("Field1"), (ColumnDataType::String, false))])
+///     .limits(CapacityLimits {
+///         max_rows: Some(1000),
+///         max_bytes: None
+///     })
+///     .eviction_policy(LfuPolicy::default())
+///     .build()
+///     .unwrap();
+/// ```
+///
 /// - `CREATE TABLE SchemaName.TableName COLUMNS (Field1 STRING NOT NULL, Field2 U64 PRIMARY KEY)`
 /// ```
 /// use qlcache::{
@@ -165,8 +310,12 @@ pub struct CreateTableBuilder {
     pub(crate) name: Option<String>,
     pub(crate) columns: Vec<(String, (ColumnDataType, bool))>,
     pub(crate) primary_key: Option<PrimaryKey>,
+    pub(crate) foreign_keys: Vec<ForeignKey>,
     pub(crate) schema: Option<String>,
-    pub(crate) if_not_exist: bool
+    pub(crate) if_not_exist: bool,
+    pub(crate) ttl: Option<Duration>,
+    pub(crate) limits: CapacityLimits,
+    pub(crate) eviction_policy: Option<Arc<dyn EvictionPolicy>>
 }
 
 impl CreateTableBuilder {
@@ -203,7 +352,8 @@ impl CreateTableBuilder {
 
     /// # Instance Method `CreateTableBuilder::primary_key`
     ///
-    /// Sets the primary key for this table.
+    /// Sets the primary key for this table. The column cannot be `null`, and no two live rows of
+    /// the table can have the same value of it.
     ///
     /// ## Parameters
     /// - `primary_key`, type `PrimaryKey`; the primary key
@@ -243,6 +393,32 @@ impl CreateTableBuilder {
         Ok(self)
     }
 
+    /// # Instance Method `CreateTableBuilder::foreign_key`
+    ///
+    /// Adds a foreign key to this table. When a row of the table referred to is evicted because a
+    /// capacity limit is exceeded, the rows of this table that refer to it are evicted with it.
+    ///
+    /// Creating the table fails with `RelationDoesNotExist` if the table referred to does not
+    /// exist, with `PrimaryKeyDoesNotExist` if it has no primary key, and with
+    /// `ColumnTypeMismatch` if the column cannot be compared with its primary key.
+    ///
+    /// ## Parameters
+    /// - `foreign_key`, type `ForeignKey`; the foreign key
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ColumnDoesNotExist` if the column of the foreign key does not exist in the table.
+    pub fn foreign_key(mut self, foreign_key: ForeignKey) -> QlResult<Self> {
+        if !self.columns.iter().any(|(name, _)| *name == foreign_key.column) {
+            return Err(QlError::ColumnDoesNotExist {
+                name: foreign_key.column
+            });
+        }
+
+        self.foreign_keys.push(foreign_key);
+        Ok(self)
+    }
+
     /// # Instance Method `CreateTableBuilder::schema`
     ///
     /// Sets the parent schema for this table to be created.
@@ -264,6 +440,43 @@ impl CreateTableBuilder {
         self
     }
 
+    /// # Instance Method `CreateTableBuilder::ttl`
+    ///
+    /// Sets the default time-to-live of the rows of the table.
+    ///
+    /// ## Parameters
+    /// - `ttl`, type `Duration`; the default time-to-live
+    #[must_use]
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl.replace(ttl);
+        self
+    }
+
+    /// # Instance Method `CreateTableBuilder::limits`
+    ///
+    /// Sets the capacity limits of the table.
+    ///
+    /// ## Parameters
+    /// - `limits`, type `CapacityLimits`; the capacity limits
+    #[must_use]
+    pub fn limits(mut self, limits: CapacityLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// # Instance Method `CreateTableBuilder::eviction_policy`
+    ///
+    /// Sets the policy deciding which rows are evicted when a capacity limit of the table, or of
+    /// the whole cache, is exceeded. Rows are evicted in least recently used order otherwise.
+    ///
+    /// ## Parameters
+    /// - `policy`, type `impl EvictionPolicy`; the eviction policy
+    #[must_use]
+    pub fn eviction_policy(mut self, policy: impl EvictionPolicy + 'static) -> Self {
+        self.eviction_policy.replace(Arc::new(policy));
+        self
+    }
+
     /// # Instance Method `CreateTableBuilder::build`
     ///
     /// Consumes the builder and returns a `Query<CreateTable>`.
@@ -284,8 +497,12 @@ impl CreateTableBuilder {
                 name: self.name.unwrap(),
                 columns: self.columns,
                 primary_key: self.primary_key,
+                foreign_keys: self.foreign_keys,
                 schema: self.schema.unwrap_or_else(|| String::from("PUBLIC")),
-                if_not_exist: self.if_not_exist
+                if_not_exist: self.if_not_exist,
+                ttl: self.ttl,
+                limits: self.limits,
+                eviction_policy: self.eviction_policy
             }
         })
     }
@@ -0,0 +1,240 @@
+//! # Module `view`
+//!
+//! This module implements the `CREATE VIEW` query.
+
+use std::sync::Arc;
+
+use crate::{
+    error::{
+        QlError,
+        QlResult,
+        QueryError
+    },
+    ql::{
+        select::Select,
+        view::View,
+        Query,
+        QueryContext,
+        QueryRow
+    },
+    CacheTableRow,
+    QlCache
+};
+
+/// # Struct `CreateView`
+///
+/// A `CREATE VIEW ... AS SELECT ...` query.
+#[allow(clippy::module_name_repetitions)]
+pub struct CreateView {
+    pub(crate) name: String,
+    pub(crate) select: Select,
+    pub(crate) schema: String,
+    pub(crate) if_not_exist: bool
+}
+
+impl QueryRow for CreateView {
+    fn execute(self, cache: &QlCache) -> QlResult<Vec<CacheTableRow>> {
+        // the query is checked against the relations it reads before the view is stored, and none
+        // of them can be dropped until it is
+        let _definitions = cache.lock_definitions();
+        let snapshot = cache.snapshot();
+        self.select
+            .result_columns(&QueryContext::new(cache, snapshot.timestamp))?;
+
+        let schema = cache.cache.get(&self.schema).ok_or_else(|| {
+            QlError::QueryError(QueryError::RelationDoesNotExist {
+                name: self.schema.clone()
+            })
+        })?;
+
+        if schema.contains_relation(&self.name) {
+            if !self.if_not_exist {
+                return Err(QlError::QueryError(QueryError::RelationAlreadyExists {
+                    name: self.name
+                }));
+            }
+
+            return Ok(vec![]);
+        }
+
+        schema.views.insert(self.name.clone(), View {
+            name: self.name,
+            select: Arc::new(self.select)
+        });
+
+        Ok(vec![])
+    }
+}
+
+/// # Struct `CreateViewBuilder`
+///
+/// A builder for a `CreateView`, constructs a `CREATE VIEW ... AS SELECT ...` query.
+///
+/// The snapshot of the query of the view is discarded, as the view reads the snapshot of the query
+/// it is read by, and a stored snapshot would keep the versions it reads from being reclaimed.
+///
+/// ## Example
+///
+/// `CREATE VIEW Moderators AS SELECT UserId, GuildId FROM Members WHERE Moderator = true`:
+/// ```
+/// use qlcache::{
+///     ql::{
+///         constraints::{
+///             Constraint,
+///             ConstraintOp
+///         },
+///         select::SelectScope,
+///         QueryBuilder
+///     },
+///     ColumnDataType,
+///     ColumnValue,
+///     QlCache
+/// };
+///
+/// let cache = QlCache::new();
+/// cache
+///     .execute(
+///         QueryBuilder::create()
+///             .table()
+///             .name(String::from("Members"))
+///             .columns(vec![
+///                 (String::from("UserId"), (ColumnDataType::U64, false)),
+///                 (String::from("GuildId"), (ColumnDataType::U64, false)),
+///                 (String::from("Moderator"), (ColumnDataType::Bool, false))
+///             ])
+///             .build()
+///             .unwrap()
+///     )
+///     .unwrap();
+///
+/// let create_view = QueryBuilder::create()
+///     .view()
+///     .name(String::from("Moderators"))
+///     .select(
+///         QueryBuilder::select()
+///             .table_name(String::from("Members"))
+///             .scope(SelectScope::Fields(vec![String::from("UserId"), String::from("GuildId")]))
+///             .constraint(Box::new(
+///                 Constraint::builder()
+///                     .field_name(String::from("Moderator"))
+///                     .op(ConstraintOp::Eq)
+///                     .value(ColumnValue::Bool(true))
+///                     .build()
+///                     .unwrap()
+///             ))
+///             .build()
+///             .unwrap()
+///     )
+///     .build()
+///     .unwrap();
+/// cache.execute(create_view).unwrap();
+///
+/// let moderators = QueryBuilder::select()
+///     .table_name(String::from("Moderators"))
+///     .scope(SelectScope::Everything)
+///     .build()
+///     .unwrap();
+/// assert!(cache.execute(moderators).unwrap().is_empty());
+/// ```
+#[allow(clippy::module_name_repetitions)]
+pub struct CreateViewBuilder {
+    pub(crate) name: Option<String>,
+    pub(crate) select: Option<Select>,
+    pub(crate) schema: Option<String>,
+    pub(crate) if_not_exist: bool
+}
+
+impl CreateViewBuilder {
+    /// # Instance Method `CreateViewBuilder::name`
+    ///
+    /// Sets the name of the view to create.
+    ///
+    /// ## Parameters
+    /// - `name`, type `String`; the name of the view
+    #[must_use]
+    pub fn name(mut self, name: String) -> Self {
+        self.name.replace(name);
+        self
+    }
+
+    /// # Instance Method `CreateViewBuilder::select`
+    ///
+    /// Sets the query of the view, discarding its snapshot.
+    ///
+    /// ## Parameters
+    /// - `select`, type `Query<Select>`; the query of the view
+    #[must_use]
+    pub fn select(mut self, select: Query<Select>) -> Self {
+        let mut select = select.query;
+        select.snapshot = None;
+
+        self.select.replace(select);
+        self
+    }
+
+    /// # Instance Method `CreateViewBuilder::schema`
+    ///
+    /// Sets the parent schema for this view to be created.
+    ///
+    /// ## Parameters
+    /// - `schema`, type `String`; the parent schema name
+    #[must_use]
+    pub fn schema(mut self, schema: String) -> Self {
+        self.schema.replace(schema);
+        self
+    }
+
+    /// # Instance Method `CreateViewBuilder::if_not_exist`
+    ///
+    /// Sets the view to be created if it does not exist yet, otherwise does nothing.
+    #[must_use]
+    pub fn if_not_exist(mut self) -> Self {
+        self.if_not_exist = true;
+        self
+    }
+
+    /// # Instance Method `CreateViewBuilder::build`
+    ///
+    /// Consumes the builder and returns a `Query<CreateView>`. The query of the view is checked
+    /// against the relations it reads when the `CREATE VIEW` query is executed, which fails with
+    /// `RelationDoesNotExist` if one of them does not exist.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `RequiredFieldIsNone` if any of the fields required is `None`.
+    #[allow(clippy::missing_panics_doc)] // this function never panics
+    pub fn build(self) -> QlResult<Query<CreateView>> {
+        if self.name.is_none() {
+            return Err(QlError::RequiredFieldIsNone {
+                field_name: String::from("CreateViewBuilder.name")
+            });
+        }
+
+        if self.select.is_none() {
+            return Err(QlError::RequiredFieldIsNone {
+                field_name: String::from("CreateViewBuilder.select")
+            });
+        }
+
+        Ok(Query {
+            query: CreateView {
+                name: self.name.unwrap(),
+                select: self.select.unwrap(),
+                schema: self.schema.unwrap_or_else(|| String::from("PUBLIC")),
+                if_not_exist: self.if_not_exist
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        CreateView,
+        CreateViewBuilder,
+        QueryRow
+    };
+
+    static_assertions::assert_impl_all!(CreateView: QueryRow, Send, Sync);
+    static_assertions::assert_impl_all!(CreateViewBuilder: Send, Sync);
+}
@@ -0,0 +1,158 @@
+//! # Module `materialized_view`
+//!
+//! This module implements the `DROP MATERIALIZED VIEW` query.
+
+use crate::{
+    error::{
+        QlError,
+        QlResult,
+        QueryError
+    },
+    ql::{
+        Query,
+        QueryRow
+    },
+    CacheTableRow,
+    QlCache
+};
+
+/// # Struct `DropMaterializedView`
+///
+/// A `DROP MATERIALIZED VIEW` query.
+#[allow(clippy::module_name_repetitions)]
+pub struct DropMaterializedView {
+    pub(crate) name: String,
+    pub(crate) schema: String,
+    pub(crate) if_exists: bool
+}
+
+impl QueryRow for DropMaterializedView {
+    fn execute(self, cache: &QlCache) -> QlResult<Vec<CacheTableRow>> {
+        let _definitions = cache.lock_definitions();
+        let removed = cache
+            .cache
+            .get(&self.schema)
+            .ok_or_else(|| {
+                QlError::QueryError(QueryError::RelationDoesNotExist {
+                    name: self.schema.clone()
+                })
+            })?
+            .materialized_views
+            .remove(&self.name);
+
+        match removed {
+            Some((_, view)) => view.detach(cache, &self.schema),
+            None if !self.if_exists => {
+                return Err(QlError::QueryError(QueryError::RelationDoesNotExist {
+                    name: self.name
+                }));
+            }
+            None => {}
+        }
+
+        Ok(vec![])
+    }
+}
+
+/// # Struct `DropMaterializedViewBuilder`
+///
+/// A builder for a `DropMaterializedView`, constructs a `DROP MATERIALIZED VIEW` query.
+///
+/// The table of the dropped view stops recording its changes for the view.
+///
+/// ## Example
+///
+/// `DROP MATERIALIZED VIEW IF EXISTS MemberCounts`:
+/// ```
+/// use qlcache::{
+///     ql::QueryBuilder,
+///     QlCache
+/// };
+///
+/// let cache = QlCache::new();
+///
+/// let drop_view = QueryBuilder::drop()
+///     .materialized_view()
+///     .name(String::from("MemberCounts"))
+///     .if_exists()
+///     .build()
+///     .unwrap();
+/// cache.execute(drop_view).unwrap();
+/// ```
+#[allow(clippy::module_name_repetitions)]
+pub struct DropMaterializedViewBuilder {
+    pub(crate) name: Option<String>,
+    pub(crate) schema: Option<String>,
+    pub(crate) if_exists: bool
+}
+
+impl DropMaterializedViewBuilder {
+    /// # Instance Method `DropMaterializedViewBuilder::name`
+    ///
+    /// Sets the name of the materialized view to drop.
+    ///
+    /// ## Parameters
+    /// - `name`, type `String`; the name of the materialized view
+    #[must_use]
+    pub fn name(mut self, name: String) -> Self {
+        self.name.replace(name);
+        self
+    }
+
+    /// # Instance Method `DropMaterializedViewBuilder::schema`
+    ///
+    /// Sets the parent schema of the materialized view to drop.
+    ///
+    /// ## Parameters
+    /// - `schema`, type `String`; the parent schema name
+    #[must_use]
+    pub fn schema(mut self, schema: String) -> Self {
+        self.schema.replace(schema);
+        self
+    }
+
+    /// # Instance Method `DropMaterializedViewBuilder::if_exists`
+    ///
+    /// Sets the materialized view to be dropped if it exists, otherwise does nothing.
+    #[must_use]
+    pub fn if_exists(mut self) -> Self {
+        self.if_exists = true;
+        self
+    }
+
+    /// # Instance Method `DropMaterializedViewBuilder::build`
+    ///
+    /// Consumes the builder and returns a `Query<DropMaterializedView>`.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `RequiredFieldIsNone` if any of the fields required is `None`.
+    #[allow(clippy::missing_panics_doc)] // this function never panics
+    pub fn build(self) -> QlResult<Query<DropMaterializedView>> {
+        if self.name.is_none() {
+            return Err(QlError::RequiredFieldIsNone {
+                field_name: String::from("DropMaterializedViewBuilder.name")
+            });
+        }
+
+        Ok(Query {
+            query: DropMaterializedView {
+                name: self.name.unwrap(),
+                schema: self.schema.unwrap_or_else(|| String::from("PUBLIC")),
+                if_exists: self.if_exists
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        DropMaterializedView,
+        DropMaterializedViewBuilder,
+        QueryRow
+    };
+
+    static_assertions::assert_impl_all!(DropMaterializedView: QueryRow, Send, Sync);
+    static_assertions::assert_impl_all!(DropMaterializedViewBuilder: Send, Sync);
+}
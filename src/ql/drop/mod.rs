@@ -0,0 +1,52 @@
+//! # Module `drop`
+//!
+//! This module implements the `DROP` query.
+
+use crate::ql::drop::{
+    materialized_view::DropMaterializedViewBuilder,
+    view::DropViewBuilder
+};
+
+pub mod materialized_view;
+pub mod view;
+
+/// # Struct `DropBuilder`
+///
+/// A builder for a `DROP` query.
+#[allow(clippy::module_name_repetitions)]
+pub struct DropBuilder;
+
+impl DropBuilder {
+    /// # Instance Method `DropBuilder::materialized_view`
+    ///
+    /// Returns a builder for a `DROP MATERIALIZED VIEW` query.
+    #[allow(clippy::unused_self)]
+    #[must_use]
+    pub fn materialized_view(self) -> DropMaterializedViewBuilder {
+        DropMaterializedViewBuilder {
+            name: None,
+            schema: None,
+            if_exists: false
+        }
+    }
+
+    /// # Instance Method `DropBuilder::view`
+    ///
+    /// Returns a builder for a `DROP VIEW` query.
+    #[allow(clippy::unused_self)]
+    #[must_use]
+    pub fn view(self) -> DropViewBuilder {
+        DropViewBuilder {
+            name: None,
+            schema: None,
+            if_exists: false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DropBuilder;
+
+    static_assertions::assert_impl_all!(DropBuilder: Send, Sync);
+}
@@ -0,0 +1,160 @@
+//! # Module `view`
+//!
+//! This module implements the `DROP VIEW` query.
+
+use crate::{
+    error::{
+        QlError,
+        QlResult,
+        QueryError
+    },
+    ql::{
+        Query,
+        QueryRow
+    },
+    CacheTableRow,
+    QlCache
+};
+
+/// # Struct `DropView`
+///
+/// A `DROP VIEW` query.
+#[allow(clippy::module_name_repetitions)]
+pub struct DropView {
+    pub(crate) name: String,
+    pub(crate) schema: String,
+    pub(crate) if_exists: bool
+}
+
+impl QueryRow for DropView {
+    fn execute(self, cache: &QlCache) -> QlResult<Vec<CacheTableRow>> {
+        // the schemas are searched for dependents before the schema of the view is held, and no
+        // dependent can be created until the view is removed
+        let _definitions = cache.lock_definitions();
+        if let Some(dependent) = cache.dependent_of(&self.schema, &self.name) {
+            return Err(QlError::QueryError(QueryError::RelationHasDependents {
+                name: self.name,
+                dependent
+            }));
+        }
+
+        let schema = cache.cache.get(&self.schema).ok_or_else(|| {
+            QlError::QueryError(QueryError::RelationDoesNotExist {
+                name: self.schema.clone()
+            })
+        })?;
+
+        if schema.views.remove(&self.name).is_none() && !self.if_exists {
+            return Err(QlError::QueryError(QueryError::RelationDoesNotExist {
+                name: self.name
+            }));
+        }
+
+        Ok(vec![])
+    }
+}
+
+/// # Struct `DropViewBuilder`
+///
+/// A builder for a `DropView`, constructs a `DROP VIEW` query.
+///
+/// A view cannot be dropped while other views or materialized views read it, in their `FROM` or
+/// `JOIN` clauses or in subqueries; they have to be dropped first.
+///
+/// ## Example
+///
+/// `DROP VIEW IF EXISTS Moderators`:
+/// ```
+/// use qlcache::{
+///     ql::QueryBuilder,
+///     QlCache
+/// };
+///
+/// let cache = QlCache::new();
+///
+/// let drop_view = QueryBuilder::drop()
+///     .view()
+///     .name(String::from("Moderators"))
+///     .if_exists()
+///     .build()
+///     .unwrap();
+/// cache.execute(drop_view).unwrap();
+/// ```
+#[allow(clippy::module_name_repetitions)]
+pub struct DropViewBuilder {
+    pub(crate) name: Option<String>,
+    pub(crate) schema: Option<String>,
+    pub(crate) if_exists: bool
+}
+
+impl DropViewBuilder {
+    /// # Instance Method `DropViewBuilder::name`
+    ///
+    /// Sets the name of the view to drop.
+    ///
+    /// ## Parameters
+    /// - `name`, type `String`; the name of the view
+    #[must_use]
+    pub fn name(mut self, name: String) -> Self {
+        self.name.replace(name);
+        self
+    }
+
+    /// # Instance Method `DropViewBuilder::schema`
+    ///
+    /// Sets the parent schema of the view to drop.
+    ///
+    /// ## Parameters
+    /// - `schema`, type `String`; the parent schema name
+    #[must_use]
+    pub fn schema(mut self, schema: String) -> Self {
+        self.schema.replace(schema);
+        self
+    }
+
+    /// # Instance Method `DropViewBuilder::if_exists`
+    ///
+    /// Sets the view to be dropped if it exists, otherwise does nothing.
+    #[must_use]
+    pub fn if_exists(mut self) -> Self {
+        self.if_exists = true;
+        self
+    }
+
+    /// # Instance Method `DropViewBuilder::build`
+    ///
+    /// Consumes the builder and returns a `Query<DropView>`. Executing the query fails with
+    /// `RelationHasDependents` if other views or materialized views read the view.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `RequiredFieldIsNone` if any of the fields required is `None`.
+    #[allow(clippy::missing_panics_doc)] // this function never panics
+    pub fn build(self) -> QlResult<Query<DropView>> {
+        if self.name.is_none() {
+            return Err(QlError::RequiredFieldIsNone {
+                field_name: String::from("DropViewBuilder.name")
+            });
+        }
+
+        Ok(Query {
+            query: DropView {
+                name: self.name.unwrap(),
+                schema: self.schema.unwrap_or_else(|| String::from("PUBLIC")),
+                if_exists: self.if_exists
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        DropView,
+        DropViewBuilder,
+        QueryRow
+    };
+
+    static_assertions::assert_impl_all!(DropView: QueryRow, Send, Sync);
+    static_assertions::assert_impl_all!(DropViewBuilder: Send, Sync);
+}
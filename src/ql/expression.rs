@@ -0,0 +1,928 @@
+//! # Module `expression`
+//!
+//! This module implements expressions, which compute a value from the columns of a row, for use
+//! on either side of a constraint.
+//!
+//! Expressions are evaluated per row. Arithmetic follows
+//! [`ColumnValue::checked_add`](crate::ColumnValue::checked_add) and the other checked
+//! operations of `ColumnValue`, and evaluates to `null` where they return `None`: if an operand is
+//! `null`, if the result overflows its type, or if the divisor is zero.
+
+use std::{
+    fmt::{
+        Display,
+        Formatter,
+        Result as FmtResult
+    },
+    ops::{
+        Add,
+        Div,
+        Mul,
+        Neg,
+        Rem,
+        Sub
+    }
+};
+
+use dashmap::DashMap;
+
+use crate::{
+    error::{
+        QlError,
+        QlResult
+    },
+    numeric,
+    ql::{
+        extract::Extraction,
+        function::ScalarFunction,
+        subquery::Subquery,
+        QueryContext
+    },
+    types::SnowflakePart,
+    CacheTableRow,
+    ColumnDataType,
+    ColumnValue
+};
+
+/// # Enumeration `BinaryOperator`
+///
+/// An operator of an expression with two operands.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BinaryOperator {
+    /// # Enumeration Variant `BinaryOperator::Add`
+    ///
+    /// `+`, the sum of two numbers, or a time and a `Duration`.
+    Add,
+
+    /// # Enumeration Variant `BinaryOperator::Sub`
+    ///
+    /// `-`, the difference of two numbers, a time and a `Duration`, or two times of the same type.
+    Sub,
+
+    /// # Enumeration Variant `BinaryOperator::Mul`
+    ///
+    /// `*`, the product of two numbers.
+    Mul,
+
+    /// # Enumeration Variant `BinaryOperator::Div`
+    ///
+    /// `/`, the quotient of two numbers, which is truncated towards zero for integers.
+    Div,
+
+    /// # Enumeration Variant `BinaryOperator::Rem`
+    ///
+    /// `%`, the remainder of dividing two numbers.
+    Rem,
+
+    /// # Enumeration Variant `BinaryOperator::Concat`
+    ///
+    /// `||`, the concatenation of a `String` with any value, or of two `Array`s.
+    Concat
+}
+
+/// # Enumeration `Expression`
+///
+/// An expression over the columns of a row.
+///
+/// Expressions are usually built from columns and values with the arithmetic operators of Rust,
+/// which construct `Binary` and `Negate` expressions.
+///
+/// ## Examples
+///
+/// - `Xp + Bonus * 2`:
+/// ```
+/// use qlcache::{
+///     ql::expression::Expression,
+///     ColumnValue
+/// };
+///
+/// let expression = Expression::column(String::from("Xp"))
+///     + Expression::column(String::from("Bonus")) * ColumnValue::U32(2);
+/// ```
+///
+/// - `lower(Name)`:
+/// ```
+/// use qlcache::ql::{
+///     expression::Expression,
+///     function::ScalarFunction
+/// };
+///
+/// let expression = Expression::function(ScalarFunction::Lower, vec![Expression::column(
+///     String::from("Name")
+/// )]);
+/// ```
+///
+/// - `FirstName || ' ' || LastName`:
+/// ```
+/// use qlcache::ql::expression::Expression;
+///
+/// let expression = Expression::column(String::from("FirstName"))
+///     .concat(Expression::value(" "))
+///     .concat(Expression::column(String::from("LastName")));
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Expression {
+    /// # Enumeration Variant `Expression::Column`
+    ///
+    /// The value of a column, which is `null` if the column is missing from a row.
+    Column(String),
+
+    /// # Enumeration Variant `Expression::Outer`
+    ///
+    /// The value of a column of the row of the enclosing query, in a correlated subquery.
+    Outer(String),
+
+    /// # Enumeration Variant `Expression::Value`
+    ///
+    /// A constant value.
+    Value(ColumnValue),
+
+    /// # Enumeration Variant `Expression::Now`
+    ///
+    /// `now()`, the current time as a `Timestamp`.
+    Now,
+
+    /// # Enumeration Variant `Expression::Binary`
+    ///
+    /// `left op right`
+    Binary(Box<Expression>, BinaryOperator, Box<Expression>),
+
+    /// # Enumeration Variant `Expression::Negate`
+    ///
+    /// `-expression`, the negation of a number.
+    Negate(Box<Expression>),
+
+    /// # Enumeration Variant `Expression::Extract`
+    ///
+    /// The parts extracted in a chain from the value of an expression, each from the result of the
+    /// previous extraction, see [`Expression::extract`].
+    Extract(Box<Expression>, Vec<Extraction>),
+
+    /// # Enumeration Variant `Expression::Function`
+    ///
+    /// `function(arguments...)`, a call of a scalar function.
+    Function(ScalarFunction, Vec<Expression>),
+
+    /// # Enumeration Variant `Expression::Call`
+    ///
+    /// `function(arguments...)`, a call of the user-defined function registered on the cache
+    /// with the name, see [`Expression::call`].
+    Call(String, Vec<Expression>),
+
+    /// # Enumeration Variant `Expression::Subquery`
+    ///
+    /// `(SELECT ...)`, the value of the only column of the only row a subquery returns, or `null`
+    /// if it returns no rows. The query fails with `SubqueryRowCount` if it returns more than one
+    /// row.
+    Subquery(Subquery)
+}
+
+impl Expression {
+    /// # Constructor `Expression::column`
+    ///
+    /// Constructs a new `Expression` of the value of a column.
+    #[must_use]
+    pub fn column(name: String) -> Self {
+        Self::Column(name)
+    }
+
+    /// # Constructor `Expression::outer`
+    ///
+    /// Constructs a new `Expression` of the value of a column of the row of the enclosing query,
+    /// for use in a subquery.
+    #[must_use]
+    pub fn outer(name: String) -> Self {
+        Self::Outer(name)
+    }
+
+    /// # Constructor `Expression::value`
+    ///
+    /// Constructs a new `Expression` of a constant value.
+    #[must_use]
+    pub fn value(value: impl Into<ColumnValue>) -> Self {
+        Self::Value(value.into())
+    }
+
+    /// # Constructor `Expression::function`
+    ///
+    /// Constructs a new `Expression` of a call of a scalar function.
+    #[must_use]
+    pub fn function(function: ScalarFunction, arguments: Vec<Expression>) -> Self {
+        Self::Function(function, arguments)
+    }
+
+    /// # Constructor `Expression::call`
+    ///
+    /// Constructs a new `Expression` of a call of the user-defined function registered on the
+    /// cache with the name `name`, which is looked up each time a query using the expression is
+    /// executed, see [`UserFunction`](crate::ql::function::UserFunction). The arguments are
+    /// checked against the declared types before any row is computed.
+    #[must_use]
+    pub fn call(name: String, arguments: Vec<Expression>) -> Self {
+        Self::Call(name, arguments)
+    }
+
+    /// # Constructor `Expression::subquery`
+    ///
+    /// Constructs a new `Expression` of the value a scalar subquery returns.
+    #[must_use]
+    pub fn subquery(subquery: impl Into<Subquery>) -> Self {
+        Self::Subquery(subquery.into())
+    }
+
+    /// # Instance Method `Expression::extract`
+    ///
+    /// Returns the part extracted from the value of this expression, such as the creation time of
+    /// a `Snowflake`, or from the result of the previous extraction if this expression is an
+    /// extraction. The result is `null` if the value does not have the part.
+    ///
+    /// ## Parameters
+    /// - `extraction`, type `impl Into<Extraction>`; the part to extract
+    ///
+    /// ## Example
+    ///
+    /// - `SORT BY extract(timestamp FROM AuthorId)`:
+    /// ```
+    /// use qlcache::{
+    ///     ql::{
+    ///         expression::Expression,
+    ///         sortby::SortBy
+    ///     },
+    ///     types::SnowflakePart
+    /// };
+    ///
+    /// let sort_by = SortBy::expressions(
+    ///     vec![Expression::column(String::from("AuthorId")).extract(SnowflakePart::Timestamp)],
+    ///     None
+    /// )
+    /// .unwrap();
+    /// ```
+    #[must_use]
+    pub fn extract(self, extraction: impl Into<Extraction>) -> Self {
+        match self {
+            Self::Extract(expression, mut extractions) => {
+                extractions.push(extraction.into());
+                Self::Extract(expression, extractions)
+            }
+            expression => Self::Extract(Box::new(expression), vec![extraction.into()])
+        }
+    }
+
+    /// # Instance Method `Expression::concat`
+    ///
+    /// Returns the concatenation of this expression with another expression (`||`).
+    ///
+    /// ## Parameters
+    /// - `other`, type `impl Into<Expression>`; the expression to append
+    #[must_use]
+    pub fn concat(self, other: impl Into<Self>) -> Self {
+        Self::Binary(Box::new(self), BinaryOperator::Concat, Box::new(other.into()))
+    }
+
+    /// Evaluates the expression against the values of a row in the context of a query. Fails if
+    /// a user-defined function the expression calls is not registered or fails, or if a subquery
+    /// of the expression fails.
+    pub(crate) fn evaluate(
+        &self,
+        row: &CacheTableRow,
+        context: &QueryContext<'_>
+    ) -> QlResult<ColumnValue> {
+        let value = match self {
+            Self::Column(name) => return Ok(value_of(row, name)),
+            Self::Outer(name) => {
+                return Ok(context
+                    .outer_row
+                    .map_or(ColumnValue::Null, |row| value_of(row, name)));
+            }
+            Self::Value(value) => return Ok(value.clone()),
+            Self::Now => return Ok(ColumnValue::Timestamp(context.now)),
+            Self::Binary(left, op, right) => {
+                let (left, right) = (left.evaluate(row, context)?, right.evaluate(row, context)?);
+
+                match op {
+                    BinaryOperator::Add => left.checked_add(&right),
+                    BinaryOperator::Sub => left.checked_sub(&right),
+                    BinaryOperator::Mul => left.checked_mul(&right),
+                    BinaryOperator::Div => left.checked_div(&right),
+                    BinaryOperator::Rem => left.checked_rem(&right),
+                    BinaryOperator::Concat => left.concat(&right)
+                }
+            }
+            Self::Negate(expression) => expression.evaluate(row, context)?.checked_neg(),
+            Self::Extract(expression, extractions) => {
+                return Ok(Extraction::apply_all(extractions, expression.evaluate(row, context)?));
+            }
+            Self::Function(function, arguments) => {
+                return Ok(function.evaluate(evaluate_all(arguments, row, context)?));
+            }
+            Self::Call(name, arguments) => {
+                let arguments = evaluate_all(arguments, row, context)?;
+                return context
+                    .cache
+                    .function(name)?
+                    .evaluate(arguments, || self.to_string());
+            }
+            Self::Subquery(subquery) => return subquery.value(row, context)
+        };
+
+        Ok(value.unwrap_or(ColumnValue::Null))
+    }
+
+    /// Returns the columns the expression refers to.
+    pub(crate) fn columns(&self) -> Vec<&String> {
+        match self {
+            Self::Column(name) => vec![name],
+            Self::Outer(_) | Self::Value(_) | Self::Now | Self::Subquery(_) => Vec::new(),
+            Self::Binary(left, _, right) => {
+                let mut columns = left.columns();
+                columns.extend(right.columns());
+                columns
+            }
+            Self::Negate(expression) | Self::Extract(expression, _) => expression.columns(),
+            Self::Function(_, arguments) | Self::Call(_, arguments) => {
+                arguments.iter().flat_map(Self::columns).collect()
+            }
+        }
+    }
+
+    /// Returns the names of the tables and views the subqueries of the expression read.
+    pub(crate) fn relations(&self) -> Vec<&str> {
+        match self {
+            Self::Column(_) | Self::Outer(_) | Self::Value(_) | Self::Now => Vec::new(),
+            Self::Subquery(subquery) => subquery.select.relations(),
+            Self::Binary(left, _, right) => {
+                let mut relations = left.relations();
+                relations.extend(right.relations());
+                relations
+            }
+            Self::Negate(expression) | Self::Extract(expression, _) => expression.relations(),
+            Self::Function(_, arguments) | Self::Call(_, arguments) => {
+                arguments.iter().flat_map(Self::relations).collect()
+            }
+        }
+    }
+
+    /// Returns whether the value of the expression only depends on the values of the row it is
+    /// evaluated for. User-defined functions are not known to, and neither are `now()`,
+    /// subqueries and the columns of an enclosing query.
+    pub(crate) fn is_row_local(&self) -> bool {
+        match self {
+            Self::Column(_) | Self::Value(_) => true,
+            Self::Outer(_) | Self::Now | Self::Call(..) | Self::Subquery(_) => false,
+            Self::Binary(left, _, right) => left.is_row_local() && right.is_row_local(),
+            Self::Negate(expression) | Self::Extract(expression, _) => expression.is_row_local(),
+            Self::Function(_, arguments) => arguments.iter().all(Self::is_row_local)
+        }
+    }
+
+    /// Returns the type of the expression over a table with columns `columns` in the context of a
+    /// query, or `None` if the expression is always `null`.
+    pub(crate) fn data_type(
+        &self,
+        columns: &DashMap<String, (ColumnDataType, bool)>,
+        context: &QueryContext<'_>
+    ) -> QlResult<Option<ColumnDataType>> {
+        let data_type = match self {
+            Self::Column(name) => return column_type(columns, name).map(Some),
+            // a subquery is checked against the columns of the enclosing query before it is
+            // computed for its rows, which only have the values of the columns
+            Self::Outer(name) => {
+                return match (context.outer_columns, context.outer_row) {
+                    (Some(outer_columns), _) => column_type(outer_columns, name).map(Some),
+                    (None, Some(row)) => Ok(value_of(row, name).data_type()),
+                    (None, None) => {
+                        Err(QlError::ColumnDoesNotExist {
+                            name: name.clone()
+                        })
+                    }
+                };
+            }
+            Self::Subquery(subquery) => return subquery.column_type(columns, context),
+            Self::Value(value) => return Ok(value.data_type()),
+            Self::Now => return Ok(Some(ColumnDataType::Timestamp)),
+            Self::Binary(left, op, right) => {
+                match (left.data_type(columns, context)?, right.data_type(columns, context)?) {
+                    (Some(left), Some(right)) => binary_type(&left, *op, &right),
+                    _ => return Ok(None)
+                }
+            }
+            Self::Negate(expression) => {
+                match expression.data_type(columns, context)? {
+                    Some(data_type) if data_type.is_numeric() => Some(data_type),
+                    Some(_) => None,
+                    None => return Ok(None)
+                }
+            }
+            Self::Extract(expression, extractions) => {
+                return Ok(expression.data_type(columns, context)?.and_then(|data_type| {
+                    extractions.iter().try_fold(data_type, |data_type, extraction| {
+                        extraction.data_type(&data_type)
+                    })
+                }));
+            }
+            Self::Function(function, arguments) => {
+                let arguments = data_types(arguments, columns, context)?;
+                return function.data_type(&arguments, || self.to_string());
+            }
+            Self::Call(name, arguments) => {
+                let arguments = data_types(arguments, columns, context)?;
+                return context
+                    .cache
+                    .function(name)?
+                    .data_type(&arguments, || self.to_string());
+            }
+        };
+
+        data_type.map(Some).ok_or_else(|| {
+            QlError::ColumnTypeMismatch {
+                name: self.to_string()
+            }
+        })
+    }
+}
+
+impl Display for BinaryOperator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let op = match self {
+            Self::Add => "+",
+            Self::Sub => "-",
+            Self::Mul => "*",
+            Self::Div => "/",
+            Self::Rem => "%",
+            Self::Concat => "||"
+        };
+
+        f.write_str(op)
+    }
+}
+
+impl Display for Expression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Column(name) => f.write_str(name),
+            Self::Outer(name) => write!(f, "outer.{name}"),
+            Self::Value(ColumnValue::String(string)) => {
+                write!(f, "'{}'", string.replace('\'', "''"))
+            }
+            Self::Value(value) => Display::fmt(value, f),
+            Self::Now => f.write_str("now()"),
+            Self::Binary(left, op, right) => write!(f, "({left} {op} {right})"),
+            Self::Negate(expression) => write!(f, "-{expression}"),
+            Self::Extract(expression, extractions) => write_extract(f, expression, extractions),
+            Self::Function(function, arguments) => write_call(f, function.name(), arguments),
+            Self::Call(name, arguments) => write_call(f, name, arguments),
+            Self::Subquery(subquery) => {
+                write!(f, "(SELECT ... FROM {})", subquery.select.table_name)
+            }
+        }
+    }
+}
+
+impl From<ColumnValue> for Expression {
+    fn from(value: ColumnValue) -> Self {
+        Self::Value(value)
+    }
+}
+
+/// Implements an arithmetic operator of Rust for expressions.
+macro_rules! binary_operators {
+    ($($trait:ident::$method:ident => $op:ident),*) => {
+        $(
+            impl<T: Into<Expression>> $trait<T> for Expression {
+                type Output = Self;
+
+                fn $method(self, other: T) -> Self {
+                    Self::Binary(Box::new(self), BinaryOperator::$op, Box::new(other.into()))
+                }
+            }
+        )*
+    };
+}
+
+binary_operators!(
+    Add::add => Add,
+    Sub::sub => Sub,
+    Mul::mul => Mul,
+    Div::div => Div,
+    Rem::rem => Rem
+);
+
+impl Neg for Expression {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::Negate(Box::new(self))
+    }
+}
+
+fn value_of(row: &CacheTableRow, name: &str) -> ColumnValue {
+    row.column_values
+        .get(name)
+        .map_or(ColumnValue::Null, |value| value.clone())
+}
+
+fn column_type(
+    columns: &DashMap<String, (ColumnDataType, bool)>,
+    name: &str
+) -> QlResult<ColumnDataType> {
+    columns
+        .get(name)
+        .map(|column| column.0.clone())
+        .ok_or_else(|| {
+            QlError::ColumnDoesNotExist {
+                name: name.to_string()
+            }
+        })
+}
+
+fn evaluate_all(
+    arguments: &[Expression],
+    row: &CacheTableRow,
+    context: &QueryContext<'_>
+) -> QlResult<Vec<ColumnValue>> {
+    arguments
+        .iter()
+        .map(|argument| argument.evaluate(row, context))
+        .collect()
+}
+
+fn data_types(
+    arguments: &[Expression],
+    columns: &DashMap<String, (ColumnDataType, bool)>,
+    context: &QueryContext<'_>
+) -> QlResult<Vec<Option<ColumnDataType>>> {
+    arguments
+        .iter()
+        .map(|argument| argument.data_type(columns, context))
+        .collect()
+}
+
+fn write_call(f: &mut Formatter<'_>, name: &str, arguments: &[Expression]) -> FmtResult {
+    write!(f, "{name}(")?;
+    for (index, argument) in arguments.iter().enumerate() {
+        if index > 0 {
+            f.write_str(", ")?;
+        }
+
+        Display::fmt(argument, f)?;
+    }
+    f.write_str(")")
+}
+
+fn write_extract(
+    f: &mut Formatter<'_>,
+    expression: &Expression,
+    extractions: &[Extraction]
+) -> FmtResult {
+    match extractions.split_last() {
+        None => Display::fmt(expression, f),
+        Some((Extraction::Snowflake(part), extractions)) => {
+            let part = match part {
+                SnowflakePart::Timestamp => "timestamp",
+                SnowflakePart::WorkerId => "worker_id",
+                SnowflakePart::ProcessId => "process_id",
+                SnowflakePart::Increment => "increment"
+            };
+            write!(f, "extract({part} FROM ")?;
+            write_extract(f, expression, extractions)?;
+            f.write_str(")")
+        }
+        Some((Extraction::Length, extractions)) => {
+            f.write_str("length(")?;
+            write_extract(f, expression, extractions)?;
+            f.write_str(")")
+        }
+        Some((Extraction::Element(index), extractions)) => {
+            write_extract(f, expression, extractions)?;
+            write!(f, "->{index}")
+        }
+        Some((Extraction::ElementText(index), extractions)) => {
+            write_extract(f, expression, extractions)?;
+            write!(f, "->>{index}")
+        }
+        Some((Extraction::Field(name), extractions)) => {
+            write_extract(f, expression, extractions)?;
+            write!(f, "->'{name}'")
+        }
+        Some((Extraction::FieldText(name), extractions)) => {
+            write_extract(f, expression, extractions)?;
+            write!(f, "->>'{name}'")
+        }
+    }
+}
+
+/// Returns the type of the result of an operator on values of two types, or `None` if the operator
+/// cannot be applied to them.
+fn binary_type(
+    left: &ColumnDataType,
+    op: BinaryOperator,
+    right: &ColumnDataType
+) -> Option<ColumnDataType> {
+    match (op, left, right) {
+        (BinaryOperator::Concat, ColumnDataType::Array(element), ColumnDataType::Array(other)) => {
+            element.is_comparable_to(other).then(|| left.clone())
+        }
+        (BinaryOperator::Concat, ColumnDataType::String, _)
+        | (BinaryOperator::Concat, _, ColumnDataType::String) => Some(ColumnDataType::String),
+        (BinaryOperator::Concat, _, _) => None,
+        (BinaryOperator::Add | BinaryOperator::Sub, _, _) if !left.is_numeric() => {
+            time_type(left, op, right)
+        }
+        _ => numeric::result_type(left, right)
+    }
+}
+
+/// Returns the type of the sum or difference of times and durations, as described in
+/// [`ColumnValue::checked_add`] and [`ColumnValue::checked_sub`].
+fn time_type(
+    left: &ColumnDataType,
+    op: BinaryOperator,
+    right: &ColumnDataType
+) -> Option<ColumnDataType> {
+    let time_type = match (left, right) {
+        (ColumnDataType::Timestamp | ColumnDataType::Date, ColumnDataType::Duration) => {
+            ColumnDataType::Timestamp
+        }
+        (ColumnDataType::Duration, ColumnDataType::Timestamp | ColumnDataType::Date)
+            if op == BinaryOperator::Add =>
+        {
+            ColumnDataType::Timestamp
+        }
+        (ColumnDataType::Duration, ColumnDataType::Duration) => ColumnDataType::Duration,
+        (ColumnDataType::Timestamp, ColumnDataType::Timestamp)
+        | (ColumnDataType::Date, ColumnDataType::Date)
+            if op == BinaryOperator::Sub =>
+        {
+            ColumnDataType::Duration
+        }
+        _ => return None
+    };
+
+    Some(time_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fmt::{
+            Debug,
+            Display
+        },
+        ops::{
+            Add,
+            Div,
+            Mul,
+            Neg,
+            Rem,
+            Sub
+        },
+        time::{
+            Duration,
+            SystemTime
+        }
+    };
+
+    use dashmap::DashMap;
+
+    use super::{
+        BinaryOperator,
+        Expression
+    };
+    use crate::{
+        error::QlError,
+        ql::{
+            constraints::{
+                ComputableConstraint,
+                ConstraintOp,
+                ExpressionConstraint
+            },
+            QueryContext
+        },
+        CacheTableRow,
+        ColumnDataType,
+        ColumnValue,
+        QlCache
+    };
+
+    static_assertions::assert_impl_all!(
+        BinaryOperator: Clone, Copy, Debug, Display, Eq, PartialEq, Send, Sync
+    );
+    static_assertions::assert_impl_all!(
+        Expression: Add, Clone, Debug, Display, Div, Eq, Mul, Neg, PartialEq, Rem, Send, Sub, Sync
+    );
+
+    /// The columns and values of the row expressions are evaluated against.
+    fn columns() -> Vec<(&'static str, ColumnDataType, ColumnValue)> {
+        vec![
+            ("Xp", ColumnDataType::U64, ColumnValue::U64(10)),
+            ("Bonus", ColumnDataType::U32, ColumnValue::U32(3)),
+            ("Level", ColumnDataType::I8, ColumnValue::I8(-7)),
+            ("Ratio", ColumnDataType::F64, ColumnValue::F64(0.5)),
+            ("Name", ColumnDataType::String, ColumnValue::from("ada")),
+            (
+                "Tags",
+                ColumnDataType::Array(Box::new(ColumnDataType::String)),
+                ColumnValue::Array(vec![ColumnValue::from("a")])
+            ),
+            ("JoinedAt", ColumnDataType::Timestamp, ColumnValue::Timestamp(SystemTime::UNIX_EPOCH)),
+            ("Missing", ColumnDataType::U64, ColumnValue::Null)
+        ]
+    }
+
+    fn column(name: &str) -> Expression {
+        Expression::column(String::from(name))
+    }
+
+    fn evaluate(expression: &Expression) -> ColumnValue {
+        let cache = QlCache::new();
+        let row = CacheTableRow {
+            column_values: columns()
+                .into_iter()
+                .map(|(name, _, value)| (String::from(name), value))
+                .collect()
+        };
+
+        expression
+            .evaluate(&row, &QueryContext::new(&cache, 0))
+            .unwrap()
+    }
+
+    fn data_type(expression: &Expression) -> Result<Option<ColumnDataType>, QlError> {
+        let cache = QlCache::new();
+        let columns = columns()
+            .into_iter()
+            .map(|(name, data_type, _)| (String::from(name), (data_type, true)))
+            .collect::<DashMap<_, _>>();
+
+        expression.data_type(&columns, &QueryContext::new(&cache, 0))
+    }
+
+    /// Asserts that an expression evaluates to a value of the type it is checked to be of.
+    fn assert_evaluates(expression: &Expression, value: &ColumnValue) {
+        assert_eq!(evaluate(expression), *value, "{expression}");
+        assert_eq!(data_type(expression).unwrap(), value.data_type(), "{expression}");
+    }
+
+    #[test]
+    fn arithmetic_operators_compute_in_the_promoted_type() {
+        let cases = [
+            (column("Xp") + column("Bonus"), ColumnValue::U64(13)),
+            (column("Xp") - column("Bonus"), ColumnValue::U64(7)),
+            (column("Xp") * column("Bonus"), ColumnValue::U64(30)),
+            (column("Xp") / column("Bonus"), ColumnValue::U64(3)),
+            (column("Xp") % column("Bonus"), ColumnValue::U64(1)),
+            // an unsigned and a signed integer result in a signed type that holds both
+            (column("Xp") + column("Level"), ColumnValue::I128(3)),
+            (column("Bonus") * column("Level"), ColumnValue::I64(-21)),
+            // the quotient is truncated towards zero, the remainder has the sign of the dividend
+            (column("Level") / ColumnValue::I8(2), ColumnValue::I8(-3)),
+            (column("Level") % ColumnValue::I8(2), ColumnValue::I8(-1)),
+            (column("Level") % ColumnValue::I8(-2), ColumnValue::I8(-1)),
+            (column("Ratio") * column("Xp"), ColumnValue::F64(5.0)),
+            (column("Xp") / ColumnValue::F32(4.0), ColumnValue::F64(2.5)),
+            (column("Ratio") % ColumnValue::F64(0.375), ColumnValue::F64(0.125)),
+            // operators of Rust nest like those of SQL
+            (column("Xp") + column("Bonus") * ColumnValue::U8(2), ColumnValue::U64(16)),
+            (
+                column("JoinedAt") + ColumnValue::Duration(Duration::from_secs(1)),
+                ColumnValue::Timestamp(SystemTime::UNIX_EPOCH + Duration::from_secs(1))
+            )
+        ];
+
+        for (expression, value) in cases {
+            assert_evaluates(&expression, &value);
+        }
+    }
+
+    #[test]
+    fn division_by_zero_and_overflow_are_null() {
+        let cases = [
+            column("Xp") / ColumnValue::U64(0),
+            column("Xp") % ColumnValue::U8(0),
+            column("Ratio") / ColumnValue::F64(0.0),
+            column("Ratio") % ColumnValue::I8(0),
+            Expression::value(i8::MAX) + ColumnValue::I8(1),
+            Expression::value(i8::MIN) / ColumnValue::I8(-1),
+            Expression::value(u64::MAX) * ColumnValue::U8(2),
+            Expression::value(u128::MAX) + ColumnValue::I8(-1) + column("Xp"),
+            // an unsigned result cannot be negative
+            column("Bonus") - column("Xp"),
+            -Expression::value(i8::MIN),
+            -column("Xp")
+        ];
+
+        for expression in cases {
+            assert_eq!(evaluate(&expression), ColumnValue::Null, "{expression}");
+            assert!(data_type(&expression).unwrap().is_some(), "{expression}");
+        }
+    }
+
+    #[test]
+    fn negation_keeps_the_type() {
+        assert_evaluates(&-column("Level"), &ColumnValue::I8(7));
+        assert_evaluates(&-column("Ratio"), &ColumnValue::F64(-0.5));
+        assert_evaluates(&-Expression::value(0_u8), &ColumnValue::U8(0));
+        assert_evaluates(&-(column("Level") * ColumnValue::I8(2)), &ColumnValue::I8(14));
+
+        assert!(matches!(
+            data_type(&-column("Name")),
+            Err(QlError::ColumnTypeMismatch { name }) if name == "-Name"
+        ));
+    }
+
+    #[test]
+    fn strings_concatenate_with_any_value() {
+        assert_evaluates(
+            &column("Name")
+                .concat(Expression::value(" "))
+                .concat(column("Xp")),
+            &ColumnValue::from("ada 10")
+        );
+        assert_evaluates(
+            &Expression::value(true).concat(column("Name")),
+            &ColumnValue::from("trueada")
+        );
+        assert_evaluates(
+            &column("Tags").concat(column("Tags")),
+            &ColumnValue::Array(vec![ColumnValue::from("a"), ColumnValue::from("a")])
+        );
+
+        // other values cannot be concatenated
+        for expression in [
+            column("Xp").concat(column("Bonus")),
+            column("Tags").concat(column("Xp")),
+            column("Xp") + column("Name"),
+            column("Name") * ColumnValue::U8(2)
+        ] {
+            assert!(matches!(
+                data_type(&expression),
+                Err(QlError::ColumnTypeMismatch { .. })
+            ));
+            assert_eq!(evaluate(&expression), ColumnValue::Null, "{expression}");
+        }
+    }
+
+    #[test]
+    fn null_operands_result_in_null() {
+        let cases = [
+            column("Xp") + column("Missing"),
+            column("Missing") * column("Bonus"),
+            column("Missing") % ColumnValue::U8(0),
+            -column("Missing"),
+            column("Name").concat(column("Missing")),
+            column("Xp") + Expression::value(ColumnValue::Null)
+        ];
+
+        for expression in cases {
+            assert_eq!(evaluate(&expression), ColumnValue::Null, "{expression}");
+        }
+
+        // the type of an expression with a `null` constant is unknown
+        assert_eq!(
+            data_type(&(column("Xp") + Expression::value(ColumnValue::Null))).unwrap(),
+            None
+        );
+
+        // a column that the table does not have is an error, but `null` if a row is missing it
+        assert!(matches!(
+            data_type(&(column("Xp") + column("Nope"))),
+            Err(QlError::ColumnDoesNotExist { name }) if name == "Nope"
+        ));
+        assert_eq!(evaluate(&(column("Xp") + column("Nope"))), ColumnValue::Null);
+    }
+
+    #[test]
+    fn columns_are_compared_with_columns() {
+        let cache = QlCache::new();
+        let context = QueryContext::new(&cache, 0);
+        let row = CacheTableRow {
+            column_values: columns()
+                .into_iter()
+                .map(|(name, _, value)| (String::from(name), value))
+                .collect()
+        };
+        let compare = |left: Expression, op, right: Expression| {
+            ExpressionConstraint::new(left, op, right)
+                .compute_row(&row, &context)
+                .unwrap()
+        };
+
+        assert_eq!(compare(column("Xp"), ConstraintOp::Gt, column("Bonus")), Some(true));
+        assert_eq!(compare(column("Xp"), ConstraintOp::Eq, column("Bonus")), Some(false));
+        assert_eq!(compare(column("Level"), ConstraintOp::Lt, column("Xp")), Some(true));
+        assert_eq!(compare(column("Ratio"), ConstraintOp::Le, column("Bonus")), Some(true));
+        assert_eq!(
+            compare(column("Bonus") * ColumnValue::U8(4), ConstraintOp::Ge, column("Xp")),
+            Some(true)
+        );
+        assert_eq!(
+            compare(column("Xp") - column("Bonus"), ConstraintOp::Eq, -column("Level")),
+            Some(true)
+        );
+        assert_eq!(compare(column("Name"), ConstraintOp::Eq, column("Name")), Some(true));
+
+        // comparing `null` is unknown
+        assert_eq!(compare(column("Xp"), ConstraintOp::Gt, column("Missing")), None);
+        assert_eq!(compare(column("Bonus") - column("Xp"), ConstraintOp::Lt, column("Xp")), None);
+    }
+}
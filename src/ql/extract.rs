@@ -0,0 +1,278 @@
+//! # Module `extract`
+//!
+//! This module implements the extraction of parts of column values, such as the length of an array
+//! or a field of a JSON document, for use in constraints, projections and expressions.
+
+use crate::{
+    types::{
+        JsonValue,
+        SnowflakePart
+    },
+    ColumnDataType,
+    ColumnValue
+};
+
+/// # Enumeration `Extraction`
+///
+/// A part to extract from a column value. The result is `null` if the value does not have the
+/// part.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Extraction {
+    /// # Enumeration Variant `Extraction::Snowflake`
+    ///
+    /// A part of a `Snowflake`.
+    Snowflake(SnowflakePart),
+
+    /// # Enumeration Variant `Extraction::Length`
+    ///
+    /// The length of an `Array` or a JSON array, as a `U64`.
+    Length,
+
+    /// # Enumeration Variant `Extraction::Element`
+    ///
+    /// An element of an `Array`, or an element of a JSON array as `Json` (`->` with an index).
+    Element(usize),
+
+    /// # Enumeration Variant `Extraction::ElementText`
+    ///
+    /// An element of a JSON array as a `String` (`->>` with an index).
+    ElementText(usize),
+
+    /// # Enumeration Variant `Extraction::Field`
+    ///
+    /// A field of a JSON object as `Json` (`->` with a key).
+    Field(String),
+
+    /// # Enumeration Variant `Extraction::FieldText`
+    ///
+    /// A field of a JSON object as a `String` (`->>` with a key).
+    FieldText(String)
+}
+
+impl Extraction {
+    /// # Instance Method `Extraction::apply`
+    ///
+    /// Extracts the part from a value.
+    ///
+    /// ## Parameters
+    /// - `value`, type `&ColumnValue`; the value to extract the part from
+    #[must_use]
+    pub fn apply(&self, value: &ColumnValue) -> ColumnValue {
+        match (self, value) {
+            (Self::Snowflake(part), value) => part.extract(value),
+            (Self::Length, ColumnValue::Array(values)) => ColumnValue::U64(values.len() as u64),
+            (Self::Length, ColumnValue::Json(JsonValue::Array(elements))) => {
+                ColumnValue::U64(elements.len() as u64)
+            }
+            (Self::Element(index), ColumnValue::Array(values)) => {
+                values.get(*index).cloned().unwrap_or(ColumnValue::Null)
+            }
+            (Self::Element(index), ColumnValue::Json(json)) => {
+                json.element(*index).map_or(ColumnValue::Null, as_json)
+            }
+            (Self::ElementText(index), ColumnValue::Json(json)) => {
+                json.element(*index).map_or(ColumnValue::Null, as_text)
+            }
+            (Self::Field(name), ColumnValue::Json(json)) => {
+                json.field(name).map_or(ColumnValue::Null, as_json)
+            }
+            (Self::FieldText(name), ColumnValue::Json(json)) => {
+                json.field(name).map_or(ColumnValue::Null, as_text)
+            }
+            _ => ColumnValue::Null
+        }
+    }
+
+    /// Returns the type of the part extracted from values of type `data_type`, or `None` if the
+    /// values do not have the part, so that it is always `null`.
+    pub(crate) fn data_type(&self, data_type: &ColumnDataType) -> Option<ColumnDataType> {
+        let part_type = match (self, data_type) {
+            (Self::Snowflake(part), ColumnDataType::Snowflake) => {
+                match part {
+                    SnowflakePart::Timestamp => ColumnDataType::Timestamp,
+                    SnowflakePart::WorkerId | SnowflakePart::ProcessId => ColumnDataType::U8,
+                    SnowflakePart::Increment => ColumnDataType::U16
+                }
+            }
+            (Self::Length, ColumnDataType::Array(_) | ColumnDataType::Json) => ColumnDataType::U64,
+            (Self::Element(_), ColumnDataType::Array(element)) => element.as_ref().clone(),
+            (Self::Element(_) | Self::Field(_), ColumnDataType::Json) => ColumnDataType::Json,
+            (Self::ElementText(_) | Self::FieldText(_), ColumnDataType::Json) => {
+                ColumnDataType::String
+            }
+            _ => return None
+        };
+
+        Some(part_type)
+    }
+
+    /// Applies a chain of extractions to a value, in order.
+    pub(crate) fn apply_all(extractions: &[Self], value: ColumnValue) -> ColumnValue {
+        extractions
+            .iter()
+            .fold(value, |value, extraction| extraction.apply(&value))
+    }
+}
+
+impl From<SnowflakePart> for Extraction {
+    fn from(part: SnowflakePart) -> Self {
+        Self::Snowflake(part)
+    }
+}
+
+fn as_json(json: &JsonValue) -> ColumnValue {
+    ColumnValue::Json(json.clone())
+}
+
+fn as_text(json: &JsonValue) -> ColumnValue {
+    match json {
+        JsonValue::Null => ColumnValue::Null,
+        JsonValue::String(string) => ColumnValue::String(string.clone()),
+        json => ColumnValue::String(json.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dashmap::DashMap;
+
+    use super::Extraction;
+    use crate::{
+        ql::{
+            expression::Expression,
+            QueryContext
+        },
+        types::{
+            JsonValue,
+            Snowflake,
+            SnowflakePart
+        },
+        CacheTableRow,
+        ColumnDataType,
+        ColumnValue,
+        QlCache
+    };
+
+    static_assertions::assert_impl_all!(Extraction: Clone, Eq, PartialEq, Send, Sync);
+
+    fn json(text: &str) -> ColumnValue {
+        ColumnValue::Json(text.parse::<JsonValue>().unwrap())
+    }
+
+    fn field(name: &str) -> Extraction {
+        Extraction::Field(String::from(name))
+    }
+
+    fn field_text(name: &str) -> Extraction {
+        Extraction::FieldText(String::from(name))
+    }
+
+    #[test]
+    fn lengths_are_extracted_from_arrays() {
+        let array = ColumnValue::Array(vec![ColumnValue::U8(1), ColumnValue::Null]);
+
+        assert_eq!(Extraction::Length.apply(&array), ColumnValue::U64(2));
+        assert_eq!(Extraction::Length.apply(&ColumnValue::Array(Vec::new())), ColumnValue::U64(0));
+        assert_eq!(Extraction::Length.apply(&json("[1, [2, 3]]")), ColumnValue::U64(2));
+
+        // objects, strings and `null` have no length
+        assert_eq!(Extraction::Length.apply(&json(r#"{"a": 1}"#)), ColumnValue::Null);
+        assert_eq!(Extraction::Length.apply(&json(r#""abc""#)), ColumnValue::Null);
+        assert_eq!(Extraction::Length.apply(&ColumnValue::from("abc")), ColumnValue::Null);
+        assert_eq!(Extraction::Length.apply(&ColumnValue::Null), ColumnValue::Null);
+    }
+
+    #[test]
+    fn fields_are_extracted_as_json_or_text() {
+        let document = json(r#"{"a": {"b": "c"}, "n": 1.5, "t": true, "z": null}"#);
+
+        assert_eq!(field("a").apply(&document), json(r#"{"b": "c"}"#));
+        assert_eq!(field("n").apply(&document), json("1.5"));
+        assert_eq!(field("z").apply(&document), json("null"));
+
+        // `->>` unquotes strings, formats other values and turns JSON `null` into `null`
+        assert_eq!(field_text("a").apply(&document), ColumnValue::from(r#"{"b":"c"}"#));
+        assert_eq!(field_text("n").apply(&document), ColumnValue::from("1.5"));
+        assert_eq!(field_text("t").apply(&document), ColumnValue::from("true"));
+        assert_eq!(field_text("z").apply(&document), ColumnValue::Null);
+        assert_eq!(
+            Extraction::apply_all(&[field("a"), field_text("b")], document.clone()),
+            ColumnValue::from("c")
+        );
+
+        // missing fields and values that are not objects have no fields
+        assert_eq!(field("missing").apply(&document), ColumnValue::Null);
+        assert_eq!(field_text("missing").apply(&document), ColumnValue::Null);
+        assert_eq!(field("0").apply(&json("[1]")), ColumnValue::Null);
+        assert_eq!(field("a").apply(&ColumnValue::from(r#"{"a": 1}"#)), ColumnValue::Null);
+        assert_eq!(
+            Extraction::apply_all(&[field("n"), field("b")], document),
+            ColumnValue::Null
+        );
+    }
+
+    #[test]
+    fn elements_are_extracted_from_arrays() {
+        let array = ColumnValue::Array(vec![ColumnValue::U8(1), ColumnValue::U8(2)]);
+        let document = json(r#"["a", 2, null]"#);
+
+        assert_eq!(Extraction::Element(1).apply(&array), ColumnValue::U8(2));
+        assert_eq!(Extraction::Element(2).apply(&array), ColumnValue::Null);
+        assert_eq!(Extraction::Element(0).apply(&document), json(r#""a""#));
+        assert_eq!(Extraction::ElementText(0).apply(&document), ColumnValue::from("a"));
+        assert_eq!(Extraction::ElementText(1).apply(&document), ColumnValue::from("2"));
+        assert_eq!(Extraction::ElementText(2).apply(&document), ColumnValue::Null);
+        assert_eq!(Extraction::ElementText(3).apply(&document), ColumnValue::Null);
+
+        // `->>` only applies to JSON documents
+        assert_eq!(Extraction::ElementText(0).apply(&array), ColumnValue::Null);
+    }
+
+    #[test]
+    fn extracted_parts_have_types() {
+        let json = ColumnDataType::Json;
+        let array = ColumnDataType::Array(Box::new(ColumnDataType::I32));
+
+        assert_eq!(Extraction::Length.data_type(&array), Some(ColumnDataType::U64));
+        assert_eq!(Extraction::Length.data_type(&json), Some(ColumnDataType::U64));
+        assert_eq!(Extraction::Element(0).data_type(&array), Some(ColumnDataType::I32));
+        assert_eq!(Extraction::Element(0).data_type(&json), Some(ColumnDataType::Json));
+        assert_eq!(field("a").data_type(&json), Some(ColumnDataType::Json));
+        assert_eq!(field_text("a").data_type(&json), Some(ColumnDataType::String));
+        assert_eq!(
+            Extraction::from(SnowflakePart::Increment).data_type(&ColumnDataType::Snowflake),
+            Some(ColumnDataType::U16)
+        );
+        assert_eq!(field("a").data_type(&ColumnDataType::String), None);
+        assert_eq!(Extraction::Length.data_type(&ColumnDataType::String), None);
+        assert_eq!(Extraction::ElementText(0).data_type(&array), None);
+    }
+
+    #[test]
+    fn expressions_extract_from_a_column_of_the_row() {
+        let cache = QlCache::new();
+        let context = QueryContext::new(&cache, 0);
+        let row = CacheTableRow {
+            column_values: DashMap::from_iter([
+                (String::from("Data"), json(r#"{"a": [10, 20]}"#)),
+                (String::from("Id"), ColumnValue::Snowflake(Snowflake(5 << 17)))
+            ])
+        };
+        let extract = |column: &str, extractions: Vec<Extraction>| {
+            extractions
+                .into_iter()
+                .fold(Expression::column(String::from(column)), Expression::extract)
+                .evaluate(&row, &context)
+                .unwrap()
+        };
+
+        assert_eq!(
+            extract("Data", vec![field("a"), Extraction::ElementText(1)]),
+            ColumnValue::from("20")
+        );
+        assert_eq!(extract("Id", vec![SnowflakePart::WorkerId.into()]), ColumnValue::U8(5));
+
+        // a column missing from the row is `null`
+        assert_eq!(extract("Missing", vec![Extraction::Length]), ColumnValue::Null);
+    }
+}
@@ -0,0 +1,901 @@
+//! # Module `function`
+//!
+//! This module implements scalar functions, which compute a value from the values of their
+//! arguments in a single row, for use in expressions.
+//!
+//! Except for `coalesce` and `nullif`, the result of a function is `null` if any of its arguments
+//! is `null`. Besides the built-in functions, functions can be registered on a cache with
+//! [`QlCache::register_function`](crate::QlCache::register_function).
+
+use std::{
+    cmp::Ordering,
+    fmt::{
+        Debug,
+        Formatter,
+        Result as FmtResult
+    },
+    sync::Arc
+};
+
+use crate::{
+    error::{
+        QlError,
+        QlResult
+    },
+    numeric::{
+        self,
+        Number
+    },
+    ql::expression::Expression,
+    ColumnDataType,
+    ColumnValue
+};
+
+/// The implementation of a user-defined function.
+type FunctionBody = Arc<dyn Fn(&[ColumnValue]) -> ColumnValue + Send + Sync>;
+
+/// # Enumeration `ScalarFunction`
+///
+/// A built-in scalar function.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ScalarFunction {
+    /// # Enumeration Variant `ScalarFunction::Lower`
+    ///
+    /// `lower(string)`, a `String` in lower case.
+    Lower,
+
+    /// # Enumeration Variant `ScalarFunction::Upper`
+    ///
+    /// `upper(string)`, a `String` in upper case.
+    Upper,
+
+    /// # Enumeration Variant `ScalarFunction::Length`
+    ///
+    /// `length(value)`, the number of characters of a `String`, bytes of `Bytes` or elements of
+    /// an `Array`, as a `U64`.
+    Length,
+
+    /// # Enumeration Variant `ScalarFunction::Substr`
+    ///
+    /// `substr(string, start [, count])`, the part of a `String` starting at the character at
+    /// `start`, counted from `1`, with at most `count` characters, or the rest of the string if
+    /// there is no `count`. The result is `null` if `count` is negative.
+    Substr,
+
+    /// # Enumeration Variant `ScalarFunction::Coalesce`
+    ///
+    /// `coalesce(value, ...)`, the first of the arguments that is not `null`, or `null` if there
+    /// is none. The arguments must be of the same type, or all be numbers, which are promoted to a
+    /// common type as in arithmetic, see [`numeric`](crate::numeric).
+    Coalesce,
+
+    /// # Enumeration Variant `ScalarFunction::Abs`
+    ///
+    /// `abs(number)`, the absolute value of a number, of the same type. The result is `null` if
+    /// the type cannot represent it, as for `I32(i32::MIN)`.
+    Abs,
+
+    /// # Enumeration Variant `ScalarFunction::NullIf`
+    ///
+    /// `nullif(value, other)`, `null` if the value is equal to the other value, and the value
+    /// otherwise.
+    NullIf
+}
+
+impl ScalarFunction {
+    /// # Instance Method `ScalarFunction::name`
+    ///
+    /// Returns the name of the function.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Lower => "lower",
+            Self::Upper => "upper",
+            Self::Length => "length",
+            Self::Substr => "substr",
+            Self::Coalesce => "coalesce",
+            Self::Abs => "abs",
+            Self::NullIf => "nullif"
+        }
+    }
+
+    /// Returns the type of the result of the function with arguments of `arguments`, where the
+    /// type of an argument that is always `null` is `None`, or `None` if the result is always
+    /// `null`. `call` names the call of the function for errors.
+    pub(crate) fn data_type(
+        self,
+        arguments: &[Option<ColumnDataType>],
+        call: impl FnOnce() -> String
+    ) -> QlResult<Option<ColumnDataType>> {
+        let (min, max) = match self {
+            Self::Lower | Self::Upper | Self::Length | Self::Abs => (1, 1),
+            Self::Substr => (2, 3),
+            Self::Coalesce => (1, usize::MAX),
+            Self::NullIf => (2, 2)
+        };
+        if arguments.len() < min || arguments.len() > max {
+            return Err(QlError::InvalidArgumentCount {
+                function: self.name().to_string(),
+                count: arguments.len()
+            });
+        }
+
+        let mismatch = || QlError::ColumnTypeMismatch { name: call() };
+        let known = arguments.iter().flatten().collect::<Vec<_>>();
+
+        // the result of the other functions is null if any of the arguments is null
+        let strict = !matches!(self, Self::Coalesce | Self::NullIf);
+        if strict && known.len() < arguments.len() {
+            return Ok(None);
+        }
+
+        let data_type = match (self, known.as_slice()) {
+            (Self::Lower | Self::Upper, [ColumnDataType::String]) => ColumnDataType::String,
+            (
+                Self::Length,
+                [ColumnDataType::String | ColumnDataType::Bytes | ColumnDataType::Array(_)]
+            ) => ColumnDataType::U64,
+            (Self::Substr, [ColumnDataType::String, bounds @ ..])
+                if bounds.iter().all(|bound| is_integer(bound)) =>
+            {
+                ColumnDataType::String
+            }
+            (Self::Abs, [data_type]) if data_type.is_numeric() => (*data_type).clone(),
+            (Self::Coalesce, [data_type, others @ ..]) => {
+                others
+                    .iter()
+                    .try_fold((*data_type).clone(), |data_type, other| {
+                        if data_type == **other {
+                            Some(data_type)
+                        }
+                        else {
+                            numeric::result_type(&data_type, other)
+                        }
+                    })
+                    .ok_or_else(mismatch)?
+            }
+            (Self::Coalesce, []) => return Ok(None),
+            (Self::NullIf, _) => {
+                match arguments {
+                    [Some(data_type), Some(other)] if !data_type.is_comparable_to(other) => {
+                        return Err(mismatch());
+                    }
+                    [data_type, _] => return Ok(data_type.clone()),
+                    _ => return Err(mismatch())
+                }
+            }
+            _ => return Err(mismatch())
+        };
+
+        Ok(Some(data_type))
+    }
+
+    /// Computes the function from the values of its arguments.
+    pub(crate) fn evaluate(self, arguments: Vec<ColumnValue>) -> ColumnValue {
+        let value = match (self, arguments.as_slice()) {
+            (Self::Coalesce, _) => {
+                return arguments
+                    .into_iter()
+                    .find(|argument| *argument != ColumnValue::Null)
+                    .unwrap_or(ColumnValue::Null);
+            }
+            (Self::NullIf, [value, other]) => {
+                if value.compare(other) == Some(Ordering::Equal) {
+                    None
+                }
+                else {
+                    Some(value.clone())
+                }
+            }
+            (Self::Lower, [ColumnValue::String(string)]) => {
+                Some(ColumnValue::String(string.to_lowercase()))
+            }
+            (Self::Upper, [ColumnValue::String(string)]) => {
+                Some(ColumnValue::String(string.to_uppercase()))
+            }
+            (Self::Length, [ColumnValue::String(string)]) => {
+                Some(ColumnValue::U64(string.chars().count() as u64))
+            }
+            (Self::Length, [ColumnValue::Bytes(bytes)]) => {
+                Some(ColumnValue::U64(bytes.len() as u64))
+            }
+            (Self::Length, [ColumnValue::Array(values)]) => {
+                Some(ColumnValue::U64(values.len() as u64))
+            }
+            (Self::Substr, [ColumnValue::String(string), start, bounds @ ..]) => {
+                substr(string, start, bounds.first())
+            }
+            (Self::Abs, [value]) => {
+                match value.compare(&ColumnValue::I8(0)) {
+                    Some(Ordering::Less) => value.checked_neg(),
+                    Some(_) => Some(value.clone()),
+                    None => None
+                }
+            }
+            _ => None
+        };
+
+        value.unwrap_or(ColumnValue::Null)
+    }
+}
+
+/// # Struct `UserFunction`
+///
+/// A user-defined scalar function, implemented by a Rust closure, with declared argument and
+/// return types.
+///
+/// The closure is only called with arguments that are not `null`, and its result is `null`
+/// otherwise. Numeric arguments are converted to the declared numeric types, which accept
+/// arguments of any numeric type; the query fails with `InvalidConversion` if an argument cannot
+/// be represented by its declared type. Arguments of other types must be of the declared types,
+/// and the query fails with `ColumnTypeMismatch` otherwise. A result that is not of the declared
+/// return type fails the query with `InvalidConversion`.
+///
+/// Calls are resolved by the name of the function each time a query is executed, so a function
+/// that is registered again under the same name replaces the old one in every expression calling
+/// it, and a query calling a function that is no longer registered fails.
+///
+/// ## Example
+///
+/// To call a registered function `has_permission(Permissions, 8)`:
+///
+/// ```
+/// use qlcache::{
+///     ql::{
+///         expression::Expression,
+///         function::UserFunction
+///     },
+///     ColumnDataType,
+///     ColumnValue,
+///     QlCache
+/// };
+///
+/// let cache = QlCache::new();
+/// cache
+///     .register_function(UserFunction::new(
+///         String::from("has_permission"),
+///         vec![ColumnDataType::U64, ColumnDataType::U64],
+///         ColumnDataType::Bool,
+///         |arguments| match arguments {
+///             [ColumnValue::U64(permissions), ColumnValue::U64(bit)] => {
+///                 ColumnValue::Bool(permissions & bit == *bit)
+///             }
+///             _ => ColumnValue::Null
+///         }
+///     ))
+///     .unwrap();
+///
+/// let expression = cache
+///     .function("has_permission")
+///     .unwrap()
+///     .call(vec![
+///         Expression::column(String::from("Permissions")),
+///         Expression::value(ColumnValue::U64(8))
+///     ])
+///     .unwrap();
+/// ```
+#[derive(Clone)]
+pub struct UserFunction {
+    pub(crate) name: String,
+    pub(crate) arguments: Vec<ColumnDataType>,
+    pub(crate) return_type: ColumnDataType,
+    pub(crate) body: FunctionBody
+}
+
+impl UserFunction {
+    /// # Constructor `UserFunction::new`
+    ///
+    /// Constructs a new `UserFunction`.
+    ///
+    /// ## Parameters
+    /// - `name`, type `String`; the name of the function
+    /// - `arguments`, type `Vec<ColumnDataType>`; the types of the arguments
+    /// - `return_type`, type `ColumnDataType`; the type of the result
+    /// - `body`, type `impl Fn(&[ColumnValue]) -> ColumnValue`; computes the result from the
+    ///   values of the arguments
+    #[must_use]
+    pub fn new(
+        name: String,
+        arguments: Vec<ColumnDataType>,
+        return_type: ColumnDataType,
+        body: impl Fn(&[ColumnValue]) -> ColumnValue + Send + Sync + 'static
+    ) -> Self {
+        Self {
+            name,
+            arguments,
+            return_type,
+            body: Arc::new(body)
+        }
+    }
+
+    /// # Instance Method `UserFunction::name`
+    ///
+    /// Returns the name of the function.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// # Instance Method `UserFunction::call`
+    ///
+    /// Returns an expression calling the function with arguments, see
+    /// [`Expression::call`](crate::ql::expression::Expression::call). The number of arguments and
+    /// the types of constant arguments are checked against the declared types here, and the
+    /// other arguments are checked when the query using the expression is executed, before any
+    /// row is computed.
+    ///
+    /// ## Parameters
+    /// - `arguments`, type `Vec<Expression>`; the arguments
+    ///
+    /// ## Errors
+    ///
+    /// Returns `InvalidArgumentCount` if the function declares a different number of arguments;
+    /// returns `ColumnTypeMismatch` if a constant argument is not of its declared type.
+    pub fn call(&self, arguments: Vec<Expression>) -> QlResult<Expression> {
+        // only the types of constants are known without the columns of a query
+        let data_types = arguments
+            .iter()
+            .map(|argument| {
+                match argument {
+                    Expression::Value(value) => value.data_type(),
+                    _ => None
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let expression = Expression::call(self.name.clone(), arguments);
+        self.data_type(&data_types, || expression.to_string())?;
+
+        Ok(expression)
+    }
+
+    /// Returns the type of the result of the function with arguments of `arguments`, as described
+    /// in [`ScalarFunction::data_type`].
+    pub(crate) fn data_type(
+        &self,
+        arguments: &[Option<ColumnDataType>],
+        call: impl FnOnce() -> String
+    ) -> QlResult<Option<ColumnDataType>> {
+        if arguments.len() != self.arguments.len() {
+            return Err(QlError::InvalidArgumentCount {
+                function: self.name.clone(),
+                count: arguments.len()
+            });
+        }
+
+        let mut always_null = false;
+        for (argument, declared) in arguments.iter().zip(&self.arguments) {
+            match argument {
+                Some(argument)
+                    if argument != declared && !(argument.is_numeric() && declared.is_numeric()) =>
+                {
+                    return Err(QlError::ColumnTypeMismatch {
+                        name: call()
+                    });
+                }
+                Some(_) => (),
+                None => always_null = true
+            }
+        }
+
+        if always_null {
+            return Ok(None);
+        }
+
+        Ok(Some(self.return_type.clone()))
+    }
+
+    /// Computes the function from the values of its arguments. `call` names the call of the
+    /// function for errors.
+    pub(crate) fn evaluate(
+        &self,
+        arguments: Vec<ColumnValue>,
+        call: impl FnOnce() -> String
+    ) -> QlResult<ColumnValue> {
+        if arguments.len() != self.arguments.len() {
+            return Err(QlError::InvalidArgumentCount {
+                function: self.name.clone(),
+                count: arguments.len()
+            });
+        }
+
+        if arguments.contains(&ColumnValue::Null) {
+            return Ok(ColumnValue::Null);
+        }
+
+        let mut values = Vec::with_capacity(arguments.len());
+        for (argument, declared) in arguments.into_iter().zip(&self.arguments) {
+            // numbers of any type are converted to declared numeric types
+            let is_number = Number::of(&argument).is_some() && declared.is_numeric();
+            if !is_number && !declared.accepts(&argument) {
+                return Err(QlError::ColumnTypeMismatch {
+                    name: call()
+                });
+            }
+
+            values.push(convert(&argument, declared)?);
+        }
+
+        convert(&(self.body)(&values), &self.return_type)
+    }
+}
+
+impl Debug for UserFunction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("UserFunction")
+            .field("name", &self.name)
+            .field("arguments", &self.arguments)
+            .field("return_type", &self.return_type)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Functions are equal if they are clones of the same function.
+impl PartialEq for UserFunction {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.arguments == other.arguments
+            && self.return_type == other.return_type
+            && Arc::ptr_eq(&self.body, &other.body)
+    }
+}
+
+impl Eq for UserFunction {}
+
+/// Converts a value to a declared type, which converts numbers to numeric types that can represent
+/// them. Fails with `InvalidConversion` if the value is not of the type and cannot be converted.
+fn convert(value: &ColumnValue, data_type: &ColumnDataType) -> QlResult<ColumnValue> {
+    let converted = match Number::of(value) {
+        Some(number) if data_type.is_numeric() => number.to_value(data_type),
+        _ => Some(value.clone())
+    };
+
+    match converted {
+        Some(converted) if data_type.accepts(&converted) => Ok(converted),
+        _ => {
+            Err(QlError::InvalidConversion {
+                value: value.to_string(),
+                target: data_type.to_string()
+            })
+        }
+    }
+}
+
+fn is_integer(data_type: &ColumnDataType) -> bool {
+    data_type.is_numeric() && !matches!(data_type, ColumnDataType::F32 | ColumnDataType::F64)
+}
+
+/// Returns the part of a string starting at the character at `start`, counted from `1`, with at
+/// most `count` characters. The characters before the first are counted as well, so that
+/// `substr('abc', 0, 2)` is `'a'`.
+fn substr(string: &str, start: &ColumnValue, count: Option<&ColumnValue>) -> Option<ColumnValue> {
+    let start = i128::try_from(start.clone()).ok()?;
+    let end = match count {
+        Some(count) => {
+            let count = i128::try_from(count.clone()).ok().filter(|count| *count >= 0)?;
+            Some(start.saturating_add(count))
+        }
+        None => None
+    };
+
+    let skip = usize::try_from(start.saturating_sub(1).max(0)).unwrap_or(usize::MAX);
+    let take = match end {
+        Some(end) => usize::try_from(end.saturating_sub(start.max(1)).max(0)).unwrap_or(usize::MAX),
+        None => usize::MAX
+    };
+
+    Some(ColumnValue::String(string.chars().skip(skip).take(take).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Debug;
+
+    use super::{
+        ScalarFunction,
+        UserFunction
+    };
+    use crate::{
+        error::{
+            QlError,
+            QlResult,
+            QueryError
+        },
+        ql::{
+            expression::Expression,
+            select::SelectScope,
+            sortby::{
+                SortBy,
+                SortOrdering
+            },
+            QueryBuilder,
+            QueryContext
+        },
+        testing,
+        ColumnDataType,
+        ColumnValue,
+        QlCache
+    };
+
+    static_assertions::assert_impl_all!(ScalarFunction: Clone, Copy, Eq, PartialEq, Send, Sync);
+    static_assertions::assert_impl_all!(UserFunction: Clone, Debug, Eq, PartialEq, Send, Sync);
+
+    /// Returns a cache with the table `Players (XP U32, Bonus I32 NULL)` of the rows `(250, NULL)`.
+    fn cache() -> QlCache {
+        let cache = QlCache::new();
+        testing::table(
+            &cache,
+            "Players",
+            [("XP", ColumnDataType::U32, false), ("Bonus", ColumnDataType::I32, true)],
+            [[ColumnValue::U32(250), ColumnValue::Null]]
+        );
+
+        cache
+    }
+
+    /// Returns a cache with the table `Users (Name String, XP U32)` of the rows `('bob', 250)`,
+    /// `('Émile', 120)` and `('Ada', 30)`.
+    fn users() -> QlCache {
+        let cache = QlCache::new();
+        testing::table(
+            &cache,
+            "Users",
+            [("Name", ColumnDataType::String, false), ("XP", ColumnDataType::U32, false)],
+            [("bob", 250), ("Émile", 120), ("Ada", 30)]
+                .map(|(name, xp)| [ColumnValue::from(name), ColumnValue::U32(xp)])
+        );
+
+        cache
+    }
+
+    fn function(function: ScalarFunction, arguments: Vec<ColumnValue>) -> ColumnValue {
+        function.evaluate(arguments)
+    }
+
+    fn name() -> Expression {
+        Expression::column(String::from("Name"))
+    }
+
+    /// Returns a function of a `U64` argument, dividing it by `divisor`.
+    fn level(divisor: u64) -> UserFunction {
+        UserFunction::new(
+            String::from("level"),
+            vec![ColumnDataType::U64],
+            ColumnDataType::U64,
+            move |arguments| match arguments {
+                [ColumnValue::U64(xp)] => ColumnValue::U64(xp / divisor),
+                _ => ColumnValue::Null
+            }
+        )
+    }
+
+    /// Returns the value of `expression` for the row of the table `Players`.
+    fn select(cache: &QlCache, expression: Expression) -> QlResult<ColumnValue> {
+        let rows = QueryBuilder::select()
+            .table_name(String::from("Players"))
+            .scope(SelectScope::Expressions(vec![(String::from("value"), expression)]))
+            .build()
+            .unwrap()
+            .execute(cache)?;
+
+        let value = rows[0].column_values.get("value").unwrap().clone();
+        Ok(value)
+    }
+
+    #[test]
+    fn calls_are_resolved_by_name_when_executed() {
+        let cache = cache();
+        cache.register_function(level(100)).unwrap();
+        let expression = cache
+            .function("level")
+            .unwrap()
+            .call(vec![Expression::column(String::from("XP"))])
+            .unwrap();
+        assert_eq!(select(&cache, expression.clone()).unwrap(), ColumnValue::U64(2));
+
+        assert!(cache.unregister_function("level"));
+        assert!(matches!(
+            select(&cache, expression.clone()),
+            Err(QlError::QueryError(QueryError::RelationDoesNotExist { .. }))
+        ));
+
+        cache.register_function(level(10)).unwrap();
+        assert_eq!(select(&cache, expression).unwrap(), ColumnValue::U64(25));
+    }
+
+    #[test]
+    fn arguments_and_results_are_checked_against_declared_types() {
+        let cache = cache();
+        let level = level(100);
+        assert!(matches!(level.call(vec![]), Err(QlError::InvalidArgumentCount { count: 0, .. })));
+        assert!(matches!(
+            level.call(vec![Expression::value(ColumnValue::String(String::from("250")))]),
+            Err(QlError::ColumnTypeMismatch { .. })
+        ));
+
+        // 250 is too large for the argument of `byte`
+        cache
+            .register_function(UserFunction::new(
+                String::from("byte"),
+                vec![ColumnDataType::I8],
+                ColumnDataType::I8,
+                |arguments| arguments[0].clone()
+            ))
+            .unwrap();
+        let byte = Expression::call(
+            String::from("byte"),
+            vec![Expression::column(String::from("XP"))]
+        );
+        assert!(matches!(select(&cache, byte), Err(QlError::InvalidConversion { .. })));
+
+        // `label` returns a number although it is declared to return a string
+        cache
+            .register_function(UserFunction::new(
+                String::from("label"),
+                vec![ColumnDataType::U32],
+                ColumnDataType::String,
+                |arguments| arguments[0].clone()
+            ))
+            .unwrap();
+        let label = Expression::call(
+            String::from("label"),
+            vec![Expression::column(String::from("XP"))]
+        );
+        assert!(matches!(select(&cache, label), Err(QlError::InvalidConversion { .. })));
+    }
+
+    #[test]
+    fn coalesce_promotes_numbers_of_different_types() {
+        let cache = cache();
+        let coalesce = Expression::function(
+            ScalarFunction::Coalesce,
+            vec![
+                Expression::column(String::from("Bonus")),
+                Expression::column(String::from("XP"))
+            ]
+        );
+        let data_type = QueryBuilder::select()
+            .table_name(String::from("Players"))
+            .scope(SelectScope::Expressions(vec![(String::from("value"), coalesce.clone())]))
+            .build()
+            .unwrap()
+            .query
+            .result_columns(&QueryContext::new(&cache, 0))
+            .unwrap();
+        assert_eq!(data_type[0].1, Some(ColumnDataType::I64));
+
+        assert!(matches!(select(&cache, coalesce).unwrap(), ColumnValue::I64(250)));
+    }
+
+    #[test]
+    fn string_functions_count_characters() {
+        let string = ColumnValue::from("Émile");
+
+        let lower = function(ScalarFunction::Lower, vec![string.clone()]);
+        assert_eq!(lower, ColumnValue::from("émile"));
+        let upper = function(ScalarFunction::Upper, vec![string.clone()]);
+        assert_eq!(upper, ColumnValue::from("ÉMILE"));
+        assert_eq!(function(ScalarFunction::Length, vec![string.clone()]), ColumnValue::U64(5));
+
+        // the length of `Bytes` is in bytes
+        let bytes = ColumnValue::Bytes(String::from("É").into_bytes());
+        assert_eq!(function(ScalarFunction::Length, vec![bytes]), ColumnValue::U64(2));
+        assert_eq!(
+            function(ScalarFunction::Length, vec![ColumnValue::Array(vec![ColumnValue::Null])]),
+            ColumnValue::U64(1)
+        );
+
+        let substr = |bounds: &[ColumnValue]| {
+            let mut arguments = vec![string.clone()];
+            arguments.extend_from_slice(bounds);
+            function(ScalarFunction::Substr, arguments)
+        };
+        assert_eq!(substr(&[ColumnValue::U8(2), ColumnValue::I64(3)]), ColumnValue::from("mil"));
+        assert_eq!(substr(&[ColumnValue::I8(2)]), ColumnValue::from("mile"));
+        // characters before the first count towards `count`
+        assert_eq!(substr(&[ColumnValue::I8(0), ColumnValue::I8(2)]), ColumnValue::from("É"));
+        assert_eq!(substr(&[ColumnValue::I8(-3), ColumnValue::I8(3)]), ColumnValue::from(""));
+        assert_eq!(substr(&[ColumnValue::U8(9)]), ColumnValue::from(""));
+        assert_eq!(substr(&[ColumnValue::U8(1), ColumnValue::U64(u64::MAX)]), string);
+        assert_eq!(substr(&[ColumnValue::U8(1), ColumnValue::I8(-1)]), ColumnValue::Null);
+
+        // `null` arguments result in `null`
+        for name in [ScalarFunction::Lower, ScalarFunction::Upper, ScalarFunction::Length] {
+            assert_eq!(function(name, vec![ColumnValue::Null]), ColumnValue::Null);
+        }
+        assert_eq!(substr(&[ColumnValue::Null]), ColumnValue::Null);
+        assert_eq!(
+            function(ScalarFunction::Substr, vec![ColumnValue::Null, ColumnValue::U8(1)]),
+            ColumnValue::Null
+        );
+    }
+
+    #[test]
+    fn arguments_of_builtin_functions_are_checked() {
+        let data_type = |function: ScalarFunction, arguments: &[Option<ColumnDataType>]| {
+            function.data_type(arguments, || String::from(function.name()))
+        };
+        let string = Some(ColumnDataType::String);
+
+        assert_eq!(
+            data_type(ScalarFunction::Lower, &[Some(ColumnDataType::String)]).unwrap(),
+            Some(ColumnDataType::String)
+        );
+        assert_eq!(
+            data_type(ScalarFunction::Length, &[Some(ColumnDataType::Bytes)]).unwrap(),
+            Some(ColumnDataType::U64)
+        );
+        assert_eq!(
+            data_type(ScalarFunction::Substr, &[string.clone(), Some(ColumnDataType::I16)])
+                .unwrap(),
+            Some(ColumnDataType::String)
+        );
+        assert_eq!(data_type(ScalarFunction::Upper, &[None]).unwrap(), None);
+
+        for (function, arguments) in [
+            (ScalarFunction::Lower, vec![Some(ColumnDataType::U8)]),
+            (ScalarFunction::Length, vec![Some(ColumnDataType::Json)]),
+            (ScalarFunction::Substr, vec![string.clone(), Some(ColumnDataType::F64)]),
+            (ScalarFunction::Substr, vec![Some(ColumnDataType::Bytes), Some(ColumnDataType::U8)]),
+            (ScalarFunction::Abs, vec![string.clone()]),
+            (ScalarFunction::NullIf, vec![string.clone(), Some(ColumnDataType::U8)])
+        ] {
+            assert!(matches!(
+                data_type(function, &arguments),
+                Err(QlError::ColumnTypeMismatch { name }) if name == function.name()
+            ));
+        }
+
+        for (function, count) in [
+            (ScalarFunction::Lower, 0),
+            (ScalarFunction::Length, 2),
+            (ScalarFunction::Substr, 1),
+            (ScalarFunction::Substr, 4),
+            (ScalarFunction::NullIf, 3)
+        ] {
+            assert!(matches!(
+                data_type(function, &vec![string.clone(); count]),
+                Err(QlError::InvalidArgumentCount { count: actual, .. }) if actual == count
+            ));
+        }
+    }
+
+    #[test]
+    fn abs_keeps_the_type() {
+        let abs = |value| function(ScalarFunction::Abs, vec![value]);
+
+        assert_eq!(abs(ColumnValue::I32(-5)), ColumnValue::I32(5));
+        assert_eq!(abs(ColumnValue::U8(5)), ColumnValue::U8(5));
+        assert_eq!(abs(ColumnValue::F64(-1.5)), ColumnValue::F64(1.5));
+        assert_eq!(abs(ColumnValue::Null), ColumnValue::Null);
+
+        // the absolute value of the least integer of a type does not fit in it
+        assert_eq!(abs(ColumnValue::I32(i32::MIN)), ColumnValue::Null);
+
+        assert_eq!(
+            ScalarFunction::Abs
+                .data_type(&[Some(ColumnDataType::I16)], String::new)
+                .unwrap(),
+            Some(ColumnDataType::I16)
+        );
+    }
+
+    #[test]
+    fn nullif_compares_values_of_any_numeric_type() {
+        let nullif = |value: ColumnValue, other: ColumnValue| {
+            function(ScalarFunction::NullIf, vec![value, other])
+        };
+
+        assert_eq!(nullif(ColumnValue::U32(250), ColumnValue::I64(250)), ColumnValue::Null);
+        assert_eq!(nullif(ColumnValue::U32(250), ColumnValue::U32(1)), ColumnValue::U32(250));
+        assert_eq!(nullif(ColumnValue::from("a"), ColumnValue::from("a")), ColumnValue::Null);
+        assert_eq!(nullif(ColumnValue::U32(250), ColumnValue::Null), ColumnValue::U32(250));
+        assert_eq!(nullif(ColumnValue::Null, ColumnValue::U32(250)), ColumnValue::Null);
+
+        // the result is of the type of the first argument
+        let data_type = |arguments: &[Option<ColumnDataType>]| {
+            ScalarFunction::NullIf.data_type(arguments, String::new).unwrap()
+        };
+        assert_eq!(
+            data_type(&[Some(ColumnDataType::U32), Some(ColumnDataType::I64)]),
+            Some(ColumnDataType::U32)
+        );
+        assert_eq!(data_type(&[Some(ColumnDataType::U32), None]), Some(ColumnDataType::U32));
+        assert_eq!(data_type(&[None, Some(ColumnDataType::U32)]), None);
+    }
+
+    #[test]
+    fn aliases_name_computed_columns() {
+        let cache = users();
+        let select = QueryBuilder::select()
+            .table_name(String::from("Users"))
+            .scope(SelectScope::Expressions(vec![
+                (String::from("shout"), Expression::function(ScalarFunction::Upper, vec![name()])),
+                (String::from("n"), Expression::function(ScalarFunction::Length, vec![name()])),
+                (
+                    String::from("rest"),
+                    Expression::function(ScalarFunction::Substr, vec![
+                        name(),
+                        Expression::value(ColumnValue::U8(2))
+                    ])
+                ),
+                (
+                    String::from("doubled"),
+                    Expression::column(String::from("XP")) * ColumnValue::U32(2)
+                )
+            ]))
+            .sort_by(SortBy::expressions(
+                vec![Expression::function(ScalarFunction::Lower, vec![name()])],
+                Some(SortOrdering::Descending)
+            ).unwrap())
+            .build()
+            .unwrap();
+
+        let result_columns = select
+            .query
+            .result_columns(&QueryContext::new(&cache, 0))
+            .unwrap();
+        assert_eq!(result_columns, vec![
+            (String::from("shout"), Some(ColumnDataType::String)),
+            (String::from("n"), Some(ColumnDataType::U64)),
+            (String::from("rest"), Some(ColumnDataType::String)),
+            (String::from("doubled"), Some(ColumnDataType::U32))
+        ]);
+
+        // 'émile' sorts after 'bob', which sorts after 'ada'
+        let rows = select
+            .execute(&cache)
+            .unwrap()
+            .into_iter()
+            .map(|row| {
+                assert_eq!(row.column_values.len(), 4);
+                (
+                    row.get::<String>("shout").unwrap(),
+                    row.get::<u64>("n").unwrap(),
+                    row.get::<String>("rest").unwrap(),
+                    row.get::<u32>("doubled").unwrap()
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(rows, vec![
+            (String::from("ÉMILE"), 5, String::from("mile"), 240),
+            (String::from("BOB"), 3, String::from("ob"), 500),
+            (String::from("ADA"), 3, String::from("da"), 60)
+        ]);
+    }
+
+    #[test]
+    fn rows_are_sorted_by_the_values_of_expressions() {
+        let cache = users();
+        let names = |sort_by: SortBy| {
+            QueryBuilder::select()
+                .table_name(String::from("Users"))
+                .scope(SelectScope::Fields(vec![String::from("Name")]))
+                .sort_by(sort_by)
+                .build()
+                .unwrap()
+                .execute(&cache)
+                .unwrap()
+                .into_iter()
+                .map(|row| row.get::<String>("Name").unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        // by length, then by name
+        let by_length = SortBy::expressions(
+            vec![Expression::function(ScalarFunction::Length, vec![name()]), name()],
+            None
+        )
+        .unwrap();
+        assert_eq!(names(by_length), ["Ada", "bob", "Émile"]);
+
+        // |XP - 100| is 150, 20 and 70
+        let xp = Expression::column(String::from("XP"));
+        let by_distance = SortBy::expressions(
+            vec![Expression::function(ScalarFunction::Abs, vec![xp - ColumnValue::I16(100)])],
+            None
+        )
+        .unwrap();
+        assert_eq!(names(by_distance), ["Émile", "Ada", "bob"]);
+    }
+}
@@ -0,0 +1,482 @@
+//! # Module `join`
+//!
+//! This module implements joins of tables in `SELECT` queries.
+//!
+//! The columns of joined rows are qualified with the names of their tables, as in `Users.Id`. A
+//! table is named by its alias if it has one, and otherwise by its name without the schema.
+
+use std::{
+    collections::HashMap,
+    sync::Arc
+};
+
+use dashmap::DashMap;
+
+use crate::{
+    error::{
+        QlError,
+        QlResult
+    },
+    ql::constraints::ConstraintOp,
+    CacheTableRow,
+    ColumnDataType,
+    ColumnValue
+};
+
+/// A joined row, with the keys of the rows it is joined from in each table, which are `None` for
+/// tables that the row has no values of, and for named results.
+pub(crate) type JoinedRow = (Vec<Option<u64>>, Arc<CacheTableRow>);
+
+/// # Enumeration `JoinKind`
+///
+/// The kind of a join, which determines the rows that are kept if there are no matching rows in
+/// the other table. The columns of the missing table are `null` in these rows.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum JoinKind {
+    /// # Enumeration Variant `JoinKind::Inner`
+    ///
+    /// `INNER JOIN`, only rows with a matching row in the other table are kept.
+    Inner,
+
+    /// # Enumeration Variant `JoinKind::Left`
+    ///
+    /// `LEFT JOIN`, rows of the tables joined so far are kept.
+    Left,
+
+    /// # Enumeration Variant `JoinKind::Right`
+    ///
+    /// `RIGHT JOIN`, rows of the joined table are kept.
+    Right,
+
+    /// # Enumeration Variant `JoinKind::Full`
+    ///
+    /// `FULL JOIN`, rows of both sides are kept.
+    Full
+}
+
+/// # Struct `Join`
+///
+/// A join of a table with the tables of a `SELECT` query joined so far, on conditions comparing
+/// their columns. A join without conditions joins every pair of rows.
+///
+/// The join is executed as a hash join if any of the conditions is an equality of a column of the
+/// joined table and a column of the other tables, and as a nested loop join otherwise.
+///
+/// ## Example
+///
+/// To join `LEFT JOIN Members ON Users.Id = Members.UserId`:
+///
+/// ```
+/// use qlcache::ql::{
+///     constraints::ConstraintOp,
+///     join::{
+///         Join,
+///         JoinKind
+///     }
+/// };
+///
+/// let join = Join::new(JoinKind::Left, String::from("Members")).on(
+///     String::from("Users.Id"),
+///     ConstraintOp::Eq,
+///     String::from("Members.UserId")
+/// );
+/// ```
+#[derive(Clone, Eq, PartialEq)]
+pub struct Join {
+    pub(crate) kind: JoinKind,
+    pub(crate) table_name: String,
+    pub(crate) alias: Option<String>,
+    pub(crate) conditions: Vec<(String, ConstraintOp, String)>
+}
+
+impl Join {
+    /// # Constructor `Join::new`
+    ///
+    /// Constructs a new `Join` of a table. The name is either a table in the `PUBLIC` schema, or a
+    /// schema name and a table name separated by a space.
+    #[must_use]
+    pub fn new(kind: JoinKind, table_name: String) -> Self {
+        Self {
+            kind,
+            table_name,
+            alias: None,
+            conditions: Vec::new()
+        }
+    }
+
+    /// # Instance Method `Join::alias`
+    ///
+    /// Sets the name to qualify the columns of the joined table with, which is required to join a
+    /// table more than once.
+    ///
+    /// ## Parameters
+    /// - `alias`, type `String`; the alias of the table
+    #[must_use]
+    pub fn alias(mut self, alias: String) -> Self {
+        self.alias.replace(alias);
+        self
+    }
+
+    /// # Instance Method `Join::on`
+    ///
+    /// Adds a condition comparing two qualified columns, which must be satisfied along with the
+    /// other conditions for rows to match. As in constraints, comparisons with `null` are never
+    /// satisfied.
+    ///
+    /// ## Parameters
+    /// - `left`, type `String`; the qualified column on the left of the operator
+    /// - `op`, type `ConstraintOp`; the operator
+    /// - `right`, type `String`; the qualified column on the right of the operator
+    #[must_use]
+    pub fn on(mut self, left: String, op: ConstraintOp, right: String) -> Self {
+        self.conditions.push((left, op, right));
+        self
+    }
+
+    /// Returns the name to qualify the columns of the joined table with.
+    pub(crate) fn qualifier(&self) -> &str {
+        qualifier(&self.table_name, self.alias.as_deref())
+    }
+
+    /// Checks the conditions against the columns of the tables, including the joined table.
+    pub(crate) fn validate(
+        &self,
+        columns: &DashMap<String, (ColumnDataType, bool)>
+    ) -> QlResult<()> {
+        for (left, op, right) in &self.conditions {
+            let mut data_types = Vec::with_capacity(2);
+
+            for column in [left, right] {
+                let data_type = columns.get(column).ok_or_else(|| {
+                    QlError::ColumnDoesNotExist {
+                        name: column.clone()
+                    }
+                })?;
+
+                data_types.push(data_type.0.clone());
+            }
+
+            // only comparisons are checked, as the other operators compare with elements
+            let comparison = !matches!(
+                op,
+                ConstraintOp::Contains | ConstraintOp::Any(_) | ConstraintOp::In
+            );
+            if comparison && !data_types[0].is_comparable_to(&data_types[1]) {
+                return Err(QlError::ColumnTypeMismatch {
+                    name: right.clone()
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Joins the rows of the tables joined so far, which have the columns `left_columns`, with the
+    /// rows of the joined table, which are qualified and have the columns `right_columns`.
+    /// `relations` is the number of tables joined so far.
+    pub(crate) fn execute(
+        &self,
+        left: Vec<JoinedRow>,
+        left_columns: &DashMap<String, (ColumnDataType, bool)>,
+        right: &[(Option<u64>, CacheTableRow)],
+        right_columns: &DashMap<String, (ColumnDataType, bool)>,
+        relations: usize
+    ) -> Vec<JoinedRow> {
+        // the columns of equality conditions with a column on each side are hashed
+        let (left_keys, right_keys) = self
+            .conditions
+            .iter()
+            .filter(|(_, op, _)| *op == ConstraintOp::Eq)
+            .filter_map(|(left, _, right)| {
+                match (right_columns.contains_key(left), right_columns.contains_key(right)) {
+                    (false, true) => Some((left.clone(), right.clone())),
+                    (true, false) => Some((right.clone(), left.clone())),
+                    _ => None
+                }
+            })
+            .unzip::<_, _, Vec<_>, Vec<_>>();
+
+        let mut buckets = HashMap::<_, Vec<_>>::new();
+        if !right_keys.is_empty() {
+            for (index, (_, row)) in right.iter().enumerate() {
+                let key = row.values_of(&right_keys);
+
+                // null is never equal to anything
+                if !key.contains(&ColumnValue::Null) {
+                    buckets.entry(key).or_default().push(index);
+                }
+            }
+        }
+
+        let mut matched = vec![false; right.len()];
+        let mut rows = Vec::new();
+
+        for (keys, row) in left {
+            let candidates = if right_keys.is_empty() {
+                (0..right.len()).collect()
+            }
+            else {
+                buckets
+                    .get(&row.values_of(&left_keys))
+                    .cloned()
+                    .unwrap_or_default()
+            };
+
+            let mut found = false;
+            for index in candidates {
+                let (key, right_row) = &right[index];
+                let joined = merge(&row, right_row);
+
+                if self.is_satisfied(&joined) {
+                    found = true;
+                    matched[index] = true;
+
+                    let mut keys = keys.clone();
+                    keys.push(*key);
+                    rows.push((keys, Arc::new(joined)));
+                }
+            }
+
+            if !found && matches!(self.kind, JoinKind::Left | JoinKind::Full) {
+                let mut keys = keys;
+                keys.push(None);
+                rows.push((keys, Arc::new(merge(&row, &nulls(right_columns)))));
+            }
+        }
+
+        if matches!(self.kind, JoinKind::Right | JoinKind::Full) {
+            let left_nulls = nulls(left_columns);
+
+            for ((key, row), _) in right.iter().zip(matched).filter(|(_, matched)| !matched) {
+                let mut keys = vec![None; relations];
+                keys.push(*key);
+                rows.push((keys, Arc::new(merge(&left_nulls, row))));
+            }
+        }
+
+        rows
+    }
+
+    fn is_satisfied(&self, row: &CacheTableRow) -> bool {
+        let value = |column: &String| {
+            row.column_values
+                .get(column)
+                .map_or(ColumnValue::Null, |value| value.clone())
+        };
+
+        self.conditions
+            .iter()
+            .all(|(left, op, right)| op.is_satisfied(&value(left), &value(right)))
+    }
+}
+
+/// Returns the name to qualify the columns of a table with, the alias if there is one, and the
+/// name of the table without the schema otherwise.
+pub(crate) fn qualifier<'name>(table_name: &'name str, alias: Option<&'name str>) -> &'name str {
+    alias.unwrap_or_else(|| {
+        table_name
+            .split_once(' ')
+            .map_or(table_name, |(_, table_name)| table_name)
+    })
+}
+
+/// Qualifies the names of the columns of a table or of a row.
+pub(crate) fn qualify<T: Clone>(
+    qualifier: &str,
+    columns: &DashMap<String, T>
+) -> DashMap<String, T> {
+    columns
+        .iter()
+        .map(|column| (format!("{qualifier}.{}", column.key()), column.value().clone()))
+        .collect()
+}
+
+fn merge(left: &CacheTableRow, right: &CacheTableRow) -> CacheTableRow {
+    CacheTableRow {
+        column_values: left
+            .column_values
+            .iter()
+            .chain(right.column_values.iter())
+            .map(|column| (column.key().clone(), column.value().clone()))
+            .collect()
+    }
+}
+
+/// Returns a row with every column `null`.
+fn nulls(columns: &DashMap<String, (ColumnDataType, bool)>) -> CacheTableRow {
+    CacheTableRow {
+        column_values: columns
+            .iter()
+            .map(|column| (column.key().clone(), ColumnValue::Null))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Join,
+        JoinKind
+    };
+    use crate::{
+        error::{
+            QlError,
+            QlResult
+        },
+        ql::{
+            constraints::ConstraintOp,
+            select::SelectScope,
+            QueryBuilder
+        },
+        testing,
+        ColumnDataType,
+        ColumnValue,
+        QlCache
+    };
+
+    static_assertions::assert_impl_all!(JoinKind: Clone, Copy, Eq, PartialEq, Send, Sync);
+    static_assertions::assert_impl_all!(Join: Clone, Eq, PartialEq, Send, Sync);
+
+    /// Returns a cache with the table `Users (Id U64, Name String)` of the users 1 to 3 named
+    /// `a` to `c`, and the table `Guild Members (UserId U64 NULL, Nickname String)` of the members
+    /// `(1, x)`, `(1, y)`, `(9, z)` and `(NULL, w)`.
+    fn cache() -> QlCache {
+        let cache = QlCache::new();
+        QueryBuilder::create()
+            .schema()
+            .name(String::from("Guild"))
+            .build()
+            .unwrap()
+            .execute(&cache)
+            .unwrap();
+        testing::table(
+            &cache,
+            "Users",
+            [("Id", ColumnDataType::U64, false), ("Name", ColumnDataType::String, false)],
+            [(1, "a"), (2, "b"), (3, "c")]
+                .map(|(id, name)| [ColumnValue::U64(id), ColumnValue::from(name)])
+        );
+        testing::table(
+            &cache,
+            "Guild Members",
+            [("UserId", ColumnDataType::U64, true), ("Nickname", ColumnDataType::String, false)],
+            [(Some(1), "x"), (Some(1), "y"), (Some(9), "z"), (None, "w")].map(
+                |(user_id, nickname)| {
+                    [
+                        user_id.map_or(ColumnValue::Null, ColumnValue::U64),
+                        ColumnValue::from(nickname)
+                    ]
+                }
+            )
+        );
+
+        cache
+    }
+
+    /// Returns the pairs of the names and nicknames of the joined rows in order, with `-` for
+    /// `null`.
+    fn pairs(cache: &QlCache, join: Join) -> QlResult<Vec<String>> {
+        let rows = QueryBuilder::select()
+            .table_name(String::from("Users"))
+            .join(join)
+            .scope(SelectScope::Fields(vec![
+                String::from("Users.Name"),
+                String::from("Members.Nickname")
+            ]))
+            .build()
+            .unwrap()
+            .execute(cache)?;
+
+        let mut pairs = rows
+            .iter()
+            .map(|row| {
+                let value = |column: &str| {
+                    row.get::<Option<String>>(column)
+                        .unwrap()
+                        .unwrap_or_else(|| String::from("-"))
+                };
+                format!("{}:{}", value("Users.Name"), value("Members.Nickname"))
+            })
+            .collect::<Vec<_>>();
+        pairs.sort();
+
+        Ok(pairs)
+    }
+
+    fn members(kind: JoinKind) -> Join {
+        Join::new(kind, String::from("Guild Members")).on(
+            String::from("Users.Id"),
+            ConstraintOp::Eq,
+            String::from("Members.UserId")
+        )
+    }
+
+    #[test]
+    fn rows_without_matches_are_kept_by_the_kind_of_the_join() {
+        let cache = cache();
+
+        // `null` never equals a user, so `w` has no match in any join
+        assert_eq!(pairs(&cache, members(JoinKind::Inner)).unwrap(), ["a:x", "a:y"]);
+        assert_eq!(pairs(&cache, members(JoinKind::Left)).unwrap(), [
+            "a:x", "a:y", "b:-", "c:-"
+        ]);
+        assert_eq!(pairs(&cache, members(JoinKind::Right)).unwrap(), [
+            "-:w", "-:z", "a:x", "a:y"
+        ]);
+        assert_eq!(pairs(&cache, members(JoinKind::Full)).unwrap(), [
+            "-:w", "-:z", "a:x", "a:y", "b:-", "c:-"
+        ]);
+    }
+
+    #[test]
+    fn tables_are_joined_with_themselves_under_aliases() {
+        let cache = cache();
+
+        // `Lt` is not hashed, so the rows are joined in a nested loop
+        let rows = QueryBuilder::select()
+            .table_name(String::from("Users"))
+            .alias(String::from("Lower"))
+            .join(Join::new(JoinKind::Inner, String::from("Users"))
+                .alias(String::from("Higher"))
+                .on(String::from("Lower.Id"), ConstraintOp::Lt, String::from("Higher.Id")))
+            .scope(SelectScope::Fields(vec![
+                String::from("Lower.Name"),
+                String::from("Higher.Name")
+            ]))
+            .build()
+            .unwrap()
+            .execute(&cache)
+            .unwrap();
+        let mut pairs = rows
+            .iter()
+            .map(|row| {
+                let lower = row.get::<String>("Lower.Name").unwrap();
+                format!("{lower}<{}", row.get::<String>("Higher.Name").unwrap())
+            })
+            .collect::<Vec<_>>();
+        pairs.sort();
+
+        assert_eq!(pairs, ["a<b", "a<c", "b<c"]);
+    }
+
+    #[test]
+    fn conditions_are_checked_against_the_joined_columns() {
+        let cache = cache();
+        let join = |right: &str| {
+            Join::new(JoinKind::Inner, String::from("Guild Members")).on(
+                String::from("Users.Id"),
+                ConstraintOp::Eq,
+                String::from(right)
+            )
+        };
+
+        assert!(matches!(
+            pairs(&cache, join("Members.Rank")),
+            Err(QlError::ColumnDoesNotExist { name }) if name == "Members.Rank"
+        ));
+        assert!(matches!(
+            pairs(&cache, join("Members.Nickname")),
+            Err(QlError::ColumnTypeMismatch { name }) if name == "Members.Nickname"
+        ));
+    }
+}
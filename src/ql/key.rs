@@ -1,6 +1,31 @@
 //! # Module `key`
 //!
 //! This module implements `PRIMARY KEY` and `FOREIGN KEY`.
+//!
+//! The values of the primary key of a table are unique among its live rows, which is checked
+//! whenever a row is committed. Each table keeps a [`PrimaryIndex`] from the values of its primary
+//! key to the keys of its rows, which the check and the lookups of rows by their primary key use
+//! instead of scanning the table.
+//!
+//! A foreign key refers to the rows of another table by the values of its primary key. Foreign
+//! keys are not checked when rows are written; they are relationships that eviction respects, see
+//! [`eviction`](crate::eviction). Each table keeps a [`ForeignIndex`] per foreign key, from the
+//! values of the column to the keys of the rows that have them.
+
+use std::{
+    collections::BTreeSet,
+    sync::atomic::{
+        AtomicU64,
+        Ordering
+    }
+};
+
+use dashmap::DashMap;
+
+use crate::{
+    CacheTableRow,
+    ColumnValue
+};
 
 /// # Struct `PrimaryKey`
 ///
@@ -15,11 +40,280 @@ impl PrimaryKey {
     pub fn new(name: String) -> PrimaryKey {
         Self(name)
     }
+
+    /// # Instance Method `PrimaryKey::column`
+    ///
+    /// Returns the name of the column of the primary key.
+    #[must_use]
+    pub fn column(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the value of the primary key of `row`, unless it is missing or `null`.
+    pub(crate) fn value_of(&self, row: &CacheTableRow) -> Option<ColumnValue> {
+        value_of(row, &self.0)
+    }
+}
+
+/// # Struct `ForeignKey`
+///
+/// A foreign key of a table, a column whose values refer to the rows of another table by the
+/// values of its primary key.
+#[allow(clippy::module_name_repetitions)]
+#[derive(Clone)]
+pub struct ForeignKey {
+    pub(crate) column: String,
+    pub(crate) table_name: String
+}
+
+impl ForeignKey {
+    /// # Constructor `ForeignKey::new`
+    ///
+    /// Creates a foreign key on the specified column, referring to the specified table, which is
+    /// either a table in the `PUBLIC` schema, or a schema name and a table name separated by a
+    /// space.
+    ///
+    /// ## Parameters
+    /// - `column`, type `String`; the name of the column
+    /// - `table_name`, type `String`; the name of the table referred to
+    #[must_use]
+    pub fn new(column: String, table_name: String) -> ForeignKey {
+        Self {
+            column,
+            table_name
+        }
+    }
+
+    /// # Instance Method `ForeignKey::column`
+    ///
+    /// Returns the name of the column of the foreign key.
+    #[must_use]
+    pub fn column(&self) -> &str {
+        &self.column
+    }
+
+    /// # Instance Method `ForeignKey::table_name`
+    ///
+    /// Returns the name of the table referred to.
+    #[must_use]
+    pub fn table_name(&self) -> &str {
+        &self.table_name
+    }
+
+    /// Returns the value of the foreign key of `row`, unless it is missing or `null`.
+    pub(crate) fn value_of(&self, row: &CacheTableRow) -> Option<ColumnValue> {
+        value_of(row, &self.column)
+    }
+
+    /// Returns whether the foreign key refers to the table named `name` in the schema named
+    /// `schema`.
+    pub(crate) fn refers_to(&self, schema: &str, name: &str) -> bool {
+        self.table_name.split_once(' ').unwrap_or(("PUBLIC", &self.table_name)) == (schema, name)
+    }
+}
+
+/// # Struct `ForeignIndex`
+///
+/// The index of a foreign key of a table, maps each value of the column of the foreign key to the
+/// keys of the rows whose latest versions have it.
+#[derive(Clone, Default)]
+pub struct ForeignIndex {
+    pub(crate) keys: DashMap<ColumnValue, BTreeSet<u64>>
+}
+
+impl ForeignIndex {
+    /// # Instance Method `ForeignIndex::len`
+    ///
+    /// Returns the number of values in the index.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// # Instance Method `ForeignIndex::is_empty`
+    ///
+    /// Returns whether the index is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Returns the keys of the rows that have `value` as the value of the foreign key.
+    pub(crate) fn get(&self, value: &ColumnValue) -> Vec<u64> {
+        self.keys
+            .get(value)
+            .map(|keys| keys.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Adds the row with the specified key to the rows that have `value`. Must be called while
+    /// committing.
+    pub(crate) fn insert(&self, value: ColumnValue, key: u64) {
+        self.keys.entry(value).or_default().insert(key);
+    }
+
+    /// Removes the row with the specified key from the rows that have `value`. Must be called
+    /// while committing.
+    pub(crate) fn remove(&self, value: &ColumnValue, key: u64) {
+        if let Some(mut keys) = self.keys.get_mut(value) {
+            keys.remove(&key);
+        }
+
+        self.keys.remove_if(value, |_, keys| keys.is_empty());
+    }
+}
+
+/// # Struct `PrimaryIndex`
+///
+/// The index of the primary key of a table, maps each value of the primary key to the key of the
+/// row whose latest version has it.
+#[derive(Default)]
+pub struct PrimaryIndex {
+    pub(crate) keys: DashMap<ColumnValue, u64>,
+    pub(crate) changed_at: AtomicU64
+}
+
+impl PrimaryIndex {
+    /// # Instance Method `PrimaryIndex::len`
+    ///
+    /// Returns the number of values in the index.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// # Instance Method `PrimaryIndex::is_empty`
+    ///
+    /// Returns whether the index is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Returns the key of the row that has `value` as the value of its primary key, and the
+    /// timestamp of the latest commit that changed the index. The key is read first, so the index
+    /// did not change after a snapshot taken at or after the timestamp when the key was read.
+    pub(crate) fn get(&self, value: &ColumnValue) -> (Option<u64>, u64) {
+        let key = self.keys.get(value).map(|key| *key);
+
+        (key, self.changed_at.load(Ordering::Acquire))
+    }
+
+    /// Maps `value` to the row with the specified key, written at `timestamp`. Must be called
+    /// while committing.
+    pub(crate) fn insert(&self, timestamp: u64, value: ColumnValue, key: u64) {
+        // the timestamp is published before the index changes, see `PrimaryIndex::get`
+        self.changed_at.store(timestamp, Ordering::Release);
+        self.keys.insert(value, key);
+    }
+
+    /// Removes the mapping of `value` if it maps to the row with the specified key, which was
+    /// written at `timestamp`. Must be called while committing.
+    pub(crate) fn remove(&self, timestamp: u64, value: &ColumnValue, key: u64) {
+        self.changed_at.store(timestamp, Ordering::Release);
+        self.keys.remove_if(value, |_, mapped| *mapped == key);
+    }
+}
+
+impl Clone for PrimaryIndex {
+    fn clone(&self) -> Self {
+        Self {
+            keys: self.keys.clone(),
+            changed_at: AtomicU64::new(self.changed_at.load(Ordering::Acquire))
+        }
+    }
+}
+
+fn value_of(row: &CacheTableRow, column: &str) -> Option<ColumnValue> {
+    row.column_values
+        .get(column)
+        .map(|value| value.clone())
+        .filter(|value| *value != ColumnValue::Null)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::PrimaryKey;
+    use std::{
+        sync::Arc,
+        time::{
+            Duration,
+            SystemTime
+        }
+    };
 
+    use super::{
+        ForeignIndex,
+        ForeignKey,
+        PrimaryIndex,
+        PrimaryKey
+    };
+    use crate::{
+        clock::ManualClock,
+        error::QlError,
+        ql::QueryBuilder,
+        testing,
+        CacheTableRow,
+        ColumnDataType,
+        ColumnValue,
+        QlCache
+    };
+
+    static_assertions::assert_impl_all!(ForeignIndex: Clone, Default, Send, Sync);
+    static_assertions::assert_impl_all!(ForeignKey: Clone, Send, Sync);
+    static_assertions::assert_impl_all!(PrimaryIndex: Clone, Default, Send, Sync);
     static_assertions::assert_impl_all!(PrimaryKey: Clone, Send, Sync);
+
+    fn handle(handle: &str) -> CacheTableRow {
+        testing::row([("Handle", ColumnValue::from(handle))])
+    }
+
+    #[test]
+    fn primary_keys_are_unique_among_live_rows() {
+        let clock = Arc::new(ManualClock::new(SystemTime::UNIX_EPOCH));
+        let cache = QlCache::with_clock(clock.clone());
+        QueryBuilder::create()
+            .table()
+            .name(String::from("Members"))
+            .columns(vec![(String::from("Handle"), (ColumnDataType::String, false))])
+            .primary_key(PrimaryKey::new(String::from("Handle")))
+            .unwrap()
+            .build()
+            .unwrap()
+            .execute(&cache)
+            .unwrap();
+
+        cache
+            .with_table("Members", |table| {
+                table.insert_row(1, handle("alice")).unwrap();
+                table.insert_row(1, handle("alice")).unwrap();
+                assert!(matches!(
+                    table.insert_row(2, handle("alice")),
+                    Err(QlError::PrimaryKeyViolation { table_name, key: 1 })
+                        if table_name == "Members"
+                ));
+            })
+            .unwrap();
+        assert!(matches!(
+            cache.compare_and_swap("Members", 2, 0, handle("alice")),
+            Err(QlError::PrimaryKeyViolation { key: 1, .. })
+        ));
+
+        cache
+            .with_table("Members", |table| {
+                assert!(table.row(2).is_none());
+
+                // removed and expired rows give up their values
+                assert!(table.remove_row(1));
+                table.insert_row(2, handle("alice")).unwrap();
+                table
+                    .insert_row_with_ttl(3, handle("bob"), Duration::from_secs(10))
+                    .unwrap();
+                assert!(table.insert_row(4, handle("bob")).is_err());
+                clock.advance(Duration::from_secs(10));
+                table.insert_row(4, handle("bob")).unwrap();
+
+                assert_eq!(table.primary_index.len(), 2);
+            })
+            .unwrap();
+    }
 }
@@ -0,0 +1,960 @@
+//! # Module `materialized`
+//!
+//! This module implements materialized views, views whose rows are stored instead of being
+//! computed each time they are read.
+//!
+//! A materialized view is maintained incrementally if its query selects from a single table,
+//! filters the rows by their own values only, and either projects them or groups them with
+//! `COUNT` and `SUM` aggregates of integer columns. Every change that is committed to the rows of
+//! the table is applied to the rows of the view by the commit, so reading the view costs time
+//! proportional to its rows rather than to the table. A query that reads a snapshot taken before
+//! the latest change applied to the view computes the view from the table instead. The rows of
+//! any other view are only computed when it is created and refreshed.
+
+use std::{
+    collections::{
+        BTreeMap,
+        BTreeSet
+    },
+    sync::{
+        Arc,
+        Mutex,
+        MutexGuard,
+        PoisonError
+    },
+    time::SystemTime
+};
+
+use dashmap::DashMap;
+
+use crate::{
+    error::{
+        QlError,
+        QlResult
+    },
+    mvcc::RowVersion,
+    numeric::Number,
+    ql::{
+        aggregate::{
+            Aggregate,
+            AggregateFunction
+        },
+        select::{
+            self,
+            ResultColumns,
+            Select
+        },
+        with::NamedResult,
+        QueryContext
+    },
+    CacheTable,
+    CacheTableRow,
+    ColumnDataType,
+    ColumnValue,
+    QlCache
+};
+
+/// # Struct `MaterializedView`
+///
+/// A materialized view of a schema (`CREATE MATERIALIZED VIEW name AS SELECT ...`), which is
+/// created with
+/// [`CreateBuilder::materialized_view`](crate::ql::create::CreateBuilder::materialized_view),
+/// refreshed with [`QueryBuilder::refresh`](crate::ql::QueryBuilder::refresh) and dropped with
+/// [`DropBuilder::materialized_view`](crate::ql::drop::DropBuilder::materialized_view).
+///
+/// A materialized view is read like a table by naming it in
+/// [`SelectBuilder::table_name`](crate::ql::select::SelectBuilder::table_name). It reflects the
+/// rows of its table the snapshot of the query that reads it sees if it is maintained
+/// incrementally, and the rows at the time it was last refreshed otherwise, regardless of the
+/// snapshot. Rows of an incrementally maintained view leave it as soon as they expire.
+#[derive(Clone)]
+pub struct MaterializedView {
+    /// # Struct Field `name`
+    ///
+    /// The name of the materialized view.
+    pub name: String,
+    pub(crate) select: Arc<Select>,
+    pub(crate) columns: ResultColumns,
+    pub(crate) materialization: Arc<Materialization>
+}
+
+impl MaterializedView {
+    /// # Instance Method `MaterializedView::table_name`
+    ///
+    /// Returns the name of the table, or the view, the query of the materialized view selects
+    /// from.
+    #[must_use]
+    pub fn table_name(&self) -> &str {
+        &self.select.table_name
+    }
+
+    /// # Instance Method `MaterializedView::columns`
+    ///
+    /// Returns the names of the columns of the materialized view in order, with their types if
+    /// the query of the view knows them.
+    #[must_use]
+    pub fn columns(&self) -> &[(String, Option<ColumnDataType>)] {
+        &self.columns
+    }
+
+    /// # Instance Method `MaterializedView::is_incremental`
+    ///
+    /// Returns whether the materialized view is maintained incrementally, or only computed when it
+    /// is refreshed.
+    #[must_use]
+    pub fn is_incremental(&self) -> bool {
+        !matches!(*lock(&self.materialization.contents), Contents::Refreshed(_))
+    }
+
+    /// Constructs the materialized view `name` of the schema `schema` with the rows of its query,
+    /// which is maintained incrementally if possible. The table applies its changes to the view
+    /// until it is dropped.
+    pub(crate) fn new(
+        cache: &QlCache,
+        schema: &str,
+        name: String,
+        select: Select
+    ) -> QlResult<Self> {
+        let snapshot = cache.snapshot();
+        let context = QueryContext::new(cache, snapshot.timestamp);
+        let columns = select.result_columns(&context)?;
+        let select = Arc::new(select);
+
+        let view = Self {
+            name,
+            select: select.clone(),
+            columns,
+            materialization: Arc::new(Materialization {
+                select,
+                cache: QlCache::with_clock(cache.time.clone()),
+                contents: Mutex::new(Contents::Refreshed(Vec::new()))
+            })
+        };
+
+        if view.is_maintainable(cache) {
+            cache.with_table(&view.select.table_name, |table| {
+                cache.clock.exclusive(|| {
+                    view.rebuild(table, &context)?;
+                    table
+                        .materializations
+                        .insert(view.key(schema), view.materialization.clone());
+
+                    Ok::<_, QlError>(())
+                })
+            })??;
+        }
+        else {
+            view.refresh(cache)?;
+        }
+
+        Ok(view)
+    }
+
+    /// Stops applying the changes of the table to the materialized view of the schema `schema`,
+    /// after it is dropped.
+    pub(crate) fn detach(&self, cache: &QlCache, schema: &str) {
+        // the table of a view that is not maintained incrementally has nothing to remove
+        let _ = cache.with_table(&self.select.table_name, |table| {
+            table.materializations.remove(&self.key(schema));
+        });
+    }
+
+    /// Computes the rows of the materialized view again from the rows of its table.
+    pub(crate) fn refresh(&self, cache: &QlCache) -> QlResult<()> {
+        if !self.is_incremental() {
+            let snapshot = cache.snapshot();
+            let rows = self
+                .select
+                .run(&QueryContext::new(cache, snapshot.timestamp))?
+                .into_iter()
+                .map(Arc::new)
+                .collect();
+
+            *lock(&self.materialization.contents) = Contents::Refreshed(rows);
+            return Ok(());
+        }
+
+        cache.with_table(&self.select.table_name, |table| {
+            let snapshot = cache.snapshot();
+            let context = QueryContext::new(cache, snapshot.timestamp);
+
+            cache.clock.exclusive(|| self.rebuild(table, &context))
+        })?
+    }
+
+    /// Returns the rows of the materialized view for a query in a context. The stored rows of an
+    /// incrementally maintained view are read if the snapshot of the query sees every change
+    /// applied to them, and the view is computed from the rows of its table the snapshot sees
+    /// otherwise.
+    pub(crate) fn evaluate(&self, context: &QueryContext<'_>) -> QlResult<NamedResult> {
+        let context = context.detached();
+        let stored = {
+            let mut contents = lock(&self.materialization.contents);
+
+            match &mut *contents {
+                Contents::Refreshed(rows) => {
+                    return Ok(NamedResult::new(&self.columns, rows.clone()));
+                }
+                Contents::Maintained(maintained)
+                    if maintained.applied_through <= context.timestamp =>
+                {
+                    if maintained.catch_up(&self.select, context.now).is_ok() {
+                        Some(maintained.rows(&self.select)?)
+                    }
+                    else {
+                        *contents = Contents::Invalid;
+                        None
+                    }
+                }
+                Contents::Maintained(_) | Contents::Invalid => None
+            }
+        };
+
+        let rows = match stored {
+            // the rest of the query runs without the lock, as its expressions may read views
+            Some(mut rows) => {
+                if let Some(having) = &self.select.having {
+                    select::retain_satisfying(&mut rows, having, |row| &**row, &context)?;
+                }
+                self.select.finish(rows, &self.columns, &context)?
+            }
+            None => self.select.run(&context)?
+        };
+
+        Ok(NamedResult::new(&self.columns, rows.into_iter().map(Arc::new).collect()))
+    }
+
+    /// Returns whether the query of the materialized view can be maintained incrementally.
+    fn is_maintainable(&self, cache: &QlCache) -> bool {
+        let select = &self.select;
+
+        // views are computed from other relations, whose changes are not applied
+        if cache.find_view(&select.table_name).is_some()
+            || cache.find_materialized_view(&select.table_name).is_some()
+            || !select.joins.is_empty()
+            || !select.windows.is_empty()
+            || matches!(&select.constraint, Some(constraint) if !constraint.is_row_local())
+        {
+            return false;
+        }
+
+        // floating point sums are not maintained, as subtracting rounded values drifts
+        cache
+            .with_table(&select.table_name, |table| {
+                select.aggregates().iter().all(|aggregate| {
+                    !aggregate.distinct
+                        && match aggregate.function {
+                            AggregateFunction::Count => true,
+                            AggregateFunction::Sum => {
+                                aggregate.data_type(&table.columns) != ColumnDataType::F64
+                            }
+                            _ => false
+                        }
+                })
+            })
+            .unwrap_or(false)
+    }
+
+    /// Replaces the rows of an incrementally maintained materialized view with the latest rows of
+    /// its table that have not expired. Must be called while no write can be committed.
+    fn rebuild(&self, table: &CacheTable, context: &QueryContext<'_>) -> QlResult<()> {
+        // the latest versions are those of the latest commit, as none is in progress
+        let mut maintained =
+            Maintained::new(&self.select, &table.columns, table.clock.timestamp());
+        for chain in &table.rows {
+            if let Some(version) = chain.latest_version() {
+                maintained.apply(&self.select, *chain.key(), Some(version), context)?;
+            }
+        }
+
+        *lock(&self.materialization.contents) = Contents::Maintained(maintained);
+        Ok(())
+    }
+
+    /// Returns the key the changes of the table are applied to the view under.
+    fn key(&self, schema: &str) -> String {
+        format!("{schema} {}", self.name)
+    }
+}
+
+/// The rows of a materialized view, which the table of an incrementally maintained view applies
+/// its changes to while committing.
+pub(crate) struct Materialization {
+    select: Arc<Select>,
+
+    /// An empty cache with the clock of the cache of the view, which the constraint of the query
+    /// is computed in while committing. The constraint of an incrementally maintained view only
+    /// reads the row it is computed against.
+    cache: QlCache,
+
+    contents: Mutex<Contents>
+}
+
+impl Materialization {
+    /// Applies a change of the row with the specified key that is committed at `timestamp`, where
+    /// `version` is the new version of the row, or `None` if the row was removed. If the change
+    /// cannot be applied, the view is computed from its table when it is read until it is
+    /// refreshed. Must be called while committing.
+    pub(crate) fn record(&self, timestamp: u64, key: u64, version: Option<&RowVersion>) {
+        let context = QueryContext::new(&self.cache, timestamp);
+        let mut contents = lock(&self.contents);
+
+        if let Contents::Maintained(maintained) = &mut *contents {
+            if maintained.apply(&self.select, key, version, &context).is_ok() {
+                maintained.applied_through = timestamp;
+                return;
+            }
+
+            *contents = Contents::Invalid;
+        }
+    }
+}
+
+/// The stored rows of a materialized view.
+enum Contents {
+    /// The rows of an incrementally maintained view.
+    Maintained(Maintained),
+
+    /// The rows of an incrementally maintained view that a change could not be applied to.
+    Invalid,
+
+    /// The result of the query when the view was last refreshed.
+    Refreshed(Vec<Arc<CacheTableRow>>)
+}
+
+/// The rows of an incrementally maintained materialized view.
+struct Maintained {
+    /// The rows of the table that satisfy the constraint of the query, by their keys, with the
+    /// times they expire at.
+    rows: BTreeMap<u64, (Arc<CacheTableRow>, Option<SystemTime>)>,
+
+    /// The keys of the rows that expire, by the times they expire at.
+    expirations: BTreeSet<(SystemTime, u64)>,
+
+    /// The groups of the rows by the values of their `GROUP BY` columns, if the query groups its
+    /// rows.
+    groups: Option<BTreeMap<Vec<ColumnValue>, Group>>,
+
+    /// The types of the aggregates of the query.
+    targets: Vec<ColumnDataType>,
+
+    /// The timestamp of the latest commit applied to the rows.
+    applied_through: u64
+}
+
+impl Maintained {
+    /// Returns the rows of a view of a table with the columns `columns` without rows, which has a
+    /// single group if the query has aggregates but no `GROUP BY` columns.
+    fn new(
+        select: &Select,
+        columns: &DashMap<String, (ColumnDataType, bool)>,
+        applied_through: u64
+    ) -> Self {
+        let groups = select.is_grouped().then(|| {
+            let mut groups = BTreeMap::new();
+            if select.group_by.is_none() {
+                groups.insert(Vec::new(), Group::new(select.aggregates().len()));
+            }
+
+            groups
+        });
+
+        Self {
+            rows: BTreeMap::new(),
+            expirations: BTreeSet::new(),
+            groups,
+            targets: select
+                .aggregates()
+                .iter()
+                .map(|aggregate| aggregate.data_type(columns))
+                .collect(),
+            applied_through
+        }
+    }
+
+    /// Replaces the row with the specified key by the row of `version`, or removes it if `version`
+    /// is `None`. The row is only kept if it satisfies the constraint of the query and has not
+    /// expired at the time of the context.
+    fn apply(
+        &mut self,
+        select: &Select,
+        key: u64,
+        version: Option<&RowVersion>,
+        context: &QueryContext<'_>
+    ) -> QlResult<()> {
+        self.remove(select, key)?;
+
+        let version = match version {
+            Some(version) if !version.is_expired_at(context.now) => version,
+            _ => return Ok(())
+        };
+        let satisfied = match &select.constraint {
+            Some(constraint) => constraint.compute_row(&version.row, context)?,
+            None => Some(true)
+        };
+
+        if satisfied == Some(true) {
+            self.insert(select, key, version.row.clone(), version.expires_at)?;
+        }
+
+        Ok(())
+    }
+
+    fn insert(
+        &mut self,
+        select: &Select,
+        key: u64,
+        row: Arc<CacheTableRow>,
+        expires_at: Option<SystemTime>
+    ) -> QlResult<()> {
+        if let Some(groups) = &mut self.groups {
+            let group_by = select.group_by.as_deref().unwrap_or_default();
+            let aggregates = select.aggregates();
+
+            groups
+                .entry(row.values_of(group_by))
+                .or_insert_with(|| Group::new(aggregates.len()))
+                .update(&row, aggregates, true)?;
+        }
+
+        if let Some(expires_at) = expires_at {
+            self.expirations.insert((expires_at, key));
+        }
+        self.rows.insert(key, (row, expires_at));
+
+        Ok(())
+    }
+
+    fn remove(&mut self, select: &Select, key: u64) -> QlResult<()> {
+        if let Some((row, expires_at)) = self.rows.remove(&key) {
+            if let Some(expires_at) = expires_at {
+                self.expirations.remove(&(expires_at, key));
+            }
+
+            if let Some(groups) = &mut self.groups {
+                let group_by = select.group_by.as_deref().unwrap_or_default();
+                let group_key = row.values_of(group_by);
+
+                if let Some(group) = groups.get_mut(&group_key) {
+                    group.update(&row, select.aggregates(), false)?;
+
+                    // the only group of a query without `GROUP BY` columns is kept when empty
+                    if group.rows == 0 && !group_by.is_empty() {
+                        groups.remove(&group_key);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Removes the rows that have expired at `now`, and computes the groups whose sums have
+    /// overflowed again from their rows, as the sums may fit again after rows were removed.
+    fn catch_up(&mut self, select: &Select, now: SystemTime) -> QlResult<()> {
+        while let Some((expires_at, key)) = self.expirations.pop_first() {
+            if expires_at > now {
+                self.expirations.insert((expires_at, key));
+                break;
+            }
+
+            self.remove(select, key)?;
+        }
+
+        match &mut self.groups {
+            Some(groups) => regroup_stale(select, groups, &self.rows),
+            None => Ok(())
+        }
+    }
+
+    /// Returns the rows of the view, which are the row of each group with the values of the
+    /// `GROUP BY` columns and of the aggregates if the query groups its rows.
+    fn rows(&self, select: &Select) -> QlResult<Vec<Arc<CacheTableRow>>> {
+        match &self.groups {
+            Some(groups) => {
+                let group_by = select.group_by.as_deref().unwrap_or_default();
+
+                groups
+                    .iter()
+                    .map(|(key, group)| {
+                        let column_values = group_by
+                            .iter()
+                            .cloned()
+                            .zip(key.clone())
+                            .collect::<DashMap<_, _>>();
+                        for (index, aggregate) in select.aggregates().iter().enumerate() {
+                            let value = group.value(index, aggregate, &self.targets[index])?;
+                            column_values.insert(aggregate.name.clone(), value);
+                        }
+
+                        Ok(Arc::new(CacheTableRow {
+                            column_values
+                        }))
+                    })
+                    .collect()
+            }
+            None => Ok(self.rows.values().map(|(row, _)| row.clone()).collect())
+        }
+    }
+}
+
+/// Computes the groups whose sums have overflowed again from the rows of the view.
+fn regroup_stale(
+    select: &Select,
+    groups: &mut BTreeMap<Vec<ColumnValue>, Group>,
+    rows: &BTreeMap<u64, (Arc<CacheTableRow>, Option<SystemTime>)>
+) -> QlResult<()> {
+    let stale = groups
+        .iter()
+        .filter(|(_, group)| group.is_stale())
+        .map(|(key, _)| key.clone())
+        .collect::<BTreeSet<_>>();
+    if stale.is_empty() {
+        return Ok(());
+    }
+
+    let group_by = select.group_by.as_deref().unwrap_or_default();
+    let aggregates = select.aggregates();
+    for key in &stale {
+        groups.insert(key.clone(), Group::new(aggregates.len()));
+    }
+    for (row, _) in rows.values() {
+        let key = row.values_of(group_by);
+        if stale.contains(&key) {
+            if let Some(group) = groups.get_mut(&key) {
+                group.update(row, aggregates, true)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The running aggregates of a group of rows, which are also the running aggregates of windows.
+/// Only `COUNT`, `SUM` and `AVG` are accumulated.
+pub(crate) struct Group {
+    /// The number of rows.
+    rows: u64,
+
+    /// The number of values of the column of each aggregate that are not `null`.
+    values: Vec<u64>,
+
+    /// The sum of the values of the column of each `SUM` and `AVG` aggregate, or `None` if it has
+    /// overflowed, in which case the group is stale until it is computed again from its rows.
+    sums: Vec<Option<Number>>
+}
+
+impl Group {
+    pub(crate) fn new(aggregates: usize) -> Self {
+        let zero = Number::Integer {
+            negative: false,
+            magnitude: 0
+        };
+
+        Self {
+            rows: 0,
+            values: vec![0; aggregates],
+            sums: vec![Some(zero); aggregates]
+        }
+    }
+
+    /// Adds a row to the group, or removes it if `added` is `false`. Fails with `NumericOverflow`
+    /// if a row that was never added is removed.
+    pub(crate) fn update(
+        &mut self,
+        row: &CacheTableRow,
+        aggregates: &[Aggregate],
+        added: bool
+    ) -> QlResult<()> {
+        self.rows = count(self.rows, added)?;
+
+        for (index, aggregate) in aggregates.iter().enumerate() {
+            let value = aggregate
+                .column
+                .as_ref()
+                .and_then(|column| row.column_values.get(column))
+                .map_or(ColumnValue::Null, |value| value.clone());
+            if value == ColumnValue::Null {
+                continue;
+            }
+
+            self.values[index] = count(self.values[index], added)?;
+            if matches!(aggregate.function, AggregateFunction::Sum | AggregateFunction::Avg) {
+                let sum = self.sums[index].zip(Number::of(&value));
+                self.sums[index] = sum.and_then(|(sum, value)| {
+                    if added {
+                        sum.checked_add(value)
+                    }
+                    else {
+                        sum.checked_sub(value)
+                    }
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns whether a sum of the group has overflowed.
+    fn is_stale(&self) -> bool {
+        self.sums.iter().any(Option::is_none)
+    }
+
+    /// Returns the value of the aggregate at `index` for the group, which is of type `target`.
+    pub(crate) fn value(
+        &self,
+        index: usize,
+        aggregate: &Aggregate,
+        target: &ColumnDataType
+    ) -> QlResult<ColumnValue> {
+        match (aggregate.function, &aggregate.column) {
+            (AggregateFunction::Count, None) => Ok(ColumnValue::U64(self.rows)),
+            (AggregateFunction::Count, Some(_)) => Ok(ColumnValue::U64(self.values[index])),
+            _ if self.values[index] == 0 => Ok(ColumnValue::Null),
+            (AggregateFunction::Avg, _) => {
+                #[allow(clippy::cast_precision_loss)] // the average is allowed to round
+                let average = self.sums[index]
+                    .map(|sum| ColumnValue::F64(sum.to_f64() / self.values[index] as f64));
+
+                average.ok_or_else(|| {
+                    QlError::NumericOverflow {
+                        target: target.to_string()
+                    }
+                })
+            }
+            _ => {
+                self.sums[index]
+                    .and_then(|sum| sum.to_value(target))
+                    .ok_or_else(|| {
+                        QlError::NumericOverflow {
+                            target: target.to_string()
+                        }
+                    })
+            }
+        }
+    }
+}
+
+/// Adds one to a count, or subtracts one if `added` is `false`.
+fn count(count: u64, added: bool) -> QlResult<u64> {
+    let count = if added {
+        count.checked_add(1)
+    }
+    else {
+        count.checked_sub(1)
+    };
+
+    count.ok_or_else(|| {
+        QlError::NumericOverflow {
+            target: ColumnDataType::U64.to_string()
+        }
+    })
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Group,
+        Maintained,
+        MaterializedView
+    };
+    use crate::{
+        clock::ManualClock,
+        eviction::{
+            CapacityLimit,
+            CapacityLimits,
+            EvictionReason
+        },
+        ql::{
+            aggregate::{
+                Aggregate,
+                AggregateFunction
+            },
+            constraints::{
+                Constraint,
+                ConstraintOp
+            },
+            select::{
+                Select,
+                SelectScope
+            },
+            sortby::SortBy,
+            Query,
+            QueryBuilder
+        },
+        testing,
+        CacheTableRow,
+        ColumnDataType,
+        ColumnValue,
+        QlCache
+    };
+    use std::{
+        collections::BTreeMap,
+        sync::Arc,
+        time::{
+            Duration,
+            SystemTime
+        }
+    };
+
+    static_assertions::assert_impl_all!(MaterializedView: Clone, Send, Sync);
+
+    fn cache(limits: CapacityLimits) -> (QlCache, Arc<ManualClock>) {
+        let clock = Arc::new(ManualClock::new(SystemTime::UNIX_EPOCH));
+        let cache = QlCache::with_clock(clock.clone());
+        QueryBuilder::create()
+            .table()
+            .name(String::from("Scores"))
+            .columns(vec![
+                (String::from("Team"), (ColumnDataType::U64, false)),
+                (String::from("Points"), (ColumnDataType::I64, true))
+            ])
+            .limits(limits)
+            .build()
+            .unwrap()
+            .execute(&cache)
+            .unwrap();
+
+        for (name, select) in [("Totals", totals()), ("Leaders", leaders())] {
+            QueryBuilder::create()
+                .materialized_view()
+                .name(String::from(name))
+                .select(select)
+                .build()
+                .unwrap()
+                .execute(&cache)
+                .unwrap();
+        }
+
+        (cache, clock)
+    }
+
+    /// `SELECT Team, COUNT(*) AS Rows, COUNT(Points) AS Scored, SUM(Points) AS Points FROM Scores
+    /// WHERE Points < 100 GROUP BY Team ORDER BY Team`
+    fn totals() -> Query<Select> {
+        QueryBuilder::select()
+            .table_name(String::from("Scores"))
+            .scope(SelectScope::Aggregates(vec![
+                Aggregate::count_all(String::from("Rows")),
+                Aggregate::new(
+                    String::from("Scored"),
+                    AggregateFunction::Count,
+                    String::from("Points")
+                ),
+                Aggregate::new(
+                    String::from("Points"),
+                    AggregateFunction::Sum,
+                    String::from("Points")
+                )
+            ]))
+            .group_by(vec![String::from("Team")])
+            .constraint(Box::new(
+                Constraint::builder()
+                    .field_name(String::from("Points"))
+                    .op(ConstraintOp::Lt)
+                    .value(ColumnValue::I64(100))
+                    .build()
+                    .unwrap()
+            ))
+            .sort_by(SortBy::new(vec![String::from("Team")], None).unwrap())
+            .build()
+            .unwrap()
+    }
+
+    /// `SELECT Points FROM Scores WHERE Team = 1 ORDER BY Points`
+    fn leaders() -> Query<Select> {
+        QueryBuilder::select()
+            .table_name(String::from("Scores"))
+            .scope(SelectScope::Fields(vec![String::from("Points")]))
+            .constraint(Box::new(
+                Constraint::builder()
+                    .field_name(String::from("Team"))
+                    .op(ConstraintOp::Eq)
+                    .value(ColumnValue::U64(1))
+                    .build()
+                    .unwrap()
+            ))
+            .sort_by(SortBy::new(vec![String::from("Points")], None).unwrap())
+            .build()
+            .unwrap()
+    }
+
+    fn row(team: u64, points: Option<i64>) -> CacheTableRow {
+        testing::row([
+            ("Team", ColumnValue::U64(team)),
+            ("Points", points.map_or(ColumnValue::Null, ColumnValue::I64))
+        ])
+    }
+
+    fn row_of_points(points: i64) -> CacheTableRow {
+        testing::row([("Points", ColumnValue::I64(points))])
+    }
+
+    fn values(rows: Vec<CacheTableRow>) -> Vec<BTreeMap<String, ColumnValue>> {
+        rows.into_iter()
+            .map(|row| row.column_values.into_iter().collect())
+            .collect()
+    }
+
+    /// Asserts that the rows of the views are the rows of their queries.
+    fn assert_current(cache: &QlCache) {
+        let views = [("Totals", totals(), "Team"), ("Leaders", leaders(), "Points")];
+        for (name, select, order) in views {
+            let view = QueryBuilder::select()
+                .table_name(String::from(name))
+                .scope(SelectScope::Everything)
+                .sort_by(SortBy::new(vec![String::from(order)], None).unwrap())
+                .build()
+                .unwrap()
+                .execute(cache)
+                .unwrap();
+
+            assert_eq!(values(view), values(select.execute(cache).unwrap()), "{name}");
+        }
+    }
+
+    #[test]
+    fn views_follow_inserts_updates_deletes_and_evictions() {
+        let (cache, _) = cache(CapacityLimits {
+            max_rows: Some(5),
+            max_bytes: None
+        });
+        assert!(cache.materialized_view("Totals").unwrap().is_incremental());
+        assert!(cache.materialized_view("Leaders").unwrap().is_incremental());
+        assert_current(&cache);
+
+        let mut transaction = cache.begin();
+        let rows = [(1, Some(10)), (1, Some(20)), (2, None), (2, Some(150))];
+        for (key, (team, points)) in (1..).zip(rows) {
+            transaction
+                .insert_row(String::from("Scores"), key, row(team, points))
+                .unwrap();
+        }
+        transaction.commit().unwrap();
+        assert_current(&cache);
+
+        // moves a row to the other team, and then out of the constraint of the totals
+        let mut transaction = cache.begin();
+        transaction
+            .insert_row(String::from("Scores"), 1, row(2, Some(30)))
+            .unwrap();
+        transaction.commit().unwrap();
+        assert_current(&cache);
+        let mut transaction = cache.begin();
+        transaction
+            .insert_row(String::from("Scores"), 1, row(2, Some(300)))
+            .unwrap();
+        transaction.commit().unwrap();
+        assert_current(&cache);
+
+        let mut transaction = cache.begin();
+        transaction.remove_row(String::from("Scores"), 2).unwrap();
+        transaction.commit().unwrap();
+        assert_current(&cache);
+
+        // the least recently used rows are evicted beyond five rows
+        for key in 10..15 {
+            let mut transaction = cache.begin();
+            transaction
+                .insert_row(String::from("Scores"), key, row(key % 2, Some(5)))
+                .unwrap();
+            transaction.commit().unwrap();
+            assert_current(&cache);
+        }
+        let evicted = EvictionReason::TableLimit(CapacityLimit::Rows);
+        assert_eq!(cache.eviction_statistics().evicted(evicted), 3);
+    }
+
+    #[test]
+    fn expired_rows_leave_views_when_they_expire() {
+        let (cache, clock) = cache(CapacityLimits::default());
+
+        let mut transaction = cache.begin();
+        for (key, ttl) in [(1, 10), (2, 20), (3, 30)] {
+            transaction
+                .insert_row_with_ttl(
+                    String::from("Scores"),
+                    key,
+                    row(1, Some(10)),
+                    Duration::from_secs(ttl)
+                )
+                .unwrap();
+        }
+        transaction
+            .insert_row(String::from("Scores"), 4, row(1, Some(10)))
+            .unwrap();
+        transaction.commit().unwrap();
+        assert_current(&cache);
+
+        // the rows are read after they expire, but before they are swept
+        for seconds in [10, 5, 10] {
+            clock.advance(Duration::from_secs(seconds));
+            assert_current(&cache);
+        }
+        cache.sweep_expired();
+        assert_current(&cache);
+    }
+
+    #[test]
+    fn older_snapshots_see_the_rows_they_read() {
+        let (cache, _) = cache(CapacityLimits::default());
+        let mut transaction = cache.begin();
+        transaction
+            .insert_row(String::from("Scores"), 1, row(1, Some(10)))
+            .unwrap();
+        transaction.commit().unwrap();
+
+        let snapshot = cache.snapshot();
+        let mut transaction = cache.begin();
+        transaction
+            .insert_row(String::from("Scores"), 2, row(1, Some(20)))
+            .unwrap();
+        transaction.remove_row(String::from("Scores"), 1).unwrap();
+        transaction.commit().unwrap();
+        assert_current(&cache);
+
+        let points = QueryBuilder::select()
+            .table_name(String::from("Leaders"))
+            .scope(SelectScope::Everything)
+            .snapshot(snapshot)
+            .build()
+            .unwrap()
+            .execute(&cache)
+            .unwrap();
+        assert_eq!(values(points), values(vec![row_of_points(10)]));
+    }
+
+    #[test]
+    fn groups_report_underflows_and_recompute_overflowed_sums() {
+        let select = totals().query;
+        let aggregates = select.aggregates();
+        let mut group = Group::new(aggregates.len());
+        assert!(group.update(&row(1, Some(10)), aggregates, false).is_err());
+
+        let (cache, _) = cache(CapacityLimits::default());
+        let columns = cache.with_table("Scores", |table| table.columns.clone()).unwrap();
+        let mut maintained = Maintained::new(&select, &columns, 0);
+        for key in 1..=2 {
+            maintained
+                .insert(&select, key, Arc::new(row(1, Some(10))), None)
+                .unwrap();
+        }
+
+        // an overflowed sum is computed again from the rows of its group when it is read
+        if let Some(groups) = &mut maintained.groups {
+            for group in groups.values_mut() {
+                group.sums[2] = None;
+            }
+        }
+        maintained.catch_up(&select, SystemTime::UNIX_EPOCH).unwrap();
+        let rows = maintained.rows(&select).unwrap();
+        assert_eq!(
+            rows[0].column_values.get("Points").map(|value| value.clone()),
+            Some(ColumnValue::I64(20))
+        );
+    }
+}
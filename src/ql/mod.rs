@@ -2,30 +2,71 @@
 //!
 //! This module implements the query language.
 
-use std::marker::PhantomData;
+use std::{
+    collections::HashMap,
+    marker::PhantomData,
+    sync::Arc,
+    time::SystemTime
+};
+
+use dashmap::DashMap;
 
 use crate::{
     error::QlResult,
     ql::{
+        compound::{
+            Compound,
+            CompoundBuilder
+        },
         create::{
             Create,
             CreateBuilder
         },
+        drop::DropBuilder,
+        refresh::{
+            Refresh,
+            RefreshBuilder
+        },
         select::{
             Select,
             SelectBuilder
+        },
+        update::{
+            Update,
+            UpdateBuilder
+        },
+        with::{
+            NamedResult,
+            With,
+            WithBuilder
         }
     },
     CacheTableRow,
+    ColumnDataType,
+    ColumnValue,
     FromRow,
     QlCache
 };
 
+pub mod aggregate;
+pub mod compound;
 pub mod constraints;
 pub mod create;
+pub mod drop;
+pub mod expression;
+pub mod extract;
+pub mod function;
+pub mod join;
 pub mod key;
+pub mod materialized;
+pub mod refresh;
 pub mod select;
 pub mod sortby;
+pub mod subquery;
+pub mod update;
+pub mod view;
+pub mod window;
+pub mod with;
 
 /// # Trait `QueryRow`
 ///
@@ -103,12 +144,228 @@ impl<'row, T: QueryAsType<'row, U>, U: FromRow<'row>> QueryAs<'row, T, U> {
     }
 }
 
+/// The columns and the rows of a table or a named result, with the keys of the rows of a table.
+pub(crate) type Relation = (
+    DashMap<String, (ColumnDataType, bool)>,
+    Vec<(Option<u64>, Arc<CacheTableRow>)>
+);
+
+/// # Struct `QueryContext`
+///
+/// The context a query is executed in, which its constraints and expressions are validated and
+/// computed with: the cache and the snapshot the query reads, the time `now()` is evaluated as,
+/// the named results of `WITH` clauses, and the columns and the row of the enclosing query if the
+/// query is a subquery.
+#[derive(Clone, Copy)]
+pub struct QueryContext<'query> {
+    pub(crate) cache: &'query QlCache,
+    pub(crate) timestamp: u64,
+    pub(crate) now: SystemTime,
+    pub(crate) named_results: Option<&'query HashMap<String, Arc<NamedResult>>>,
+    pub(crate) outer_columns: Option<&'query DashMap<String, (ColumnDataType, bool)>>,
+    pub(crate) outer_row: Option<&'query CacheTableRow>
+}
+
+impl<'query> QueryContext<'query> {
+    /// Returns the context of a query reading the snapshot at `timestamp`.
+    pub(crate) fn new(cache: &'query QlCache, timestamp: u64) -> Self {
+        Self {
+            cache,
+            timestamp,
+            now: cache.time.now(),
+            named_results: None,
+            outer_columns: None,
+            outer_row: None
+        }
+    }
+
+    /// Returns the context of a subquery of a query in this context, which is validated against
+    /// the columns of the query, or computed for one of its rows.
+    pub(crate) fn nested<'outer>(
+        &self,
+        outer_columns: Option<&'outer DashMap<String, (ColumnDataType, bool)>>,
+        outer_row: Option<&'outer CacheTableRow>
+    ) -> QueryContext<'outer>
+    where
+        'query: 'outer
+    {
+        QueryContext {
+            cache: self.cache,
+            timestamp: self.timestamp,
+            now: self.now,
+            named_results: self.named_results,
+            outer_columns,
+            outer_row
+        }
+    }
+
+    /// Returns the context of a query that reads the same snapshot at the same time, but neither
+    /// the named results nor the enclosing query of this context, such as the query of a view.
+    pub(crate) fn detached(&self) -> Self {
+        Self {
+            named_results: None,
+            outer_columns: None,
+            outer_row: None,
+            ..*self
+        }
+    }
+
+    /// Returns the context of a query in this context that can read the named results
+    /// `named_results` as tables.
+    pub(crate) fn with_results<'results>(
+        &self,
+        named_results: &'results HashMap<String, Arc<NamedResult>>
+    ) -> QueryContext<'results>
+    where
+        'query: 'results
+    {
+        QueryContext {
+            named_results: Some(named_results),
+            ..*self
+        }
+    }
+
+    /// Returns the columns of a table, of a view, of a materialized view, or of a named result. A
+    /// named result shadows the relations of the same name, and a view or a materialized view
+    /// shadows the table of the same name.
+    pub(crate) fn columns(&self, name: &str) -> QlResult<DashMap<String, (ColumnDataType, bool)>> {
+        if let Some(result) = self.named_result(name) {
+            return Ok(result.columns.clone());
+        }
+
+        match self.view_result(name)? {
+            Some(result) => Ok(result.columns),
+            None => self.cache.with_table(name, |table| table.columns.clone())
+        }
+    }
+
+    /// Returns the columns and the visible rows of a table, of a view, of a materialized view, or
+    /// of a named result. The rows of a table have their keys, which the rows of the others have
+    /// not.
+    pub(crate) fn relation(&self, name: &str) -> QlResult<Relation> {
+        if let Some(result) = self.named_result(name) {
+            let rows = result.rows.iter().map(|row| (None, row.clone())).collect();
+            return Ok((result.columns.clone(), rows));
+        }
+
+        if let Some(result) = self.view_result(name)? {
+            let rows = result.rows.into_iter().map(|row| (None, row)).collect();
+            return Ok((result.columns, rows));
+        }
+
+        self.cache.with_table(name, |table| {
+            let rows = table
+                .visible_rows(self.timestamp)
+                .into_iter()
+                .map(|(key, row)| (Some(key), row))
+                .collect();
+
+            (table.columns.clone(), rows)
+        })
+    }
+
+    /// Returns the columns and the visible row of the table named `name` whose primary key, the
+    /// column named `column`, has the specified value, looked up in the index of the primary key.
+    /// Returns `None` if `name` is not a table with that primary key, if the value is `null` or
+    /// cannot be compared with the column, or if the index cannot tell which row is visible to the
+    /// snapshot, in which case the relation has to be scanned.
+    pub(crate) fn relation_by_primary_key(
+        &self,
+        name: &str,
+        column: &str,
+        value: &ColumnValue
+    ) -> QlResult<Option<Relation>> {
+        if self.named_result(name).is_some()
+            || self.cache.find_view(name).is_some()
+            || self.cache.find_materialized_view(name).is_some()
+        {
+            return Ok(None);
+        }
+
+        self.cache.with_table(name, |table| {
+            let data_type = table
+                .primary_key
+                .as_ref()
+                .filter(|primary_key| primary_key.column() == column)
+                .and_then(|primary_key| table.columns.get(primary_key.column()))
+                .map(|column| column.0.clone());
+
+            // values that are equal hash the same, numbers of different types included
+            if value.data_type().is_none()
+                || !data_type.is_some_and(|data_type| data_type.is_comparable_with(value))
+            {
+                return None;
+            }
+
+            table.row_by_primary_key_at(value, self.timestamp).map(|rows| {
+                let rows = rows.into_iter().map(|(key, row)| (Some(key), row)).collect();
+
+                (table.columns.clone(), rows)
+            })
+        })
+    }
+
+    /// Records that the rows with the specified keys have been read from a table, which does
+    /// nothing for the other relations.
+    pub(crate) fn record_access(
+        &self,
+        name: &str,
+        keys: impl IntoIterator<Item = u64>
+    ) -> QlResult<()> {
+        if self.named_result(name).is_some()
+            || self.cache.find_view(name).is_some()
+            || self.cache.find_materialized_view(name).is_some()
+        {
+            return Ok(());
+        }
+
+        self.cache.with_table(name, |table| table.record_access(keys))
+    }
+
+    /// Returns the result of the view or the materialized view named `name`, if there is one. A
+    /// view is resolved when it is read, so that it reflects the rows of the snapshot.
+    fn view_result(&self, name: &str) -> QlResult<Option<NamedResult>> {
+        if let Some(view) = self.cache.find_view(name) {
+            return view.evaluate(self).map(Some);
+        }
+
+        self.cache
+            .find_materialized_view(name)
+            .map(|view| view.evaluate(self))
+            .transpose()
+    }
+
+    fn named_result(&self, name: &str) -> Option<&'query NamedResult> {
+        self.named_results
+            .and_then(|results| results.get(name))
+            .map(|result| &**result)
+    }
+
+    /// # Instance Method `QueryContext::now`
+    ///
+    /// Returns the time `now()` is evaluated as, which is fixed to the time of the clock of the
+    /// cache when the query started executing.
+    #[must_use]
+    pub fn now(&self) -> SystemTime {
+        self.now
+    }
+}
+
 /// # Struct `QueryBuilder`
 ///
 /// The builder for a `Query`.
 pub struct QueryBuilder;
 
 impl QueryBuilder {
+    /// # Static Method `QueryBuilder::compound`
+    ///
+    /// Returns a builder for building a compound query of `SELECT` queries combined with set
+    /// operations.
+    #[must_use]
+    pub fn compound() -> CompoundBuilder {
+        Compound::builder()
+    }
+
     /// # Static Method `QueryBuilder::create`
     ///
     /// Returns a builder for building a `CREATE` query.
@@ -117,6 +374,22 @@ impl QueryBuilder {
         Create::builder()
     }
 
+    /// # Static Method `QueryBuilder::drop`
+    ///
+    /// Returns a builder for building a `DROP` query.
+    #[must_use]
+    pub fn drop() -> DropBuilder {
+        DropBuilder
+    }
+
+    /// # Static Method `QueryBuilder::refresh`
+    ///
+    /// Returns a builder for building a `REFRESH MATERIALIZED VIEW` query.
+    #[must_use]
+    pub fn refresh() -> RefreshBuilder {
+        Refresh::builder()
+    }
+
     /// # Static Method `QueryBuilder::select`
     ///
     /// Returns a builder for building a `SELECT` query.
@@ -124,6 +397,22 @@ impl QueryBuilder {
     pub fn select() -> SelectBuilder {
         Select::builder()
     }
+
+    /// # Static Method `QueryBuilder::with`
+    ///
+    /// Returns a builder for building a `SELECT` query with `WITH` clauses.
+    #[must_use]
+    pub fn with() -> WithBuilder {
+        With::builder()
+    }
+
+    /// # Static Method `QueryBuilder::update`
+    ///
+    /// Returns a builder for building an `UPDATE` query.
+    #[must_use]
+    pub fn update() -> UpdateBuilder {
+        Update::builder()
+    }
 }
 
 #[cfg(test)]
@@ -131,6 +420,7 @@ mod tests {
     use super::{
         Query,
         QueryBuilder,
+        QueryContext,
         QueryRow
     };
     use crate::{
@@ -149,4 +439,5 @@ mod tests {
 
     static_assertions::assert_impl_all!(Query<Dummy>: Send, Sync);
     static_assertions::assert_impl_all!(QueryBuilder: Send, Sync);
+    static_assertions::assert_impl_all!(QueryContext<'static>: Clone, Copy, Send, Sync);
 }
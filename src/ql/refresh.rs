@@ -0,0 +1,141 @@
+//! # Module `refresh`
+//!
+//! This module implements the `REFRESH MATERIALIZED VIEW` query.
+
+use crate::{
+    error::{
+        QlError,
+        QlResult,
+        QueryError
+    },
+    ql::{
+        Query,
+        QueryRow
+    },
+    CacheTableRow,
+    QlCache
+};
+
+/// # Struct `Refresh`
+///
+/// A `REFRESH MATERIALIZED VIEW` query, which computes the rows of a materialized view again from
+/// the latest rows of the relation it selects from.
+pub struct Refresh {
+    pub(crate) name: String,
+    pub(crate) schema: String
+}
+
+impl Refresh {
+    pub(crate) fn builder() -> RefreshBuilder {
+        RefreshBuilder {
+            name: None,
+            schema: None
+        }
+    }
+}
+
+impl QueryRow for Refresh {
+    fn execute(self, cache: &QlCache) -> QlResult<Vec<CacheTableRow>> {
+        let view = cache
+            .cache
+            .get(&self.schema)
+            .and_then(|schema| {
+                schema
+                    .materialized_views
+                    .get(&self.name)
+                    .map(|view| view.clone())
+            })
+            .ok_or(QlError::QueryError(QueryError::RelationDoesNotExist {
+                name: self.name
+            }))?;
+
+        view.refresh(cache)?;
+        Ok(vec![])
+    }
+}
+
+/// # Struct `RefreshBuilder`
+///
+/// A builder for a `Refresh`, constructs a `REFRESH MATERIALIZED VIEW` query.
+///
+/// Refreshing is required for the rows of a materialized view that is not maintained
+/// incrementally to reflect the changes of its relation, and also recovers an incrementally
+/// maintained view from a sum that has overflowed.
+///
+/// ## Example
+///
+/// `REFRESH MATERIALIZED VIEW MemberCounts`:
+/// ```
+/// use qlcache::ql::QueryBuilder;
+///
+/// let refresh = QueryBuilder::refresh()
+///     .name(String::from("MemberCounts"))
+///     .build()
+///     .unwrap();
+/// ```
+#[allow(clippy::module_name_repetitions)]
+pub struct RefreshBuilder {
+    pub(crate) name: Option<String>,
+    pub(crate) schema: Option<String>
+}
+
+impl RefreshBuilder {
+    /// # Instance Method `RefreshBuilder::name`
+    ///
+    /// Sets the name of the materialized view to refresh.
+    ///
+    /// ## Parameters
+    /// - `name`, type `String`; the name of the materialized view
+    #[must_use]
+    pub fn name(mut self, name: String) -> Self {
+        self.name.replace(name);
+        self
+    }
+
+    /// # Instance Method `RefreshBuilder::schema`
+    ///
+    /// Sets the parent schema of the materialized view to refresh.
+    ///
+    /// ## Parameters
+    /// - `schema`, type `String`; the parent schema name
+    #[must_use]
+    pub fn schema(mut self, schema: String) -> Self {
+        self.schema.replace(schema);
+        self
+    }
+
+    /// # Instance Method `RefreshBuilder::build`
+    ///
+    /// Consumes the builder and returns a `Query<Refresh>`.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `RequiredFieldIsNone` if any of the fields required is `None`.
+    #[allow(clippy::missing_panics_doc)] // this function never panics
+    pub fn build(self) -> QlResult<Query<Refresh>> {
+        if self.name.is_none() {
+            return Err(QlError::RequiredFieldIsNone {
+                field_name: String::from("RefreshBuilder.name")
+            });
+        }
+
+        Ok(Query {
+            query: Refresh {
+                name: self.name.unwrap(),
+                schema: self.schema.unwrap_or_else(|| String::from("PUBLIC"))
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        QueryRow,
+        Refresh,
+        RefreshBuilder
+    };
+
+    static_assertions::assert_impl_all!(Refresh: QueryRow, Send, Sync);
+    static_assertions::assert_impl_all!(RefreshBuilder: Send, Sync);
+}
@@ -2,7 +2,18 @@
 //!
 //! This module implements the `SELECT` query of the query language.
 
-use std::marker::PhantomData;
+use std::{
+    collections::{
+        HashMap,
+        HashSet
+    },
+    hash::Hash,
+    iter,
+    marker::PhantomData,
+    sync::Arc
+};
+
+use dashmap::DashMap;
 
 use crate::{
     error::{
@@ -10,32 +21,58 @@ use crate::{
         QlResult,
         QueryError
     },
+    mvcc::Snapshot,
+    numeric::Number,
     ql::{
+        aggregate::Aggregate,
         constraints::{
             AndConstraint,
             BoxedConstraint,
             OrConstraint
         },
+        expression::Expression,
+        join::{
+            self,
+            Join,
+            JoinKind,
+            JoinedRow
+        },
         sortby::SortBy,
+        window::Window,
         Query,
         QueryAs,
         QueryAsType,
+        QueryContext,
         QueryRow
     },
     CacheTableRow,
+    ColumnDataType,
+    ColumnValue,
     FromRow,
     QlCache
 };
 
+/// The names and the types of the columns of the result of a query, in order. The type is `None`
+/// if it is unknown, or if the column is always `null`.
+pub(crate) type ResultColumns = Vec<(String, Option<ColumnDataType>)>;
+
 /// # Struct `Select`
 ///
 /// A `SELECT` query.
 #[allow(dead_code)]
 pub struct Select {
     pub(crate) table_name: String,
+    pub(crate) alias: Option<String>,
+    pub(crate) joins: Vec<Join>,
     pub(crate) scope: SelectScope,
     pub(crate) constraint: Option<BoxedConstraint>,
-    pub(crate) sort_by: Option<SortBy>
+    pub(crate) group_by: Option<Vec<String>>,
+    pub(crate) having: Option<BoxedConstraint>,
+    pub(crate) sort_by: Option<SortBy>,
+    pub(crate) distinct: bool,
+    pub(crate) distinct_on: Option<Vec<String>>,
+    pub(crate) windows: Vec<Window>,
+    pub(crate) snapshot: Option<Snapshot>
 }
 
 impl Select {
@@ -43,102 +80,504 @@ impl Select {
     pub(crate) fn builder() -> SelectBuilder {
         SelectBuilder {
             table_name: None,
+            alias: None,
+            joins: Vec::new(),
             scope: None,
             constraint: None,
-            sort_by: None
+            group_by: None,
+            having: None,
+            sort_by: None,
+            distinct: false,
+            distinct_on: None,
+            windows: Vec::new(),
+            snapshot: None
         }
     }
-}
 
-impl QueryRow for Select {
-    fn execute(self, cache: &QlCache) -> QlResult<Vec<CacheTableRow>> {
-        let mut name = self.table_name.split(' ');
-        let (table, all) = if name.clone().count() == 1 {
-            // if the length of the split is only 1, we select from the PUBLIC schema.
-            let name = name.next().unwrap();
-
-            let entry = cache.cache.get("PUBLIC").unwrap();
-            let public = entry.value();
-            let table = if let Some(entry) = public.tables.get(name) {
-                entry.value().clone()
+    /// Returns whether the selected rows are grouped, which is the case if the query has a
+    /// `GROUP BY` or `HAVING` clause, or aggregates.
+    pub(crate) fn is_grouped(&self) -> bool {
+        self.group_by.is_some()
+            || self.having.is_some()
+            || matches!(self.scope, SelectScope::Aggregates(_))
+    }
+
+    /// Returns the aggregates the query selects.
+    pub(crate) fn aggregates(&self) -> &[Aggregate] {
+        match &self.scope {
+            SelectScope::Aggregates(aggregates) => aggregates,
+            _ => &[]
+        }
+    }
+
+    /// Returns the names of the tables and views the query reads, in its `FROM` and `JOIN` clauses
+    /// and in its subqueries, named as in the query.
+    pub(crate) fn relations(&self) -> Vec<&str> {
+        let mut relations = vec![self.table_name.as_str()];
+        relations.extend(self.joins.iter().map(|join| join.table_name.as_str()));
+
+        if let SelectScope::Expressions(expressions) = &self.scope {
+            relations.extend(expressions.iter().flat_map(|(_, expression)| expression.relations()));
+        }
+
+        for constraint in self.constraint.iter().chain(&self.having) {
+            relations.extend(constraint.relations());
+        }
+
+        let orders = self.windows.iter().filter_map(|window| window.order_by.as_ref());
+        for sort_by in self.sort_by.iter().chain(orders) {
+            relations.extend(sort_by.relations());
+        }
+
+        relations
+    }
+
+    /// Returns whether a column is a window of the query.
+    fn is_window(&self, column: &str) -> bool {
+        self.windows.iter().any(|window| window.name == column)
+    }
+
+    /// Checks the query against the columns of the rows it selects from, and returns the columns
+    /// of the rows the scope is applied to, which are the columns of the groups if the selected
+    /// rows are grouped, and the windows.
+    fn validate(
+        &self,
+        columns: &DashMap<String, (ColumnDataType, bool)>,
+        context: &QueryContext<'_>
+    ) -> QlResult<DashMap<String, (ColumnDataType, bool)>> {
+        // the sort and distinct columns of grouped rows are checked against the columns of the
+        // groups instead, and the windows are checked once they are computed
+        let sort_columns = self
+            .sort_by
+            .iter()
+            .flat_map(SortBy::columns)
+            .chain(self.distinct_on.iter().flatten())
+            .filter(|_| !self.is_grouped());
+        let group_columns = self.group_by.iter().flatten();
+        if let Some(field) = self
+            .scope
+            .columns()
+            .into_iter()
+            .chain(sort_columns)
+            .filter(|name| !self.is_window(name))
+            .chain(group_columns)
+            .find(|name| !columns.contains_key(*name))
+        {
+            return Err(QlError::QueryError(QueryError::RelationDoesNotExist {
+                name: self.column_name(field)
+            }));
+        }
+
+        if let Some(constraint) = &self.constraint {
+            constraint.validate(columns, context)?;
+        }
+
+        let columns = if self.is_grouped() {
+            self.validate_groups(columns, context)?
+        }
+        else {
+            self.validate_windows(columns.clone(), context)?
+        };
+
+        self.scope.validate(&columns, context)?;
+        if let Some(sort_by) = &self.sort_by {
+            sort_by.validate(&columns, context)?;
+        }
+
+        Ok(columns)
+    }
+
+    /// Checks the windows against the columns of the rows they are computed on, and returns the
+    /// columns with the windows.
+    fn validate_windows(
+        &self,
+        columns: DashMap<String, (ColumnDataType, bool)>,
+        context: &QueryContext<'_>
+    ) -> QlResult<DashMap<String, (ColumnDataType, bool)>> {
+        let data_types = self
+            .windows
+            .iter()
+            .map(|window| window.validate(&self.table_name, &columns, context))
+            .collect::<QlResult<Vec<_>>>()?;
+
+        for (window, data_type) in self.windows.iter().zip(data_types) {
+            columns.insert(window.name.clone(), (data_type, true));
+        }
+
+        Ok(columns)
+    }
+
+    /// Checks the grouped parts of the query, and returns the columns of the groups, which are
+    /// the `GROUP BY` columns, the aggregates and the windows.
+    fn validate_groups(
+        &self,
+        columns: &DashMap<String, (ColumnDataType, bool)>,
+        context: &QueryContext<'_>
+    ) -> QlResult<DashMap<String, (ColumnDataType, bool)>> {
+        let group_by = self.group_by.as_deref().unwrap_or_default();
+
+        // only grouped columns can be selected, as the other columns have many values in a group
+        if let Some(column) = self
+            .scope
+            .columns()
+            .into_iter()
+            .find(|column| !group_by.contains(column) && !self.is_window(column))
+        {
+            return Err(QlError::ColumnNotGrouped {
+                name: column.clone()
+            });
+        }
+
+        let group_columns = DashMap::new();
+        for column in group_by {
+            if let Some(entry) = columns.get(column) {
+                group_columns.insert(column.clone(), (entry.0.clone(), true));
             }
-            else {
-                return Err(QlError::QueryError(QueryError::RelationDoesNotExist {
-                    name: name.to_string()
-                }));
-            };
+        }
+        for aggregate in self.aggregates() {
+            aggregate.validate(&self.table_name, columns)?;
+            group_columns.insert(aggregate.name.clone(), (aggregate.data_type(columns), true));
+        }
+
+        if let Some(having) = &self.having {
+            having.validate(&group_columns, context)?;
+        }
+
+        let group_columns = self.validate_windows(group_columns, context)?;
+        if let Some(column) = self
+            .sort_by
+            .iter()
+            .flat_map(SortBy::columns)
+            .chain(self.distinct_on.iter().flatten())
+            .find(|column| !group_columns.contains_key(*column))
+        {
+            return Err(QlError::QueryError(QueryError::RelationDoesNotExist {
+                name: self.column_name(column)
+            }));
+        }
+
+        Ok(group_columns)
+    }
+
+    /// Groups the selected rows by the `GROUP BY` columns, or into a single group if there are
+    /// none, and computes a row for each group that satisfies the `HAVING` constraint. The row of
+    /// a group has the values of the `GROUP BY` columns and of the aggregates.
+    fn group(
+        &self,
+        columns: &DashMap<String, (ColumnDataType, bool)>,
+        rows: &[Arc<CacheTableRow>],
+        context: &QueryContext<'_>
+    ) -> QlResult<Vec<Arc<CacheTableRow>>> {
+        let group_by = self.group_by.as_deref().unwrap_or_default();
 
-            (table.clone(), table.rows)
+        // groups are kept in the order of their first rows
+        let mut groups: Vec<(Vec<ColumnValue>, Vec<&CacheTableRow>)> = Vec::new();
+        if group_by.is_empty() {
+            groups.push((Vec::new(), rows.iter().map(|row| &**row).collect()));
         }
         else {
-            // if the length of the split is 2, we select from the schema specified by the first
-            // segment, and the table specified by the second segment.
-            let schema_name = name.next().unwrap();
-            let schema = if let Some(entry) = cache.cache.get(schema_name) {
-                entry.value().clone()
+            let mut indices = HashMap::new();
+
+            for row in rows {
+                let key = row.values_of(group_by);
+                let index = *indices.entry(key.clone()).or_insert_with(|| {
+                    groups.push((key, Vec::new()));
+                    groups.len() - 1
+                });
+
+                groups[index].1.push(&**row);
             }
-            else {
-                return Err(QlError::QueryError(QueryError::RelationDoesNotExist {
-                    name: self.table_name
-                }));
+        }
+
+        let mut results = Vec::new();
+        for (key, rows) in groups {
+            let column_values = group_by.iter().cloned().zip(key).collect::<DashMap<_, _>>();
+            for aggregate in self.aggregates() {
+                column_values.insert(aggregate.name.clone(), aggregate.compute(&rows, columns)?);
+            }
+
+            let row = CacheTableRow {
+                column_values
             };
+            if let Some(having) = &self.having {
+                if having.compute_row(&row, context)? != Some(true) {
+                    continue;
+                }
+            }
+
+            results.push(Arc::new(row));
+        }
 
-            let table_name = name.next().unwrap();
-            let table = if let Some(entry) = schema.tables.get(table_name) {
-                entry.value().clone()
+        Ok(results)
+    }
+
+    /// Computes the windows for the selected rows, which have the columns `columns`, and returns
+    /// the rows with the values of the windows.
+    fn window(
+        &self,
+        rows: Vec<Arc<CacheTableRow>>,
+        columns: &DashMap<String, (ColumnDataType, bool)>,
+        context: &QueryContext<'_>
+    ) -> QlResult<Vec<Arc<CacheTableRow>>> {
+        if self.windows.is_empty() {
+            return Ok(rows);
+        }
+
+        let windowed = rows.iter().map(|row| (**row).clone()).collect::<Vec<_>>();
+        for window in &self.windows {
+            let values = window.compute(&rows, columns, context)?;
+            for (row, value) in windowed.iter().zip(values) {
+                row.column_values.insert(window.name.clone(), value);
             }
-            else {
-                return Err(QlError::QueryError(QueryError::RelationDoesNotExist {
-                    name: self.table_name
+        }
+
+        Ok(windowed.into_iter().map(Arc::new).collect())
+    }
+
+    /// Joins the rows of the table with the joined tables, and returns the joined rows. The
+    /// columns of the table are replaced with the qualified columns of the tables.
+    fn join(
+        &self,
+        context: &QueryContext<'_>,
+        columns: &mut DashMap<String, (ColumnDataType, bool)>,
+        rows: Vec<(Option<u64>, Arc<CacheTableRow>)>
+    ) -> QlResult<Vec<JoinedRow>> {
+        let qualifier = join::qualifier(&self.table_name, self.alias.as_deref());
+        let mut qualifiers = vec![qualifier];
+        *columns = join::qualify(qualifier, columns);
+        let mut rows = rows
+            .into_iter()
+            .map(|(key, row)| {
+                let row = CacheTableRow {
+                    column_values: join::qualify(qualifier, &row.column_values)
+                };
+
+                (vec![key], Arc::new(row))
+            })
+            .collect::<Vec<_>>();
+
+        for join in &self.joins {
+            let qualifier = join.qualifier();
+            if qualifiers.contains(&qualifier) {
+                return Err(QlError::QueryError(QueryError::RelationAlreadyExists {
+                    name: qualifier.to_string()
                 }));
-            };
+            }
+
+            let (right_columns, right_rows) = context.relation(&join.table_name)?;
+            let right_columns = join::qualify(qualifier, &right_columns);
+            let right_rows = right_rows
+                .into_iter()
+                .map(|(key, row)| {
+                    let row = CacheTableRow {
+                        column_values: join::qualify(qualifier, &row.column_values)
+                    };
 
-            (table.clone(), table.rows)
+                    (key, row)
+                })
+                .collect::<Vec<_>>();
+
+            // the columns of a side that rows may be missing from become nullable
+            if matches!(join.kind, JoinKind::Right | JoinKind::Full) {
+                columns.iter_mut().for_each(|mut column| column.1 = true);
+            }
+            if matches!(join.kind, JoinKind::Left | JoinKind::Full) {
+                right_columns.iter_mut().for_each(|mut column| column.1 = true);
+            }
+
+            let joined_columns = columns.clone();
+            for column in &right_columns {
+                joined_columns.insert(column.key().clone(), column.value().clone());
+            }
+            join.validate(&joined_columns)?;
+
+            rows = join.execute(rows, columns, &right_rows, &right_columns, qualifiers.len());
+            *columns = joined_columns;
+            qualifiers.push(qualifier);
+        }
+
+        Ok(rows)
+    }
+
+    /// Returns the columns of the rows the query selects from, which are the qualified columns of
+    /// the tables if the query has joins.
+    fn source_columns(
+        &self,
+        context: &QueryContext<'_>
+    ) -> QlResult<DashMap<String, (ColumnDataType, bool)>> {
+        let mut columns = context.columns(&self.table_name)?;
+        if !self.joins.is_empty() {
+            self.join(context, &mut columns, Vec::new())?;
+        }
+
+        Ok(columns)
+    }
+
+    /// Runs the query in a context, which is the context of the enclosing query for a subquery.
+    pub(crate) fn run(&self, context: &QueryContext<'_>) -> QlResult<Vec<CacheTableRow>> {
+        // only the row pointers are collected while the table is borrowed, the rest of the query
+        // runs against the snapshot without blocking writers. A constraint on the value of the
+        // primary key only reads the row that has it.
+        let equality = self.constraint.as_ref().and_then(|constraint| constraint.equality());
+        let indexed = match equality {
+            Some((column, value)) if self.joins.is_empty() => {
+                context.relation_by_primary_key(&self.table_name, column, value)?
+            }
+            _ => None
         };
-        let rows: Vec<CacheTableRow> = all.into_iter().map(|(_, row)| row).collect();
-
-        match self.scope {
-            SelectScope::Everything if self.constraint.is_none() => return Ok(rows),
-            SelectScope::Everything => todo!(),
-            SelectScope::Fields(fields) => {
-                let mut table_columns_iter = table.columns.iter();
-                if let Some(field) = fields
+        let (mut columns, rows) = match indexed {
+            Some(relation) => relation,
+            None => context.relation(&self.table_name)?
+        };
+        let mut rows = if self.joins.is_empty() {
+            rows.into_iter()
+                .map(|(key, row)| (vec![key], row))
+                .collect()
+        }
+        else {
+            self.join(context, &mut columns, rows)?
+        };
+
+        let selected_columns = self.validate(&columns, context)?;
+        if let Some(constraint) = &self.constraint {
+            retain_satisfying(&mut rows, constraint, |(_, row)| row, context)?;
+        }
+
+        let table_names = iter::once(&self.table_name)
+            .chain(self.joins.iter().map(|join| &join.table_name));
+        for (index, table_name) in table_names.enumerate() {
+            context.record_access(table_name, rows.iter().filter_map(|(keys, _)| keys[index]))?;
+        }
+
+        let rows = rows.into_iter().map(|(_, row)| row).collect::<Vec<_>>();
+        let rows = if self.is_grouped() {
+            self.group(&columns, &rows, context)?
+        }
+        else {
+            rows
+        };
+        let rows = self.window(rows, &selected_columns, context)?;
+        let result_columns = self.selected_result_columns(&selected_columns, context)?;
+
+        self.finish(rows, &result_columns, context)
+    }
+
+    /// Checks the query in a context, and returns the columns of its result.
+    pub(crate) fn result_columns(&self, context: &QueryContext<'_>) -> QlResult<ResultColumns> {
+        let columns = self.validate(&self.source_columns(context)?, context)?;
+
+        self.selected_result_columns(&columns, context)
+    }
+
+    /// Returns the columns of the result of the query, where `columns` are the checked columns of
+    /// the selected rows.
+    fn selected_result_columns(
+        &self,
+        columns: &DashMap<String, (ColumnDataType, bool)>,
+        context: &QueryContext<'_>
+    ) -> QlResult<ResultColumns> {
+        let data_type = |name: &String| columns.get(name).map(|column| column.0.clone());
+
+        let result_columns = match &self.scope {
+            SelectScope::Fields(fields) => fields
+                .iter()
+                .map(|field| (field.clone(), data_type(field)))
+                .collect(),
+            SelectScope::Expressions(expressions) => expressions
+                .iter()
+                .map(|(name, expression)| {
+                    Ok((name.clone(), expression.data_type(columns, context)?))
+                })
+                .collect::<QlResult<_>>()?,
+            SelectScope::Everything | SelectScope::Aggregates(_) if self.is_grouped() => self
+                .group_by
+                .iter()
+                .flatten()
+                .chain(self.aggregates().iter().map(|aggregate| &aggregate.name))
+                .chain(self.windows.iter().map(|window| &window.name))
+                .map(|name| (name.clone(), data_type(name)))
+                .collect(),
+            SelectScope::Everything | SelectScope::Aggregates(_) => {
+                let mut names = columns
                     .iter()
-                    .find(|name| {
-                        table_columns_iter
-                            .find(|ref_multi| ref_multi.key() == *name)
-                            .is_none()
+                    .map(|column| column.key().clone())
+                    .collect::<Vec<_>>();
+                names.sort();
+
+                names
+                    .into_iter()
+                    .map(|name| {
+                        let data_type = data_type(&name);
+                        (name, data_type)
                     })
-                {
-                    return Err(QlError::QueryError(QueryError::RelationDoesNotExist {
-                        name: format!("{}.{}", self.table_name, field)
-                    }));
-                }
+                    .collect()
+            }
+        };
 
-                if self.constraint.is_none() {
-                    return Ok(rows.iter()
-                        .map(|row| {
-                            CacheTableRow {
-                                column_values: row
-                                    .column_values
-                                    .clone()
-                                    .into_iter()
-                                    .filter(|column| fields.contains(&column.0))
-                                    .collect()
-                            }
-                        })
-                        .collect());
-                }
+        Ok(result_columns)
+    }
 
-                todo!()
-            }
+    /// Returns the name of a column for errors, which is qualified with the table name unless the
+    /// query has joins and the columns are qualified already.
+    fn column_name(&self, column: &str) -> String {
+        if self.joins.is_empty() {
+            format!("{}.{}", self.table_name, column)
+        }
+        else {
+            column.to_string()
+        }
+    }
+
+    /// Sorts the selected rows, and returns the selected columns of the distinct rows, which are
+    /// the columns `result_columns`.
+    pub(crate) fn finish(
+        &self,
+        mut rows: Vec<Arc<CacheTableRow>>,
+        result_columns: &ResultColumns,
+        context: &QueryContext<'_>
+    ) -> QlResult<Vec<CacheTableRow>> {
+        if let Some(sort_by) = &self.sort_by {
+            sort_by.sort(&mut rows, context)?;
+        }
+
+        if let Some(columns) = &self.distinct_on {
+            retain_first(&mut rows, |row| row.values_of(columns));
+        }
+
+        let mut rows = rows
+            .iter()
+            .map(|row| self.scope.apply(row, result_columns, context))
+            .collect::<QlResult<Vec<_>>>()?;
+
+        if self.distinct {
+            retain_first(&mut rows, |row| {
+                let mut values = row
+                    .column_values
+                    .iter()
+                    .map(|column| (column.key().clone(), column.value().clone()))
+                    .collect::<Vec<_>>();
+                values.sort_by(|(left, _), (right, _)| left.cmp(right));
+
+                values
+            });
         }
+
+        Ok(rows)
+    }
+}
+
+impl QueryRow for Select {
+    fn execute(mut self, cache: &QlCache) -> QlResult<Vec<CacheTableRow>> {
+        let snapshot = self.snapshot.take().unwrap_or_else(|| cache.snapshot());
+
+        self.run(&QueryContext::new(cache, snapshot.timestamp))
     }
 }
 
-impl<'row, T: FromRow<'row>> QueryAsType<'row, T> for Select {
+// the rows are dropped when the query returns, so `T` cannot borrow from them
+impl<T: for<'any> FromRow<'any>> QueryAsType<'_, T> for Select {
     fn execute_as(self, cache: &QlCache) -> QlResult<Vec<T>> {
-        todo!()
+        self.execute(cache)?.iter().map(T::from_row).collect()
     }
 }
 
@@ -326,12 +765,263 @@ impl<'row, T: FromRow<'row>> QueryAsType<'row, T> for Select {
 /// ```
 ///
 /// Pass in `SortOrdering::Ascending` if you want to sort the items in ascending order instead.
+///
+/// Case 4: Aggregates
+/// - *count* and *sum* (`SELECT COUNT(*) AS count, SUM(Field2) AS total FROM TableName`):
+/// ```
+/// use qlcache::ql::{
+///     aggregate::{
+///         Aggregate,
+///         AggregateFunction
+///     },
+///     select::SelectScope,
+///     QueryBuilder
+/// };
+///
+/// let select = QueryBuilder::select()
+///     .table_name(String::from("TableName"))
+///     .scope(SelectScope::Aggregates(vec![
+///         Aggregate::count_all(String::from("count")),
+///         Aggregate::new(String::from("total"), AggregateFunction::Sum, String::from("Field2"))
+///     ]))
+///     .build()
+///     .unwrap();
+/// ```
+///
+/// The result is a single row with a column for each aggregate, even if no rows are selected.
+///
+/// - *group by* and *having* (`SELECT GuildId, ChannelId, COUNT(*) AS count FROM Messages
+///   GROUP BY GuildId, ChannelId HAVING count > 100`):
+/// ```
+/// use qlcache::{
+///     ql::{
+///         aggregate::Aggregate,
+///         constraints::{
+///             Constraint,
+///             ConstraintOp
+///         },
+///         select::SelectScope,
+///         QueryBuilder
+///     },
+///     ColumnValue
+/// };
+///
+/// let select = QueryBuilder::select()
+///     .table_name(String::from("Messages"))
+///     .scope(SelectScope::Aggregates(vec![Aggregate::count_all(String::from("count"))]))
+///     .group_by(vec![String::from("GuildId"), String::from("ChannelId")])
+///     .having(Box::new(Constraint::builder()
+///         .field_name(String::from("count"))
+///         .op(ConstraintOp::Gt)
+///         .value(ColumnValue::U64(100))
+///         .build()
+///         .unwrap()))
+///     .build()
+///     .unwrap();
+/// ```
+///
+/// The rows of the groups have the grouped columns in addition to the aggregates.
+///
+/// Case 5: Distinct Rows
+/// - the *latest* message per channel (`SELECT DISTINCT ON (ChannelId) * FROM Messages
+///   SORT BY MessageId DESCENDING`):
+/// ```
+/// use qlcache::ql::{
+///     select::SelectScope,
+///     sortby::{
+///         SortBy,
+///         SortOrdering
+///     },
+///     QueryBuilder
+/// };
+///
+/// let select = QueryBuilder::select()
+///     .table_name(String::from("Messages"))
+///     .scope(SelectScope::Everything)
+///     .distinct_on(vec![String::from("ChannelId")])
+///     .sort_by(SortBy::new(
+///         vec![String::from("MessageId")],
+///         Some(SortOrdering::Descending)).unwrap())
+///     .build()
+///     .unwrap();
+/// ```
+///
+/// Use `distinct` instead to only return one of the rows with equal values in the selected
+/// columns.
+///
+/// Case 6: Joins
+/// - *inner join* (`SELECT Users.Name, Members.Nickname FROM Users INNER JOIN Guild Members ON
+///   Users.Id = Members.UserId`):
+/// ```
+/// use qlcache::ql::{
+///     constraints::ConstraintOp,
+///     join::{
+///         Join,
+///         JoinKind
+///     },
+///     select::SelectScope,
+///     QueryBuilder
+/// };
+///
+/// let select = QueryBuilder::select()
+///     .table_name(String::from("Users"))
+///     .join(Join::new(JoinKind::Inner, String::from("Guild Members")).on(
+///         String::from("Users.Id"),
+///         ConstraintOp::Eq,
+///         String::from("Members.UserId")
+///     ))
+///     .scope(SelectScope::Fields(vec![
+///         String::from("Users.Name"),
+///         String::from("Members.Nickname")
+///     ]))
+///     .build()
+///     .unwrap();
+/// ```
+///
+/// Case 7: Computed Columns
+/// - *expressions* with aliases (`SELECT lower(Name) AS n, Xp * 2 AS doubled FROM Users
+///   SORT BY length(Name)`):
+/// ```
+/// use qlcache::{
+///     ql::{
+///         expression::Expression,
+///         function::ScalarFunction,
+///         select::SelectScope,
+///         sortby::SortBy,
+///         QueryBuilder
+///     },
+///     ColumnValue
+/// };
+///
+/// let name = Expression::column(String::from("Name"));
+/// let select = QueryBuilder::select()
+///     .table_name(String::from("Users"))
+///     .scope(SelectScope::Expressions(vec![
+///         (
+///             String::from("n"),
+///             Expression::function(ScalarFunction::Lower, vec![name.clone()])
+///         ),
+///         (
+///             String::from("doubled"),
+///             Expression::column(String::from("Xp")) * ColumnValue::U64(2)
+///         )
+///     ]))
+///     .sort_by(SortBy::expressions(
+///         vec![Expression::function(ScalarFunction::Length, vec![name])],
+///         None
+///     ).unwrap())
+///     .build()
+///     .unwrap();
+/// ```
+///
+/// - *extracted parts* of a JSON column (`SELECT Id, Data->'a'->>'b' AS b FROM Events`):
+/// ```
+/// use qlcache::ql::{
+///     expression::Expression,
+///     extract::Extraction,
+///     select::SelectScope,
+///     QueryBuilder
+/// };
+///
+/// let select = QueryBuilder::select()
+///     .table_name(String::from("Events"))
+///     .scope(SelectScope::Expressions(vec![
+///         (String::from("Id"), Expression::column(String::from("Id"))),
+///         (
+///             String::from("b"),
+///             Expression::column(String::from("Data"))
+///                 .extract(Extraction::Field(String::from("a")))
+///                 .extract(Extraction::FieldText(String::from("b")))
+///         )
+///     ]))
+///     .build()
+///     .unwrap();
+/// ```
+///
+/// Case 8: Subqueries
+/// - a *scalar subquery* correlated to the selected row (`SELECT Name, (SELECT count(*) FROM
+///   Posts WHERE Posts.AuthorId = Users.Id) AS posts FROM Users`):
+/// ```
+/// use qlcache::ql::{
+///     aggregate::Aggregate,
+///     constraints::{
+///         ConstraintOp,
+///         ExpressionConstraint
+///     },
+///     expression::Expression,
+///     select::SelectScope,
+///     QueryBuilder
+/// };
+///
+/// let posts = QueryBuilder::select()
+///     .table_name(String::from("Posts"))
+///     .scope(SelectScope::Aggregates(vec![Aggregate::count_all(String::from("count"))]))
+///     .constraint(Box::new(ExpressionConstraint::new(
+///         Expression::column(String::from("AuthorId")),
+///         ConstraintOp::Eq,
+///         Expression::outer(String::from("Id"))
+///     )))
+///     .build()
+///     .unwrap();
+///
+/// let select = QueryBuilder::select()
+///     .table_name(String::from("Users"))
+///     .scope(SelectScope::Expressions(vec![
+///         (String::from("Name"), Expression::column(String::from("Name"))),
+///         (String::from("posts"), Expression::subquery(posts))
+///     ]))
+///     .build()
+///     .unwrap();
+/// ```
+///
+/// Case 9: Window Functions
+/// - a *leaderboard* of each guild (`SELECT UserId, GuildId, RANK() OVER (PARTITION BY GuildId
+///   ORDER BY Xp DESC) AS rank FROM Members SORT BY rank`):
+/// ```
+/// use qlcache::ql::{
+///     select::SelectScope,
+///     sortby::{
+///         SortBy,
+///         SortOrdering
+///     },
+///     window::{
+///         Window,
+///         WindowFunction
+///     },
+///     QueryBuilder
+/// };
+///
+/// let select = QueryBuilder::select()
+///     .table_name(String::from("Members"))
+///     .window(Window::new(String::from("rank"), WindowFunction::Rank)
+///         .partition_by(vec![String::from("GuildId")])
+///         .order_by(SortBy::new(
+///             vec![String::from("Xp")],
+///             Some(SortOrdering::Descending)
+///         ).unwrap()))
+///     .scope(SelectScope::Fields(vec![
+///         String::from("UserId"),
+///         String::from("GuildId"),
+///         String::from("rank")
+///     ]))
+///     .sort_by(SortBy::new(vec![String::from("rank")], None).unwrap())
+///     .build()
+///     .unwrap();
+/// ```
 #[allow(clippy::module_name_repetitions)]
 pub struct SelectBuilder {
     pub(crate) table_name: Option<String>,
+    pub(crate) alias: Option<String>,
+    pub(crate) joins: Vec<Join>,
     pub(crate) scope: Option<SelectScope>,
     pub(crate) constraint: Option<BoxedConstraint>,
-    pub(crate) sort_by: Option<SortBy>
+    pub(crate) group_by: Option<Vec<String>>,
+    pub(crate) having: Option<BoxedConstraint>,
+    pub(crate) sort_by: Option<SortBy>,
+    pub(crate) distinct: bool,
+    pub(crate) distinct_on: Option<Vec<String>>,
+    pub(crate) windows: Vec<Window>,
+    pub(crate) snapshot: Option<Snapshot>
 }
 
 impl SelectBuilder {
@@ -347,6 +1037,32 @@ impl SelectBuilder {
         self
     }
 
+    /// # Instance Method `SelectBuilder::alias`
+    ///
+    /// Sets the name to qualify the columns of the table with if the selection has joins.
+    ///
+    /// ## Parameters
+    /// - `alias`, type `String`; the alias of the table
+    #[must_use]
+    pub fn alias(mut self, alias: String) -> Self {
+        self.alias.replace(alias);
+        self
+    }
+
+    /// # Instance Method `SelectBuilder::join`
+    ///
+    /// Adds a table to join with the tables of the selection, in order. If the selection has
+    /// joins, the columns in the scope, constraints, groups and sorting are qualified with the
+    /// names of their tables, as described in [`join`](crate::ql::join).
+    ///
+    /// ## Parameters
+    /// - `join`, type `Join`; the join to add
+    #[must_use]
+    pub fn join(mut self, join: Join) -> Self {
+        self.joins.push(join);
+        self
+    }
+
     /// # Instance Method `SelectBuilder::scope`
     ///
     /// Sets the selection scope for the selection.
@@ -421,6 +1137,34 @@ impl SelectBuilder {
         Ok(self)
     }
 
+    /// # Instance Method `SelectBuilder::group_by`
+    ///
+    /// Sets the columns to group the selected rows by. Each group of rows with equal values in the
+    /// columns results in a single row, with the values of the columns and of the aggregates of the
+    /// scope; other columns cannot be selected.
+    ///
+    /// ## Parameters
+    /// - `columns`, type `Vec<String>`; the columns to group by
+    #[must_use]
+    pub fn group_by(mut self, columns: Vec<String>) -> Self {
+        self.group_by.replace(columns);
+        self
+    }
+
+    /// # Instance Method `SelectBuilder::having`
+    ///
+    /// Sets a constraint for the groups of the selection, which is computed against the row of
+    /// each group: the columns of the constraint are the grouped columns, and the aggregates by
+    /// their names.
+    ///
+    /// ## Parameters
+    /// - `constraint`, type `BoxedConstraint`; the constraint to set
+    #[must_use]
+    pub fn having(mut self, constraint: BoxedConstraint) -> Self {
+        self.having.replace(constraint);
+        self
+    }
+
     /// # Instance Method `SelectBuilder::or`
     ///
     /// Configures how results are sorted.
@@ -433,13 +1177,63 @@ impl SelectBuilder {
         self
     }
 
+    /// # Instance Method `SelectBuilder::distinct`
+    ///
+    /// Sets the selection to only return one of the rows with equal values in all of the selected
+    /// columns (`DISTINCT`).
+    #[must_use]
+    pub fn distinct(mut self) -> Self {
+        self.distinct = true;
+        self
+    }
+
+    /// # Instance Method `SelectBuilder::distinct_on`
+    ///
+    /// Sets the selection to only return the first row, in the sort order, of the rows with equal
+    /// values in the columns (`DISTINCT ON`). The columns do not need to be selected.
+    ///
+    /// ## Parameters
+    /// - `columns`, type `Vec<String>`; the columns of which the values are to be distinct
+    #[must_use]
+    pub fn distinct_on(mut self, columns: Vec<String>) -> Self {
+        self.distinct_on.replace(columns);
+        self
+    }
+
+    /// # Instance Method `SelectBuilder::window`
+    ///
+    /// Adds a window function, which is a column of the selected rows that can be selected and
+    /// sorted by.
+    ///
+    /// ## Parameters
+    /// - `window`, type `Window`; the window function to add
+    #[must_use]
+    pub fn window(mut self, window: Window) -> Self {
+        self.windows.push(window);
+        self
+    }
+
+    /// # Instance Method `SelectBuilder::snapshot`
+    ///
+    /// Sets the snapshot to run the selection against. If no snapshot is set, a snapshot is taken
+    /// when the query is executed.
+    ///
+    /// ## Parameters
+    /// - `snapshot`, type `Snapshot`; the snapshot to read from
+    #[must_use]
+    pub fn snapshot(mut self, snapshot: Snapshot) -> Self {
+        self.snapshot.replace(snapshot);
+        self
+    }
+
     /// # Instance Method `SelectBuilder::build`
     ///
     /// Consumes the builder and returns a `Query<Select>`.
     ///
     /// ## Errors
     ///
-    /// Returns `RequiredFieldIsNone` if any of the fields required is `None`.
+    /// Returns `RequiredFieldIsNone` if any of the fields required is `None`; returns
+    /// `VecCannotBeEmpty` if the `GROUP BY` or `DISTINCT ON` columns are set but empty.
     #[allow(clippy::missing_panics_doc)] // this function never panics
     pub fn build(self) -> QlResult<Query<Select>> {
         if self.table_name.is_none() {
@@ -454,25 +1248,47 @@ impl SelectBuilder {
             });
         }
 
+        if matches!(&self.group_by, Some(group_by) if group_by.is_empty()) {
+            return Err(QlError::VecCannotBeEmpty {
+                vec_name: String::from("SelectBuilder.group_by")
+            });
+        }
+
+        if matches!(&self.distinct_on, Some(distinct_on) if distinct_on.is_empty()) {
+            return Err(QlError::VecCannotBeEmpty {
+                vec_name: String::from("SelectBuilder.distinct_on")
+            });
+        }
+
         Ok(Query {
             query: Select {
                 table_name: self.table_name.unwrap(),
+                alias: self.alias,
+                joins: self.joins,
                 scope: self.scope.unwrap(),
                 constraint: self.constraint,
-                sort_by: self.sort_by
+                group_by: self.group_by,
+                having: self.having,
+                sort_by: self.sort_by,
+                distinct: self.distinct,
+                distinct_on: self.distinct_on,
+                windows: self.windows,
+                snapshot: self.snapshot
             }
         })
     }
 
     /// # Instance Method `SelectBuilder::build_as`
     ///
-    /// Consumes the builder and returns a `QueryAs<Select, T>`.
+    /// Consumes the builder and returns a `QueryAs<Select, T>`. The rows are converted to `T` by
+    /// [`FromRow`], so `T` cannot borrow from them.
     ///
     /// ## Errors
     ///
-    /// Returns `RequiredFieldIsNone` if any of the fields required is `None`.
+    /// Returns `RequiredFieldIsNone` if any of the fields required is `None`; returns
+    /// `VecCannotBeEmpty` if the `GROUP BY` or `DISTINCT ON` columns are set but empty.
     #[allow(clippy::missing_panics_doc)] // this function never panics
-    pub fn build_as<'row, T: FromRow<'row>>(self) -> QlResult<QueryAs<'row, Select, T>> {
+    pub fn build_as<'row, T: for<'any> FromRow<'any>>(self) -> QlResult<QueryAs<'row, Select, T>> {
         if self.table_name.is_none() {
             return Err(QlError::RequiredFieldIsNone {
                 field_name: String::from("SelectBuilder.table_name")
@@ -485,12 +1301,32 @@ impl SelectBuilder {
             });
         }
 
+        if matches!(&self.group_by, Some(group_by) if group_by.is_empty()) {
+            return Err(QlError::VecCannotBeEmpty {
+                vec_name: String::from("SelectBuilder.group_by")
+            });
+        }
+
+        if matches!(&self.distinct_on, Some(distinct_on) if distinct_on.is_empty()) {
+            return Err(QlError::VecCannotBeEmpty {
+                vec_name: String::from("SelectBuilder.distinct_on")
+            });
+        }
+
         Ok(QueryAs {
             query: Select {
                 table_name: self.table_name.unwrap(),
+                alias: self.alias,
+                joins: self.joins,
                 scope: self.scope.unwrap(),
                 constraint: self.constraint,
-                sort_by: self.sort_by
+                group_by: self.group_by,
+                having: self.having,
+                sort_by: self.sort_by,
+                distinct: self.distinct,
+                distinct_on: self.distinct_on,
+                windows: self.windows,
+                snapshot: self.snapshot
             },
             phantom: PhantomData
         })
@@ -501,11 +1337,138 @@ impl SelectBuilder {
 ///
 /// The scope for the `SELECT` query.
 #[allow(clippy::module_name_repetitions)]
-#[allow(missing_docs)] // variants are pretty self explanatory?
 #[derive(Clone, Eq, PartialEq)]
 pub enum SelectScope {
+    /// # Enumeration Variant `SelectScope::Everything`
+    ///
+    /// `SELECT *`, all the columns of the selected rows, or the grouped columns, aggregates and
+    /// windows if the rows are grouped.
     Everything,
-    Fields(Vec<String>)
+
+    /// # Enumeration Variant `SelectScope::Fields`
+    ///
+    /// `SELECT a, b`, the named columns of the selected rows.
+    Fields(Vec<String>),
+
+    /// # Enumeration Variant `SelectScope::Aggregates`
+    ///
+    /// The grouped columns and the aggregates computed over each group, or over all the selected
+    /// rows if the query has no `GROUP BY` clause.
+    Aggregates(Vec<Aggregate>),
+
+    /// # Enumeration Variant `SelectScope::Expressions`
+    ///
+    /// `SELECT expression AS name, ...`, a column computed for each selected row per
+    /// `(name, expression)` pair, such as a function of columns or a part extracted from a column
+    /// with [`Expression::extract`]. Numbers are converted to the type of their column.
+    Expressions(Vec<(String, Expression)>)
+}
+
+impl SelectScope {
+    /// Returns the columns the scope selects from, or refers to in expressions.
+    fn columns(&self) -> Vec<&String> {
+        match self {
+            Self::Everything | Self::Aggregates(_) => Vec::new(),
+            Self::Fields(fields) => fields.iter().collect(),
+            Self::Expressions(expressions) => expressions
+                .iter()
+                .flat_map(|(_, expression)| expression.columns())
+                .collect()
+        }
+    }
+
+    /// Checks the expressions of the scope against the columns of the rows they are computed on.
+    fn validate(
+        &self,
+        columns: &DashMap<String, (ColumnDataType, bool)>,
+        context: &QueryContext<'_>
+    ) -> QlResult<()> {
+        match self {
+            Self::Expressions(expressions) => expressions
+                .iter()
+                .try_for_each(|(_, expression)| {
+                    expression.data_type(columns, context).map(|_| ())
+                }),
+            _ => Ok(())
+        }
+    }
+
+    /// Returns the selected columns of a row, which are the columns `result_columns`. The values of
+    /// numeric expressions are converted to the types of their columns, as the values of their
+    /// operands may be of narrower types, such as the arguments of `coalesce`.
+    fn apply(
+        &self,
+        row: &CacheTableRow,
+        result_columns: &ResultColumns,
+        context: &QueryContext<'_>
+    ) -> QlResult<CacheTableRow> {
+        let row = match self {
+            Self::Fields(fields) => {
+                CacheTableRow {
+                    column_values: row
+                        .column_values
+                        .iter()
+                        .filter(|column| fields.contains(column.key()))
+                        .map(|column| (column.key().clone(), column.value().clone()))
+                        .collect()
+                }
+            }
+            Self::Expressions(expressions) => {
+                CacheTableRow {
+                    column_values: expressions
+                        .iter()
+                        .zip(result_columns)
+                        .map(|((name, expression), (_, data_type))| {
+                            let value = expression.evaluate(row, context)?;
+                            let value = match (Number::of(&value), data_type) {
+                                (Some(number), Some(data_type)) if data_type.is_numeric() => {
+                                    number.to_value(data_type).unwrap_or(value)
+                                }
+                                _ => value
+                            };
+
+                            Ok((name.clone(), value))
+                        })
+                        .collect::<QlResult<_>>()?
+                }
+            }
+            Self::Everything | Self::Aggregates(_) => row.clone()
+        };
+
+        Ok(row)
+    }
+}
+
+/// Keeps only the rows that satisfy a constraint, where `row` returns the row of an item. Fails
+/// with the first error of the constraint.
+pub(crate) fn retain_satisfying<T>(
+    items: &mut Vec<T>,
+    constraint: &BoxedConstraint,
+    row: impl Fn(&T) -> &CacheTableRow,
+    context: &QueryContext<'_>
+) -> QlResult<()> {
+    let mut result = Ok(());
+    items.retain(|item| {
+        if result.is_err() {
+            return false;
+        }
+
+        match constraint.compute_row(row(item), context) {
+            Ok(satisfied) => satisfied == Some(true),
+            Err(error) => {
+                result = Err(error);
+                false
+            }
+        }
+    });
+
+    result
+}
+
+/// Keeps only the first of the rows with equal keys.
+pub(crate) fn retain_first<T, K: Eq + Hash>(rows: &mut Vec<T>, key: impl Fn(&T) -> K) {
+    let mut seen = HashSet::new();
+    rows.retain(|row| seen.insert(key(row)));
 }
 
 #[cfg(test)]
@@ -518,9 +1481,39 @@ mod tests {
         SelectScope
     };
     use crate::{
-        error::QlResult,
+        error::{
+            QlError,
+            QlResult
+        },
+        ql::{
+            aggregate::{
+                Aggregate,
+                AggregateFunction
+            },
+            constraints::{
+                AndConstraint,
+                BoxedConstraint,
+                Constraint,
+                ConstraintOp
+            },
+            key::PrimaryKey,
+            sortby::{
+                SortBy,
+                SortOrdering
+            },
+            QueryBuilder,
+            QueryContext
+        },
+        testing,
+        types::{
+            FromEnumValue,
+            Uuid
+        },
         CacheTableRow,
-        FromRow
+        ColumnDataType,
+        ColumnValue,
+        FromRow,
+        QlCache
     };
 
     struct Dummy;
@@ -531,6 +1524,532 @@ mod tests {
         }
     }
 
+    #[derive(Debug, Eq, PartialEq)]
+    enum InfractionKind {
+        Warn,
+        Ban
+    }
+
+    impl FromEnumValue for InfractionKind {
+        fn from_label(label: &str) -> Option<Self> {
+            match label {
+                "warn" => Some(Self::Warn),
+                "ban" => Some(Self::Ban),
+                _ => None
+            }
+        }
+    }
+
+    #[derive(Debug, Eq, PartialEq)]
+    struct Infraction {
+        user_id: u64,
+        kind: InfractionKind
+    }
+
+    impl<'row> FromRow<'row> for Infraction {
+        fn from_row(row: &'row CacheTableRow) -> QlResult<Self> {
+            let mismatch = |name: &str| {
+                QlError::ColumnTypeMismatch {
+                    name: name.to_string()
+                }
+            };
+
+            let user_id = match row.column_values.get("UserId").as_deref() {
+                Some(ColumnValue::U64(user_id)) => *user_id,
+                _ => return Err(mismatch("UserId"))
+            };
+            let kind = match row.column_values.get("Kind").as_deref() {
+                Some(ColumnValue::Enum(kind)) => kind.to_enum()?,
+                _ => return Err(mismatch("Kind"))
+            };
+
+            Ok(Self {
+                user_id,
+                kind
+            })
+        }
+    }
+
+    /// Returns a cache with the type `InfractionKind ('warn', 'mute', 'ban')` and the table
+    /// `Infractions (UserId U64, Kind InfractionKind)` of the rows `(1, 'ban')` and `(2, 'warn')`.
+    fn cache(kinds: [&str; 2]) -> QlCache {
+        let cache = QlCache::new();
+        QueryBuilder::create()
+            .enum_type()
+            .name(String::from("InfractionKind"))
+            .labels(vec![String::from("warn"), String::from("mute"), String::from("ban")])
+            .build()
+            .unwrap()
+            .execute(&cache)
+            .unwrap();
+        let kind_type = cache.enum_type("InfractionKind").unwrap();
+        testing::table(
+            &cache,
+            "Infractions",
+            [
+                ("UserId", ColumnDataType::U64, false),
+                ("Kind", ColumnDataType::Enum(String::from("InfractionKind")), false)
+            ],
+            (1..).zip(kinds).map(|(user_id, kind)| {
+                [ColumnValue::U64(user_id), kind_type.value(kind).unwrap()]
+            })
+        );
+
+        cache
+    }
+
+    #[test]
+    fn rows_are_mapped_to_rust_types() {
+        let cache = cache(["ban", "warn"]);
+        let infractions = QueryBuilder::select()
+            .table_name(String::from("Infractions"))
+            .scope(SelectScope::Everything)
+            .sort_by(SortBy::new(vec![String::from("Kind")], None).unwrap())
+            .build_as::<Infraction>()
+            .unwrap();
+
+        assert_eq!(cache.execute_as(infractions).unwrap(), vec![
+            Infraction {
+                user_id: 2,
+                kind: InfractionKind::Warn
+            },
+            Infraction {
+                user_id: 1,
+                kind: InfractionKind::Ban
+            }
+        ]);
+    }
+
+    #[test]
+    fn labels_without_variants_fail_the_mapping() {
+        let cache = cache(["ban", "mute"]);
+        let infractions = QueryBuilder::select()
+            .table_name(String::from("Infractions"))
+            .scope(SelectScope::Everything)
+            .build_as::<Infraction>()
+            .unwrap();
+
+        assert!(matches!(
+            cache.execute_as(infractions),
+            Err(QlError::InvalidEnumValue { label, .. }) if label == "mute"
+        ));
+    }
+
+    /// Returns a cache with the table `Messages (ChannelId U64, Length U32 NULL)` of the rows
+    /// `(1, 10)`, `(2, 5)`, `(1, 30)`, `(3, NULL)`, `(2, 7)` and `(3, NULL)`.
+    fn messages() -> QlCache {
+        let cache = QlCache::new();
+        testing::table(
+            &cache,
+            "Messages",
+            [("ChannelId", ColumnDataType::U64, false), ("Length", ColumnDataType::U32, true)],
+            [(1, Some(10)), (2, Some(5)), (1, Some(30)), (3, None), (2, Some(7)), (3, None)].map(
+                |(channel_id, length)| {
+                    [
+                        ColumnValue::U64(channel_id),
+                        length.map_or(ColumnValue::Null, ColumnValue::U32)
+                    ]
+                }
+            )
+        );
+
+        cache
+    }
+
+    /// `SELECT COUNT(*) AS Count, SUM(Length) AS Total FROM Messages`, with the specified
+    /// `GROUP BY` columns and `HAVING` constraint, ordered by the `GROUP BY` columns.
+    fn totals(group_by: &[&str], having: Option<BoxedConstraint>) -> Vec<Vec<(String, String)>> {
+        let cache = messages();
+        let group_by = group_by.iter().map(|column| String::from(*column)).collect::<Vec<_>>();
+
+        let mut select = QueryBuilder::select()
+            .table_name(String::from("Messages"))
+            .scope(SelectScope::Aggregates(vec![
+                Aggregate::count_all(String::from("Count")),
+                Aggregate::new(
+                    String::from("Total"),
+                    AggregateFunction::Sum,
+                    String::from("Length")
+                )
+            ]));
+        if !group_by.is_empty() {
+            select = select
+                .group_by(group_by.clone())
+                .sort_by(SortBy::new(group_by, None).unwrap());
+        }
+        if let Some(having) = having {
+            select = select.having(having);
+        }
+
+        select
+            .build()
+            .unwrap()
+            .execute(&cache)
+            .unwrap()
+            .iter()
+            .map(|row| {
+                let mut values = row
+                    .column_values
+                    .iter()
+                    .map(|column| (column.key().clone(), column.value().to_string()))
+                    .collect::<Vec<_>>();
+                values.sort();
+
+                values
+            })
+            .collect()
+    }
+
+    fn group(values: &[(&str, &str)]) -> Vec<(String, String)> {
+        values
+            .iter()
+            .map(|(column, value)| (String::from(*column), String::from(*value)))
+            .collect()
+    }
+
+    #[test]
+    fn rows_are_grouped_by_the_values_of_the_group_by_columns() {
+        assert_eq!(totals(&["ChannelId"], None), vec![
+            group(&[("ChannelId", "1"), ("Count", "2"), ("Total", "40")]),
+            group(&[("ChannelId", "2"), ("Count", "2"), ("Total", "12")]),
+            group(&[("ChannelId", "3"), ("Count", "2"), ("Total", "NULL")])
+        ]);
+
+        // every row is in a single group without `GROUP BY` columns
+        assert_eq!(totals(&[], None), vec![group(&[("Count", "6"), ("Total", "52")])]);
+    }
+
+    #[test]
+    fn groups_are_filtered_by_having_on_their_aggregates() {
+        let having = Constraint::builder()
+            .field_name(String::from("Total"))
+            .op(ConstraintOp::Gt)
+            .value(ColumnValue::U64(20))
+            .build()
+            .unwrap();
+        assert_eq!(totals(&["ChannelId"], Some(Box::new(having))), vec![group(&[
+            ("ChannelId", "1"),
+            ("Count", "2"),
+            ("Total", "40")
+        ])]);
+
+        // the single group without `GROUP BY` columns is filtered as well
+        let having = Constraint::builder()
+            .field_name(String::from("Count"))
+            .op(ConstraintOp::Gt)
+            .value(ColumnValue::U64(6))
+            .build()
+            .unwrap();
+        assert_eq!(totals(&[], Some(Box::new(having))), Vec::<Vec<_>>::new());
+    }
+
+    #[test]
+    fn only_grouped_columns_can_be_selected() {
+        let cache = messages();
+        let select = |column: &str| {
+            QueryBuilder::select()
+                .table_name(String::from("Messages"))
+                .scope(SelectScope::Fields(vec![String::from(column)]))
+                .group_by(vec![String::from("ChannelId")])
+                .build()
+                .unwrap()
+                .execute(&cache)
+        };
+
+        assert_eq!(select("ChannelId").unwrap().len(), 3);
+        assert!(matches!(
+            select("Length"),
+            Err(QlError::ColumnNotGrouped { name }) if name == "Length"
+        ));
+    }
+
+    /// `SELECT columns FROM Messages`, with the specified `DISTINCT ON` columns and `ORDER BY`
+    /// columns and order, and `DISTINCT` if `distinct_on` is empty.
+    fn distinct(
+        columns: &[&str],
+        distinct_on: &[&str],
+        sort_by: Option<(&[&str], SortOrdering)>
+    ) -> SelectBuilder {
+        let names = |columns: &[&str]| {
+            columns
+                .iter()
+                .map(|column| String::from(*column))
+                .collect::<Vec<_>>()
+        };
+
+        let mut select = QueryBuilder::select()
+            .table_name(String::from("Messages"))
+            .scope(SelectScope::Fields(names(columns)));
+        select = if distinct_on.is_empty() {
+            select.distinct()
+        }
+        else {
+            select.distinct_on(names(distinct_on))
+        };
+        if let Some((columns, order)) = sort_by {
+            select = select.sort_by(SortBy::new(names(columns), Some(order)).unwrap());
+        }
+
+        select
+    }
+
+    /// Returns the values of the rows in the order of the columns, as text.
+    fn values(rows: &[CacheTableRow], columns: &[&str]) -> Vec<Vec<String>> {
+        rows.iter()
+            .map(|row| {
+                columns
+                    .iter()
+                    .map(|column| row.column_values.get(*column).unwrap().to_string())
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn row(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| String::from(*value)).collect()
+    }
+
+    #[test]
+    fn distinct_keeps_one_of_the_equal_selected_rows() {
+        let cache = messages();
+        let by_channel = Some((&["ChannelId"][..], SortOrdering::Ascending));
+        let channels = distinct(&["ChannelId"], &[], by_channel)
+            .build()
+            .unwrap()
+            .execute(&cache)
+            .unwrap();
+        assert_eq!(values(&channels, &["ChannelId"]), [row(&["1"]), row(&["2"]), row(&["3"])]);
+
+        // rows are equal if all their selected values are, and `null` is equal to `null` here
+        let columns = ["ChannelId", "Length"];
+        let rows = distinct(&columns, &[], Some((&columns, SortOrdering::Ascending)))
+            .build()
+            .unwrap()
+            .execute(&cache)
+            .unwrap();
+        assert_eq!(values(&rows, &columns), [
+            row(&["1", "10"]),
+            row(&["1", "30"]),
+            row(&["2", "5"]),
+            row(&["2", "7"]),
+            row(&["3", "NULL"])
+        ]);
+    }
+
+    #[test]
+    fn distinct_on_keeps_the_first_row_of_each_key_in_the_sort_order() {
+        let cache = messages();
+        let columns = ["ChannelId", "Length"];
+        let first = |order| {
+            let rows = distinct(&columns, &["ChannelId"], Some((&["Length"], order)))
+                .build()
+                .unwrap()
+                .execute(&cache)
+                .unwrap();
+
+            values(&rows, &columns)
+        };
+
+        // the longest message of each channel, where `null` sorts first in descending order
+        assert_eq!(first(SortOrdering::Descending), [
+            row(&["3", "NULL"]),
+            row(&["1", "30"]),
+            row(&["2", "7"])
+        ]);
+
+        // the shortest message of each channel, where `null` sorts last in ascending order
+        assert_eq!(first(SortOrdering::Ascending), [
+            row(&["2", "5"]),
+            row(&["1", "10"]),
+            row(&["3", "NULL"])
+        ]);
+
+        // the `DISTINCT ON` columns need not be selected
+        let longest_first = Some((&["Length"][..], SortOrdering::Descending));
+        let lengths = distinct(&["Length"], &["ChannelId"], longest_first)
+            .build()
+            .unwrap()
+            .execute(&cache)
+            .unwrap();
+        assert_eq!(values(&lengths, &["Length"]), [row(&["NULL"]), row(&["30"]), row(&["7"])]);
+    }
+
+    #[test]
+    fn limits_apply_to_the_distinct_rows() {
+        let cache = messages();
+        let columns = ["ChannelId", "Length"];
+
+        // `... UNION ALL SELECT ChannelId, Length FROM Messages WHERE ChannelId = 0 LIMIT 2`
+        let limited = |select: SelectBuilder| {
+            let none = QueryBuilder::select()
+                .table_name(String::from("Messages"))
+                .scope(SelectScope::Fields(vec![
+                    String::from("ChannelId"),
+                    String::from("Length")
+                ]))
+                .constraint(Box::new(
+                    Constraint::builder()
+                        .field_name(String::from("ChannelId"))
+                        .op(ConstraintOp::Eq)
+                        .value(ColumnValue::U64(0))
+                        .build()
+                        .unwrap()
+                ))
+                .build()
+                .unwrap();
+            let rows = QueryBuilder::compound()
+                .select(select.build().unwrap())
+                .union_all(none)
+                .limit(2)
+                .build()
+                .unwrap()
+                .execute(&cache)
+                .unwrap();
+
+            values(&rows, &columns)
+        };
+
+        let longest_first = Some((&["Length"][..], SortOrdering::Descending));
+        assert_eq!(limited(distinct(&columns, &["ChannelId"], longest_first)), [
+            row(&["3", "NULL"]),
+            row(&["1", "30"])
+        ]);
+
+        // the duplicate row `(3, NULL)` is removed before the limit is applied
+        assert_eq!(
+            limited(distinct(&columns, &[], Some((&columns, SortOrdering::Descending)))),
+            [row(&["3", "NULL"]), row(&["2", "7"])]
+        );
+    }
+
+    #[test]
+    fn rows_are_selected_by_their_primary_key() {
+        let cache = QlCache::new();
+        QueryBuilder::create()
+            .table()
+            .name(String::from("Sessions"))
+            .columns(vec![
+                (String::from("Id"), (ColumnDataType::Uuid, false)),
+                (String::from("Active"), (ColumnDataType::Bool, false)),
+            ])
+            .primary_key(PrimaryKey::new(String::from("Id")))
+            .unwrap()
+            .build()
+            .unwrap()
+            .execute(&cache)
+            .unwrap();
+
+        let id_is = |id: u128| {
+            Constraint::builder()
+                .field_name(String::from("Id"))
+                .op(ConstraintOp::Eq)
+                .value(ColumnValue::Uuid(Uuid(id)))
+                .build()
+                .unwrap()
+        };
+        let select = |constraint: BoxedConstraint, snapshot| {
+            let select = QueryBuilder::select()
+                .table_name(String::from("Sessions"))
+                .scope(SelectScope::Fields(vec![String::from("Id")]))
+                .constraint(constraint);
+            let select = match snapshot {
+                Some(snapshot) => select.snapshot(snapshot),
+                None => select
+            };
+
+            select
+                .build()
+                .unwrap()
+                .execute(&cache)
+                .unwrap()
+                .iter()
+                .map(|row| row.column_values.get("Id").unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let session = |id: u128, active: bool| {
+            [ColumnValue::Uuid(Uuid(id)), ColumnValue::Bool(active)]
+        };
+        let columns = ["Id", "Active"];
+        testing::insert_rows(&cache, "Sessions", columns, [session(1, true), session(2, false)]);
+
+        let snapshot = cache.snapshot();
+        let timestamp = snapshot.timestamp();
+        let mut transaction = cache.begin();
+        let row = testing::row(columns.into_iter().zip(session(3, true)));
+        transaction.insert_row(String::from("Sessions"), 1, row).unwrap();
+        transaction.commit().unwrap();
+
+        let first = Uuid(1).to_string();
+        let third = Uuid(3).to_string();
+        assert_eq!(select(Box::new(id_is(1)), None), Vec::<String>::new());
+        assert_eq!(select(Box::new(id_is(3)), None), [third]);
+
+        // the index no longer knows the row that had the value at the snapshot, which is scanned
+        // for instead
+        assert_eq!(select(Box::new(id_is(1)), Some(snapshot)), [first]);
+        let lookup = |timestamp, id| {
+            QueryContext::new(&cache, timestamp)
+                .relation_by_primary_key("Sessions", "Id", &ColumnValue::Uuid(Uuid(id)))
+                .unwrap()
+                .map(|(_, rows)| rows.len())
+        };
+        assert_eq!(lookup(timestamp, 1), None);
+        assert_eq!(lookup(cache.snapshot().timestamp(), 1), Some(0));
+        assert_eq!(lookup(cache.snapshot().timestamp(), 3), Some(1));
+
+        // the rest of the constraint still applies to the row that is looked up
+        let inactive = Constraint::builder()
+            .field_name(String::from("Active"))
+            .op(ConstraintOp::Eq)
+            .value(ColumnValue::Bool(false))
+            .build()
+            .unwrap();
+        let and = |id| AndConstraint::new(Box::new(id_is(id)), Box::new(inactive.clone()));
+        assert_eq!(select(Box::new(and(3)), None), Vec::<String>::new());
+        assert_eq!(select(Box::new(and(2)), None), [Uuid(2).to_string()]);
+        let third = cache
+            .with_table("Sessions", |table| table.row_by_primary_key(&ColumnValue::Uuid(Uuid(3))))
+            .unwrap();
+        assert_eq!(third.map(|(key, _)| key), Some(1));
+    }
+
+    #[test]
+    fn primary_keys_are_looked_up_by_numbers_of_any_type() {
+        let cache = QlCache::new();
+        QueryBuilder::create()
+            .table()
+            .name(String::from("Channels"))
+            .columns(vec![(String::from("Id"), (ColumnDataType::U64, false))])
+            .primary_key(PrimaryKey::new(String::from("Id")))
+            .unwrap()
+            .build()
+            .unwrap()
+            .execute(&cache)
+            .unwrap();
+        cache
+            .with_table("Channels", |table| {
+                table.insert_row(1, testing::row([("Id", ColumnValue::U64(5))]))
+            })
+            .unwrap()
+            .unwrap();
+
+        let context = QueryContext::new(&cache, cache.snapshot().timestamp());
+        let lookup = |value| {
+            context
+                .relation_by_primary_key("Channels", "Id", &value)
+                .unwrap()
+                .map(|(_, rows)| rows.len())
+        };
+        assert_eq!(lookup(ColumnValue::I32(5)), Some(1));
+        assert_eq!(lookup(ColumnValue::F64(5.0)), Some(1));
+        assert_eq!(lookup(ColumnValue::I8(-5)), Some(0));
+
+        // values that cannot be equal to the key leave the table to be scanned
+        assert_eq!(lookup(ColumnValue::String(String::from("5"))), None);
+        assert_eq!(lookup(ColumnValue::Null), None);
+    }
+
     const _: fn() = || {
         fn assert_impl_all<'row, T: ?Sized + QueryAsType<'row, Dummy> + QueryRow + Send + Sync>() {}
         assert_impl_all::<Select>();
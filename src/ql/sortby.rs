@@ -1,11 +1,29 @@
 //! # Module `sortby`
 //!
 //! This module implements `SORT BY`, where items are sorted with the columns provided, as well as
-//! the order to sort for, whether it is ascending or descending order.
+//! the order to sort for, whether it is ascending or descending order. Items can also be sorted
+//! with expressions computed from their columns.
 
-use crate::error::{
-    QlError,
-    QlResult
+use std::{
+    cmp::Ordering,
+    sync::Arc
+};
+
+use dashmap::DashMap;
+
+use crate::{
+    error::{
+        QlError,
+        QlResult
+    },
+    ql::{
+        expression::Expression,
+        extract::Extraction,
+        QueryContext
+    },
+    CacheTableRow,
+    ColumnDataType,
+    ColumnValue
 };
 
 /// # Struct `SortBy`
@@ -13,7 +31,7 @@ use crate::error::{
 /// Sort items according to a certain column and a certain order.
 #[allow(dead_code)]
 pub struct SortBy {
-    pub(crate) columns: Vec<String>,
+    pub(crate) keys: Vec<Expression>,
     pub(crate) order: SortOrdering
 }
 
@@ -33,10 +51,122 @@ impl SortBy {
         }
 
         Ok(Self {
-            columns,
+            keys: columns.into_iter().map(Expression::Column).collect(),
             order: order.unwrap_or(SortOrdering::Ascending)
         })
     }
+
+    /// # Constructor `SortBy::expressions`
+    ///
+    /// Constructs a new `SortBy` of expressions, such as `SORT BY lower(Name)`.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `VecCannotBeEmpty` if the `Vec` of the `keys` parameter is empty.
+    pub fn expressions(keys: Vec<Expression>, order: Option<SortOrdering>) -> QlResult<Self> {
+        if keys.is_empty() {
+            return Err(QlError::VecCannotBeEmpty {
+                vec_name: String::from("Sortby.keys")
+            });
+        }
+
+        Ok(Self {
+            keys,
+            order: order.unwrap_or(SortOrdering::Ascending)
+        })
+    }
+
+    /// # Instance Method `SortBy::extract`
+    ///
+    /// Sorts by a part of the values of the sort keys, such as the creation time of a
+    /// `Snowflake`, instead of by the values themselves. Values that do not have the part are
+    /// sorted as `null`.
+    ///
+    /// ## Parameters
+    /// - `extraction`, type `impl Into<Extraction>`; the part of the values to sort by
+    ///
+    /// ## Example
+    ///
+    /// - `SORT BY extract(increment FROM AuthorId)`:
+    /// ```
+    /// use qlcache::{
+    ///     ql::sortby::SortBy,
+    ///     types::SnowflakePart
+    /// };
+    ///
+    /// let sort_by = SortBy::new(vec![String::from("AuthorId")], None)
+    ///     .unwrap()
+    ///     .extract(SnowflakePart::Increment);
+    /// ```
+    #[must_use]
+    pub fn extract(mut self, extraction: impl Into<Extraction>) -> Self {
+        let extraction = extraction.into();
+        self.keys = self
+            .keys
+            .into_iter()
+            .map(|key| key.extract(extraction.clone()))
+            .collect();
+        self
+    }
+
+    /// Returns the columns the sort keys refer to.
+    pub(crate) fn columns(&self) -> impl Iterator<Item = &String> {
+        self.keys.iter().flat_map(Expression::columns)
+    }
+
+    /// Returns the names of the tables and views the subqueries of the sort keys read.
+    pub(crate) fn relations(&self) -> impl Iterator<Item = &str> {
+        self.keys.iter().flat_map(Expression::relations)
+    }
+
+    /// Checks the sort keys against the columns of the rows to sort.
+    pub(crate) fn validate(
+        &self,
+        columns: &DashMap<String, (ColumnDataType, bool)>,
+        context: &QueryContext<'_>
+    ) -> QlResult<()> {
+        self.keys
+            .iter()
+            .try_for_each(|key| key.data_type(columns, context).map(|_| ()))
+    }
+
+    /// Returns the values of the sort keys of a row.
+    pub(crate) fn key(
+        &self,
+        row: &CacheTableRow,
+        context: &QueryContext<'_>
+    ) -> QlResult<Vec<ColumnValue>> {
+        self.keys
+            .iter()
+            .map(|key| key.evaluate(row, context))
+            .collect()
+    }
+
+    /// Compares the values of the sort keys of two rows in the order of the sort.
+    pub(crate) fn compare(&self, left: &[ColumnValue], right: &[ColumnValue]) -> Ordering {
+        match self.order {
+            SortOrdering::Ascending => left.cmp(right),
+            SortOrdering::Descending => right.cmp(left)
+        }
+    }
+
+    /// Sorts rows by the sort keys in turn, which are evaluated once per row. `null` values are
+    /// sorted after every other value in ascending order, and before every other value in
+    /// descending order. Rows with equal keys keep their order.
+    pub(crate) fn sort(
+        &self,
+        rows: &mut Vec<Arc<CacheTableRow>>,
+        context: &QueryContext<'_>
+    ) -> QlResult<()> {
+        let mut keyed = rows
+            .drain(..)
+            .map(|row| Ok((self.key(&row, context)?, row)))
+            .collect::<QlResult<Vec<_>>>()?;
+        keyed.sort_by(|(left, _), (right, _)| self.compare(left, right));
+
+        rows.extend(keyed.into_iter().map(|(_, row)| row));
+        Ok(())
+    }
 }
 
 /// # Struct `SortOrdering`
@@ -55,7 +185,61 @@ mod tests {
         SortBy,
         SortOrdering
     };
+    use crate::{
+        ql::{
+            select::SelectScope,
+            QueryBuilder
+        },
+        testing,
+        types::{
+            Snowflake,
+            SnowflakePart
+        },
+        ColumnDataType,
+        ColumnValue,
+        QlCache
+    };
 
     static_assertions::assert_impl_all!(SortBy: Send, Sync);
     static_assertions::assert_impl_all!(SortOrdering: Clone, Eq, PartialEq, Send, Sync);
+
+    #[test]
+    fn rows_are_sorted_by_extracted_snowflake_parts() {
+        let cache = QlCache::new();
+        // the creation times and the increments of the authors are in different orders
+        testing::table(
+            &cache,
+            "Messages",
+            [("Key", ColumnDataType::U64, false), ("AuthorId", ColumnDataType::Snowflake, false)],
+            (1..).zip([(3, 1), (1, 3), (2, 2)]).map(|(key, (millis, increment))| {
+                let author_id = Snowflake(millis << 22 | increment);
+                [ColumnValue::U64(key), ColumnValue::Snowflake(author_id)]
+            })
+        );
+
+        let sorted = |part: SnowflakePart| {
+            let sort_by = SortBy::new(vec![String::from("AuthorId")], None)
+                .unwrap()
+                .extract(part);
+            QueryBuilder::select()
+                .table_name(String::from("Messages"))
+                .scope(SelectScope::Fields(vec![String::from("Key")]))
+                .sort_by(sort_by)
+                .build()
+                .unwrap()
+                .execute(&cache)
+                .unwrap()
+                .iter()
+                .map(|row| {
+                    match row.column_values.get("Key").as_deref() {
+                        Some(ColumnValue::U64(key)) => *key,
+                        _ => unreachable!()
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(sorted(SnowflakePart::Timestamp), [2, 3, 1]);
+        assert_eq!(sorted(SnowflakePart::Increment), [1, 3, 2]);
+    }
 }
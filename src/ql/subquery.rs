@@ -0,0 +1,276 @@
+//! # Module `subquery`
+//!
+//! This module implements subqueries, `SELECT` queries used inside the constraints and expressions
+//! of another query.
+//!
+//! A subquery reads the same snapshot as the query it is used in, and is executed for each row
+//! it is computed against, so that it can refer to the columns of that row with
+//! [`Expression::outer`](crate::ql::expression::Expression::outer).
+
+use std::{
+    fmt::{
+        Debug,
+        Formatter,
+        Result as FmtResult
+    },
+    sync::Arc
+};
+
+use dashmap::DashMap;
+
+use crate::{
+    error::{
+        QlError,
+        QlResult
+    },
+    ql::{
+        select::{
+            ResultColumns,
+            Select
+        },
+        Query,
+        QueryContext
+    },
+    CacheTableRow,
+    ColumnDataType,
+    ColumnValue
+};
+
+/// # Struct `Subquery`
+///
+/// A `SELECT` query used inside another query, in an
+/// [`ExistsConstraint`](crate::ql::constraints::ExistsConstraint), an
+/// [`InConstraint`](crate::ql::constraints::InConstraint) or as a scalar
+/// [`Expression::Subquery`](crate::ql::expression::Expression::Subquery).
+///
+/// The snapshot of the query is ignored, as the subquery reads the snapshot of the query it is
+/// used in.
+#[derive(Clone)]
+pub struct Subquery {
+    pub(crate) select: Arc<Select>
+}
+
+impl Subquery {
+    /// # Constructor `Subquery::new`
+    ///
+    /// Constructs a new `Subquery` of a `SELECT` query.
+    #[must_use]
+    pub fn new(query: Query<Select>) -> Self {
+        Self {
+            select: Arc::new(query.query)
+        }
+    }
+
+    /// Checks the subquery against the columns of the query it is used in, and returns the
+    /// columns of its result.
+    pub(crate) fn validate(
+        &self,
+        columns: &DashMap<String, (ColumnDataType, bool)>,
+        context: &QueryContext<'_>
+    ) -> QlResult<ResultColumns> {
+        self.select.result_columns(&context.nested(Some(columns), None))
+    }
+
+    /// Checks the subquery against the columns of the query it is used in, and returns the type
+    /// of the only column of its result.
+    pub(crate) fn column_type(
+        &self,
+        columns: &DashMap<String, (ColumnDataType, bool)>,
+        context: &QueryContext<'_>
+    ) -> QlResult<Option<ColumnDataType>> {
+        let mut result_columns = self.validate(columns, context)?;
+        if result_columns.len() != 1 {
+            return Err(QlError::SubqueryColumnCount {
+                count: result_columns.len()
+            });
+        }
+
+        Ok(result_columns.remove(0).1)
+    }
+
+    /// Executes the subquery for a row of the query it is used in.
+    pub(crate) fn execute(
+        &self,
+        row: &CacheTableRow,
+        context: &QueryContext<'_>
+    ) -> QlResult<Vec<CacheTableRow>> {
+        self.select.run(&context.nested(None, Some(row)))
+    }
+
+    /// Returns the values of the only column of the result of the subquery for a row.
+    pub(crate) fn values(
+        &self,
+        row: &CacheTableRow,
+        context: &QueryContext<'_>
+    ) -> QlResult<Vec<ColumnValue>> {
+        Ok(self
+            .execute(row, context)?
+            .iter()
+            .map(only_value)
+            .collect())
+    }
+
+    /// Returns the value of the only column of the only row of the result of the subquery for a
+    /// row, or `null` if there is no row. A scalar subquery that returns more than one row fails
+    /// with `SubqueryRowCount`.
+    pub(crate) fn value(
+        &self,
+        row: &CacheTableRow,
+        context: &QueryContext<'_>
+    ) -> QlResult<ColumnValue> {
+        match self.execute(row, context)?.as_slice() {
+            [] => Ok(ColumnValue::Null),
+            [result] => Ok(only_value(result)),
+            results => {
+                Err(QlError::SubqueryRowCount {
+                    count: results.len()
+                })
+            }
+        }
+    }
+}
+
+impl Debug for Subquery {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("Subquery")
+            .field("table_name", &self.select.table_name)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Subqueries are equal if they are clones of the same subquery.
+impl PartialEq for Subquery {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.select, &other.select)
+    }
+}
+
+impl Eq for Subquery {}
+
+impl From<Query<Select>> for Subquery {
+    fn from(query: Query<Select>) -> Self {
+        Self::new(query)
+    }
+}
+
+fn only_value(row: &CacheTableRow) -> ColumnValue {
+    row.column_values
+        .iter()
+        .next()
+        .map_or(ColumnValue::Null, |value| value.value().clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Debug;
+
+    use super::Subquery;
+    use crate::{
+        error::QlError,
+        ql::{
+            constraints::{
+                ConstraintOp,
+                ExistsConstraint,
+                ExpressionConstraint
+            },
+            expression::Expression,
+            select::SelectScope,
+            QueryBuilder
+        },
+        testing,
+        ColumnDataType,
+        ColumnValue,
+        QlCache
+    };
+
+    static_assertions::assert_impl_all!(Subquery: Clone, Debug, Eq, PartialEq, Send, Sync);
+
+    fn cache() -> QlCache {
+        let cache = QlCache::new();
+        testing::table(
+            &cache,
+            "Members",
+            [("UserId", ColumnDataType::U64, false)],
+            [[ColumnValue::U64(1)], [ColumnValue::U64(2)]]
+        );
+        testing::table(
+            &cache,
+            "Posts",
+            [("AuthorId", ColumnDataType::U64, false)],
+            [[ColumnValue::U64(1)], [ColumnValue::U64(1)]]
+        );
+
+        cache
+    }
+
+    /// `SELECT AuthorId FROM Posts WHERE AuthorId = outer.UserId`
+    fn posts() -> Subquery {
+        Subquery::new(
+            QueryBuilder::select()
+                .table_name(String::from("Posts"))
+                .scope(SelectScope::Fields(vec![String::from("AuthorId")]))
+                .constraint(Box::new(ExpressionConstraint::new(
+                    Expression::column(String::from("AuthorId")),
+                    ConstraintOp::Eq,
+                    Expression::outer(String::from("UserId"))
+                )))
+                .build()
+                .unwrap()
+        )
+    }
+
+    #[test]
+    fn scalar_subqueries_are_null_without_rows_and_fail_with_many() {
+        let cache = cache();
+        let select = |key| {
+            QueryBuilder::select()
+                .table_name(String::from("Members"))
+                .scope(SelectScope::Expressions(vec![(
+                    String::from("post"),
+                    Expression::subquery(posts())
+                )]))
+                .constraint(Box::new(ExpressionConstraint::new(
+                    Expression::column(String::from("UserId")),
+                    ConstraintOp::Eq,
+                    Expression::value(ColumnValue::U64(key))
+                )))
+                .build()
+                .unwrap()
+                .execute(&cache)
+        };
+
+        let rows = select(2).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(*rows[0].column_values.get("post").unwrap(), ColumnValue::Null);
+
+        // member 1 wrote two posts, which a scalar subquery cannot return
+        assert!(matches!(select(1), Err(QlError::SubqueryRowCount { count: 2 })));
+    }
+
+    #[test]
+    fn subquery_errors_fail_the_enclosing_query() {
+        let cache = cache();
+        let result = QueryBuilder::select()
+            .table_name(String::from("Members"))
+            .scope(SelectScope::Everything)
+            .constraint(Box::new(ExpressionConstraint::new(
+                Expression::subquery(posts()),
+                ConstraintOp::Eq,
+                Expression::value(ColumnValue::U64(1))
+            )))
+            .build()
+            .unwrap()
+            .execute(&cache);
+        assert!(matches!(result, Err(QlError::SubqueryRowCount { count: 2 })));
+
+        let rows = QueryBuilder::select()
+            .table_name(String::from("Members"))
+            .scope(SelectScope::Everything)
+            .constraint(Box::new(ExistsConstraint::new(posts())))
+            .build()
+            .unwrap()
+            .execute(&cache)
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+    }
+}
@@ -0,0 +1,362 @@
+//! # Module `update`
+//!
+//! This module implements the `UPDATE` query of the query language.
+
+use std::time::Duration;
+
+use crate::{
+    error::{
+        QlError,
+        QlResult
+    },
+    ql::{
+        Query,
+        QueryRow
+    },
+    CacheTableRow,
+    ColumnValue,
+    QlCache
+};
+
+/// # Struct `Update`
+///
+/// An `UPDATE` query.
+pub struct Update {
+    pub(crate) table_name: String,
+    pub(crate) key: u64,
+    pub(crate) values: Vec<(String, ColumnValue)>,
+    pub(crate) version: Option<u64>,
+    pub(crate) ttl: Option<Duration>
+}
+
+impl Update {
+    #[must_use]
+    pub(crate) fn builder() -> UpdateBuilder {
+        UpdateBuilder {
+            table_name: None,
+            key: None,
+            values: Vec::new(),
+            version: None,
+            ttl: None
+        }
+    }
+}
+
+impl QueryRow for Update {
+    fn execute(self, cache: &QlCache) -> QlResult<Vec<CacheTableRow>> {
+        let mut updated = None;
+
+        cache.with_table(&self.table_name, |table| {
+            // a row that does not exist is not updated, so nothing is committed
+            if table.row_version(self.key).is_none() {
+                return match self.version {
+                    Some(expected) if expected != 0 => Err(QlError::RowVersionConflict {
+                        key: self.key,
+                        expected,
+                        found: None
+                    }),
+                    _ => Ok(())
+                };
+            }
+
+            table.commit_row(self.key, self.version, self.ttl, |latest| {
+                let row = if let Some(row) = latest {
+                    row.clone()
+                }
+                else {
+                    return Ok(None);
+                };
+
+                self.values.into_iter().for_each(|(column, value)| {
+                    row.column_values.insert(column, value);
+                });
+                table.validate_row(&row)?;
+
+                updated.replace(row.clone());
+                Ok(Some(row))
+            })?;
+
+            Ok(())
+        })??;
+
+        Ok(updated.into_iter().collect())
+    }
+}
+
+/// # Struct `UpdateBuilder`
+///
+/// A builder for an `Update`, constructs an `UPDATE` query. The updated row is returned.
+///
+/// ## Examples
+///
+/// - `UPDATE TableName SET Field1 = 2138 WHERE KEY = 1`:
+/// ```
+/// use qlcache::{
+///     ql::QueryBuilder,
+///     ColumnValue
+/// };
+///
+/// let update = QueryBuilder::update()
+///     .table_name(String::from("TableName"))
+///     .key(1)
+///     .set(String::from("Field1"), ColumnValue::I32(2138))
+///     .build()
+///     .unwrap();
+/// ```
+///
+/// - `UPDATE TableName SET Field1 = 2138 WHERE KEY = 1 AND VERSION = 5`, fails with
+///   `RowVersionConflict` if the row has been changed since version `5`:
+/// ```
+/// use qlcache::{
+///     ql::QueryBuilder,
+///     ColumnValue
+/// };
+///
+/// let update = QueryBuilder::update()
+///     .table_name(String::from("TableName"))
+///     .key(1)
+///     .set(String::from("Field1"), ColumnValue::I32(2138))
+///     .version(5)
+///     .build()
+///     .unwrap();
+/// ```
+#[allow(clippy::module_name_repetitions)]
+pub struct UpdateBuilder {
+    pub(crate) table_name: Option<String>,
+    pub(crate) key: Option<u64>,
+    pub(crate) values: Vec<(String, ColumnValue)>,
+    pub(crate) version: Option<u64>,
+    pub(crate) ttl: Option<Duration>
+}
+
+impl UpdateBuilder {
+    /// # Instance Method `UpdateBuilder::table_name`
+    ///
+    /// Sets the table name for the update.
+    ///
+    /// ## Parameters
+    /// - `table_name`, type `String`; the table name to set
+    #[must_use]
+    pub fn table_name(mut self, table_name: String) -> Self {
+        self.table_name.replace(table_name);
+        self
+    }
+
+    /// # Instance Method `UpdateBuilder::key`
+    ///
+    /// Sets the key of the row to update.
+    ///
+    /// ## Parameters
+    /// - `key`, type `u64`; the key of the row
+    #[must_use]
+    pub fn key(mut self, key: u64) -> Self {
+        self.key.replace(key);
+        self
+    }
+
+    /// # Instance Method `UpdateBuilder::set`
+    ///
+    /// Adds a column to set to a new value.
+    ///
+    /// ## Parameters
+    /// - `column`, type `String`; the name of the column
+    /// - `value`, type `ColumnValue`; the new value of the column
+    #[must_use]
+    pub fn set(mut self, column: String, value: ColumnValue) -> Self {
+        self.values.push((column, value));
+        self
+    }
+
+    /// # Instance Method `UpdateBuilder::version`
+    ///
+    /// Only updates the row if its version number is still the specified version.
+    ///
+    /// ## Parameters
+    /// - `version`, type `u64`; the expected version number of the row
+    #[must_use]
+    pub fn version(mut self, version: u64) -> Self {
+        self.version.replace(version);
+        self
+    }
+
+    /// # Instance Method `UpdateBuilder::ttl`
+    ///
+    /// Sets a new time-to-live for the row, counting from the update. Otherwise, the row keeps its
+    /// expiry time.
+    ///
+    /// ## Parameters
+    /// - `ttl`, type `Duration`; the time-to-live of the row
+    #[must_use]
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl.replace(ttl);
+        self
+    }
+
+    /// # Instance Method `UpdateBuilder::build`
+    ///
+    /// Consumes the builder and returns a `Query<Update>`.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `RequiredFieldIsNone` if any of the fields required is `None`; returns
+    /// `VecCannotBeEmpty` if no column is set.
+    #[allow(clippy::missing_panics_doc)] // this function never panics
+    pub fn build(self) -> QlResult<Query<Update>> {
+        if self.table_name.is_none() {
+            return Err(QlError::RequiredFieldIsNone {
+                field_name: String::from("UpdateBuilder.table_name")
+            });
+        }
+
+        if self.key.is_none() {
+            return Err(QlError::RequiredFieldIsNone {
+                field_name: String::from("UpdateBuilder.key")
+            });
+        }
+
+        if self.values.is_empty() {
+            return Err(QlError::VecCannotBeEmpty {
+                vec_name: String::from("UpdateBuilder.values")
+            });
+        }
+
+        Ok(Query {
+            query: Update {
+                table_name: self.table_name.unwrap(),
+                key: self.key.unwrap(),
+                values: self.values,
+                version: self.version,
+                ttl: self.ttl
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        QueryRow,
+        Update,
+        UpdateBuilder
+    };
+    use crate::{
+        error::QlError,
+        ql::QueryBuilder,
+        testing,
+        CacheTableRow,
+        ColumnDataType,
+        ColumnValue,
+        QlCache
+    };
+
+    static_assertions::assert_impl_all!(Update: QueryRow, Send, Sync);
+    static_assertions::assert_impl_all!(UpdateBuilder: Send, Sync);
+
+    fn cache() -> QlCache {
+        let cache = QlCache::new();
+        testing::table(&cache, "Members", [("Xp", ColumnDataType::U64, false)], []);
+
+        cache
+    }
+
+    fn xp(xp: u64) -> CacheTableRow {
+        testing::row([("Xp", ColumnValue::U64(xp))])
+    }
+
+    #[test]
+    fn compare_and_swap_rejects_rows_changed_since_the_expected_version() {
+        let cache = cache();
+
+        let first = cache.compare_and_swap("Members", 1, 0, xp(1)).unwrap();
+        assert!(matches!(
+            cache.compare_and_swap("Members", 1, 0, xp(2)),
+            Err(QlError::RowVersionConflict { key: 1, expected: 0, found: Some(found) })
+                if found == first
+        ));
+        let second = cache.compare_and_swap("Members", 1, first, xp(2)).unwrap();
+        assert!(matches!(
+            cache.compare_and_swap("Members", 1, first, xp(3)),
+            Err(QlError::RowVersionConflict { found: Some(found), .. }) if found == second
+        ));
+
+        let update = |version| {
+            QueryBuilder::update()
+                .table_name(String::from("Members"))
+                .key(1)
+                .set(String::from("Xp"), ColumnValue::U64(4))
+                .version(version)
+                .build()
+                .unwrap()
+                .execute(&cache)
+        };
+        assert!(matches!(update(first), Err(QlError::RowVersionConflict { .. })));
+        update(second).unwrap();
+
+        // version numbers are not reused after the row is removed and inserted again
+        let removed = cache
+            .with_table("Members", |table| {
+                let version = table.row_version(1).unwrap();
+                assert!(table.remove_row(1));
+                version
+            })
+            .unwrap();
+        assert!(matches!(
+            cache.compare_and_swap("Members", 1, removed, xp(5)),
+            Err(QlError::RowVersionConflict { found: None, .. })
+        ));
+        let inserted = cache.compare_and_swap("Members", 1, 0, xp(5)).unwrap();
+        assert!(inserted > removed);
+        let row = cache.with_table("Members", |table| table.row(1)).unwrap().unwrap();
+        assert!(matches!(row.column_values.get("Xp").as_deref(), Some(ColumnValue::U64(5))));
+    }
+
+    #[test]
+    fn only_one_of_concurrent_swaps_from_a_version_succeeds() {
+        let cache = cache();
+        let version = cache.compare_and_swap("Members", 1, 0, xp(0)).unwrap();
+
+        let swapped = std::thread::scope(|scope| {
+            let swaps = (1..=8)
+                .map(|value| {
+                    let cache = &cache;
+                    scope.spawn(move || {
+                        cache.compare_and_swap("Members", 1, version, xp(value)).is_ok()
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            swaps
+                .into_iter()
+                .map(|swap| swap.join().unwrap())
+                .filter(|swapped| *swapped)
+                .count()
+        });
+        assert_eq!(swapped, 1);
+    }
+
+    #[test]
+    fn updating_a_missing_row_commits_nothing() {
+        let cache = cache();
+        let timestamp = cache.clock.timestamp();
+        let update = |version: Option<u64>| {
+            let mut builder = QueryBuilder::update()
+                .table_name(String::from("Members"))
+                .key(1)
+                .set(String::from("Xp"), ColumnValue::U64(1));
+            if let Some(version) = version {
+                builder = builder.version(version);
+            }
+
+            builder.build().unwrap().execute(&cache)
+        };
+
+        assert!(update(None).unwrap().is_empty());
+        assert!(update(Some(0)).unwrap().is_empty());
+        assert!(matches!(
+            update(Some(1)),
+            Err(QlError::RowVersionConflict { key: 1, expected: 1, found: None })
+        ));
+        assert_eq!(cache.clock.timestamp(), timestamp);
+        assert!(cache.with_table("Members", |table| table.row(1)).unwrap().is_none());
+    }
+}
@@ -0,0 +1,269 @@
+//! # Module `view`
+//!
+//! This module implements views, `SELECT` queries stored in a schema under a name, which can be
+//! selected from like tables.
+//!
+//! A view stores its query rather than its rows, and is resolved each time a query reads it, so
+//! that it always reflects the rows of the snapshot the query reads.
+
+use std::sync::Arc;
+
+use crate::{
+    error::QlResult,
+    ql::{
+        select::Select,
+        with::NamedResult,
+        QueryContext
+    },
+    ColumnDataType,
+    QlCache
+};
+
+/// # Struct `View`
+///
+/// A view of a schema (`CREATE VIEW name AS SELECT ...`), which is created with
+/// [`CreateBuilder::view`](crate::ql::create::CreateBuilder::view) and dropped with
+/// [`DropBuilder::view`](crate::ql::drop::DropBuilder::view).
+///
+/// A view is read like a table by naming it in
+/// [`SelectBuilder::table_name`](crate::ql::select::SelectBuilder::table_name), and shadows a
+/// table of the same name. Its rows have no keys, so they cannot be updated.
+#[derive(Clone)]
+pub struct View {
+    /// # Struct Field `name`
+    ///
+    /// The name of the view.
+    pub name: String,
+    pub(crate) select: Arc<Select>
+}
+
+impl View {
+    /// # Instance Method `View::table_name`
+    ///
+    /// Returns the name of the table, or the view, the query of the view selects from.
+    #[must_use]
+    pub fn table_name(&self) -> &str {
+        &self.select.table_name
+    }
+
+    /// # Instance Method `View::columns`
+    ///
+    /// Returns the names of the columns of the view in order, with their types if the query of
+    /// the view knows them. The type of an extracted value depends on the values it is
+    /// extracted from.
+    ///
+    /// ## Parameters
+    /// - `cache`, type `&QlCache`; the cache of the view
+    ///
+    /// ## Errors
+    ///
+    /// Returns `RelationDoesNotExist` if a relation the view reads has been dropped, and other
+    /// query-related errors if the query of the view is no longer valid.
+    pub fn columns(&self, cache: &QlCache) -> QlResult<Vec<(String, Option<ColumnDataType>)>> {
+        let snapshot = cache.snapshot();
+
+        self.select
+            .result_columns(&QueryContext::new(cache, snapshot.timestamp))
+    }
+
+    /// Executes the query of the view in a context, and returns its result.
+    pub(crate) fn evaluate(&self, context: &QueryContext<'_>) -> QlResult<NamedResult> {
+        // the view reads the snapshot of the query, but not its named results
+        let context = context.detached();
+        let columns = self.select.result_columns(&context)?;
+        let rows = self
+            .select
+            .run(&context)?
+            .into_iter()
+            .map(Arc::new)
+            .collect();
+
+        Ok(NamedResult::new(&columns, rows))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::View;
+    use crate::{
+        error::{
+            QlError,
+            QlResult,
+            QueryError
+        },
+        ql::{
+            constraints::ExistsConstraint,
+            select::{
+                Select,
+                SelectScope
+            },
+            subquery::Subquery,
+            Query,
+            QueryBuilder
+        },
+        testing,
+        ColumnDataType,
+        QlCache
+    };
+
+    static_assertions::assert_impl_all!(View: Clone, Send, Sync);
+
+    fn cache() -> QlCache {
+        let cache = QlCache::new();
+        testing::table(&cache, "Members", [("UserId", ColumnDataType::U64, false)], []);
+
+        cache
+    }
+
+    /// `SELECT * FROM table_name`
+    fn select(table_name: &str) -> Query<Select> {
+        QueryBuilder::select()
+            .table_name(String::from(table_name))
+            .scope(SelectScope::Everything)
+            .build()
+            .unwrap()
+    }
+
+    fn create_view(cache: &QlCache, name: &str, select: Query<Select>) {
+        try_create_view(cache, name, select).unwrap();
+    }
+
+    fn try_create_view(cache: &QlCache, name: &str, select: Query<Select>) -> QlResult<()> {
+        QueryBuilder::create()
+            .view()
+            .name(String::from(name))
+            .select(select)
+            .build()
+            .unwrap()
+            .execute(cache)
+            .map(|_| ())
+    }
+
+    fn drop_view(cache: &QlCache, name: &str) -> Result<(), Option<String>> {
+        let result = QueryBuilder::drop()
+            .view()
+            .name(String::from(name))
+            .build()
+            .unwrap()
+            .execute(cache);
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(QlError::QueryError(QueryError::RelationHasDependents {
+                dependent, ..
+            })) => Err(Some(dependent)),
+            Err(_) => Err(None)
+        }
+    }
+
+    #[test]
+    fn views_cannot_be_dropped_while_other_views_read_them() {
+        let cache = cache();
+        create_view(&cache, "Everyone", select("Members"));
+        create_view(&cache, "Copies", select("Everyone"));
+
+        // the materialized view only reads the view in a subquery
+        let subquery = QueryBuilder::select()
+            .table_name(String::from("Members"))
+            .scope(SelectScope::Everything)
+            .constraint(Box::new(ExistsConstraint::new(Subquery::new(select("Everyone")))))
+            .build()
+            .unwrap();
+        QueryBuilder::create()
+            .materialized_view()
+            .name(String::from("Snapshot"))
+            .select(subquery)
+            .build()
+            .unwrap()
+            .execute(&cache)
+            .unwrap();
+
+        assert!(matches!(drop_view(&cache, "Everyone"), Err(Some(_))));
+        drop_view(&cache, "Copies").unwrap();
+        assert_eq!(drop_view(&cache, "Everyone"), Err(Some(String::from("Snapshot"))));
+
+        QueryBuilder::drop()
+            .materialized_view()
+            .name(String::from("Snapshot"))
+            .build()
+            .unwrap()
+            .execute(&cache)
+            .unwrap();
+        drop_view(&cache, "Everyone").unwrap();
+        assert!(cache.view("Everyone").is_err());
+    }
+
+    #[test]
+    fn stored_views_do_not_hold_the_snapshots_of_their_queries() {
+        let cache = cache();
+        let with_snapshot = || {
+            QueryBuilder::select()
+                .table_name(String::from("Members"))
+                .scope(SelectScope::Everything)
+                .snapshot(cache.snapshot())
+                .build()
+                .unwrap()
+        };
+
+        create_view(&cache, "Everyone", with_snapshot());
+        QueryBuilder::create()
+            .materialized_view()
+            .name(String::from("Snapshot"))
+            .select(with_snapshot())
+            .build()
+            .unwrap()
+            .execute(&cache)
+            .unwrap();
+
+        assert_eq!(cache.clock.active_snapshots(), 0);
+    }
+
+    #[test]
+    fn views_are_never_left_reading_views_dropped_concurrently() {
+        let cache = cache();
+
+        for _ in 0..50 {
+            create_view(&cache, "Everyone", select("Members"));
+
+            let (dropped, created) = std::thread::scope(|scope| {
+                let dropped = scope.spawn(|| drop_view(&cache, "Everyone").is_ok());
+                let created = scope.spawn(|| try_create_view(&cache, "Copies", select("Everyone")));
+
+                (dropped.join().unwrap(), created.join().unwrap().is_ok())
+            });
+
+            // whichever statement runs second sees the result of the first
+            assert_ne!(dropped, created);
+            assert_eq!(cache.view("Everyone").is_ok(), created);
+            if created {
+                drop_view(&cache, "Copies").unwrap();
+                drop_view(&cache, "Everyone").unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn only_one_of_concurrent_relations_of_a_name_is_created() {
+        let cache = cache();
+
+        for round in 0..50 {
+            let name = format!("Guilds{round}");
+            let created = std::thread::scope(|scope| {
+                let view = scope.spawn(|| try_create_view(&cache, &name, select("Members")));
+                let table = scope.spawn(|| {
+                    QueryBuilder::create()
+                        .table()
+                        .name(name.clone())
+                        .columns(vec![(String::from("Id"), (ColumnDataType::U64, false))])
+                        .build()
+                        .unwrap()
+                        .execute(&cache)
+                });
+
+                [view.join().unwrap().is_ok(), table.join().unwrap().is_ok()]
+            });
+
+            assert_eq!(created.iter().filter(|created| **created).count(), 1);
+        }
+    }
+}
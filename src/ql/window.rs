@@ -0,0 +1,535 @@
+//! # Module `window`
+//!
+//! This module implements window functions, which compute a value for each selected row from the
+//! rows of its partition, such as the rank of a member by experience in a guild, for use in
+//! `SELECT` queries.
+
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    slice,
+    sync::Arc
+};
+
+use dashmap::DashMap;
+
+use crate::{
+    error::{
+        QlError,
+        QlResult
+    },
+    ql::{
+        aggregate::{
+            Aggregate,
+            AggregateFunction
+        },
+        materialized::Group,
+        sortby::SortBy,
+        QueryContext
+    },
+    CacheTableRow,
+    ColumnDataType,
+    ColumnValue
+};
+
+/// # Enumeration `WindowFunction`
+///
+/// A window function, which is computed for a row from the rows of its partition in order.
+#[allow(clippy::module_name_repetitions)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum WindowFunction {
+    /// # Enumeration Variant `WindowFunction::RowNumber`
+    ///
+    /// `ROW_NUMBER()`, the position of the row in its partition from 1, as a `U64`.
+    RowNumber,
+
+    /// # Enumeration Variant `WindowFunction::Rank`
+    ///
+    /// `RANK()`, the position of the first row that is equal to the row in the order of its
+    /// partition from 1, as a `U64`. Equal rows have the same rank, and leave gaps after it.
+    Rank,
+
+    /// # Enumeration Variant `WindowFunction::DenseRank`
+    ///
+    /// `DENSE_RANK()`, the number of distinct rows up to the row in the order of its partition, as
+    /// a `U64`. Equal rows have the same rank, without gaps after it.
+    DenseRank,
+
+    /// # Enumeration Variant `WindowFunction::Lag`
+    ///
+    /// `LAG(column, offset)`, the value of a column in the row `offset` rows before the row in
+    /// its partition, or `null` if there is none.
+    Lag(String, usize),
+
+    /// # Enumeration Variant `WindowFunction::Lead`
+    ///
+    /// `LEAD(column, offset)`, the value of a column in the row `offset` rows after the row in
+    /// its partition, or `null` if there is none.
+    Lead(String, usize),
+
+    /// # Enumeration Variant `WindowFunction::Aggregate`
+    ///
+    /// A running aggregate of a column, or `COUNT(*)` if there is no column, over the rows of the
+    /// partition up to the row and the rows equal to it, such as a running `SUM`. Without an
+    /// order, the aggregate is over the whole partition.
+    Aggregate(AggregateFunction, Option<String>)
+}
+
+/// # Struct `Window`
+///
+/// A window function over the partitions of the selected rows, which is a column of the rows
+/// that can be selected and sorted by. Windows are computed after the rows are filtered and
+/// grouped.
+///
+/// ## Examples
+///
+/// - the rank of members by experience in each guild (`RANK() OVER (PARTITION BY GuildId ORDER
+///   BY Xp DESC) AS rank`):
+/// ```
+/// use qlcache::ql::{
+///     sortby::{
+///         SortBy,
+///         SortOrdering
+///     },
+///     window::{
+///         Window,
+///         WindowFunction
+///     }
+/// };
+///
+/// let window = Window::new(String::from("rank"), WindowFunction::Rank)
+///     .partition_by(vec![String::from("GuildId")])
+///     .order_by(SortBy::new(vec![String::from("Xp")], Some(SortOrdering::Descending)).unwrap());
+/// ```
+///
+/// - the running total of experience by date (`SUM(Xp) OVER (ORDER BY Date) AS total`):
+/// ```
+/// use qlcache::ql::{
+///     aggregate::AggregateFunction,
+///     sortby::SortBy,
+///     window::{
+///         Window,
+///         WindowFunction
+///     }
+/// };
+///
+/// let window = Window::new(
+///     String::from("total"),
+///     WindowFunction::Aggregate(AggregateFunction::Sum, Some(String::from("Xp")))
+/// )
+/// .order_by(SortBy::new(vec![String::from("Date")], None).unwrap());
+/// ```
+#[allow(dead_code)]
+pub struct Window {
+    pub(crate) name: String,
+    pub(crate) function: WindowFunction,
+    pub(crate) partition_by: Vec<String>,
+    pub(crate) order_by: Option<SortBy>
+}
+
+impl Window {
+    /// # Constructor `Window::new`
+    ///
+    /// Constructs a new `Window` of a window function over all the selected rows, which is named
+    /// `name` in the result.
+    #[must_use]
+    pub fn new(name: String, function: WindowFunction) -> Self {
+        Self {
+            name,
+            function,
+            partition_by: Vec::new(),
+            order_by: None
+        }
+    }
+
+    /// # Instance Method `Window::partition_by`
+    ///
+    /// Sets the columns to partition the selected rows by (`PARTITION BY`).
+    ///
+    /// ## Parameters
+    /// - `columns`, type `Vec<String>`; the columns to partition by
+    #[must_use]
+    pub fn partition_by(mut self, columns: Vec<String>) -> Self {
+        self.partition_by = columns;
+        self
+    }
+
+    /// # Instance Method `Window::order_by`
+    ///
+    /// Sets the order of the rows in each partition (`ORDER BY`). Without an order, the rows of a
+    /// partition are in the order they are selected in, and are all equal.
+    ///
+    /// ## Parameters
+    /// - `order_by`, type `SortBy`; the order to set
+    #[must_use]
+    pub fn order_by(mut self, order_by: SortBy) -> Self {
+        self.order_by.replace(order_by);
+        self
+    }
+
+    /// Returns the aggregate computed by a running aggregate.
+    fn aggregate(&self, function: AggregateFunction, column: Option<&String>) -> Aggregate {
+        Aggregate {
+            name: self.name.clone(),
+            function,
+            column: column.cloned(),
+            distinct: false
+        }
+    }
+
+    /// Checks the window against the columns of the rows of the table `table_name` it is computed
+    /// on, and returns the type of its values.
+    pub(crate) fn validate(
+        &self,
+        table_name: &str,
+        columns: &DashMap<String, (ColumnDataType, bool)>,
+        context: &QueryContext<'_>
+    ) -> QlResult<ColumnDataType> {
+        if let Some(column) = self
+            .partition_by
+            .iter()
+            .find(|column| !columns.contains_key(*column))
+        {
+            return Err(QlError::ColumnDoesNotExist {
+                name: column.clone()
+            });
+        }
+
+        if let Some(order_by) = &self.order_by {
+            order_by.validate(columns, context)?;
+        }
+
+        match &self.function {
+            WindowFunction::RowNumber | WindowFunction::Rank | WindowFunction::DenseRank => {
+                Ok(ColumnDataType::U64)
+            }
+            WindowFunction::Lag(column, _) | WindowFunction::Lead(column, _) => {
+                columns
+                    .get(column)
+                    .map(|entry| entry.0.clone())
+                    .ok_or_else(|| {
+                        QlError::ColumnDoesNotExist {
+                            name: column.clone()
+                        }
+                    })
+            }
+            WindowFunction::Aggregate(function, column) => {
+                let aggregate = self.aggregate(*function, column.as_ref());
+                aggregate.validate(table_name, columns)?;
+
+                Ok(aggregate.data_type(columns))
+            }
+        }
+    }
+
+    /// Computes the window for rows with columns `columns`, and returns the value of each row in
+    /// the order of the rows.
+    pub(crate) fn compute(
+        &self,
+        rows: &[Arc<CacheTableRow>],
+        columns: &DashMap<String, (ColumnDataType, bool)>,
+        context: &QueryContext<'_>
+    ) -> QlResult<Vec<ColumnValue>> {
+        // partitions are kept in the order of their first rows
+        let mut partitions: Vec<Vec<usize>> = Vec::new();
+        let mut indices = HashMap::new();
+        for (index, row) in rows.iter().enumerate() {
+            let partition = *indices
+                .entry(row.values_of(&self.partition_by))
+                .or_insert_with(|| {
+                    partitions.push(Vec::new());
+                    partitions.len() - 1
+                });
+
+            partitions[partition].push(index);
+        }
+
+        let keys = rows
+            .iter()
+            .map(|row| {
+                match &self.order_by {
+                    Some(order_by) => order_by.key(row, context),
+                    None => Ok(Vec::new())
+                }
+            })
+            .collect::<QlResult<Vec<_>>>()?;
+
+        let mut values = vec![ColumnValue::Null; rows.len()];
+        for mut partition in partitions {
+            if let Some(order_by) = &self.order_by {
+                partition.sort_by(|left, right| order_by.compare(&keys[*left], &keys[*right]));
+            }
+
+            self.compute_partition(&partition, rows, &keys, columns, &mut values)?;
+        }
+
+        Ok(values)
+    }
+
+    /// Computes the window for the rows of a partition, which are the rows at the indices
+    /// `partition` in order, and stores the value of each row at its index in `values`.
+    fn compute_partition(
+        &self,
+        partition: &[usize],
+        rows: &[Arc<CacheTableRow>],
+        keys: &[Vec<ColumnValue>],
+        columns: &DashMap<String, (ColumnDataType, bool)>,
+        values: &mut [ColumnValue]
+    ) -> QlResult<()> {
+        let value_at = |position: Option<usize>, column: &str| {
+            position
+                .and_then(|position| partition.get(position))
+                .and_then(|index| rows[*index].column_values.get(column))
+                .map_or(ColumnValue::Null, |value| value.clone())
+        };
+
+        let aggregate = match &self.function {
+            WindowFunction::Aggregate(function, column) => {
+                Some(self.aggregate(*function, column.as_ref()))
+            }
+            _ => None
+        };
+        let (mut rank, mut dense_rank) = (0, 0);
+        let mut running = Running::new(aggregate.as_ref(), columns);
+
+        for (position, index) in partition.iter().enumerate() {
+            // equal rows are peers, which share their rank and running aggregate, so the rows up
+            // to the last peer are added to the aggregate at the first one
+            let is_peer = position > 0 && keys[partition[position - 1]] == keys[*index];
+            if !is_peer {
+                rank = position + 1;
+                dense_rank += 1;
+
+                if let Some(running) = &mut running {
+                    let peers = partition[position..]
+                        .iter()
+                        .take_while(|peer| keys[**peer] == keys[*index]);
+
+                    for peer in peers {
+                        running.add(&rows[*peer])?;
+                    }
+                }
+            }
+
+            values[*index] = match &self.function {
+                WindowFunction::RowNumber => ColumnValue::U64(position as u64 + 1),
+                WindowFunction::Rank => ColumnValue::U64(rank as u64),
+                WindowFunction::DenseRank => ColumnValue::U64(dense_rank),
+                WindowFunction::Lag(column, offset) => {
+                    value_at(position.checked_sub(*offset), column)
+                }
+                WindowFunction::Lead(column, offset) => {
+                    value_at(position.checked_add(*offset), column)
+                }
+                WindowFunction::Aggregate(..) => {
+                    running.as_ref().map_or(Ok(ColumnValue::Null), Running::value)?
+                }
+            };
+        }
+
+        Ok(())
+    }
+}
+
+/// The running value of the aggregate of a window over the rows of a partition that have been
+/// added so far, which only takes the time to add each row once.
+struct Running {
+    aggregate: Aggregate,
+    target: ColumnDataType,
+
+    /// The count, sum or average, accumulated like the groups of materialized views.
+    group: Group,
+
+    /// The least or greatest value that is not `null`, for `MIN` and `MAX`.
+    extreme: ColumnValue
+}
+
+impl Running {
+    fn new(
+        aggregate: Option<&Aggregate>,
+        columns: &DashMap<String, (ColumnDataType, bool)>
+    ) -> Option<Self> {
+        aggregate.map(|aggregate| {
+            Self {
+                aggregate: aggregate.clone(),
+                target: aggregate.data_type(columns),
+                group: Group::new(1),
+                extreme: ColumnValue::Null
+            }
+        })
+    }
+
+    /// Adds a row to the aggregate.
+    fn add(&mut self, row: &CacheTableRow) -> QlResult<()> {
+        let ordering = match self.aggregate.function {
+            AggregateFunction::Min => Ordering::Less,
+            AggregateFunction::Max => Ordering::Greater,
+            _ => return self.group.update(row, slice::from_ref(&self.aggregate), true)
+        };
+
+        let value = self
+            .aggregate
+            .column
+            .as_ref()
+            .and_then(|column| row.column_values.get(column))
+            .map_or(ColumnValue::Null, |value| value.clone());
+        if value != ColumnValue::Null
+            && (self.extreme == ColumnValue::Null || value.cmp(&self.extreme) == ordering)
+        {
+            self.extreme = value;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the value of the aggregate over the rows added so far.
+    fn value(&self) -> QlResult<ColumnValue> {
+        match self.aggregate.function {
+            AggregateFunction::Min | AggregateFunction::Max => Ok(self.extreme.clone()),
+            _ => self.group.value(0, &self.aggregate, &self.target)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Debug;
+
+    use super::{
+        Window,
+        WindowFunction
+    };
+    use crate::{
+        ql::{
+            aggregate::AggregateFunction,
+            select::SelectScope,
+            sortby::{
+                SortBy,
+                SortOrdering
+            },
+            QueryBuilder
+        },
+        testing,
+        ColumnDataType,
+        ColumnValue,
+        QlCache
+    };
+
+    static_assertions::assert_impl_all!(Window: Send, Sync);
+    static_assertions::assert_impl_all!(WindowFunction: Clone, Debug, Eq, PartialEq, Send, Sync);
+
+    /// Returns the values of a window for the rows of the table `Members (Id U64, GuildId U64, Xp
+    /// U64)` of the members 1 to 4 of guild 1 with 30, 20, 20 and 10 experience, and the members
+    /// 5 and 6 of guild 2 with 50 and 40 experience, in the order of the members.
+    fn values(window: Window) -> Vec<String> {
+        let cache = QlCache::new();
+        testing::table(
+            &cache,
+            "Members",
+            [
+                ("Id", ColumnDataType::U64, false),
+                ("GuildId", ColumnDataType::U64, false),
+                ("Xp", ColumnDataType::U64, false)
+            ],
+            (1..).zip([(1, 30), (1, 20), (1, 20), (1, 10), (2, 50), (2, 40)]).map(
+                |(id, (guild_id, xp))| [id, guild_id, xp].map(ColumnValue::U64)
+            )
+        );
+
+        let name = window.name.clone();
+        QueryBuilder::select()
+            .table_name(String::from("Members"))
+            .scope(SelectScope::Fields(vec![name.clone()]))
+            .window(window)
+            .sort_by(SortBy::new(vec![String::from("Id")], None).unwrap())
+            .build()
+            .unwrap()
+            .execute(&cache)
+            .unwrap()
+            .iter()
+            .map(|row| row.column_values.get(&name).unwrap().to_string())
+            .collect()
+    }
+
+    fn by_guild(function: WindowFunction, order_by: Option<(&str, SortOrdering)>) -> Vec<String> {
+        let mut window = Window::new(String::from("Value"), function)
+            .partition_by(vec![String::from("GuildId")]);
+        if let Some((column, ordering)) = order_by {
+            let order_by = SortBy::new(vec![String::from(column)], Some(ordering)).unwrap();
+            window = window.order_by(order_by);
+        }
+
+        values(window)
+    }
+
+    #[test]
+    fn ranks_are_shared_by_peers() {
+        let xp = || Some(("Xp", SortOrdering::Descending));
+
+        assert_eq!(by_guild(WindowFunction::Rank, xp()), ["1", "2", "2", "4", "1", "2"]);
+        assert_eq!(by_guild(WindowFunction::DenseRank, xp()), ["1", "2", "2", "3", "1", "2"]);
+        assert_eq!(
+            by_guild(WindowFunction::RowNumber, Some(("Id", SortOrdering::Ascending))),
+            ["1", "2", "3", "4", "1", "2"]
+        );
+    }
+
+    #[test]
+    fn running_aggregates_include_the_peers_of_the_row() {
+        let sum = || WindowFunction::Aggregate(AggregateFunction::Sum, Some(String::from("Xp")));
+
+        // the members with 20 experience in guild 1 both count each other
+        assert_eq!(by_guild(sum(), Some(("Xp", SortOrdering::Ascending))), [
+            "80", "50", "50", "10", "90", "40"
+        ]);
+
+        // the members with 20 experience in guild 1 are counted at once, at the first of them
+        let count = || {
+            WindowFunction::Aggregate(AggregateFunction::Count, Some(String::from("Xp")))
+        };
+        assert_eq!(by_guild(count(), Some(("Xp", SortOrdering::Ascending))), [
+            "4", "3", "3", "1", "2", "1"
+        ]);
+        assert_eq!(by_guild(count(), Some(("Xp", SortOrdering::Descending))), [
+            "1", "3", "3", "4", "1", "2"
+        ]);
+
+        // without an order, every row of the partition is a peer
+        assert_eq!(by_guild(sum(), None), ["80", "80", "80", "80", "90", "90"]);
+        assert_eq!(
+            values(Window::new(
+                String::from("Value"),
+                WindowFunction::Aggregate(AggregateFunction::Count, None)
+            )),
+            ["6", "6", "6", "6", "6", "6"]
+        );
+    }
+
+    #[test]
+    fn running_extremes_and_averages_follow_the_order() {
+        let aggregate = |function| WindowFunction::Aggregate(function, Some(String::from("Xp")));
+        let id = || Some(("Id", SortOrdering::Ascending));
+
+        assert_eq!(by_guild(aggregate(AggregateFunction::Max), id()), [
+            "30", "30", "30", "30", "50", "50"
+        ]);
+        assert_eq!(by_guild(aggregate(AggregateFunction::Min), id()), [
+            "30", "20", "20", "10", "50", "40"
+        ]);
+        assert_eq!(by_guild(aggregate(AggregateFunction::Avg), id()), [
+            "30", "25", "23.333333333333332", "20", "50", "45"
+        ]);
+    }
+
+    #[test]
+    fn offsets_stay_within_the_partition() {
+        let id = || Some(("Id", SortOrdering::Ascending));
+
+        assert_eq!(by_guild(WindowFunction::Lag(String::from("Xp"), 1), id()), [
+            "NULL", "30", "20", "20", "NULL", "50"
+        ]);
+        assert_eq!(by_guild(WindowFunction::Lead(String::from("Xp"), 2), id()), [
+            "20", "10", "NULL", "NULL", "NULL", "NULL"
+        ]);
+    }
+}
@@ -0,0 +1,606 @@
+//! # Module `with`
+//!
+//! This module implements `WITH` clauses of the query language, common table expressions that
+//! name intermediate results a `SELECT` query can read as tables, and `WITH RECURSIVE` clauses
+//! that walk trees of rows, such as parent-child relations in a table.
+
+use std::{
+    collections::{
+        HashMap,
+        HashSet
+    },
+    sync::Arc
+};
+
+use dashmap::DashMap;
+
+use crate::{
+    error::{
+        QlError,
+        QlResult,
+        QueryError
+    },
+    mvcc::Snapshot,
+    ql::{
+        compound,
+        select::{
+            self,
+            ResultColumns,
+            Select
+        },
+        Query,
+        QueryContext,
+        QueryRow
+    },
+    CacheTableRow,
+    ColumnDataType,
+    QlCache
+};
+
+/// The maximum number of recursive steps of a recursive common table expression by default.
+pub const DEFAULT_RECURSION_LIMIT: usize = 100;
+
+/// The result of a common table expression, which queries read as a table of the same name.
+pub(crate) struct NamedResult {
+    pub(crate) columns: DashMap<String, (ColumnDataType, bool)>,
+    pub(crate) rows: Vec<Arc<CacheTableRow>>
+}
+
+impl NamedResult {
+    /// Returns the result of a query with the result columns `columns`. The type of a column is
+    /// that of its first value that is not `null` if the query does not know it, and a column
+    /// without a type cannot be referred to.
+    pub(crate) fn new(columns: &ResultColumns, rows: Vec<Arc<CacheTableRow>>) -> Self {
+        let result_columns = DashMap::new();
+        for (name, data_type) in columns {
+            let data_type = data_type.clone().or_else(|| {
+                rows.iter()
+                    .find_map(|row| row.column_values.get(name)?.data_type())
+            });
+
+            if let Some(data_type) = data_type {
+                result_columns.insert(name.clone(), (data_type, true));
+            }
+        }
+
+        Self {
+            columns: result_columns,
+            rows
+        }
+    }
+}
+
+/// # Struct `CommonTableExpression`
+///
+/// A common table expression of a `WITH` clause, which names the result of a `SELECT` query.
+///
+/// A recursive common table expression (`WITH RECURSIVE`) has an initial query and a recursive
+/// query, which refers to the common table expression by its name. The recursive query is run
+/// with the rows of the previous step, starting with the rows of the initial query, until it
+/// returns no new rows. The result has the rows of every step, with the columns of the initial
+/// query, which the columns of the recursive query are matched with by position.
+///
+/// ## Example
+///
+/// The channels of a category and their descendants (`WITH RECURSIVE Tree AS (SELECT Id, Parent
+/// FROM Channels WHERE Id = 1 UNION SELECT c.Id, c.Parent FROM Channels c JOIN Tree t ON c.Parent
+/// = t.Id)`):
+/// ```
+/// use qlcache::{
+///     ql::{
+///         constraints::{
+///             Constraint,
+///             ConstraintOp
+///         },
+///         join::{
+///             Join,
+///             JoinKind
+///         },
+///         select::SelectScope,
+///         with::CommonTableExpression,
+///         QueryBuilder
+///     },
+///     ColumnValue
+/// };
+///
+/// let category = QueryBuilder::select()
+///     .table_name(String::from("Channels"))
+///     .scope(SelectScope::Fields(vec![String::from("Id"), String::from("Parent")]))
+///     .constraint(Box::new(Constraint::builder()
+///         .field_name(String::from("Id"))
+///         .op(ConstraintOp::Eq)
+///         .value(ColumnValue::U64(1))
+///         .build()
+///         .unwrap()))
+///     .build()
+///     .unwrap();
+///
+/// let children = QueryBuilder::select()
+///     .table_name(String::from("Channels"))
+///     .alias(String::from("c"))
+///     .join(Join::new(JoinKind::Inner, String::from("Tree"))
+///         .alias(String::from("t"))
+///         .on(String::from("c.Parent"), ConstraintOp::Eq, String::from("t.Id")))
+///     .scope(SelectScope::Fields(vec![String::from("c.Id"), String::from("c.Parent")]))
+///     .build()
+///     .unwrap();
+///
+/// let tree = CommonTableExpression::recursive(String::from("Tree"), category, children);
+/// ```
+#[allow(clippy::module_name_repetitions)]
+pub struct CommonTableExpression {
+    pub(crate) name: String,
+    pub(crate) select: Select,
+    pub(crate) recursive: Option<Select>,
+    pub(crate) all: bool
+}
+
+impl CommonTableExpression {
+    /// # Constructor `CommonTableExpression::new`
+    ///
+    /// Constructs a new `CommonTableExpression`, which names the result of a query.
+    #[must_use]
+    pub fn new(name: String, select: Query<Select>) -> Self {
+        Self {
+            name,
+            select: select.query,
+            recursive: None,
+            all: false
+        }
+    }
+
+    /// # Constructor `CommonTableExpression::recursive`
+    ///
+    /// Constructs a new recursive `CommonTableExpression` of an initial query, and a recursive
+    /// query that refers to the common table expression by its name. Rows are distinct, as with
+    /// `UNION`.
+    #[must_use]
+    pub fn recursive(name: String, initial: Query<Select>, recursive: Query<Select>) -> Self {
+        Self {
+            name,
+            select: initial.query,
+            recursive: Some(recursive.query),
+            all: false
+        }
+    }
+
+    /// # Instance Method `CommonTableExpression::all`
+    ///
+    /// Keeps duplicate rows of a recursive common table expression, as with `UNION ALL`. The
+    /// recursion of a cyclic relation then only ends with the recursion limit.
+    #[must_use]
+    pub fn all(mut self) -> Self {
+        self.all = true;
+        self
+    }
+
+    /// Computes the result of the common table expression in a context, with at most `limit`
+    /// recursive steps.
+    fn evaluate(&self, context: &QueryContext<'_>, limit: usize) -> QlResult<NamedResult> {
+        let columns = self.select.result_columns(context)?;
+        let mut rows = self
+            .select
+            .run(context)?
+            .into_iter()
+            .map(Arc::new)
+            .collect::<Vec<_>>();
+
+        if let Some(recursive) = &self.recursive {
+            rows = self.recurse(recursive, context, &columns, rows, limit)?;
+        }
+
+        Ok(NamedResult::new(&columns, rows))
+    }
+
+    /// Runs the recursive query until it returns no new rows, starting with the rows `rows` of
+    /// the initial query with the result columns `columns`, and returns the rows of every step.
+    fn recurse(
+        &self,
+        recursive: &Select,
+        context: &QueryContext<'_>,
+        columns: &ResultColumns,
+        mut rows: Vec<Arc<CacheTableRow>>,
+        limit: usize
+    ) -> QlResult<Vec<Arc<CacheTableRow>>> {
+        let names = columns
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+        let mut seen = HashSet::new();
+        if !self.all {
+            select::retain_first(&mut rows, |row| row.values_of(&names));
+            seen.extend(rows.iter().map(|row| row.values_of(&names)));
+        }
+
+        let mut named_results = context.named_results.cloned().unwrap_or_default();
+        let mut working = NamedResult::new(columns, rows.clone());
+        let mut depth = 0;
+
+        while !working.rows.is_empty() {
+            named_results.insert(self.name.clone(), Arc::new(working));
+            let context = context.with_results(&named_results);
+
+            // the recursive query is checked against the columns of the initial query
+            let recursive_columns = recursive.result_columns(&context)?;
+            compound::check_columns(columns, &recursive_columns)?;
+
+            let mut step = recursive
+                .run(&context)?
+                .iter()
+                .map(|row| Arc::new(compound::rename(row, &recursive_columns, &names)))
+                .collect::<Vec<_>>();
+            if !self.all {
+                step.retain(|row| seen.insert(row.values_of(&names)));
+            }
+
+            if step.is_empty() {
+                break;
+            }
+
+            depth += 1;
+            if depth > limit {
+                return Err(QlError::RecursionLimitExceeded {
+                    name: self.name.clone(),
+                    limit
+                });
+            }
+
+            rows.extend(step.iter().cloned());
+            working = NamedResult::new(columns, step);
+        }
+
+        Ok(rows)
+    }
+}
+
+/// # Struct `With`
+///
+/// A `SELECT` query with a `WITH` clause.
+#[allow(dead_code)]
+pub struct With {
+    pub(crate) expressions: Vec<CommonTableExpression>,
+    pub(crate) select: Select,
+    pub(crate) recursion_limit: usize,
+    pub(crate) snapshot: Option<Snapshot>
+}
+
+impl With {
+    #[must_use]
+    pub(crate) fn builder() -> WithBuilder {
+        WithBuilder {
+            expressions: Vec::new(),
+            select: None,
+            recursion_limit: DEFAULT_RECURSION_LIMIT,
+            snapshot: None
+        }
+    }
+}
+
+impl QueryRow for With {
+    fn execute(mut self, cache: &QlCache) -> QlResult<Vec<CacheTableRow>> {
+        let snapshot = self.snapshot.take().unwrap_or_else(|| cache.snapshot());
+        let context = QueryContext::new(cache, snapshot.timestamp);
+
+        // every common table expression can read the results of the ones before it
+        let mut named_results = HashMap::new();
+        for expression in &self.expressions {
+            let result =
+                expression.evaluate(&context.with_results(&named_results), self.recursion_limit)?;
+            named_results.insert(expression.name.clone(), Arc::new(result));
+        }
+
+        self.select.run(&context.with_results(&named_results))
+    }
+}
+
+/// # Struct `WithBuilder`
+///
+/// A builder for a `With`, constructs a `SELECT` query with a `WITH` clause. The common table
+/// expressions shadow tables of the same names, and the snapshots of the queries are ignored, as
+/// they all read the snapshot of the query.
+///
+/// ## Example
+///
+/// The number of channels in a category, including nested channels (`WITH RECURSIVE Tree AS
+/// (...) SELECT count(*) FROM Tree`):
+/// ```
+/// use qlcache::ql::{
+///     aggregate::Aggregate,
+///     select::SelectScope,
+///     with::CommonTableExpression,
+///     QueryBuilder
+/// };
+///
+/// # let tree = |name| {
+/// #     let query = || {
+/// #         QueryBuilder::select()
+/// #             .table_name(String::from("Channels"))
+/// #             .scope(SelectScope::Everything)
+/// #             .build()
+/// #             .unwrap()
+/// #     };
+/// #     CommonTableExpression::recursive(name, query(), query())
+/// # };
+/// let with = QueryBuilder::with()
+///     .expression(tree(String::from("Tree")))
+///     .recursion_limit(16)
+///     .select(QueryBuilder::select()
+///         .table_name(String::from("Tree"))
+///         .scope(SelectScope::Aggregates(vec![Aggregate::count_all(String::from("count"))]))
+///         .build()
+///         .unwrap())
+///     .build()
+///     .unwrap();
+/// ```
+#[allow(clippy::module_name_repetitions)]
+pub struct WithBuilder {
+    pub(crate) expressions: Vec<CommonTableExpression>,
+    pub(crate) select: Option<Select>,
+    pub(crate) recursion_limit: usize,
+    pub(crate) snapshot: Option<Snapshot>
+}
+
+impl WithBuilder {
+    /// # Instance Method `WithBuilder::expression`
+    ///
+    /// Adds a common table expression to the `WITH` clause, which can read the results of the
+    /// common table expressions added before it.
+    ///
+    /// ## Parameters
+    /// - `expression`, type `CommonTableExpression`; the common table expression to add
+    #[must_use]
+    pub fn expression(mut self, expression: CommonTableExpression) -> Self {
+        self.expressions.push(expression);
+        self
+    }
+
+    /// # Instance Method `WithBuilder::select`
+    ///
+    /// Sets the query that reads the results of the common table expressions.
+    ///
+    /// ## Parameters
+    /// - `select`, type `Query<Select>`; the query to set
+    #[must_use]
+    pub fn select(mut self, select: Query<Select>) -> Self {
+        self.select.replace(select.query);
+        self
+    }
+
+    /// # Instance Method `WithBuilder::recursion_limit`
+    ///
+    /// Sets the maximum number of recursive steps of each recursive common table expression,
+    /// which is [`DEFAULT_RECURSION_LIMIT`] by default.
+    ///
+    /// ## Parameters
+    /// - `limit`, type `usize`; the maximum number of recursive steps
+    #[must_use]
+    pub fn recursion_limit(mut self, limit: usize) -> Self {
+        self.recursion_limit = limit;
+        self
+    }
+
+    /// # Instance Method `WithBuilder::snapshot`
+    ///
+    /// Sets the snapshot to run the queries against. If no snapshot is set, a snapshot is taken
+    /// when the query is executed.
+    ///
+    /// ## Parameters
+    /// - `snapshot`, type `Snapshot`; the snapshot to read from
+    #[must_use]
+    pub fn snapshot(mut self, snapshot: Snapshot) -> Self {
+        self.snapshot.replace(snapshot);
+        self
+    }
+
+    /// # Instance Method `WithBuilder::build`
+    ///
+    /// Consumes the builder and returns a `Query<With>`.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `RequiredFieldIsNone` if the query is not set; returns `VecCannotBeEmpty` if no
+    /// common table expression is added; returns `RelationAlreadyExists` if two common table
+    /// expressions have the same name.
+    #[allow(clippy::missing_panics_doc)] // this function never panics
+    pub fn build(self) -> QlResult<Query<With>> {
+        if self.select.is_none() {
+            return Err(QlError::RequiredFieldIsNone {
+                field_name: String::from("WithBuilder.select")
+            });
+        }
+
+        if self.expressions.is_empty() {
+            return Err(QlError::VecCannotBeEmpty {
+                vec_name: String::from("WithBuilder.expressions")
+            });
+        }
+
+        let mut names = HashSet::new();
+        if let Some(expression) = self
+            .expressions
+            .iter()
+            .find(|expression| !names.insert(&expression.name))
+        {
+            return Err(QlError::QueryError(QueryError::RelationAlreadyExists {
+                name: expression.name.clone()
+            }));
+        }
+
+        Ok(Query {
+            query: With {
+                expressions: self.expressions,
+                select: self.select.unwrap(),
+                recursion_limit: self.recursion_limit,
+                snapshot: self.snapshot
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        CommonTableExpression,
+        QueryRow,
+        With,
+        WithBuilder
+    };
+    use crate::{
+        error::{
+            QlError,
+            QlResult
+        },
+        ql::{
+            constraints::{
+                Constraint,
+                ConstraintOp
+            },
+            join::{
+                Join,
+                JoinKind
+            },
+            select::{
+                Select,
+                SelectScope
+            },
+            Query,
+            QueryBuilder
+        },
+        testing,
+        ColumnDataType,
+        ColumnValue,
+        QlCache
+    };
+
+    static_assertions::assert_impl_all!(CommonTableExpression: Send, Sync);
+    static_assertions::assert_impl_all!(With: QueryRow, Send, Sync);
+    static_assertions::assert_impl_all!(WithBuilder: Send, Sync);
+
+    /// Returns a cache with the table `Channels (Id U64, Parent U64 NULL)` of the tree `1 -> 2 ->
+    /// 3`, `1 -> 4`, and of the channels 5 and 6, which are each other's parents.
+    fn cache() -> QlCache {
+        let cache = QlCache::new();
+        testing::table(
+            &cache,
+            "Channels",
+            [("Id", ColumnDataType::U64, false), ("Parent", ColumnDataType::U64, true)],
+            (1..).zip([None, Some(1), Some(2), Some(1), Some(6), Some(5)]).map(|(id, parent)| {
+                [ColumnValue::U64(id), parent.map_or(ColumnValue::Null, ColumnValue::U64)]
+            })
+        );
+
+        cache
+    }
+
+    /// `SELECT Id, Parent FROM Channels WHERE Id = id`
+    fn channel(id: u64) -> Query<Select> {
+        QueryBuilder::select()
+            .table_name(String::from("Channels"))
+            .scope(SelectScope::Fields(vec![String::from("Id"), String::from("Parent")]))
+            .constraint(Box::new(
+                Constraint::builder()
+                    .field_name(String::from("Id"))
+                    .op(ConstraintOp::Eq)
+                    .value(ColumnValue::U64(id))
+                    .build()
+                    .unwrap()
+            ))
+            .build()
+            .unwrap()
+    }
+
+    /// `WITH RECURSIVE Tree AS (SELECT Id, Parent FROM Channels WHERE Id = id UNION SELECT c.Id,
+    /// c.Parent FROM Channels c JOIN Tree t ON c.Parent = t.Id)`
+    fn tree(id: u64) -> CommonTableExpression {
+        let children = QueryBuilder::select()
+            .table_name(String::from("Channels"))
+            .alias(String::from("c"))
+            .join(Join::new(JoinKind::Inner, String::from("Tree"))
+                .alias(String::from("t"))
+                .on(String::from("c.Parent"), ConstraintOp::Eq, String::from("t.Id")))
+            .scope(SelectScope::Fields(vec![String::from("c.Id"), String::from("c.Parent")]))
+            .build()
+            .unwrap();
+
+        CommonTableExpression::recursive(String::from("Tree"), channel(id), children)
+    }
+
+    /// Returns the sorted ids of the rows of `SELECT Id FROM name` with the specified `WITH`
+    /// clause.
+    fn ids(
+        cache: &QlCache,
+        name: &str,
+        expressions: Vec<CommonTableExpression>,
+        recursion_limit: usize
+    ) -> QlResult<Vec<u64>> {
+        let mut builder = QueryBuilder::with().recursion_limit(recursion_limit);
+        for expression in expressions {
+            builder = builder.expression(expression);
+        }
+        let select = QueryBuilder::select()
+            .table_name(String::from(name))
+            .scope(SelectScope::Fields(vec![String::from("Id")]))
+            .build()
+            .unwrap();
+
+        let mut ids = builder
+            .select(select)
+            .build()
+            .unwrap()
+            .execute(cache)?
+            .iter()
+            .map(|row| row.get::<u64>("Id"))
+            .collect::<QlResult<Vec<_>>>()?;
+        ids.sort_unstable();
+
+        Ok(ids)
+    }
+
+    #[test]
+    fn recursive_expressions_end_when_a_step_has_no_new_rows() {
+        let cache = cache();
+
+        assert_eq!(ids(&cache, "Tree", vec![tree(1)], 16).unwrap(), [1, 2, 3, 4]);
+        assert_eq!(ids(&cache, "Tree", vec![tree(3)], 16).unwrap(), [3]);
+
+        // the rows of a cycle are only walked once, as rows are distinct
+        assert_eq!(ids(&cache, "Tree", vec![tree(5)], 16).unwrap(), [5, 6]);
+    }
+
+    #[test]
+    fn recursion_is_limited_to_the_number_of_steps() {
+        let cache = cache();
+
+        // the tree of 1 has two levels below it, and the step without new rows is not counted
+        assert_eq!(ids(&cache, "Tree", vec![tree(1)], 2).unwrap(), [1, 2, 3, 4]);
+        assert!(matches!(
+            ids(&cache, "Tree", vec![tree(1)], 1),
+            Err(QlError::RecursionLimitExceeded { limit: 1, .. })
+        ));
+
+        // a cycle with duplicate rows never runs out of new rows
+        assert!(matches!(
+            ids(&cache, "Tree", vec![tree(5).all()], 16),
+            Err(QlError::RecursionLimitExceeded { name, limit: 16 }) if name == "Tree"
+        ));
+    }
+
+    #[test]
+    fn expressions_read_the_expressions_before_them() {
+        let cache = cache();
+        let select = |table_name: &str| {
+            QueryBuilder::select()
+                .table_name(String::from(table_name))
+                .scope(SelectScope::Everything)
+                .build()
+                .unwrap()
+        };
+
+        // the expression named `Channels` shadows the table for the expressions after it
+        let expressions = vec![
+            CommonTableExpression::new(String::from("Channels"), channel(2)),
+            CommonTableExpression::new(String::from("Copy"), select("Channels"))
+        ];
+        assert_eq!(ids(&cache, "Copy", expressions, 16).unwrap(), [2]);
+    }
+}
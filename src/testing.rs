@@ -0,0 +1,62 @@
+//! # Module `testing`
+//!
+//! This module implements the fixtures shared by the tests of the crate.
+
+use crate::{
+    ql::QueryBuilder,
+    CacheTableRow,
+    ColumnDataType,
+    ColumnValue,
+    QlCache
+};
+
+/// Creates the table `name` of the specified columns, each of its name, type and whether it is
+/// nullable, and inserts the specified rows into it in one transaction, keyed from 1.
+///
+/// The name of a table in a schema is qualified as `Schema Table`; the schema must already exist.
+pub(crate) fn table<const N: usize>(
+    cache: &QlCache,
+    name: &str,
+    columns: [(&str, ColumnDataType, bool); N],
+    rows: impl IntoIterator<Item = [ColumnValue; N]>
+) {
+    let mut builder = QueryBuilder::create().table().columns(columns.iter().map(
+        |(column, data_type, nullable)| (String::from(*column), (data_type.clone(), *nullable))
+    ));
+    builder = if let Some((schema, table)) = name.split_once(' ') {
+        builder.schema(String::from(schema)).name(String::from(table))
+    }
+    else {
+        builder.name(String::from(name))
+    };
+    builder.build().unwrap().execute(cache).unwrap();
+
+    insert_rows(cache, name, columns.map(|(column, _, _)| column), rows);
+}
+
+/// Inserts the specified rows of values of the columns into the table `name` in one transaction,
+/// keyed from 1.
+pub(crate) fn insert_rows<const N: usize>(
+    cache: &QlCache,
+    name: &str,
+    columns: [&str; N],
+    rows: impl IntoIterator<Item = [ColumnValue; N]>
+) {
+    let mut transaction = cache.begin();
+    for (key, values) in (1..).zip(rows) {
+        transaction
+            .insert_row(String::from(name), key, row(columns.into_iter().zip(values)))
+            .unwrap();
+    }
+    transaction.commit().unwrap();
+}
+
+/// Returns a row of the specified values of its columns.
+pub(crate) fn row<'a>(values: impl IntoIterator<Item = (&'a str, ColumnValue)>) -> CacheTableRow {
+    CacheTableRow {
+        column_values: values
+            .into_iter()
+            .map(|(column, value)| (String::from(column), value))
+            .collect()
+    }
+}
@@ -0,0 +1,644 @@
+//! # Module `transaction`
+//!
+//! This module implements transactions, including `SAVEPOINT`, `ROLLBACK TO` and `RELEASE`.
+//!
+//! The writes of a transaction are buffered and only installed into the tables when the
+//! transaction commits, all at the same commit timestamp. Rolling back to a savepoint therefore
+//! only has to discard the buffered writes made after it.
+//!
+//! A transaction fails to commit if another commit changed a row it writes after its snapshot was
+//! taken, so that no update is lost by overwriting a change the transaction has not seen. The
+//! first transaction to commit wins.
+//!
+//! The values of the primary key of a table must also be unique among its live rows once the
+//! transaction is installed, which is checked at commit against the latest committed rows, so
+//! another commit can make the transaction fail even though it wrote different rows. Only the
+//! buffered writes take part in the check, so the writes discarded by rolling back to a savepoint
+//! do not hold on to the values of their primary keys.
+
+use std::{
+    collections::{
+        BTreeMap,
+        BTreeSet,
+        HashMap
+    },
+    time::Duration
+};
+
+use crate::{
+    error::{
+        QlError,
+        QlResult,
+        QueryError
+    },
+    mvcc::Snapshot,
+    CacheTableRow,
+    QlCache
+};
+
+/// # Struct `Transaction`
+///
+/// A transaction on the cache. Reads see the writes made in the transaction, on top of the
+/// snapshot taken when the transaction began.
+///
+/// Dropping a transaction without committing it rolls it back.
+///
+/// ## Example
+///
+/// ```
+/// use qlcache::QlCache;
+///
+/// let cache = QlCache::new();
+/// let mut transaction = cache.begin();
+///
+/// transaction.savepoint(String::from("batch"));
+/// transaction.rollback_to("batch").unwrap();
+/// transaction.release("batch").unwrap();
+///
+/// transaction.commit().unwrap();
+/// ```
+pub struct Transaction<'cache> {
+    pub(crate) cache: &'cache QlCache,
+    pub(crate) snapshot: Snapshot,
+    pub(crate) writes: Vec<TransactionWrite>,
+    pub(crate) savepoints: Vec<(String, usize)>
+}
+
+impl Transaction<'_> {
+    /// # Instance Method `Transaction::snapshot`
+    ///
+    /// Returns the snapshot the transaction reads from.
+    #[must_use]
+    pub fn snapshot(&self) -> &Snapshot {
+        &self.snapshot
+    }
+
+    /// # Instance Method `Transaction::row`
+    ///
+    /// Returns the row with the specified key in the specified table, as seen by this transaction.
+    ///
+    /// ## Parameters
+    /// - `table_name`, type `&str`; the name of the table
+    /// - `key`, type `u64`; the key of the row
+    ///
+    /// ## Errors
+    ///
+    /// Returns `RelationDoesNotExist` if the table does not exist.
+    pub fn row(&self, table_name: &str, key: u64) -> QlResult<Option<CacheTableRow>> {
+        if let Some(write) = self
+            .writes
+            .iter()
+            .rev()
+            .find(|write| write.table_name == table_name && write.key == key)
+        {
+            return Ok(write.row.clone());
+        }
+
+        self.cache.with_table(table_name, |table| {
+            table
+                .row_at(key, self.snapshot.timestamp)
+                .map(|row| (*row).clone())
+        })
+    }
+
+    /// # Instance Method `Transaction::insert_row`
+    ///
+    /// Inserts a row with the specified key into the specified table, or replaces the row if it
+    /// already exists.
+    ///
+    /// ## Parameters
+    /// - `table_name`, type `String`; the name of the table
+    /// - `key`, type `u64`; the key of the row
+    /// - `row`, type `CacheTableRow`; the row to insert
+    ///
+    /// ## Errors
+    ///
+    /// Returns `RelationDoesNotExist` if the table does not exist; returns `ColumnDoesNotExist` if
+    /// the row has a column that the table does not have; returns `ColumnCannotBeNull` if a column
+    /// that cannot be `null` is missing or `null` in the row; returns `ColumnTypeMismatch` or
+    /// `InvalidEnumValue` if a value does not have the type of its column.
+    pub fn insert_row(&mut self, table_name: String, key: u64, row: CacheTableRow) -> QlResult<()> {
+        self.write(table_name, key, row, None)
+    }
+
+    /// # Instance Method `Transaction::insert_row_with_ttl`
+    ///
+    /// Inserts a row with the specified key into the specified table that expires after the
+    /// specified time-to-live, counting from the commit of the transaction, or replaces the row
+    /// if it already exists.
+    ///
+    /// ## Parameters
+    /// - `table_name`, type `String`; the name of the table
+    /// - `key`, type `u64`; the key of the row
+    /// - `row`, type `CacheTableRow`; the row to insert
+    /// - `ttl`, type `Duration`; the time-to-live of the row
+    ///
+    /// ## Errors
+    ///
+    /// Returns `RelationDoesNotExist` if the table does not exist; returns `ColumnDoesNotExist` if
+    /// the row has a column that the table does not have; returns `ColumnCannotBeNull` if a column
+    /// that cannot be `null` is missing or `null` in the row; returns `ColumnTypeMismatch` or
+    /// `InvalidEnumValue` if a value does not have the type of its column.
+    pub fn insert_row_with_ttl(
+        &mut self,
+        table_name: String,
+        key: u64,
+        row: CacheTableRow,
+        ttl: Duration
+    ) -> QlResult<()> {
+        self.write(table_name, key, row, Some(ttl))
+    }
+
+    /// # Instance Method `Transaction::remove_row`
+    ///
+    /// Removes the row with the specified key from the specified table.
+    ///
+    /// ## Parameters
+    /// - `table_name`, type `String`; the name of the table
+    /// - `key`, type `u64`; the key of the row
+    ///
+    /// ## Errors
+    ///
+    /// Returns `RelationDoesNotExist` if the table does not exist.
+    pub fn remove_row(&mut self, table_name: String, key: u64) -> QlResult<()> {
+        self.cache.with_table(&table_name, |_| ())?;
+
+        self.writes.push(TransactionWrite {
+            table_name,
+            key,
+            row: None,
+            ttl: None
+        });
+        Ok(())
+    }
+
+    /// # Instance Method `Transaction::savepoint`
+    ///
+    /// Establishes a savepoint (`SAVEPOINT name`). A savepoint with the same name as an existing
+    /// one shadows it until it is released.
+    ///
+    /// ## Parameters
+    /// - `name`, type `String`; the name of the savepoint
+    pub fn savepoint(&mut self, name: String) {
+        self.savepoints.push((name, self.writes.len()));
+    }
+
+    /// # Instance Method `Transaction::rollback_to`
+    ///
+    /// Rolls back to a savepoint (`ROLLBACK TO name`), discarding the writes made and the
+    /// savepoints established after it. The savepoint itself is kept.
+    ///
+    /// ## Parameters
+    /// - `name`, type `&str`; the name of the savepoint
+    ///
+    /// ## Errors
+    ///
+    /// Returns `SavepointDoesNotExist` if there is no savepoint with the specified name.
+    pub fn rollback_to(&mut self, name: &str) -> QlResult<()> {
+        let index = self.find_savepoint(name)?;
+        let (_, writes) = self.savepoints[index];
+
+        self.savepoints.truncate(index + 1);
+        self.writes.truncate(writes);
+        Ok(())
+    }
+
+    /// # Instance Method `Transaction::release`
+    ///
+    /// Releases a savepoint (`RELEASE name`), destroying it and the savepoints established after
+    /// it. The writes made after it are kept.
+    ///
+    /// ## Parameters
+    /// - `name`, type `&str`; the name of the savepoint
+    ///
+    /// ## Errors
+    ///
+    /// Returns `SavepointDoesNotExist` if there is no savepoint with the specified name.
+    pub fn release(&mut self, name: &str) -> QlResult<()> {
+        let index = self.find_savepoint(name)?;
+
+        self.savepoints.truncate(index);
+        Ok(())
+    }
+
+    /// # Instance Method `Transaction::commit`
+    ///
+    /// Commits the transaction, installing all of its writes at the same commit timestamp.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `RelationDoesNotExist` if a table written to no longer exists; returns
+    /// `WriteConflict` if a row written to was changed by another commit after the snapshot of
+    /// the transaction was taken; returns `PrimaryKeyViolation` if two rows would have the same
+    /// value of the primary key of a table. Nothing is installed in any case.
+    pub fn commit(self) -> QlResult<()> {
+        // only the last write to each row matters, the intermediate ones are never visible
+        let mut writes = HashMap::new();
+        self.writes.into_iter().for_each(|write| {
+            writes.insert((write.table_name, write.key), (write.row, write.ttl));
+        });
+
+        // the tables are looked up before the commit lock is taken, in the same order as every
+        // other write, so that a commit never waits for a schema while holding the lock
+        let names = writes
+            .keys()
+            .map(|(table_name, _)| table_name.clone())
+            .collect::<BTreeSet<_>>();
+        let mut schemas = BTreeMap::new();
+        for name in &names {
+            let (schema_name, _) = name.split_once(' ').unwrap_or(("PUBLIC", name));
+
+            if !schemas.contains_key(schema_name) {
+                let schema = self
+                    .cache
+                    .cache
+                    .get(schema_name)
+                    .ok_or_else(|| relation_does_not_exist(name))?;
+                schemas.insert(schema_name, schema);
+            }
+        }
+        let tables = names
+            .iter()
+            .map(|name| {
+                let (schema_name, table_name) = name.split_once(' ').unwrap_or(("PUBLIC", name));
+                let table = schemas[schema_name]
+                    .tables
+                    .get(table_name)
+                    .ok_or_else(|| relation_does_not_exist(name))?;
+
+                Ok((name.as_str(), table))
+            })
+            .collect::<QlResult<HashMap<_, _>>>()?;
+
+        let watermark = self.cache.clock.watermark();
+        self.cache.clock.commit(|timestamp| {
+            if let Some((table_name, key)) = writes.keys().find(|(table_name, key)| {
+                tables[table_name.as_str()].is_changed_after(*key, self.snapshot.timestamp)
+            }) {
+                return Err(QlError::WriteConflict {
+                    table_name: table_name.clone(),
+                    key: *key
+                });
+            }
+
+            let mut values = HashMap::new();
+            for ((table_name, key), (row, _)) in &writes {
+                let (table, row) = match row {
+                    Some(row) => (&tables[table_name.as_str()], row),
+                    None => continue
+                };
+
+                // the rows written by the transaction must not have the same value either
+                let value = table.primary_key.as_ref().and_then(|primary| primary.value_of(row));
+                let other = value.and_then(|value| values.insert((table_name, value), *key));
+                if let Some(other) = other {
+                    return Err(QlError::PrimaryKeyViolation {
+                        table_name: table_name.clone(),
+                        key: other
+                    });
+                }
+
+                table.check_primary_key(table_name, *key, row, |other| {
+                    writes.contains_key(&(table_name.clone(), other))
+                })?;
+            }
+
+            let now = self.cache.time.now();
+            for ((table_name, key), (row, ttl)) in writes {
+                let table = &tables[table_name.as_str()];
+                let expires_at = row
+                    .as_ref()
+                    .and(ttl.or(table.ttl))
+                    .map(|ttl| now + ttl);
+
+                table.install(timestamp, watermark, key, row, expires_at);
+            }
+
+            Ok(())
+        })
+    }
+
+    /// # Instance Method `Transaction::rollback`
+    ///
+    /// Rolls back the transaction, discarding all of its writes.
+    pub fn rollback(self) {}
+
+    fn write(
+        &mut self,
+        table_name: String,
+        key: u64,
+        row: CacheTableRow,
+        ttl: Option<Duration>
+    ) -> QlResult<()> {
+        self.cache
+            .with_table(&table_name, |table| table.validate_row(&row))??;
+
+        self.writes.push(TransactionWrite {
+            table_name,
+            key,
+            row: Some(row),
+            ttl
+        });
+        Ok(())
+    }
+
+    fn find_savepoint(&self, name: &str) -> QlResult<usize> {
+        self.savepoints
+            .iter()
+            .rposition(|(savepoint, _)| savepoint == name)
+            .ok_or_else(|| {
+                QlError::SavepointDoesNotExist {
+                    name: name.to_string()
+                }
+            })
+    }
+}
+
+fn relation_does_not_exist(name: &str) -> QlError {
+    QlError::QueryError(QueryError::RelationDoesNotExist {
+        name: name.to_string()
+    })
+}
+
+/// # Struct `TransactionWrite`
+///
+/// A buffered write of a transaction, `row` is `None` if the row is removed.
+pub(crate) struct TransactionWrite {
+    pub(crate) table_name: String,
+    pub(crate) key: u64,
+    pub(crate) row: Option<CacheTableRow>,
+    pub(crate) ttl: Option<Duration>
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Transaction;
+    use crate::{
+        error::{
+            QlError,
+            QueryError
+        },
+        ql::{
+            key::PrimaryKey,
+            QueryBuilder
+        },
+        testing,
+        CacheTableRow,
+        ColumnDataType,
+        ColumnValue,
+        QlCache
+    };
+
+    static_assertions::assert_impl_all!(Transaction: Send, Sync);
+
+    fn cache() -> QlCache {
+        let cache = QlCache::new();
+        testing::table(&cache, "Members", [("Xp", ColumnDataType::U64, false)], []);
+
+        cache
+    }
+
+    fn row(xp: u64) -> CacheTableRow {
+        testing::row([("Xp", ColumnValue::U64(xp))])
+    }
+
+    fn xp(cache: &QlCache, key: u64) -> Option<u64> {
+        cache
+            .with_table("Members", |table| {
+                table
+                    .row(key)
+                    .map(|row| match row.column_values.get("Xp").as_deref() {
+                        Some(ColumnValue::U64(xp)) => *xp,
+                        _ => unreachable!()
+                    })
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn writes_are_visible_only_to_the_transaction_until_commit() {
+        let cache = cache();
+        let mut transaction = cache.begin();
+        transaction.insert_row(String::from("Members"), 1, row(10)).unwrap();
+
+        assert!(transaction.row("Members", 1).unwrap().is_some());
+        assert_eq!(xp(&cache, 1), None);
+
+        transaction.commit().unwrap();
+        assert_eq!(xp(&cache, 1), Some(10));
+    }
+
+    #[test]
+    fn reads_see_the_snapshot_taken_at_begin() {
+        let cache = cache();
+        let transaction = cache.begin();
+        let mut other = cache.begin();
+        other.insert_row(String::from("Members"), 1, row(10)).unwrap();
+        other.commit().unwrap();
+
+        assert!(transaction.row("Members", 1).unwrap().is_none());
+        assert!(cache.begin().row("Members", 1).unwrap().is_some());
+    }
+
+    #[test]
+    fn rollback_to_discards_later_writes_and_savepoints() {
+        let cache = cache();
+        let mut transaction = cache.begin();
+        transaction.insert_row(String::from("Members"), 1, row(10)).unwrap();
+        transaction.savepoint(String::from("outer"));
+        transaction.insert_row(String::from("Members"), 1, row(20)).unwrap();
+        transaction.savepoint(String::from("inner"));
+        transaction.remove_row(String::from("Members"), 1).unwrap();
+
+        transaction.rollback_to("outer").unwrap();
+        assert!(matches!(
+            transaction.rollback_to("inner"),
+            Err(QlError::SavepointDoesNotExist { .. })
+        ));
+
+        // the savepoint itself is kept, so it can be rolled back to again
+        transaction.insert_row(String::from("Members"), 2, row(30)).unwrap();
+        transaction.rollback_to("outer").unwrap();
+
+        transaction.commit().unwrap();
+        assert_eq!(xp(&cache, 1), Some(10));
+        assert_eq!(xp(&cache, 2), None);
+    }
+
+    #[test]
+    fn release_keeps_writes_and_shadowed_savepoints() {
+        let cache = cache();
+        let mut transaction = cache.begin();
+        transaction.savepoint(String::from("batch"));
+        transaction.insert_row(String::from("Members"), 1, row(10)).unwrap();
+        transaction.savepoint(String::from("batch"));
+        transaction.insert_row(String::from("Members"), 2, row(20)).unwrap();
+
+        // releasing the newer savepoint uncovers the one it shadowed
+        transaction.release("batch").unwrap();
+        transaction.rollback_to("batch").unwrap();
+        transaction.release("batch").unwrap();
+        assert!(matches!(transaction.release("batch"), Err(QlError::SavepointDoesNotExist { .. })));
+
+        transaction.insert_row(String::from("Members"), 3, row(30)).unwrap();
+        transaction.commit().unwrap();
+        assert_eq!(xp(&cache, 1), None);
+        assert_eq!(xp(&cache, 2), None);
+        assert_eq!(xp(&cache, 3), Some(30));
+    }
+
+    #[test]
+    fn commit_installs_every_write_at_one_timestamp() {
+        let cache = cache();
+        let mut transaction = cache.begin();
+        transaction.insert_row(String::from("Members"), 1, row(10)).unwrap();
+        transaction.insert_row(String::from("Members"), 2, row(20)).unwrap();
+        transaction.insert_row(String::from("Members"), 2, row(21)).unwrap();
+        transaction.commit().unwrap();
+
+        let (first, second) = cache
+            .with_table("Members", |table| {
+                let begin = |key| {
+                    table
+                        .rows
+                        .get(&key)
+                        .and_then(|chain| chain.versions().last().map(|version| version.begin))
+                };
+
+                (begin(1), begin(2))
+            })
+            .unwrap();
+        assert!(first.is_some());
+        assert_eq!(first, second);
+        assert_eq!(xp(&cache, 2), Some(21));
+    }
+
+    #[test]
+    fn rows_changed_since_the_snapshot_fail_the_commit() {
+        let cache = cache();
+        testing::insert_rows(&cache, "Members", ["Xp"], [10, 20].map(|xp| [ColumnValue::U64(xp)]));
+
+        let mut first = cache.begin();
+        let mut second = cache.begin();
+        first.insert_row(String::from("Members"), 1, row(11)).unwrap();
+        second.insert_row(String::from("Members"), 1, row(12)).unwrap();
+        second.insert_row(String::from("Members"), 2, row(22)).unwrap();
+        first.commit().unwrap();
+
+        // the second transaction did not see the first one, so none of its writes are installed
+        assert!(matches!(
+            second.commit(),
+            Err(QlError::WriteConflict { table_name, key: 1 }) if table_name == "Members"
+        ));
+        assert_eq!(xp(&cache, 1), Some(11));
+        assert_eq!(xp(&cache, 2), Some(20));
+
+        // removals conflict as well, while rows no one else wrote do not
+        let mut first = cache.begin();
+        let mut second = cache.begin();
+        first.remove_row(String::from("Members"), 1).unwrap();
+        second.insert_row(String::from("Members"), 1, row(13)).unwrap();
+        first.commit().unwrap();
+        assert!(matches!(second.commit(), Err(QlError::WriteConflict { key: 1, .. })));
+
+        let mut first = cache.begin();
+        let mut second = cache.begin();
+        first.insert_row(String::from("Members"), 1, row(14)).unwrap();
+        second.insert_row(String::from("Members"), 2, row(24)).unwrap();
+        first.commit().unwrap();
+        second.commit().unwrap();
+        assert_eq!(xp(&cache, 1), Some(14));
+        assert_eq!(xp(&cache, 2), Some(24));
+    }
+
+    #[test]
+    fn primary_keys_are_unique_when_transactions_commit() {
+        let cache = cache();
+        QueryBuilder::create()
+            .table()
+            .name(String::from("Ranks"))
+            .columns(vec![(String::from("Xp"), (ColumnDataType::U64, false))])
+            .primary_key(PrimaryKey::new(String::from("Xp")))
+            .unwrap()
+            .build()
+            .unwrap()
+            .execute(&cache)
+            .unwrap();
+
+        // transactions that write different rows conflict if the rows have the same value
+        let mut first = cache.begin();
+        let mut second = cache.begin();
+        first.insert_row(String::from("Ranks"), 1, row(10)).unwrap();
+        second.insert_row(String::from("Ranks"), 2, row(10)).unwrap();
+        second.insert_row(String::from("Ranks"), 3, row(30)).unwrap();
+        first.commit().unwrap();
+        assert!(matches!(
+            second.commit(),
+            Err(QlError::PrimaryKeyViolation { table_name, key: 1 }) if table_name == "Ranks"
+        ));
+
+        let ranks = |cache: &QlCache| {
+            cache
+                .with_table("Ranks", |table| {
+                    (1..=5).filter(|key| table.row(*key).is_some()).collect::<Vec<_>>()
+                })
+                .unwrap()
+        };
+        assert_eq!(ranks(&cache), vec![1]);
+
+        // and so do the rows written by the same transaction
+        let mut transaction = cache.begin();
+        transaction.insert_row(String::from("Ranks"), 2, row(20)).unwrap();
+        transaction.insert_row(String::from("Ranks"), 3, row(20)).unwrap();
+        assert!(matches!(transaction.commit(), Err(QlError::PrimaryKeyViolation { .. })));
+
+        // a value can move to another row in one transaction
+        let mut transaction = cache.begin();
+        transaction.insert_row(String::from("Ranks"), 1, row(11)).unwrap();
+        transaction.insert_row(String::from("Ranks"), 2, row(10)).unwrap();
+        transaction.commit().unwrap();
+        assert_eq!(ranks(&cache), vec![1, 2]);
+
+        // the writes discarded by rolling back to a savepoint do not take part in the check
+        let mut transaction = cache.begin();
+        transaction.insert_row(String::from("Ranks"), 3, row(30)).unwrap();
+        transaction.savepoint(String::from("batch"));
+        transaction.insert_row(String::from("Ranks"), 4, row(10)).unwrap();
+        transaction.insert_row(String::from("Ranks"), 5, row(30)).unwrap();
+        transaction.rollback_to("batch").unwrap();
+        transaction.remove_row(String::from("Ranks"), 2).unwrap();
+        transaction.insert_row(String::from("Ranks"), 4, row(10)).unwrap();
+        transaction.commit().unwrap();
+        assert_eq!(ranks(&cache), vec![1, 3, 4]);
+    }
+
+    #[test]
+    fn dropping_a_transaction_rolls_it_back() {
+        let cache = cache();
+        let mut transaction = cache.begin();
+        transaction.insert_row(String::from("Members"), 1, row(10)).unwrap();
+        drop(transaction);
+
+        let mut transaction = cache.begin();
+        transaction.insert_row(String::from("Members"), 2, row(20)).unwrap();
+        transaction.rollback();
+
+        assert_eq!(xp(&cache, 1), None);
+        assert_eq!(xp(&cache, 2), None);
+    }
+
+    #[test]
+    fn writes_to_missing_tables_are_rejected() {
+        let cache = cache();
+        let mut transaction = cache.begin();
+
+        assert!(matches!(
+            transaction.insert_row(String::from("Guilds"), 1, row(10)),
+            Err(QlError::QueryError(QueryError::RelationDoesNotExist { .. }))
+        ));
+        assert!(matches!(
+            transaction.insert_row(String::from("Missing Members"), 1, row(10)),
+            Err(QlError::QueryError(QueryError::RelationDoesNotExist { .. }))
+        ));
+    }
+}
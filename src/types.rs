@@ -0,0 +1,1182 @@
+//! # Module `types`
+//!
+//! This module implements the column types that do not correspond to a Rust type of the standard
+//! library.
+
+use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
+    fmt::{
+        Display,
+        Formatter,
+        Result as FmtResult
+    },
+    hash::{
+        Hash,
+        Hasher
+    },
+    mem,
+    str::FromStr,
+    time::{
+        Duration,
+        SystemTime
+    }
+};
+
+use crate::{
+    error::{
+        QlError,
+        QlResult
+    },
+    r#impl::{
+        compare_floats,
+        hash_float
+    },
+    ColumnValue
+};
+
+/// # Struct `Snowflake`
+///
+/// A Discord snowflake, a 64-bit identifier that encodes its creation time.
+///
+/// Snowflakes are ordered by their creation time, so sorting by a snowflake column sorts the rows
+/// by the creation time of their snowflakes.
+///
+/// ## Example
+///
+/// ```
+/// use std::time::{
+///     Duration,
+///     SystemTime
+/// };
+///
+/// use qlcache::types::Snowflake;
+///
+/// let snowflake = Snowflake(175_928_847_299_117_063);
+///
+/// assert_eq!(
+///     snowflake.timestamp(),
+///     SystemTime::UNIX_EPOCH + Duration::from_millis(1_462_015_105_796)
+/// );
+/// assert_eq!(snowflake.worker_id(), 1);
+/// assert_eq!(snowflake.process_id(), 0);
+/// assert_eq!(snowflake.increment(), 7);
+/// ```
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Snowflake(pub u64);
+
+impl Snowflake {
+    /// # Constant `Snowflake::DISCORD_EPOCH`
+    ///
+    /// The first millisecond of 2015, in milliseconds since the Unix epoch.
+    pub const DISCORD_EPOCH: u64 = 1_420_070_400_000;
+
+    /// # Instance Method `Snowflake::timestamp`
+    ///
+    /// Returns the creation time of the snowflake.
+    #[must_use]
+    pub fn timestamp(self) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_millis((self.0 >> 22) + Self::DISCORD_EPOCH)
+    }
+
+    /// # Instance Method `Snowflake::worker_id`
+    ///
+    /// Returns the internal ID of the worker that generated the snowflake.
+    #[must_use]
+    pub fn worker_id(self) -> u8 {
+        ((self.0 >> 17) & 0x1F) as u8
+    }
+
+    /// # Instance Method `Snowflake::process_id`
+    ///
+    /// Returns the internal ID of the process that generated the snowflake.
+    #[must_use]
+    pub fn process_id(self) -> u8 {
+        ((self.0 >> 12) & 0x1F) as u8
+    }
+
+    /// # Instance Method `Snowflake::increment`
+    ///
+    /// Returns the number of snowflakes generated by the process before this one.
+    #[must_use]
+    pub fn increment(self) -> u16 {
+        (self.0 & 0xFFF) as u16
+    }
+}
+
+impl Display for Snowflake {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        Display::fmt(&self.0, f)
+    }
+}
+
+/// # Enumeration `SnowflakePart`
+///
+/// A part of a snowflake that can be extracted from a `Snowflake` value.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SnowflakePart {
+    /// # Enumeration Variant `SnowflakePart::Timestamp`
+    ///
+    /// The creation time, as a `Timestamp`.
+    Timestamp,
+
+    /// # Enumeration Variant `SnowflakePart::WorkerId`
+    ///
+    /// The internal worker ID, as a `U8`.
+    WorkerId,
+
+    /// # Enumeration Variant `SnowflakePart::ProcessId`
+    ///
+    /// The internal process ID, as a `U8`.
+    ProcessId,
+
+    /// # Enumeration Variant `SnowflakePart::Increment`
+    ///
+    /// The increment, as a `U16`.
+    Increment
+}
+
+impl SnowflakePart {
+    /// # Instance Method `SnowflakePart::extract`
+    ///
+    /// Extracts this part from a value, returns `null` if the value is not a `Snowflake`.
+    ///
+    /// ## Parameters
+    /// - `value`, type `&ColumnValue`; the value to extract the part from
+    #[must_use]
+    pub fn extract(self, value: &ColumnValue) -> ColumnValue {
+        let snowflake = match value {
+            ColumnValue::Snowflake(snowflake) => *snowflake,
+            _ => return ColumnValue::Null
+        };
+
+        match self {
+            Self::Timestamp => ColumnValue::Timestamp(snowflake.timestamp()),
+            Self::WorkerId => ColumnValue::U8(snowflake.worker_id()),
+            Self::ProcessId => ColumnValue::U8(snowflake.process_id()),
+            Self::Increment => ColumnValue::U16(snowflake.increment())
+        }
+    }
+}
+
+/// # Struct `Uuid`
+///
+/// A universally unique identifier.
+///
+/// Like a column of any other type, a `Uuid` column can be the
+/// [`PrimaryKey`](crate::ql::key::PrimaryKey) of a table, which makes it non-null and unique. The
+/// primary key is indexed, so a row is looked up by its UUID with
+/// [`CacheTable::row_by_primary_key`](crate::CacheTable::row_by_primary_key), or by a `SELECT`
+/// whose constraint fixes the UUID, without scanning the table.
+///
+/// ## Example
+///
+/// ```
+/// use qlcache::types::Uuid;
+///
+/// let uuid = "67E55044-10B1-426F-9247-BB680E5FE0C8".parse::<Uuid>().unwrap();
+///
+/// assert_eq!(uuid.0, 0x67E5_5044_10B1_426F_9247_BB68_0E5F_E0C8);
+/// assert_eq!(uuid.to_string(), "67e55044-10b1-426f-9247-bb680e5fe0c8");
+/// ```
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Uuid(pub u128);
+
+impl Display for Uuid {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+            self.0 >> 96,
+            (self.0 >> 80) & 0xFFFF,
+            (self.0 >> 64) & 0xFFFF,
+            (self.0 >> 48) & 0xFFFF,
+            self.0 & 0xFFFF_FFFF_FFFF
+        )
+    }
+}
+
+impl FromStr for Uuid {
+    type Err = QlError;
+
+    /// Parses a UUID from its hyphenated (`67e55044-10b1-426f-9247-bb680e5fe0c8`) or simple
+    /// (`67e5504410b1426f9247bb680e5fe0c8`) form, in either case.
+    fn from_str(string: &str) -> QlResult<Self> {
+        let digits = match string.len() {
+            32 => string.to_string(),
+            36 if [8, 13, 18, 23]
+                .into_iter()
+                .all(|index| string.as_bytes()[index] == b'-') =>
+            {
+                string.replace('-', "")
+            }
+            _ => String::new()
+        };
+
+        if digits.len() != 32 || !digits.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            return Err(QlError::InvalidUuid {
+                value: string.to_string()
+            });
+        }
+
+        Ok(Self(u128::from_str_radix(&digits, 16).unwrap()))
+    }
+}
+
+/// # Struct `EnumType`
+///
+/// A user-defined enum type (`CREATE TYPE name AS ENUM (...)`), values of the type are ordered by
+/// the order their labels are declared in.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EnumType {
+    /// # Struct Field `name`
+    ///
+    /// The name of the type.
+    pub name: String,
+
+    /// # Struct Field `labels`
+    ///
+    /// The labels of the type, in declaration order.
+    pub labels: Vec<String>
+}
+
+impl EnumType {
+    /// # Instance Method `EnumType::value`
+    ///
+    /// Returns the value of this type with the specified label.
+    ///
+    /// ## Parameters
+    /// - `label`, type `&str`; the label of the value
+    ///
+    /// ## Errors
+    ///
+    /// Returns `InvalidEnumValue` if the type has no such label.
+    pub fn value(&self, label: &str) -> QlResult<ColumnValue> {
+        let ordinal = self
+            .labels
+            .iter()
+            .position(|other| other == label)
+            .ok_or_else(|| QlError::InvalidEnumValue {
+                type_name: self.name.clone(),
+                label: label.to_string()
+            })?;
+
+        Ok(ColumnValue::Enum(EnumValue {
+            type_name: self.name.clone(),
+            label: label.to_string(),
+            ordinal
+        }))
+    }
+
+    /// Returns whether `value` is a value of this type.
+    pub(crate) fn is_valid(&self, value: &EnumValue) -> bool {
+        value.type_name == self.name
+            && matches!(self.labels.get(value.ordinal), Some(label) if *label == value.label)
+    }
+}
+
+/// # Struct `EnumValue`
+///
+/// A value of a user-defined enum type, constructed with [`EnumType::value`].
+///
+/// ## Example
+///
+/// Mapping a value to a Rust enum:
+///
+/// ```
+/// use qlcache::{
+///     types::{
+///         EnumType,
+///         FromEnumValue
+///     },
+///     ColumnValue
+/// };
+///
+/// #[derive(Debug, PartialEq)]
+/// enum InfractionKind {
+///     Warn,
+///     Mute,
+///     Ban
+/// }
+///
+/// impl FromEnumValue for InfractionKind {
+///     fn from_label(label: &str) -> Option<Self> {
+///         match label {
+///             "warn" => Some(Self::Warn),
+///             "mute" => Some(Self::Mute),
+///             "ban" => Some(Self::Ban),
+///             _ => None
+///         }
+///     }
+/// }
+///
+/// let kind = EnumType {
+///     name: String::from("InfractionKind"),
+///     labels: vec![String::from("warn"), String::from("mute"), String::from("ban")]
+/// };
+///
+/// if let ColumnValue::Enum(value) = kind.value("mute").unwrap() {
+///     assert_eq!(value.to_enum::<InfractionKind>().unwrap(), InfractionKind::Mute);
+/// }
+/// ```
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct EnumValue {
+    pub(crate) type_name: String,
+    pub(crate) label: String,
+    pub(crate) ordinal: usize
+}
+
+impl EnumValue {
+    /// # Instance Method `EnumValue::type_name`
+    ///
+    /// Returns the name of the type of the value.
+    #[must_use]
+    pub fn type_name(&self) -> &str {
+        &self.type_name
+    }
+
+    /// # Instance Method `EnumValue::label`
+    ///
+    /// Returns the label of the value.
+    #[must_use]
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// # Instance Method `EnumValue::ordinal`
+    ///
+    /// Returns the position of the label of the value in the declaration of its type.
+    #[must_use]
+    pub fn ordinal(&self) -> usize {
+        self.ordinal
+    }
+
+    /// # Instance Method `EnumValue::to_enum`
+    ///
+    /// Maps the value to a Rust enum.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `InvalidEnumValue` if the Rust enum has no variant for the label.
+    pub fn to_enum<T: FromEnumValue>(&self) -> QlResult<T> {
+        T::from_label(&self.label).ok_or_else(|| QlError::InvalidEnumValue {
+            type_name: self.type_name.clone(),
+            label: self.label.clone()
+        })
+    }
+}
+
+/// # Trait `FromEnumValue`
+///
+/// A trait for Rust enums that values of a user-defined enum type can be mapped to, for use in
+/// [`FromRow`](crate::FromRow) implementations.
+pub trait FromEnumValue
+where
+    Self: Sized {
+    /// # Trait Method `from_label`
+    ///
+    /// Returns the variant for a label, or `None` if there is none.
+    ///
+    /// ## Parameters
+    /// - `label`, type `&str`; the label of the value
+    fn from_label(label: &str) -> Option<Self>;
+}
+
+/// # Enumeration `JsonValue`
+///
+/// A JSON document.
+///
+/// Documents are parsed from text as specified by RFC 8259, with arrays and objects nested at
+/// most 128 levels deep.
+///
+/// Documents are totally ordered: values of different types are ordered `null`, booleans, numbers,
+/// strings, arrays and then objects; arrays are compared element by element, and objects entry by
+/// entry in the order of their keys.
+///
+/// ## Example
+///
+/// ```
+/// use qlcache::types::JsonValue;
+///
+/// let document = r#"{"allow": ["1024"], "deny": []}"#.parse::<JsonValue>().unwrap();
+///
+/// assert_eq!(
+///     document.field("allow").and_then(|allow| allow.element(0)),
+///     Some(&JsonValue::String(String::from("1024")))
+/// );
+/// assert_eq!(document.to_string(), r#"{"allow":["1024"],"deny":[]}"#);
+/// ```
+#[allow(missing_docs)] // variants are pretty self explanatory?
+#[derive(Clone, Debug)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(BTreeMap<String, JsonValue>)
+}
+
+impl JsonValue {
+    /// # Instance Method `JsonValue::field`
+    ///
+    /// Returns the value of a field of this document, if it is an object (`->` with a key).
+    ///
+    /// ## Parameters
+    /// - `name`, type `&str`; the name of the field
+    #[must_use]
+    pub fn field(&self, name: &str) -> Option<&Self> {
+        match self {
+            Self::Object(fields) => fields.get(name),
+            _ => None
+        }
+    }
+
+    /// # Instance Method `JsonValue::element`
+    ///
+    /// Returns an element of this document, if it is an array (`->` with an index).
+    ///
+    /// ## Parameters
+    /// - `index`, type `usize`; the index of the element
+    #[must_use]
+    pub fn element(&self, index: usize) -> Option<&Self> {
+        match self {
+            Self::Array(elements) => elements.get(index),
+            _ => None
+        }
+    }
+
+    /// # Instance Method `JsonValue::contains`
+    ///
+    /// Returns whether this document contains another document (`@>`): an object contains an
+    /// object whose fields it all contains, an array contains an array whose elements are each
+    /// contained by one of its elements, an array contains a scalar equal to one of its elements,
+    /// and a scalar only contains an equal scalar.
+    ///
+    /// ## Parameters
+    /// - `other`, type `&JsonValue`; the document to check for
+    #[must_use]
+    pub fn contains(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Object(fields), Self::Object(others)) => others.iter().all(|(name, other)| {
+                matches!(fields.get(name), Some(field) if field.contains(other))
+            }),
+            (Self::Array(elements), Self::Array(others)) => others
+                .iter()
+                .all(|other| elements.iter().any(|element| element.contains(other))),
+            (
+                Self::Array(elements),
+                Self::Null | Self::Bool(_) | Self::Number(_) | Self::String(_)
+            ) => elements.contains(other),
+            _ => self == other
+        }
+    }
+
+    /// Returns the approximate size of the document in memory, in bytes.
+    pub(crate) fn approximate_size(&self) -> usize {
+        let heap = match self {
+            Self::String(string) => string.len(),
+            Self::Array(elements) => elements.iter().map(Self::approximate_size).sum(),
+            Self::Object(fields) => fields
+                .iter()
+                .map(|(name, field)| name.len() + field.approximate_size())
+                .sum(),
+            _ => 0
+        };
+
+        mem::size_of::<Self>() + heap
+    }
+
+    fn type_rank(&self) -> u8 {
+        match self {
+            Self::Null => 0,
+            Self::Bool(_) => 1,
+            Self::Number(_) => 2,
+            Self::String(_) => 3,
+            Self::Array(_) => 4,
+            Self::Object(_) => 5
+        }
+    }
+}
+
+impl Display for JsonValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Bool(boolean) => Display::fmt(boolean, f),
+            Self::Number(number) if number.is_finite() => Display::fmt(number, f),
+            Self::Null | Self::Number(_) => f.write_str("null"),
+            Self::String(string) => write_json_string(string, f),
+            Self::Array(elements) => {
+                f.write_str("[")?;
+                for (index, element) in elements.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(",")?;
+                    }
+
+                    Display::fmt(element, f)?;
+                }
+                f.write_str("]")
+            }
+            Self::Object(fields) => {
+                f.write_str("{")?;
+                for (index, (name, field)) in fields.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(",")?;
+                    }
+
+                    write_json_string(name, f)?;
+                    f.write_str(":")?;
+                    Display::fmt(field, f)?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+impl FromStr for JsonValue {
+    type Err = QlError;
+
+    fn from_str(string: &str) -> QlResult<Self> {
+        let mut parser = JsonParser {
+            text: string,
+            position: 0,
+            depth: 0
+        };
+        let value = parser.value();
+        parser.whitespace();
+
+        match value {
+            Some(value) if parser.position == string.len() => Ok(value),
+            _ => Err(QlError::InvalidJson {
+                value: string.to_string()
+            })
+        }
+    }
+}
+
+impl Eq for JsonValue {}
+
+impl Hash for JsonValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.type_rank().hash(state);
+
+        match self {
+            Self::Null => {}
+            Self::Bool(boolean) => boolean.hash(state),
+            Self::Number(number) => hash_float(*number, state),
+            Self::String(string) => string.hash(state),
+            Self::Array(elements) => elements.hash(state),
+            Self::Object(fields) => fields.hash(state)
+        }
+    }
+}
+
+impl Ord for JsonValue {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Bool(left), Self::Bool(right)) => left.cmp(right),
+            (Self::Number(left), Self::Number(right)) => compare_floats(*left, *right),
+            (Self::String(left), Self::String(right)) => left.cmp(right),
+            (Self::Array(left), Self::Array(right)) => left.cmp(right),
+            (Self::Object(left), Self::Object(right)) => left.iter().cmp(right.iter()),
+            _ => self.type_rank().cmp(&other.type_rank())
+        }
+    }
+}
+
+impl PartialEq for JsonValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl PartialOrd for JsonValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// A recursive descent parser for JSON documents.
+struct JsonParser<'text> {
+    text: &'text str,
+    position: usize,
+    depth: usize
+}
+
+impl JsonParser<'_> {
+    /// The maximum number of arrays and objects a value can be nested in, so that parsing a deeply
+    /// nested document does not overflow the stack.
+    const MAX_DEPTH: usize = 128;
+
+    fn value(&mut self) -> Option<JsonValue> {
+        self.whitespace();
+
+        match self.peek()? {
+            b'n' if self.literal("null") => Some(JsonValue::Null),
+            b't' if self.literal("true") => Some(JsonValue::Bool(true)),
+            b'f' if self.literal("false") => Some(JsonValue::Bool(false)),
+            b'"' => self.string().map(JsonValue::String),
+            b'[' => self.nested(Self::array),
+            b'{' => self.nested(Self::object),
+            _ => self.number()
+        }
+    }
+
+    fn nested(&mut self, parse: fn(&mut Self) -> Option<JsonValue>) -> Option<JsonValue> {
+        if self.depth == Self::MAX_DEPTH {
+            return None;
+        }
+
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+
+        value
+    }
+
+    fn array(&mut self) -> Option<JsonValue> {
+        let mut elements = Vec::new();
+
+        self.position += 1;
+        self.whitespace();
+        if self.peek() == Some(b']') {
+            self.position += 1;
+            return Some(JsonValue::Array(elements));
+        }
+
+        loop {
+            elements.push(self.value()?);
+            self.whitespace();
+
+            match self.next()? {
+                b',' => {}
+                b']' => return Some(JsonValue::Array(elements)),
+                _ => return None
+            }
+        }
+    }
+
+    fn object(&mut self) -> Option<JsonValue> {
+        let mut fields = BTreeMap::new();
+
+        self.position += 1;
+        self.whitespace();
+        if self.peek() == Some(b'}') {
+            self.position += 1;
+            return Some(JsonValue::Object(fields));
+        }
+
+        loop {
+            self.whitespace();
+            if self.peek()? != b'"' {
+                return None;
+            }
+
+            let name = self.string()?;
+            self.whitespace();
+            if self.next()? != b':' {
+                return None;
+            }
+
+            fields.insert(name, self.value()?);
+            self.whitespace();
+
+            match self.next()? {
+                b',' => {}
+                b'}' => return Some(JsonValue::Object(fields)),
+                _ => return None
+            }
+        }
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let mut string = String::new();
+
+        self.position += 1;
+        loop {
+            let character = self.text[self.position..].chars().next()?;
+            self.position += character.len_utf8();
+
+            let unescaped = match character {
+                '"' => return Some(string),
+                '\\' => {
+                    match self.next()? {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => self.unicode_escape()?,
+                        _ => return None
+                    }
+                }
+                character if character < ' ' => return None,
+                character => character
+            };
+
+            string.push(unescaped);
+        }
+    }
+
+    fn unicode_escape(&mut self) -> Option<char> {
+        let code = self.hex()?;
+
+        if (0xD800..0xDC00).contains(&code) {
+            if !self.literal("\\u") {
+                return None;
+            }
+
+            let low = self.hex()?;
+            if !(0xDC00..0xE000).contains(&low) {
+                return None;
+            }
+
+            return char::from_u32(0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00));
+        }
+
+        char::from_u32(code)
+    }
+
+    fn hex(&mut self) -> Option<u32> {
+        let digits = self.text.get(self.position..self.position + 4)?;
+        if !digits.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        self.position += 4;
+        u32::from_str_radix(digits, 16).ok()
+    }
+
+    fn number(&mut self) -> Option<JsonValue> {
+        let start = self.position;
+        if self.peek() == Some(b'-') {
+            self.position += 1;
+        }
+
+        // the integer part has no leading zeros, and the fraction and exponent need digits
+        match self.next()? {
+            b'0' => {}
+            b'1'..=b'9' => {
+                self.digits();
+            }
+            _ => return None
+        }
+
+        if self.literal(".") && self.digits() == 0 {
+            return None;
+        }
+
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            self.position += 1;
+            if matches!(self.peek(), Some(b'+' | b'-')) {
+                self.position += 1;
+            }
+
+            if self.digits() == 0 {
+                return None;
+            }
+        }
+
+        self.text[start..self.position]
+            .parse::<f64>()
+            .ok()
+            .filter(|number| number.is_finite())
+            .map(JsonValue::Number)
+    }
+
+    /// Skips decimal digits, returns how many were skipped.
+    fn digits(&mut self) -> usize {
+        let start = self.position;
+        while matches!(self.peek(), Some(b'0'..=b'9')) {
+            self.position += 1;
+        }
+
+        self.position - start
+    }
+
+    fn literal(&mut self, literal: &str) -> bool {
+        if self.text[self.position..].starts_with(literal) {
+            self.position += literal.len();
+            true
+        }
+        else {
+            false
+        }
+    }
+
+    fn whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let byte = self.peek()?;
+        self.position += 1;
+
+        Some(byte)
+    }
+}
+
+/// Writes a string as a JSON string literal.
+fn write_json_string(string: &str, f: &mut Formatter<'_>) -> FmtResult {
+    f.write_str("\"")?;
+    for character in string.chars() {
+        match character {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            character if character < ' ' => write!(f, "\\u{:04x}", u32::from(character))?,
+            character => Display::fmt(&character, f)?
+        }
+    }
+    f.write_str("\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeMap,
+        time::{
+            Duration,
+            SystemTime
+        }
+    };
+
+    use super::{
+        EnumType,
+        EnumValue,
+        FromEnumValue,
+        JsonValue,
+        Snowflake,
+        SnowflakePart,
+        Uuid
+    };
+    use crate::{
+        error::QlError,
+        ColumnValue
+    };
+
+    static_assertions::assert_impl_all!(EnumType: Clone, Eq, PartialEq, Send, Sync);
+    static_assertions::assert_impl_all!(EnumValue: Clone, Eq, PartialEq, Send, Sync);
+    static_assertions::assert_impl_all!(JsonValue: Clone, Eq, Ord, Send, Sync);
+    static_assertions::assert_impl_all!(Snowflake: Clone, Copy, Eq, Ord, Send, Sync);
+    static_assertions::assert_impl_all!(SnowflakePart: Clone, Copy, Eq, Send, Sync);
+    static_assertions::assert_impl_all!(Uuid: Clone, Copy, Eq, Ord, Send, Sync);
+
+    #[derive(Debug, PartialEq)]
+    enum Kind {
+        Warn,
+        Ban
+    }
+
+    impl FromEnumValue for Kind {
+        fn from_label(label: &str) -> Option<Self> {
+            match label {
+                "warn" => Some(Self::Warn),
+                "ban" => Some(Self::Ban),
+                _ => None
+            }
+        }
+    }
+
+    fn kind() -> EnumType {
+        EnumType {
+            name: String::from("Kind"),
+            labels: vec![String::from("warn"), String::from("mute"), String::from("ban")]
+        }
+    }
+
+    fn value(kind: &EnumType, label: &str) -> EnumValue {
+        match kind.value(label).unwrap() {
+            ColumnValue::Enum(value) => value,
+            _ => unreachable!()
+        }
+    }
+
+    #[test]
+    fn uuids_are_parsed_in_either_form_and_case() {
+        let uuid = Uuid(0x67E5_5044_10B1_426F_9247_BB68_0E5F_E0C8);
+
+        for string in [
+            "67e55044-10b1-426f-9247-bb680e5fe0c8",
+            "67E55044-10B1-426F-9247-BB680E5FE0C8",
+            "67e5504410b1426f9247bb680e5fe0c8",
+            "67E5504410b1426F9247BB680e5fe0c8"
+        ] {
+            assert_eq!(string.parse::<Uuid>().unwrap(), uuid);
+        }
+
+        // formatting keeps leading zeros, so that it round-trips
+        assert_eq!(Uuid(1).to_string(), "00000000-0000-0000-0000-000000000001");
+        assert_eq!(Uuid(u128::MAX).to_string().parse::<Uuid>().unwrap(), Uuid(u128::MAX));
+        assert_eq!(uuid.to_string().parse::<Uuid>().unwrap(), uuid);
+    }
+
+    #[test]
+    fn malformed_uuids_are_rejected() {
+        for string in [
+            "",
+            "67e55044-10b1-426f-9247-bb680e5fe0c",
+            "67e55044-10b1-426f-9247-bb680e5fe0c89",
+            "67e5504410b1426f9247bb680e5fe0c",
+            "67e55044_10b1_426f_9247_bb680e5fe0c8",
+            "67e5504-410b1-426f-9247-bb680e5fe0c8",
+            "67e55044-10b1-426f-9247bb680e5fe0c8-",
+            "g7e55044-10b1-426f-9247-bb680e5fe0c8",
+            "+7e5504410b1426f9247bb680e5fe0c8",
+            "{67e55044-10b1-426f-9247-bb680e5fe0}",
+            "67e55044-10b1-426f-9247-bb680e5fé0c"
+        ] {
+            assert!(
+                matches!(
+                    string.parse::<Uuid>(),
+                    Err(QlError::InvalidUuid { value }) if value == string
+                ),
+                "{string:?} was parsed"
+            );
+        }
+    }
+
+    #[test]
+    fn snowflakes_are_split_into_their_parts() {
+        let snowflake = Snowflake((5 << 22) | (31 << 17) | (2 << 12) | 0xFFF);
+
+        assert_eq!(
+            snowflake.timestamp(),
+            SystemTime::UNIX_EPOCH + Duration::from_millis(Snowflake::DISCORD_EPOCH + 5)
+        );
+        assert_eq!(snowflake.worker_id(), 31);
+        assert_eq!(snowflake.process_id(), 2);
+        assert_eq!(snowflake.increment(), 0xFFF);
+        assert_eq!(
+            Snowflake(0).timestamp(),
+            SystemTime::UNIX_EPOCH + Duration::from_millis(Snowflake::DISCORD_EPOCH)
+        );
+
+        let value = ColumnValue::Snowflake(snowflake);
+        assert_eq!(SnowflakePart::WorkerId.extract(&value), ColumnValue::U8(31));
+        assert_eq!(SnowflakePart::ProcessId.extract(&value), ColumnValue::U8(2));
+        assert_eq!(SnowflakePart::Increment.extract(&value), ColumnValue::U16(0xFFF));
+        assert_eq!(
+            SnowflakePart::Timestamp.extract(&value),
+            ColumnValue::Timestamp(snowflake.timestamp())
+        );
+
+        // other values have no parts, not even the `U64` a snowflake is stored as
+        assert_eq!(
+            SnowflakePart::WorkerId.extract(&ColumnValue::U64(snowflake.0)),
+            ColumnValue::Null
+        );
+        assert_eq!(SnowflakePart::Timestamp.extract(&ColumnValue::Null), ColumnValue::Null);
+
+        // snowflakes are ordered by their creation time first
+        assert!(Snowflake(1 << 22) > Snowflake((1 << 22) - 1));
+        assert_eq!(snowflake.to_string(), snowflake.0.to_string());
+    }
+
+    #[test]
+    fn enum_values_are_looked_up_by_label() {
+        let kind = kind();
+
+        let mute = value(&kind, "mute");
+        assert_eq!(mute.type_name(), "Kind");
+        assert_eq!(mute.label(), "mute");
+        assert_eq!(mute.ordinal(), 1);
+        assert!(kind.is_valid(&mute));
+
+        // labels are case-sensitive
+        assert!(matches!(
+            kind.value("Mute"),
+            Err(QlError::InvalidEnumValue { type_name, label })
+                if type_name == "Kind" && label == "Mute"
+        ));
+
+        // values are ordered by the declaration of their labels, not alphabetically
+        assert!(kind.value("warn").unwrap() < kind.value("ban").unwrap());
+
+        // a value of another type, or of an older declaration of the type, is not valid
+        let other = EnumType {
+            name: String::from("Other"),
+            labels: kind.labels.clone()
+        };
+        let reordered = EnumType {
+            name: String::from("Kind"),
+            labels: vec![String::from("mute"), String::from("warn"), String::from("ban")]
+        };
+        assert!(!other.is_valid(&mute));
+        assert!(!reordered.is_valid(&mute));
+    }
+
+    #[test]
+    fn enum_values_map_to_rust_enums() {
+        let kind = kind();
+
+        assert_eq!(value(&kind, "warn").to_enum::<Kind>().unwrap(), Kind::Warn);
+        assert_eq!(value(&kind, "ban").to_enum::<Kind>().unwrap(), Kind::Ban);
+        assert!(matches!(
+            value(&kind, "mute").to_enum::<Kind>(),
+            Err(QlError::InvalidEnumValue { label, .. }) if label == "mute"
+        ));
+    }
+
+    fn json(text: &str) -> JsonValue {
+        text.parse().unwrap()
+    }
+
+    fn is_invalid_json(text: &str) -> bool {
+        matches!(text.parse::<JsonValue>(), Err(QlError::InvalidJson { value }) if value == text)
+    }
+
+    #[test]
+    fn json_documents_are_parsed() {
+        let document = json(r#" { "a" : [1, -2.5e3, true, false, null, "x"] , "b" : {} } "#);
+
+        assert_eq!(
+            document.field("a"),
+            Some(&JsonValue::Array(vec![
+                JsonValue::Number(1.0),
+                JsonValue::Number(-2500.0),
+                JsonValue::Bool(true),
+                JsonValue::Bool(false),
+                JsonValue::Null,
+                JsonValue::String(String::from("x"))
+            ]))
+        );
+        assert_eq!(document.field("b"), Some(&JsonValue::Object(BTreeMap::new())));
+        assert_eq!(document.to_string(), r#"{"a":[1,-2500,true,false,null,"x"],"b":{}}"#);
+
+        // a later field of the same name replaces the earlier one
+        assert_eq!(json(r#"{"a": 1, "a": 2}"#), json(r#"{"a": 2}"#));
+
+        for text in [
+            "",
+            " ",
+            "[1,]",
+            "[1",
+            "[1 2]",
+            "{\"a\" 1}",
+            "{\"a\": 1,}",
+            "{a: 1}",
+            "{1: 1}",
+            "tru",
+            "nul",
+            "True",
+            "1 2",
+            "\"unterminated",
+            "\"\u{1}\"",
+            "'single'"
+        ] {
+            assert!(is_invalid_json(text), "{text:?} was parsed");
+        }
+    }
+
+    #[test]
+    fn json_strings_are_unescaped() {
+        assert_eq!(
+            json(r#""\"\\\/\b\f\n\r\té\u00e9""#),
+            JsonValue::String(String::from("\"\\/\u{8}\u{c}\n\r\té\u{e9}"))
+        );
+
+        // characters outside the basic multilingual plane are escaped as surrogate pairs
+        assert_eq!(json(r#""\ud83d\ude00""#), JsonValue::String(String::from("\u{1f600}")));
+        assert_eq!(json(r#""\uD83D\uDE00""#), json(r#""😀""#));
+        for text in [
+            r#""\ud83d""#,
+            r#""\ud83dA""#,
+            r#""\ud83d\ud83d""#,
+            r#""\ude00""#,
+            r#""\ud83dx""#,
+            r#""\u00""#,
+            r#""\u00g0""#,
+            r#""\x""#
+        ] {
+            assert!(is_invalid_json(text), "{text:?} was parsed");
+        }
+
+        // and are written back unescaped, except for quotes, backslashes and control characters
+        assert_eq!(
+            JsonValue::String(String::from("\"\\/\u{1}\n\u{1f600}")).to_string(),
+            r#""\"\\/\u0001\n😀""#
+        );
+    }
+
+    #[test]
+    fn json_numbers_follow_the_grammar_of_json() {
+        for (text, number) in [
+            ("0", 0.0),
+            ("-0", 0.0),
+            ("10", 10.0),
+            ("1.5", 1.5),
+            ("-0.25", -0.25),
+            ("1e2", 100.0),
+            ("1E+2", 100.0),
+            ("25e-2", 0.25),
+            ("0.5e1", 5.0)
+        ] {
+            assert_eq!(json(text), JsonValue::Number(number), "{text:?}");
+        }
+
+        for text in [
+            "+1", "01", "-01", "00", ".5", "-.5", "1.", "1.e2", "-", "--1", "1e", "1e+", "1e2.5",
+            "0x10", "1_000", "NaN", "Infinity", "-Infinity", "1e400"
+        ] {
+            assert!(is_invalid_json(text), "{text:?} was parsed");
+        }
+    }
+
+    #[test]
+    fn json_documents_are_nested_at_most_128_levels_deep() {
+        let arrays = |depth| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        let objects = |depth| format!("{}1{}", r#"{"a":"#.repeat(depth), "}".repeat(depth));
+
+        assert!(arrays(128).parse::<JsonValue>().is_ok());
+        assert!(objects(128).parse::<JsonValue>().is_ok());
+        assert!(is_invalid_json(&arrays(129)));
+        assert!(is_invalid_json(&objects(129)));
+
+        // deeply nested documents are rejected instead of overflowing the stack
+        assert!(is_invalid_json(&arrays(1_000_000)));
+    }
+
+    #[test]
+    fn json_documents_are_navigated_and_contained() {
+        let document = json(r#"{"a": {"b": [1, [2, 3], {"c": null}]}, "d": "e"}"#);
+        let b = document.field("a").and_then(|a| a.field("b")).unwrap();
+
+        assert_eq!(b.element(0), Some(&JsonValue::Number(1.0)));
+        assert_eq!(b.element(3), None);
+        assert_eq!(b.field("0"), None);
+        assert_eq!(document.element(0), None);
+        assert_eq!(document.field("missing"), None);
+        assert_eq!(
+            b.element(2).and_then(|c| c.field("c")),
+            Some(&JsonValue::Null)
+        );
+
+        assert!(document.contains(&json(r#"{"d": "e"}"#)));
+        assert!(document.contains(&json(r#"{"a": {"b": [[3], 1]}}"#)));
+        assert!(document.contains(&json("{}")));
+        assert!(!document.contains(&json(r#"{"d": "e", "f": 1}"#)));
+        assert!(!document.contains(&json(r#"{"a": {"b": [4]}}"#)));
+        assert!(!document.contains(&json(r#"["d"]"#)));
+
+        // an array contains its elements, but a scalar only contains an equal scalar
+        assert!(json("[1, 2]").contains(&json("2")));
+        assert!(json("[1, 2]").contains(&json("[2, 2, 1]")));
+        assert!(!json("[[1, 2]]").contains(&json("1")));
+        assert!(json("1").contains(&json("1.0")));
+        assert!(!json("1").contains(&json("[1]")));
+        assert!(!json(r#""ab""#).contains(&json(r#""a""#)));
+    }
+}